@@ -0,0 +1,63 @@
+//! Joint densities of two of the viewer's distributions, for the heatmap window.
+//!
+//! The two marginals are tied together by a Gaussian copula with correlation
+//! ρ. For two plain Gaussians this is exactly the bivariate normal; any other
+//! pair keeps its own marginals and gains the same rank dependence.
+
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::GaussianDistribution;
+
+/// Probabilities are kept this far from 0 and 1, where normal quantiles are infinite
+const EDGE: f64 = 1e-12;
+
+/// Largest |ρ| accepted; the copula density is singular at ±1
+pub const MAX_CORRELATION: f64 = 0.99;
+
+/// f(x, y) = c(F_X(x), F_Y(y)) f_X(x) f_Y(y) with the Gaussian copula density c
+pub fn joint_density(x_dist: &GaussianDistribution, y_dist: &GaussianDistribution, rho: f64, x: f64, y: f64) -> f64 {
+    let (fx, fy) = (x_dist.evaluate(x), y_dist.evaluate(y));
+    if fx <= 0.0 || fy <= 0.0 {
+        return 0.0;
+    }
+    let rho = rho.clamp(-MAX_CORRELATION, MAX_CORRELATION);
+    if rho == 0.0 {
+        return fx * fy;
+    }
+    let standard = Normal::new(0.0, 1.0).unwrap();
+    let a = standard.inverse_cdf(x_dist.quantile_level(x).clamp(EDGE, 1.0 - EDGE));
+    let b = standard.inverse_cdf(y_dist.quantile_level(y).clamp(EDGE, 1.0 - EDGE));
+    let residual = 1.0 - rho * rho;
+    let copula = (-(rho * rho * (a * a + b * b) - 2.0 * rho * a * b) / (2.0 * residual)).exp() / residual.sqrt();
+    copula * fx * fy
+}
+
+/// Joint density sampled at the centres of a `width` × `height` grid of cells
+pub struct DensityRaster {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row from the top (largest y), as images are laid out
+    pub values: Vec<f64>,
+    pub max: f64,
+}
+
+pub fn density_raster(
+    x_dist: &GaussianDistribution,
+    y_dist: &GaussianDistribution,
+    rho: f64,
+    (x_min, x_max): (f64, f64),
+    (y_min, y_max): (f64, f64),
+    width: usize,
+    height: usize,
+) -> DensityRaster {
+    let (width, height) = (width.max(1), height.max(1));
+    let (dx, dy) = ((x_max - x_min) / width as f64, (y_max - y_min) / height as f64);
+    let values: Vec<f64> = (0..height)
+        .flat_map(|row| {
+            let y = y_max - dy * (row as f64 + 0.5);
+            (0..width).map(move |column| joint_density(x_dist, y_dist, rho, x_min + dx * (column as f64 + 0.5), y))
+        })
+        .collect();
+    let max = values.iter().copied().filter(|v| v.is_finite()).fold(0.0, f64::max);
+    DensityRaster { width, height, values, max }
+}
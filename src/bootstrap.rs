@@ -0,0 +1,119 @@
+//! Bootstrap confidence bands around curves fitted to imported data.
+//!
+//! The data is resampled with replacement, the family refitted to every
+//! resample and the refitted density and CDF evaluated on a fixed grid; at
+//! each x the band runs between percentiles of those replicate curves.
+//! Refitting hundreds of times takes a moment, so it runs on a background
+//! thread and the result is picked up by polling, like the update check.
+
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{FitFamily, FitMethod};
+
+/// Pointwise percentile band of the bootstrap replicates on the grid `xs`
+#[derive(Clone, Debug)]
+pub struct BootstrapBand {
+    pub xs: Vec<f64>,
+    /// [lower, upper] of the density at each x
+    pub density: Vec<[f64; 2]>,
+    /// [lower, upper] of the CDF at each x
+    pub cdf: Vec<[f64; 2]>,
+    /// Probability covered by the band, e.g. 0.95
+    pub level: f64,
+    /// Resamples that could be refitted; the others are left out
+    pub replicates: usize,
+}
+
+/// Refit `family` to `replicates` resamples of `values` and collect the band
+/// covering `level` of the refitted curves at each of `xs`
+pub fn bootstrap_band(
+    values: &[f64],
+    family: FitFamily,
+    method: FitMethod,
+    xs: &[f64],
+    replicates: usize,
+    level: f64,
+    seed: u64,
+) -> Result<BootstrapBand, String> {
+    if values.is_empty() {
+        return Err("No data to resample".to_string());
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut resample = vec![0.0; values.len()];
+    let mut curves = Vec::with_capacity(replicates);
+    for _ in 0..replicates {
+        for value in resample.iter_mut() {
+            *value = values[rng.gen_range(0..values.len())];
+        }
+        // A resample can fail to fit, e.g. when it repeats a single value
+        let Ok((parameters, _)) = family.estimate(method, &resample) else {
+            continue;
+        };
+        if let Some(curve) = family.density_and_cdf([parameters[0].value, parameters[1].value], xs) {
+            curves.push(curve);
+        }
+    }
+    if curves.len() < 2 {
+        return Err("Too few resamples could be refitted for a band".to_string());
+    }
+
+    let tail = (1.0 - level.clamp(0.0, 1.0)) / 2.0;
+    let last = curves.len() - 1;
+    let (lower_rank, upper_rank) = ((tail * last as f64).round() as usize, ((1.0 - tail) * last as f64).round() as usize);
+    let mut column = vec![0.0; curves.len()];
+    let mut band = |pick: fn(&(f64, f64)) -> f64| -> Vec<[f64; 2]> {
+        (0..xs.len())
+            .map(|i| {
+                for (value, curve) in column.iter_mut().zip(&curves) {
+                    *value = pick(&curve[i]);
+                }
+                column.sort_by(f64::total_cmp);
+                [column[lower_rank], column[upper_rank]]
+            })
+            .collect()
+    };
+    let density = band(|&(density, _)| density);
+    let cdf = band(|&(_, cdf)| cdf);
+    Ok(BootstrapBand { xs: xs.to_vec(), density, cdf, level, replicates: curves.len() })
+}
+
+#[derive(Default)]
+pub enum BootstrapJob {
+    #[default]
+    Idle,
+    Running(Receiver<Result<BootstrapBand, String>>),
+    Done(BootstrapBand),
+    Failed(String),
+}
+
+impl BootstrapJob {
+    /// Start resampling in the background, replacing any earlier job
+    pub fn start(values: Vec<f64>, family: FitFamily, method: FitMethod, xs: Vec<f64>, replicates: usize, level: f64, seed: u64) -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(bootstrap_band(&values, family, method, &xs, replicates, level, seed));
+        });
+        BootstrapJob::Running(receiver)
+    }
+
+    /// Pick up the result of a running job; true when it just finished
+    pub fn poll(&mut self) -> bool {
+        let BootstrapJob::Running(receiver) = self else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err("The bootstrap stopped unexpectedly".to_string()),
+        };
+        *self = match result {
+            Ok(band) => BootstrapJob::Done(band),
+            Err(error) => BootstrapJob::Failed(error),
+        };
+        true
+    }
+}
@@ -0,0 +1,134 @@
+//! Batch operations on session files, for pipelines that have no display.
+//!
+//! `pdf_viewer ops <operation> --session in.json --ids 1,2 [--out out.json]`
+//! adds a derived distribution exactly like the buttons of the GUI would and
+//! writes the session back (to stdout without `--out`).
+//! `pdf_viewer stats --session in.json [--format csv|json]` prints the moment
+//! summary of every distribution. Ids are the ones stored in the session file.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::{Operation, PdfViewerApp};
+
+pub const USAGE: &str = "\
+Usage:
+  pdf_viewer                      start the viewer
+  pdf_viewer ops <operation> --session <file> --ids <id,id,...> [--out <file>] [--name <name>]
+      operations: multiply, divide, max, min, posterior, mix
+  pdf_viewer stats --session <file> [--format csv|json]
+  pdf_viewer help";
+
+/// Whether `arg` names a subcommand; other arguments leave the GUI to start
+pub fn is_command(arg: &str) -> bool {
+    matches!(arg, "ops" | "stats" | "help" | "--help" | "-h")
+}
+
+/// Run one subcommand; `args` excludes the program name
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    match command.as_str() {
+        "ops" => {
+            let (operation, rest) = rest.split_first().ok_or("ops: missing operation")?;
+            let flags = parse_flags(rest)?;
+            let mut app = load(&flags)?;
+            let ids = parse_ids(flags.get("ids").ok_or("ops: missing --ids")?)?;
+            let id = apply(&mut app, operation, ids)?;
+            if let Some(name) = flags.get("name") {
+                app.distributions.get_mut(&id).unwrap().name = name.clone();
+            }
+            let json = app.save_session()?;
+            match flags.get("out") {
+                Some(path) => std::fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path, e)),
+                None => writeln!(out, "{}", json).map_err(|e| e.to_string()),
+            }
+        }
+        "stats" => {
+            let flags = parse_flags(rest)?;
+            let app = load(&flags)?;
+            let text = match flags.get("format").map(String::as_str).unwrap_or("csv") {
+                "csv" => app.export_stats_csv(),
+                "json" => stats_json(&app)?,
+                other => return Err(format!("stats: unknown format '{}' (csv or json)", other)),
+            };
+            write!(out, "{}", text).map_err(|e| e.to_string())
+        }
+        "help" | "--help" | "-h" => writeln!(out, "{}", USAGE).map_err(|e| e.to_string()),
+        other => Err(format!("Unknown command '{}'\n{}", other, USAGE)),
+    }
+}
+
+/// Add the derived distribution of `operation` over `ids`, returning its id
+fn apply(app: &mut PdfViewerApp, operation: &str, ids: Vec<u32>) -> Result<u32, String> {
+    let operation = match operation {
+        "multiply" => Operation::Product,
+        "divide" => Operation::Quotient,
+        "max" => Operation::Maximum,
+        "min" => Operation::Minimum,
+        "posterior" => Operation::BayesUpdate,
+        "mix" => {
+            // Same weighting as "Mix Selected": by evidence when every part has one
+            let by_evidence = ids.iter()
+                .all(|id| app.distributions.get(id).is_some_and(|d| d.normalizing_constant.is_some()));
+            Operation::Mixture { weights: vec![1.0; ids.len()], by_evidence }
+        }
+        other => return Err(format!("ops: unknown operation '{}'", other)),
+    };
+    if let Some(missing) = ids.iter().find(|id| !app.distributions.contains_key(id)) {
+        return Err(format!("ops: no distribution with id {}", missing));
+    }
+    let label = operation.label();
+    let id = app.add_derived(operation, ids)
+        .ok_or_else(|| format!("ops: wrong number of distributions for {}", label))?;
+    app.update_product_distributions();
+    Ok(id)
+}
+
+fn load(flags: &HashMap<String, String>) -> Result<PdfViewerApp, String> {
+    let path = flags.get("session").ok_or("missing --session")?;
+    let bytes = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let mut app = PdfViewerApp::new();
+    app.load_session(bytes)?;
+    Ok(app)
+}
+
+fn stats_json(app: &PdfViewerApp) -> Result<String, String> {
+    let mut ids: Vec<u32> = app.distributions.keys().copied().collect();
+    ids.sort_unstable();
+    let rows: Vec<serde_json::Value> = ids.iter()
+        .map(|id| {
+            let dist = &app.distributions[id];
+            let summary = dist.moment_summary();
+            serde_json::json!({
+                "id": dist.id,
+                "name": dist.name,
+                "kind": if dist.is_derived() { dist.operation.label() } else { "Input" },
+                "mean": summary.mean,
+                "std_dev": summary.std_dev,
+                "skewness": summary.skewness,
+                "excess_kurtosis": summary.excess_kurtosis,
+                "median": summary.median,
+                "mode": summary.mode,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())
+}
+
+/// `--key value` pairs
+fn parse_flags(args: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut flags = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let key = arg.strip_prefix("--").ok_or_else(|| format!("Unexpected argument '{}'", arg))?;
+        let value = args.next().ok_or_else(|| format!("--{} needs a value", key))?;
+        flags.insert(key.to_string(), value.clone());
+    }
+    Ok(flags)
+}
+
+fn parse_ids(list: &str) -> Result<Vec<u32>, String> {
+    list.split(',')
+        .map(|id| id.trim().parse().map_err(|_| format!("Invalid id '{}'", id.trim())))
+        .collect()
+}
@@ -0,0 +1,100 @@
+//! Numeric columns of Parquet and Arrow IPC files.
+//!
+//! These files are often far too large to hold in memory whole, so an import
+//! reads only the schema, and a column is read on its own when it is picked.
+//! Integer, decimal and floating-point columns are offered; nulls and
+//! non-finite values are skipped and counted like blank CSV cells.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::FileReader;
+use arrow_schema::{ArrowError, DataType, SchemaRef};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+
+use crate::data::Dataset;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Parquet,
+    /// The Arrow IPC file format, also written as Feather v2
+    ArrowIpc,
+}
+
+impl Format {
+    /// Recognize a file by its leading magic bytes; None for anything else, e.g. text
+    pub fn detect(path: &Path) -> Option<Format> {
+        let mut magic = [0; 6];
+        File::open(path).ok()?.read_exact(&mut magic).ok()?;
+        if magic.starts_with(b"PAR1") {
+            Some(Format::Parquet)
+        } else if &magic == b"ARROW1" {
+            Some(Format::ArrowIpc)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Parquet => "Parquet",
+            Format::ArrowIpc => "Arrow",
+        }
+    }
+}
+
+/// Names of the numeric columns, the ones that can be imported
+pub fn numeric_columns(path: &Path, format: Format) -> Result<Vec<String>, String> {
+    let schema = match format {
+        Format::Parquet => ParquetRecordBatchReaderBuilder::try_new(open(path)?).map_err(|e| e.to_string())?.schema().clone(),
+        Format::ArrowIpc => FileReader::try_new(open(path)?, None).map_err(|e| e.to_string())?.schema(),
+    };
+    Ok(schema.fields()
+        .iter()
+        .filter(|field| field.data_type().is_numeric())
+        .map(|field| field.name().clone())
+        .collect())
+}
+
+/// Values of the column named `column`; `source` names the file
+pub fn read_column(path: &Path, format: Format, column: &str, source: &str) -> Result<Dataset, String> {
+    let index = |schema: &SchemaRef| schema.index_of(column).map_err(|_| format!("{} has no column '{}'", source, column));
+    let batches: Box<dyn Iterator<Item = Result<RecordBatch, ArrowError>>> = match format {
+        Format::Parquet => {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(open(path)?).map_err(|e| e.to_string())?;
+            // Only the chosen column is decoded
+            let mask = ProjectionMask::roots(builder.parquet_schema(), [index(builder.schema())?]);
+            Box::new(builder.with_projection(mask).build().map_err(|e| e.to_string())?)
+        }
+        Format::ArrowIpc => {
+            let schema = FileReader::try_new(open(path)?, None).map_err(|e| e.to_string())?.schema();
+            Box::new(FileReader::try_new(open(path)?, Some(vec![index(&schema)?])).map_err(|e| e.to_string())?)
+        }
+    };
+
+    let mut values = Vec::new();
+    let mut skipped = 0;
+    for batch in batches {
+        let batch = batch.map_err(|e| format!("Cannot read {}: {}", source, e))?;
+        let floats = arrow_cast::cast(batch.column(0), &DataType::Float64).map_err(|e| e.to_string())?;
+        for value in floats.as_primitive::<Float64Type>() {
+            match value {
+                Some(value) if value.is_finite() => values.push(value),
+                _ => skipped += 1,
+            }
+        }
+    }
+    if values.len() < 2 {
+        return Err(format!("Column '{}' of {} has fewer than two numbers", column, source));
+    }
+    Ok(Dataset { name: format!("{}: {}", source, column), values, skipped })
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    File::open(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+}
@@ -0,0 +1,505 @@
+//! Observed samples imported from delimited text files.
+//!
+//! A CSV file is read once and kept as text, so switching the column does not
+//! touch the disk again. Cells that are not numbers (blanks, "NA", a header
+//! row) are skipped and counted rather than rejected.
+
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+
+/// One numeric column of an imported file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    /// "file.csv: column", shown in the panel and the legend
+    pub name: String,
+    pub values: Vec<f64>,
+    /// Rows whose cell in the column was missing or not a number
+    pub skipped: usize,
+}
+
+impl Dataset {
+    pub fn mean(&self) -> f64 {
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    /// Sample standard deviation, with the n − 1 denominator
+    pub fn std_dev(&self) -> f64 {
+        let mean = self.mean();
+        let squares: f64 = self.values.iter().map(|x| (x - mean).powi(2)).sum();
+        (squares / (self.values.len() as f64 - 1.0)).sqrt()
+    }
+
+    pub fn range(&self) -> (f64, f64) {
+        self.values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lower, upper), &x| (lower.min(x), upper.max(x)))
+    }
+
+    /// Values in increasing order
+    pub fn sorted(&self) -> Vec<f64> {
+        let mut sorted = self.values.clone();
+        sorted.sort_by(f64::total_cmp);
+        sorted
+    }
+}
+
+/// Field separator of a file: whichever of comma, semicolon and tab the
+/// first line holds most of (comma when it holds none)
+pub fn detect_delimiter(text: &str) -> char {
+    let first = text.lines().next().unwrap_or("");
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&delimiter| (first.matches(delimiter).count(), delimiter == ','))
+        .unwrap()
+}
+
+/// Fields of one line; double quotes group a field and "" is a literal quote
+pub fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Column names from the header row, or "Column 1", "Column 2", … when the
+/// first row is all numbers
+pub fn column_names(text: &str) -> Vec<String> {
+    let delimiter = detect_delimiter(text);
+    let Some(first) = text.lines().find(|line| !line.trim().is_empty()) else {
+        return Vec::new();
+    };
+    let fields = split_fields(first, delimiter);
+    if has_header(&fields) {
+        fields
+    } else {
+        (1..=fields.len()).map(|n| format!("Column {}", n)).collect()
+    }
+}
+
+fn has_header(fields: &[String]) -> bool {
+    fields.iter().any(|field| !field.is_empty() && field.parse::<f64>().is_err())
+}
+
+/// Numeric values of column `column` (0-based); `source` names the file
+pub fn parse_column(text: &str, column: usize, source: &str) -> Result<Dataset, String> {
+    let delimiter = detect_delimiter(text);
+    let names = column_names(text);
+    let name = names.get(column).ok_or_else(|| format!("{} has no column {}", source, column + 1))?;
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty()).peekable();
+    if lines.peek().is_some_and(|line| has_header(&split_fields(line, delimiter))) {
+        lines.next();
+    }
+
+    let mut values = Vec::new();
+    let mut skipped = 0;
+    for line in lines {
+        match split_fields(line, delimiter).get(column).and_then(|cell| cell.parse::<f64>().ok()) {
+            Some(value) if value.is_finite() => values.push(value),
+            _ => skipped += 1,
+        }
+    }
+    if values.len() < 2 {
+        return Err(format!("Column '{}' of {} has fewer than two numbers", name, source));
+    }
+    Ok(Dataset { name: format!("{}: {}", source, name), values, skipped })
+}
+
+/// Equal-width bins on a grid anchored at 0, so changing the data keeps bin edges aligned
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// Left edge of the first bin
+    pub start: f64,
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+    pub total: usize,
+}
+
+impl Histogram {
+    pub fn new(values: &[f64], bin_width: f64) -> Self {
+        let (lower, upper) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lower, upper), &x| (lower.min(x), upper.max(x)));
+        if values.is_empty() || bin_width <= 0.0 || !bin_width.is_finite() {
+            return Self { start: 0.0, bin_width, counts: Vec::new(), total: 0 };
+        }
+        let first = (lower / bin_width).floor();
+        // Every value lands in a bin, the maximum included
+        let bins = ((upper / bin_width).floor() - first) as usize + 1;
+        let mut counts = vec![0; bins];
+        for &x in values {
+            let bin = ((x / bin_width).floor() - first) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Self { start: first * bin_width, bin_width, counts, total: values.len() }
+    }
+
+    /// (left edge, right edge, density) of each bin; the densities integrate to 1
+    pub fn densities(&self) -> Vec<(f64, f64, f64)> {
+        let scale = 1.0 / (self.total as f64 * self.bin_width);
+        self.counts.iter()
+            .enumerate()
+            .map(|(bin, &count)| {
+                let left = self.start + self.bin_width * bin as f64;
+                (left, left + self.bin_width, count as f64 * scale)
+            })
+            .collect()
+    }
+}
+
+/// Sample quantile of sorted values, interpolating linearly between order statistics
+pub fn sample_quantile(sorted: &[f64], p: f64) -> f64 {
+    let position = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (index, fraction) = (position.floor() as usize, position.fract());
+    sorted[index] + (sorted[(index + 1).min(sorted.len() - 1)] - sorted[index]) * fraction
+}
+
+/// Corners of the step function F̂(x) = #{xᵢ ≤ x} / n over increasing `sorted`
+/// values, as a polyline from height 0 at `lower` to 1 at `upper`. Tied values
+/// make a single taller step.
+pub fn ecdf_steps(sorted: &[f64], lower: f64, upper: f64) -> Vec<[f64; 2]> {
+    let (Some(&first), Some(&last)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+    let n = sorted.len() as f64;
+    let mut points = vec![[lower.min(first), 0.0]];
+    let mut index = 0;
+    while index < sorted.len() {
+        let x = sorted[index];
+        let next = index + sorted[index..].partition_point(|&value| value <= x);
+        points.push([x, index as f64 / n]);
+        points.push([x, next as f64 / n]);
+        index = next;
+    }
+    points.push([upper.max(last), 1.0]);
+    points
+}
+
+fn interquartile_range(sorted: &[f64]) -> f64 {
+    sample_quantile(sorted, 0.75) - sample_quantile(sorted, 0.25)
+}
+
+/// Freedman–Diaconis bin width 2 IQR / ∛n, falling back to Scott's rule
+/// 3.49 s / ∛n when the interquartile range is zero
+pub fn default_bin_width(dataset: &Dataset) -> f64 {
+    let sorted = dataset.sorted();
+    let cube_root = (sorted.len() as f64).cbrt();
+    let iqr = interquartile_range(&sorted);
+    let width = if iqr > 0.0 { 2.0 * iqr / cube_root } else { 3.49 * dataset.std_dev() / cube_root };
+    if width > 0.0 && width.is_finite() { width } else { 1.0 }
+}
+
+/// Silverman's rule of thumb h = 0.9 min(s, IQR / 1.34) n^(−1/5) for a
+/// Gaussian kernel; `sorted` must be in increasing order
+pub fn silverman_bandwidth(sorted: &[f64]) -> f64 {
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().sum::<f64>() / n;
+    let sd = (sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let spread = match interquartile_range(sorted) / 1.34 {
+        iqr if iqr > 0.0 => sd.min(iqr),
+        _ => sd,
+    };
+    let bandwidth = 0.9 * spread * n.powf(-0.2);
+    if bandwidth > 0.0 && bandwidth.is_finite() { bandwidth } else { 1.0 }
+}
+
+/// Kernels further than this many bandwidths away are left out of the sum
+const KERNEL_REACH: f64 = 6.0;
+
+/// Gaussian kernel density estimate f̂(x) = 1/(nh) Σ φ((x − xᵢ)/h) over
+/// increasing `sorted` samples
+pub fn kernel_density(sorted: &[f64], bandwidth: f64, x: f64) -> f64 {
+    let first = sorted.partition_point(|&sample| sample < x - KERNEL_REACH * bandwidth);
+    let last = sorted.partition_point(|&sample| sample <= x + KERNEL_REACH * bandwidth);
+    let sum: f64 = sorted[first..last].iter()
+        .map(|&sample| {
+            let z = (x - sample) / bandwidth;
+            (-0.5 * z * z).exp()
+        })
+        .sum();
+    sum / (sorted.len() as f64 * bandwidth * (2.0 * std::f64::consts::PI).sqrt())
+}
+
+/// Maximum-likelihood normal fit with its asymptotic standard errors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalFit {
+    pub mean: f64,
+    /// √(Σ (xᵢ − μ̂)² / n), the biased maximum-likelihood estimate
+    pub std_dev: f64,
+    /// σ̂ / √n
+    pub mean_se: f64,
+    /// σ̂ / √(2n)
+    pub std_dev_se: f64,
+    pub log_likelihood: f64,
+}
+
+pub fn normal_mle(values: &[f64]) -> NormalFit {
+    let n = values.len() as f64;
+    let (mean, variance) = moments(values);
+    let std_dev = variance.sqrt();
+    NormalFit {
+        mean,
+        std_dev,
+        mean_se: std_dev / n.sqrt(),
+        std_dev_se: std_dev / (2.0 * n).sqrt(),
+        log_likelihood: -0.5 * n * ((2.0 * std::f64::consts::PI * variance).ln() + 1.0),
+    }
+}
+
+/// Sample mean and variance Σ (xᵢ − x̄)² / n, the first two moments matched
+/// by method-of-moments fits
+pub fn moments(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (mean, values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n)
+}
+
+/// Gaussian mixture fitted by expectation–maximization, components in
+/// increasing order of their means
+#[derive(Clone, Debug, PartialEq)]
+pub struct MixtureFit {
+    pub weights: Vec<f64>,
+    pub means: Vec<f64>,
+    pub std_devs: Vec<f64>,
+    pub log_likelihood: f64,
+    /// Bayesian information criterion (3k − 1) ln n − 2 ln L; lower is better
+    pub bic: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+const EM_MAX_ITERATIONS: usize = 1000;
+
+/// Relative change of the log-likelihood at which EM stops
+const EM_TOLERANCE: f64 = 1e-10;
+
+/// k-component Gaussian mixture by EM, started from equal weights, means at
+/// evenly spaced sample quantiles and the overall variance. Variances are
+/// floored at a millionth of the sample variance so that no component can
+/// collapse onto a single value.
+pub fn gaussian_mixture_em(values: &[f64], k: usize) -> Result<MixtureFit, String> {
+    let n = values.len();
+    if k == 0 {
+        return Err("A mixture needs at least one component".to_string());
+    }
+    if n < 2 * k {
+        return Err(format!("{} components need at least {} values", k, 2 * k));
+    }
+    let (_, variance) = moments(values);
+    if variance <= 0.0 || !variance.is_finite() {
+        return Err("The data has no spread to fit".to_string());
+    }
+    let floor = 1e-6 * variance;
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mut weights = vec![1.0 / k as f64; k];
+    let mut means: Vec<f64> = (0..k).map(|j| sample_quantile(&sorted, (j as f64 + 0.5) / k as f64)).collect();
+    let mut variances = vec![variance; k];
+
+    let mut responsibilities = vec![0.0; n * k];
+    let mut log_likelihood = f64::NEG_INFINITY;
+    let (mut iterations, mut converged) = (0, false);
+    while iterations < EM_MAX_ITERATIONS && !converged {
+        iterations += 1;
+        // E step: each value's share in every component
+        let mut total = 0.0;
+        for (&x, row) in values.iter().zip(responsibilities.chunks_mut(k)) {
+            let log_density = component_log_terms(x, &weights, &means, &variances, row);
+            for share in row.iter_mut() {
+                *share = (*share - log_density).exp();
+            }
+            total += log_density;
+        }
+        // M step: weighted moments of every component
+        for j in 0..k {
+            let mass: f64 = responsibilities.chunks(k).map(|row| row[j]).sum();
+            weights[j] = mass / n as f64;
+            if mass > 0.0 {
+                means[j] = values.iter().zip(responsibilities.chunks(k)).map(|(x, row)| row[j] * x).sum::<f64>() / mass;
+                let spread = values.iter().zip(responsibilities.chunks(k)).map(|(x, row)| row[j] * (x - means[j]).powi(2)).sum::<f64>();
+                variances[j] = (spread / mass).max(floor);
+            }
+        }
+        converged = (total - log_likelihood).abs() <= EM_TOLERANCE * total.abs().max(1.0);
+        log_likelihood = total;
+    }
+    // Likelihood of the final parameters, one M step past the last E step
+    let mut terms = vec![0.0; k];
+    let log_likelihood: f64 = values.iter().map(|&x| component_log_terms(x, &weights, &means, &variances, &mut terms)).sum();
+
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by(|&a, &b| means[a].total_cmp(&means[b]));
+    Ok(MixtureFit {
+        weights: order.iter().map(|&j| weights[j]).collect(),
+        means: order.iter().map(|&j| means[j]).collect(),
+        std_devs: order.iter().map(|&j| variances[j].sqrt()).collect(),
+        log_likelihood,
+        bic: (3 * k - 1) as f64 * (n as f64).ln() - 2.0 * log_likelihood,
+        iterations,
+        converged,
+    })
+}
+
+/// Fill `terms` with ln(wⱼ φ(x; μⱼ, σⱼ²)) and return their log-sum-exp, the
+/// log of the mixture density at x; in logs so far-out values do not underflow
+fn component_log_terms(x: f64, weights: &[f64], means: &[f64], variances: &[f64], terms: &mut [f64]) -> f64 {
+    for (j, term) in terms.iter_mut().enumerate() {
+        *term = weights[j].ln() - 0.5 * ((2.0 * std::f64::consts::PI * variances[j]).ln() + (x - means[j]).powi(2) / variances[j]);
+    }
+    let largest = terms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    largest + terms.iter().map(|term| (term - largest).exp()).sum::<f64>().ln()
+}
+
+/// One-sample Kolmogorov–Smirnov test against a fully specified CDF
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KsTest {
+    /// D = sup |Fₙ(x) − F(x)|
+    pub statistic: f64,
+    /// Where the largest gap is, and the empirical and theoretical CDFs on
+    /// the side of the step where it occurs
+    pub location: f64,
+    pub empirical: f64,
+    pub theoretical: f64,
+    /// Asymptotic Kolmogorov distribution with Stephens' small-sample correction
+    pub p_value: f64,
+}
+
+pub fn ks_test(sorted: &[f64], cdf: impl Fn(f64) -> f64) -> KsTest {
+    let n = sorted.len() as f64;
+    let mut test = KsTest { statistic: 0.0, location: sorted[0], empirical: 0.0, theoretical: 0.0, p_value: 1.0 };
+    for (i, &x) in sorted.iter().enumerate() {
+        let theoretical = cdf(x);
+        // The ECDF jumps from i/n to (i + 1)/n at x; both sides count
+        for empirical in [i as f64 / n, (i + 1) as f64 / n] {
+            let gap = (empirical - theoretical).abs();
+            if gap > test.statistic {
+                test = KsTest { statistic: gap, location: x, empirical, theoretical, p_value: 1.0 };
+            }
+        }
+    }
+    let root = n.sqrt();
+    test.p_value = kolmogorov_survival((root + 0.12 + 0.11 / root) * test.statistic);
+    test
+}
+
+/// P(K > λ) = 2 Σ (−1)^(k−1) exp(−2k²λ²) for the Kolmogorov distribution
+fn kolmogorov_survival(lambda: f64) -> f64 {
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let sum: f64 = (1..=100)
+        .map(|k| {
+            let k = k as f64;
+            let sign = if k % 2.0 == 1.0 { 1.0 } else { -1.0 };
+            sign * (-2.0 * k * k * lambda * lambda).exp()
+        })
+        .sum();
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Anderson–Darling test against a fully specified CDF, weighting the tails
+/// more than Kolmogorov–Smirnov does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AndersonDarlingTest {
+    /// A² = −n − (1/n) Σ (2i − 1) [ln F(xᵢ) + ln(1 − F(x₍ₙ₊₁₋ᵢ₎))]
+    pub statistic: f64,
+    /// From the limiting distribution (Marsaglia & Marsaglia, 2004)
+    pub p_value: f64,
+}
+
+pub fn anderson_darling_test(sorted: &[f64], cdf: impl Fn(f64) -> f64) -> AndersonDarlingTest {
+    // Probabilities of exactly 0 or 1 would make the logarithms infinite
+    let levels: Vec<f64> = sorted.iter().map(|&x| cdf(x).clamp(1e-300, 1.0 - 1e-16)).collect();
+    let n = levels.len();
+    let sum: f64 = (0..n)
+        .map(|i| (2 * i + 1) as f64 * (levels[i].ln() + (1.0 - levels[n - 1 - i]).ln()))
+        .sum();
+    let statistic = -(n as f64) - sum / n as f64;
+    AndersonDarlingTest { statistic, p_value: 1.0 - anderson_darling_limit(statistic) }
+}
+
+/// Limiting CDF of A² (Marsaglia & Marsaglia, "Evaluating the Anderson–Darling distribution")
+fn anderson_darling_limit(z: f64) -> f64 {
+    if z <= 0.0 {
+        return 0.0;
+    }
+    if z < 2.0 {
+        (-1.2337141 / z).exp() / z.sqrt()
+            * (2.00012 + (0.247105 - (0.0649821 - (0.0347962 - (0.011672 - 0.00168691 * z) * z) * z) * z) * z)
+    } else {
+        (-(1.0776 - (2.30695 - (0.43424 - (0.082433 - (0.008056 - 0.0003146 * z) * z) * z) * z) * z).exp()).exp()
+    }
+}
+
+/// Observed against expected count in one histogram bin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResidualBin {
+    pub left: f64,
+    pub right: f64,
+    pub observed: usize,
+    /// n (F(right) − F(left)), with the tails beyond the outer bins included in them
+    pub expected: f64,
+    /// Pearson residual (O − E)/√E; not finite when E is 0
+    pub residual: f64,
+}
+
+pub fn binned_residuals(histogram: &Histogram, cdf: impl Fn(f64) -> f64) -> Vec<ResidualBin> {
+    let n = histogram.total as f64;
+    let last = histogram.counts.len().saturating_sub(1);
+    histogram.counts.iter()
+        .enumerate()
+        .map(|(bin, &observed)| {
+            let left = histogram.start + histogram.bin_width * bin as f64;
+            let right = left + histogram.bin_width;
+            let lower = if bin == 0 { 0.0 } else { cdf(left) };
+            let upper = if bin == last { 1.0 } else { cdf(right) };
+            let expected = n * (upper - lower).max(0.0);
+            ResidualBin { left, right, observed, expected, residual: (observed as f64 - expected) / expected.sqrt() }
+        })
+        .collect()
+}
+
+/// Pearson chi-square test on binned counts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChiSquareTest {
+    /// Σ (O − E)²/E over the pooled bins
+    pub statistic: f64,
+    /// Pooled bins − 1 − fitted parameters
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+    /// Bins left after pooling neighbours up to the minimum expected count
+    pub pooled_bins: usize,
+}
+
+/// Smallest expected count a bin is tested with; smaller ones are pooled
+pub const MIN_EXPECTED_COUNT: f64 = 5.0;
+
+/// Chi-square test of `bins` after pooling neighbours, left to right, until
+/// each expects at least MIN_EXPECTED_COUNT; None when no degrees of freedom remain
+pub fn chi_square_test(bins: &[ResidualBin], fitted_parameters: usize) -> Option<ChiSquareTest> {
+    let mut pooled: Vec<(f64, f64)> = Vec::new();
+    let mut pending = (0.0, 0.0);
+    for bin in bins {
+        pending = (pending.0 + bin.observed as f64, pending.1 + bin.expected);
+        if pending.1 >= MIN_EXPECTED_COUNT {
+            pooled.push(pending);
+            pending = (0.0, 0.0);
+        }
+    }
+    // A short remainder joins the last pooled bin
+    match pooled.last_mut() {
+        Some(last) => *last = (last.0 + pending.0, last.1 + pending.1),
+        None => return None,
+    }
+    let degrees_of_freedom = pooled.len().checked_sub(1 + fitted_parameters).filter(|&df| df > 0)?;
+    let statistic: f64 = pooled.iter().map(|&(observed, expected)| (observed - expected).powi(2) / expected).sum();
+    let distribution = ChiSquared::new(degrees_of_freedom as f64).ok()?;
+    Some(ChiSquareTest { statistic, degrees_of_freedom, p_value: distribution.sf(statistic), pooled_bins: pooled.len() })
+}
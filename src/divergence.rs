@@ -0,0 +1,134 @@
+//! Divergences and distances between two distributions of the viewer.
+//!
+//! Gaussian pairs use closed forms; anything else (numeric grids, restricted
+//! domains) is integrated numerically over the relevant supports. A density
+//! clipped to a domain keeps only part of its mass, so it is renormalized to
+//! that domain first: the metrics compare X | X ∈ domain, a true distribution.
+
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::GaussianDistribution;
+
+/// Subintervals used by the numerical integrals (Simpson's rule)
+const INTERVALS: usize = 4000;
+
+/// Probability levels used by the quantile integral of the Wasserstein distance
+const QUANTILE_LEVELS: usize = 2000;
+
+/// D_KL(P‖Q) = ∫ p ln(p/q) dx, in nats. Infinite when P puts mass where Q has none.
+pub fn kl_divergence(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    if p.has_closed_form() && q.has_closed_form() {
+        // ln(σq/σp) + (σp² + (μp − μq)²) / (2σq²) − ½
+        return (q.std_dev / p.std_dev).ln()
+            + (p.std_dev.powi(2) + (p.mean - q.mean).powi(2)) / (2.0 * q.std_dev.powi(2))
+            - 0.5;
+    }
+
+    let (lower, upper) = p.support();
+    let (density_of_p, density_of_q) = (normalized_density(p), normalized_density(q));
+    let mut infinite = false;
+    let divergence = integrate(lower, upper, |x| {
+        let density_p = density_of_p(x);
+        if density_p <= 0.0 {
+            return 0.0;
+        }
+        let density_q = density_of_q(x);
+        if density_q <= 0.0 {
+            infinite = true;
+            return 0.0;
+        }
+        density_p * (density_p / density_q).ln()
+    });
+    if infinite {
+        f64::INFINITY
+    } else {
+        // Quadrature noise can push a tiny true value below zero
+        divergence.max(0.0)
+    }
+}
+
+/// Hellinger distance H(P, Q) = √(1 − BC), with BC = ∫ √(p q) dx the
+/// Bhattacharyya coefficient. Symmetric and bounded in [0, 1].
+pub fn hellinger_distance(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    (1.0 - bhattacharyya_coefficient(p, q)).max(0.0).sqrt()
+}
+
+/// Total variation distance TV(P, Q) = ½ ∫ |p − q| dx, within [0, 1].
+/// The shared mass ∫ min(p, q) dx is 1 − TV.
+pub fn total_variation_distance(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    let (p_lower, p_upper) = p.support();
+    let (q_lower, q_upper) = q.support();
+    let (density_p, density_q) = (normalized_density(p), normalized_density(q));
+    let difference = integrate(p_lower.min(q_lower), p_upper.max(q_upper), |x| (density_p(x) - density_q(x)).abs());
+    (0.5 * difference).clamp(0.0, 1.0)
+}
+
+/// Earth-mover distance W₁(P, Q) = ∫₀¹ |F_P⁻¹(u) − F_Q⁻¹(u)| du, in units of x.
+/// Finite even for disjoint supports, where it is the distance mass has to travel.
+pub fn wasserstein_distance(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    if p.has_closed_form() && q.has_closed_form() {
+        // E|Δμ + Δσ Z| for Z ~ N(0, 1), the mean of a folded normal
+        let (shift, spread) = (p.mean - q.mean, (p.std_dev - q.std_dev).abs());
+        if spread == 0.0 {
+            return shift.abs();
+        }
+        let standard = Normal::new(0.0, 1.0).unwrap();
+        return spread * (2.0 / std::f64::consts::PI).sqrt() * (-shift * shift / (2.0 * spread * spread)).exp()
+            + shift * (1.0 - 2.0 * standard.cdf(-shift / spread));
+    }
+
+    // Midpoint rule in u, which keeps clear of the infinite quantiles at 0 and 1
+    (0..QUANTILE_LEVELS)
+        .map(|k| {
+            let u = (k as f64 + 0.5) / QUANTILE_LEVELS as f64;
+            (p.quantile(u) - q.quantile(u)).abs()
+        })
+        .sum::<f64>()
+        / QUANTILE_LEVELS as f64
+}
+
+/// Bhattacharyya coefficient BC(P, Q) = ∫ √(p q) dx: 1 for identical and 0
+/// for disjoint distributions
+pub fn bhattacharyya_coefficient(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    if p.has_closed_form() && q.has_closed_form() {
+        // √(2σpσq / (σp² + σq²)) · exp(−(μp − μq)² / (4(σp² + σq²)))
+        let variance_sum = p.std_dev.powi(2) + q.std_dev.powi(2);
+        return (2.0 * p.std_dev * q.std_dev / variance_sum).sqrt()
+            * (-(p.mean - q.mean).powi(2) / (4.0 * variance_sum)).exp();
+    }
+
+    // √(p q) vanishes outside the overlap of the supports
+    let (p_lower, p_upper) = p.support();
+    let (q_lower, q_upper) = q.support();
+    let (density_p, density_q) = (normalized_density(p), normalized_density(q));
+    integrate(p_lower.max(q_lower), p_upper.min(q_upper), |x| (density_p(x) * density_q(x)).sqrt())
+        .clamp(0.0, 1.0)
+}
+
+/// Bhattacharyya distance D_B = −ln BC, infinite for disjoint distributions.
+/// It bounds the Bayes error of telling P from Q with equal priors: ≤ ½ exp(−D_B).
+pub fn bhattacharyya_distance(p: &GaussianDistribution, q: &GaussianDistribution) -> f64 {
+    -bhattacharyya_coefficient(p, q).ln()
+}
+
+/// Density of `dist` divided by its mass inside the domain, so that it integrates
+/// to 1; zero everywhere when the domain holds no mass
+fn normalized_density(dist: &GaussianDistribution) -> impl Fn(f64) -> f64 + '_ {
+    let mass = dist.domain.map_or(1.0, |_| dist.cdf(f64::INFINITY));
+    move |x| if mass > 0.0 { dist.evaluate(x) / mass } else { 0.0 }
+}
+
+/// Composite Simpson's rule over [lower, upper]
+fn integrate(lower: f64, upper: f64, mut f: impl FnMut(f64) -> f64) -> f64 {
+    if upper <= lower {
+        return 0.0;
+    }
+    let step = (upper - lower) / INTERVALS as f64;
+    let interior: f64 = (1..INTERVALS)
+        .map(|i| {
+            let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+            weight * f(lower + step * i as f64)
+        })
+        .sum();
+    (f(lower) + interior + f(upper)) * step / 3.0
+}
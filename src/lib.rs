@@ -0,0 +1,10564 @@
+//! Interactive explorer for probability densities and the distributions
+//! derived from them. The crate root holds the engine (distributions,
+//! derivations, sessions, exports); `ui` draws the viewer on top of it and
+//! `cli` runs batch subcommands on it.
+
+use eframe::egui;
+use egui_plot::PlotPoints;
+use statrs::distribution::{Normal, Continuous, ContinuousCDF};
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+mod bivariate;
+mod bootstrap;
+pub mod cli;
+mod columnar;
+mod data;
+mod divergence;
+mod raster;
+mod sampling;
+mod session;
+mod spec;
+mod stream;
+mod sync;
+mod ui;
+mod updates;
+
+pub use ui::run_gui;
+
+#[derive(Default)]
+struct PdfViewerApp {
+    distributions: HashMap<u32, GaussianDistribution>,
+    next_id: u32,
+    selected_for_multiplication: Vec<u32>,
+    plot_bounds: Option<egui_plot::PlotBounds>,
+    show_shading: bool,
+    shading_opacity: f32,
+    show_std_markers: bool,
+    curve_coloring: CurveColoring,
+    // Draw products and posteriors at the amplitude of the raw product S × density
+    show_product_amplitude: bool,
+    resolution: PlotResolution,
+    point_cache: PointCache,
+    // Bounds to push into the plot on the next frame (auto-fit)
+    requested_bounds: Option<egui_plot::PlotBounds>,
+    reset_plot: bool,
+    show_cheat_sheet: bool,
+    cheat_sheet_export: TextExport,
+    // Welcome screen with the example gallery; thumbnails are sampled once
+    show_welcome: bool,
+    gallery_thumbnails: Option<Vec<Vec<Vec<[f64; 2]>>>>,
+    templates: TemplateLibrary,
+    bayes_prior: Option<u32>,
+    bayes_likelihood: Option<u32>,
+    condition_target: Option<u32>,
+    condition_interval: Option<(f64, f64)>,
+    // While set, primary-button drags on the plot select an interval instead of panning
+    selecting_interval: bool,
+    interval_drag_start: Option<f64>,
+    // Endpoint of the interval (0 = a, 1 = b) being dragged, and whether the
+    // pointer was over a handle last frame (which suspends plot panning)
+    dragged_handle: Option<usize>,
+    handle_hovered: bool,
+    // On-plot μ and σ handles of the input distributions, and the one being dragged
+    parameter_handles: bool,
+    // Fill between two curves, coloured by whichever is on top
+    shade_between: bool,
+    between: (Option<u32>, Option<u32>),
+    // Marker lists being edited, by distribution id, until they parse
+    marker_text: HashMap<u32, String>,
+    // Numerator and denominator of the likelihood ratio subplot
+    ratio_pair: (Option<u32>, Option<u32>),
+    dragged_parameter: Option<(u32, ParameterHandle)>,
+    // Report P(a ≤ X ≤ b) for every curve and shade only [a, b]
+    show_interval_probability: bool,
+    auto_export: AutoExport,
+    data: DataImport,
+    live_stream: LiveStream,
+    transform_target: Option<u32>,
+    transform: Transform,
+    custom_transform: String,
+    temper_target: Option<u32>,
+    temper_beta: f64,
+    sum_target: Option<u32>,
+    sum_copies: u32,
+    sum_average: bool,
+    sync: Option<sync::SyncSession>,
+    sync_port: u16,
+    sync_interface: String,
+    sync_address: String,
+    sync_error: Option<String>,
+    // Join was clicked with distributions that joining would discard
+    sync_join_pending: bool,
+    follow_presenter: bool,
+    // Fixed [x_min, x_max, y_min, y_max]; pan and zoom are disabled while set
+    view_lock: Option<[f64; 4]>,
+    view_input: [f64; 4],
+    // Export each curve over its whole support instead of the current view
+    export_full_curves: bool,
+    importance: ImportanceTool,
+    sampler: SampleTool,
+    png_export: PngExportTool,
+    tikz_export: TextExport,
+    python_export: TextExport,
+    r_export: TextExport,
+    report_export: TextExport,
+    monte_carlo: MonteCarloTool,
+    joint: JointTool,
+    sweep: ParameterSweep,
+    timeline: Timeline,
+    history: History,
+    workspaces: Workspaces,
+    model_spec: ModelSpecTool,
+    share: ShareTool,
+    comparison: ComparisonTool,
+    latex_theme: LatexTheme,
+    // Plot every curve against z = (x − μ_ref) / σ_ref of this distribution
+    z_reference: Option<u32>,
+    quantile_target: Option<u32>,
+    quantile_p: f64,
+    show_quantile_marker: bool,
+    pvalue_target: Option<u32>,
+    pvalue_x: f64,
+    pvalue_tail: Tail,
+    groups: Vec<DistributionGroup>,
+    group_name: String,
+    reference_lines: Vec<ReferenceLine>,
+    annotations: Vec<TextAnnotation>,
+    theme: PlotTheme,
+    // Replaces the theme's curve colours when not empty
+    custom_palette: Vec<[u8; 3]>,
+    // Theme last pushed to the egui context
+    applied_theme: Option<PlotTheme>,
+    axis_style: AxisStyle,
+    // Factor taking secondary-axis curves to primary plot units, set each frame
+    secondary_scale: Option<f64>,
+    // Draw every density divided by its own maximum
+    peak_normalized: bool,
+    // Small multiples: one plot per group instead of per distribution, and
+    // the column count (0 picks a near-square grid)
+    small_multiples_by_group: bool,
+    small_multiples_columns: usize,
+    small_multiples_shared_y: bool,
+    // Violins: all scaled by the tallest density instead of each filling its row
+    violin_common_scale: bool,
+    // Annotation whose position (false) or arrow tip (true) the next plot click sets
+    placing_annotation: Option<(usize, bool)>,
+    credible_target: Option<u32>,
+    credible_level: f64,
+    plot_mode: PlotMode,
+    plot_layout: PlotLayout,
+    show_hover_values: bool,
+    // Crosshair snapped to the active distribution, with a corner readout
+    crosshair: bool,
+    crosshair_target: Option<u32>,
+    // Release check, run only when asked for
+    show_updates: bool,
+    updates: updates::UpdateStatus,
+    // Session file last saved or loaded, where the dialogs open next, and
+    // how that went
+    session_path: Option<std::path::PathBuf>,
+    session_status: Option<Result<String, String>>,
+    autosave: Autosave,
+    // Half-width of the t-range shown by the MGF / characteristic function views
+    transform_t_max: f64,
+    probability_plot: ProbabilityPlot,
+    zscore_target: Option<u32>,
+    // Raw value of the z-score panel; its z is derived from the chosen distribution
+    zscore_x: f64,
+    // Plot log10(y) instead of y
+    log_y: bool,
+}
+
+/// Affine map from data x to the plotted horizontal axis, z = (x − origin) / unit.
+/// Densities are multiplied by `unit` so every curve still has area 1 in z.
+/// With `log_y` the vertical axis shows log10(y); y ≤ 0 maps to −∞ and has
+/// to be left out of what is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AxisMap {
+    origin: f64,
+    unit: f64,
+    log_y: bool,
+}
+
+impl AxisMap {
+    const IDENTITY: AxisMap = AxisMap { origin: 0.0, unit: 1.0, log_y: false };
+
+    /// Decades shown below the top of the view when a linear view starting at
+    /// y ≤ 0 switches to log
+    const LOG_DECADES: f64 = 6.0;
+
+    fn with_log_y(self, log_y: bool) -> Self {
+        AxisMap { log_y, ..self }
+    }
+
+    fn to_view(self, x: f64) -> f64 {
+        (x - self.origin) / self.unit
+    }
+
+    fn to_data(self, z: f64) -> f64 {
+        self.origin + self.unit * z
+    }
+
+    /// Vertical plot coordinate of a value that is not rescaled with x, e.g. a probability
+    fn y_to_view(self, y: f64) -> f64 {
+        match self.log_y {
+            true if y > 0.0 => y.log10(),
+            true => f64::NEG_INFINITY,
+            false => y,
+        }
+    }
+
+    fn y_to_data(self, y: f64) -> f64 {
+        if self.log_y { 10f64.powf(y) } else { y }
+    }
+
+    fn view_point(self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [self.to_view(x), self.y_to_view(self.unit * y)]
+    }
+
+    fn view_bounds(self, [x_min, x_max, y_min, y_max]: [f64; 4]) -> [f64; 4] {
+        let top = self.y_to_view(self.unit * y_max);
+        let top = if top.is_finite() { top } else { 0.0 };
+        let bottom = self.y_to_view(self.unit * y_min);
+        let bottom = if bottom.is_finite() { bottom } else { top - Self::LOG_DECADES };
+        [self.to_view(x_min), self.to_view(x_max), bottom, top]
+    }
+
+    fn data_bounds(self, [z_min, z_max, y_min, y_max]: [f64; 4]) -> [f64; 4] {
+        [self.to_data(z_min), self.to_data(z_max), self.y_to_data(y_min) / self.unit, self.y_to_data(y_max) / self.unit]
+    }
+}
+
+/// Re-export of the curves and statistics to fixed files, for dashboards
+/// that pick them up from disk
+#[derive(Default)]
+struct AutoExport {
+    enabled: bool,
+    directory: String,
+    trigger: ExportTrigger,
+    last_export: Option<std::time::Instant>,
+    // What was exported last, so on-change mode skips identical frames
+    last_fingerprint: Option<u64>,
+    exports: u32,
+    error: Option<String>,
+}
+
+const UNDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const REDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::Z);
+
+/// Autosave slots in the per-user autosave folder. Each running viewer holds
+/// the lock of one, so instances never write or recover each other's session.
+const AUTOSAVE_SLOTS: usize = 16;
+
+/// Seconds between autosaves of a changed session
+const AUTOSAVE_SECONDS: u64 = 30;
+
+/// Periodic copy of the session for crash recovery. A clean exit removes the
+/// file, so one found at launch was left behind by a crash.
+#[derive(Default)]
+struct Autosave {
+    /// None until enabled at launch, so tests and batch runs never write it
+    path: Option<std::path::PathBuf>,
+    // Lock on the slot of `path`, held for as long as this viewer runs
+    lock: Option<std::fs::File>,
+    last_save: Option<std::time::Instant>,
+    // Hash of the session last written, so an unchanged session is not rewritten
+    last_fingerprint: Option<u64>,
+    /// Session left by an earlier run; autosave waits until it is restored or discarded
+    recovery: Option<Recovery>,
+    error: Option<String>,
+}
+
+struct Recovery {
+    json: String,
+    saved: Option<std::time::SystemTime>,
+    // Why restoring failed; the offer stays open so it can still be discarded
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum ExportTrigger {
+    /// Whenever a curve, name or the exported range changes
+    #[default]
+    OnChange,
+    Every { seconds: u32 },
+}
+
+/// Imported data file, the chosen column and its histogram and ECDF overlays
+#[derive(Default)]
+struct DataImport {
+    path: String,
+    // Where another column is read from when one is picked
+    source: Option<DataSource>,
+    columns: Vec<String>,
+    column: usize,
+    dataset: Option<data::Dataset>,
+    show_histogram: bool,
+    bin_width: f64,
+    // Step-function ECDF drawn over the curves of the CDF view
+    show_ecdf: bool,
+    fit_family: FitFamily,
+    fit_method: FitMethod,
+    // Distribution the data is tested against, and the KS and Anderson–Darling
+    // results for it
+    gof_target: Option<u32>,
+    goodness_of_fit: Option<(data::KsTest, data::AndersonDarlingTest)>,
+    // (target, its density fingerprint) behind `goodness_of_fit`
+    gof_computed_for: Option<(u32, u64)>,
+    // Binned observed and expected counts for the same target with their
+    // chi-square test, and the histogram bin width they were binned with
+    residuals: Option<(Vec<data::ResidualBin>, Option<data::ChiSquareTest>)>,
+    residuals_bin_width: Option<f64>,
+    // Rebuilt whenever the dataset or bin width changes
+    histogram: Option<data::Histogram>,
+    mixture: MixtureTool,
+    bootstrap: BootstrapTool,
+    error: Option<String>,
+}
+
+/// File behind the imported columns
+enum DataSource {
+    /// Delimited text, kept whole so another column can be picked without rereading
+    Text { name: String, text: String },
+    /// Parquet or Arrow file, read one column at a time
+    Columnar { name: String, path: std::path::PathBuf, format: columnar::Format },
+}
+
+/// Most components offered for a Gaussian mixture fit, and compared by BIC
+const MAX_MIXTURE_COMPONENTS: usize = 6;
+
+/// Gaussian mixture fits to the imported data
+struct MixtureTool {
+    components: usize,
+    // One fit per k, sorted by k, for the log-likelihood/BIC readout
+    comparison: Vec<data::MixtureFit>,
+}
+
+impl Default for MixtureTool {
+    fn default() -> Self {
+        Self { components: 2, comparison: Vec::new() }
+    }
+}
+
+impl MixtureTool {
+    fn record(&mut self, fit: data::MixtureFit) {
+        let k = fit.weights.len();
+        self.comparison.retain(|other| other.weights.len() != k);
+        let at = self.comparison.partition_point(|other| other.weights.len() < k);
+        self.comparison.insert(at, fit);
+    }
+}
+
+/// Name the live window goes by as the imported dataset
+const LIVE_STREAM_NAME: &str = "Live stream";
+
+/// Values streamed in over stdin, TCP or UDP. The latest `window` of them
+/// replace the imported data, and a normal fit to them can follow along.
+struct LiveStream {
+    source: stream::StreamSource,
+    interface: String,
+    port: u16,
+    /// Most recent values kept; older ones slide out
+    window: usize,
+    running_fit: bool,
+    stream: Option<stream::DataStream>,
+    buffer: std::collections::VecDeque<f64>,
+    received: u64,
+    // Input distribution the running fit is written to
+    fit_id: Option<u32>,
+    error: Option<String>,
+}
+
+impl Default for LiveStream {
+    fn default() -> Self {
+        Self {
+            source: stream::StreamSource::Tcp,
+            interface: stream::DEFAULT_INTERFACE.to_string(),
+            port: stream::DEFAULT_PORT,
+            window: 10_000,
+            running_fit: true,
+            stream: None,
+            buffer: std::collections::VecDeque::new(),
+            received: 0,
+            fit_id: None,
+            error: None,
+        }
+    }
+}
+
+/// Bootstrap confidence band around one curve fitted to the imported data
+struct BootstrapTool {
+    target: Option<u32>,
+    replicates: usize,
+    /// Probability covered by the band
+    level: f64,
+    // (target, its density fingerprint) the running or finished job is for
+    computed_for: Option<(u32, u64)>,
+    job: bootstrap::BootstrapJob,
+}
+
+impl Default for BootstrapTool {
+    fn default() -> Self {
+        Self {
+            target: None,
+            replicates: 200,
+            level: 0.95,
+            computed_for: None,
+            job: bootstrap::BootstrapJob::Idle,
+        }
+    }
+}
+
+impl BootstrapTool {
+    /// Finished band of `dist`, unless the curve changed since it was computed
+    fn band_for(&self, dist: &GaussianDistribution) -> Option<&bootstrap::BootstrapBand> {
+        match &self.job {
+            bootstrap::BootstrapJob::Done(band)
+                if self.computed_for.is_some_and(|(id, fingerprint)| id == dist.id && fingerprint == dist.density_fingerprint()) =>
+            {
+                Some(band)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Named set of input distributions sharing an affine change of units
+/// x ↦ scale · x + shift, applied on top of the members' own parameters
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct DistributionGroup {
+    name: String,
+    members: Vec<u32>,
+    shift: f64,
+    scale: f64,
+}
+
+/// Dash pattern of a drawn line
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum StrokeStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl StrokeStyle {
+    const ALL: [StrokeStyle; 3] = [StrokeStyle::Solid, StrokeStyle::Dashed, StrokeStyle::Dotted];
+
+    fn label(self) -> &'static str {
+        match self {
+            StrokeStyle::Solid => "Solid",
+            StrokeStyle::Dashed => "Dashed",
+            StrokeStyle::Dotted => "Dotted",
+        }
+    }
+
+    fn line_style(self) -> egui_plot::LineStyle {
+        match self {
+            StrokeStyle::Solid => egui_plot::LineStyle::Solid,
+            StrokeStyle::Dashed => egui_plot::LineStyle::Dashed { length: 8.0 },
+            StrokeStyle::Dotted => egui_plot::LineStyle::Dotted { spacing: 5.0 },
+        }
+    }
+}
+
+/// User-placed labeled line across the plot, e.g. a spec limit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ReferenceLine {
+    label: String,
+    /// Vertical lines sit at this x; horizontal ones at this y as read off the axis
+    position: f64,
+    horizontal: bool,
+    color: [u8; 3],
+    style: StrokeStyle,
+    width: f32,
+}
+
+impl ReferenceLine {
+    fn new(position: f64) -> Self {
+        Self {
+            label: String::new(),
+            position,
+            horizontal: false,
+            color: [200, 40, 40],
+            style: StrokeStyle::Dashed,
+            width: 1.5,
+        }
+    }
+
+    /// Label drawn next to the line, with its position appended
+    fn caption(&self) -> String {
+        let axis = if self.horizontal { "y" } else { "x" };
+        match self.label.trim() {
+            "" => format!("{} = {}", axis, self.position),
+            label => format!("{} = {}", label, self.position),
+        }
+    }
+}
+
+/// Text placed on the plot, optionally with an arrow to the feature it
+/// describes. Positions are x in data units and y as read off the axis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct TextAnnotation {
+    text: String,
+    position: [f64; 2],
+    arrow_to: Option<[f64; 2]>,
+    color: [u8; 3],
+    size: f32,
+}
+
+impl TextAnnotation {
+    fn new(position: [f64; 2]) -> Self {
+        Self {
+            text: "Note".to_string(),
+            position,
+            arrow_to: None,
+            color: [30, 30, 30],
+            size: 14.0,
+        }
+    }
+}
+
+/// Which tail(s) of a distribution count as "at least as extreme" as an observation
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Tail {
+    Lower,
+    Upper,
+    #[default]
+    Two,
+}
+
+/// How curves pick their colours: in turn from the palette, or on a colormap
+/// by a statistic so the spread-out and concentrated ones stand out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum CurveColoring {
+    #[default]
+    Palette,
+    Entropy,
+    Variance,
+}
+
+impl CurveColoring {
+    fn label(self) -> &'static str {
+        match self {
+            CurveColoring::Palette => "Palette",
+            CurveColoring::Entropy => "Entropy",
+            CurveColoring::Variance => "Variance",
+        }
+    }
+
+    fn statistic(self, dist: &GaussianDistribution) -> Option<f64> {
+        match self {
+            CurveColoring::Palette => None,
+            CurveColoring::Entropy => Some(dist.entropy()),
+            CurveColoring::Variance => Some(dist.moment_summary().std_dev.powi(2)),
+        }
+    }
+}
+
+/// Built-in example session, embedded in the binary
+struct GalleryExample {
+    title: &'static str,
+    description: &'static str,
+    session: &'static str,
+}
+
+const GALLERY: [GalleryExample; 4] = [
+    GalleryExample {
+        title: "Bayes update",
+        description: "A wide prior meets a sharper likelihood; the posterior sits in between.",
+        session: include_str!("../assets/gallery/bayes_update.json"),
+    },
+    GalleryExample {
+        title: "Convolution",
+        description: "Sums of independent copies: means add, variances add, shapes widen.",
+        session: include_str!("../assets/gallery/convolution.json"),
+    },
+    GalleryExample {
+        title: "Mixture",
+        description: "Two components mixed 35/65 into a bimodal density.",
+        session: include_str!("../assets/gallery/mixture.json"),
+    },
+    GalleryExample {
+        title: "Hypothesis test",
+        description: "Null and alternative, with the null's tail beyond the 5% critical value.",
+        session: include_str!("../assets/gallery/hypothesis_test.json"),
+    },
+];
+
+/// Starting points offered next to the gallery: common distributions and setups
+const PRESETS: [GalleryExample; 4] = [
+    GalleryExample {
+        title: "Standard normal",
+        description: "N(0, 1) on its own.",
+        session: include_str!("../assets/presets/standard_normal.json"),
+    },
+    GalleryExample {
+        title: "Weakly informative priors",
+        description: "Normal(0, 2.5) for a coefficient, Exponential(1) for a scale and Beta(2, 2) for a probability.",
+        session: include_str!("../assets/presets/weakly_informative.json"),
+    },
+    GalleryExample {
+        title: "Prior, likelihood, posterior",
+        description: "A skeptical N(0, 1) prior updated by an N(2.5, 0.75²) likelihood.",
+        session: include_str!("../assets/presets/prior_posterior.json"),
+    },
+    GalleryExample {
+        title: "Central limit theorem",
+        description: "Means of 2, 5 and 30 draws from a skewed Exponential(1) turning normal.",
+        session: include_str!("../assets/presets/clt.json"),
+    },
+];
+
+/// The user's own templates: workspaces saved as sessions, one file each
+#[derive(Default)]
+struct TemplateLibrary {
+    /// Where they are kept; None disables templates
+    directory: Option<std::path::PathBuf>,
+    /// Names of the saved templates, sorted
+    names: Vec<String>,
+    new_name: String,
+    status: Option<Result<String, String>>,
+}
+
+impl TemplateLibrary {
+    fn path(&self, name: &str) -> Result<std::path::PathBuf, String> {
+        let directory = self.directory.as_ref().ok_or("No folder for templates")?;
+        let name = name.trim();
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) {
+            return Err(format!("'{}' cannot be a template name", name));
+        }
+        Ok(directory.join(format!("{}.json", name)))
+    }
+
+    /// Re-read the names from the folder, which may not exist yet
+    fn refresh(&mut self) {
+        self.names = self.directory.as_ref()
+            .and_then(|directory| std::fs::read_dir(directory).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                path.file_stem()?.to_str().map(str::to_string)
+            })
+            .collect();
+        self.names.sort_by_key(|name| name.to_lowercase());
+    }
+}
+
+/// Parse a session file of either format and any supported version
+fn parse_session(bytes: &[u8]) -> Result<SessionData, String> {
+    session_from_value(session::decode(bytes)?)
+}
+
+/// A session already brought to the current format by `session::decode`
+fn session_from_value(value: serde_json::Value) -> Result<SessionData, String> {
+    let session: SessionData = serde_json::from_value(value).map_err(|e| format!("Failed to parse session: {}", e))?;
+    let mut ids: Vec<&u32> = session.distributions.keys().collect();
+    ids.sort_unstable();
+    // Plain inputs with bad parameters are clamped and flagged once loaded
+    // (`reconcile_loaded_parameters`); a derived node has nothing to fall back on
+    for id in ids.into_iter().filter(|id| session.distributions[id].is_derived()) {
+        let dist = &session.distributions[id];
+        dist.validate().map_err(|e| format!("Failed to parse session: distribution {} ('{}'): {}", id, dist.name, e))?;
+    }
+    Ok(session)
+}
+
+/// Curves of a gallery session over its saved view, for its thumbnail
+fn gallery_thumbnail(session: &str) -> Result<Vec<Vec<[f64; 2]>>, String> {
+    const POINTS: usize = 120;
+    let mut app = PdfViewerApp::new();
+    app.load_session(session)?;
+    let (x_min, x_max) = app.data_range();
+    let mut ids: Vec<u32> = app.distributions.keys().copied().collect();
+    ids.sort_unstable();
+    Ok(ids.iter()
+        .map(|id| {
+            let dist = &app.distributions[id];
+            (0..=POINTS)
+                .map(|i| {
+                    let x = x_min + (x_max - x_min) * i as f64 / POINTS as f64;
+                    [x, dist.evaluate(x)]
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// What the main plot shows: densities over x, or a transform of them over t
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum PlotMode {
+    #[default]
+    Density,
+    /// Cumulative distribution F(x) = P(X ≤ x), renormalized to reach 1
+    Cdf,
+    /// Survival function S(x) = P(X > x) = 1 − F(x)
+    Survival,
+    /// Hazard rate h(x) = f(x) / S(x), the event rate given survival to x
+    Hazard,
+    /// Natural log of the density, where Gaussians are parabolas
+    LogDensity,
+    /// Moment generating function M(t) = E[e^{tX}]
+    Mgf,
+    /// Modulus of the characteristic function |φ(t)| = |E[e^{itX}]|
+    CharacteristicFunction,
+    /// Sample quantiles against those of a theoretical distribution
+    QuantileQuantile,
+    /// Sample cumulative probabilities against theoretical ones
+    ProbabilityProbability,
+}
+
+/// What the Q-Q and P-P views compare: the imported data (or a second
+/// distribution) against a theoretical distribution
+struct ProbabilityPlot {
+    sample_from_data: bool,
+    sample: Option<u32>,
+    reference: Option<u32>,
+}
+
+impl Default for ProbabilityPlot {
+    fn default() -> Self {
+        Self { sample_from_data: true, sample: None, reference: None }
+    }
+}
+
+/// Arrangement of the plots in the right panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum PlotLayout {
+    #[default]
+    Single,
+    /// Main plot on top and the CDFs below, sharing the x-axis and cursor
+    PdfOverCdf,
+    /// Main plot on top and the likelihood ratio of two curves below
+    PdfOverRatio,
+    /// One small plot per distribution or group, on a shared x-range
+    SmallMultiples,
+    /// Each density mirrored about its own baseline, stacked top to bottom
+    Violin,
+}
+
+impl PlotLayout {
+    /// Axis and cursor link group of the stacked plots
+    const LINK: &'static str = "stacked_plots";
+
+    /// Heights of the plots, top to bottom, sharing `height`
+    fn heights(self, height: f32) -> Vec<f32> {
+        match self {
+            PlotLayout::Single | PlotLayout::SmallMultiples | PlotLayout::Violin => vec![height],
+            PlotLayout::PdfOverCdf | PlotLayout::PdfOverRatio => {
+                let spacing = 8.0;
+                let usable = (height - spacing).max(2.0);
+                vec![0.6 * usable, 0.4 * usable]
+            }
+        }
+    }
+}
+
+impl PlotMode {
+    /// Height of a curve at x in the views that plot a function of the
+    /// density (densities themselves go through the point cache)
+    fn value(self, dist: &GaussianDistribution, x: f64) -> f64 {
+        match self {
+            PlotMode::Cdf => dist.quantile_level(x),
+            PlotMode::Survival => dist.survival(x),
+            PlotMode::Hazard => dist.hazard(x),
+            PlotMode::LogDensity => dist.ln_density(x),
+            _ => dist.evaluate(x),
+        }
+    }
+}
+
+/// On-plot handle of an input distribution: the mean at x = μ, or the spread at x = μ + σ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ParameterHandle {
+    Mean,
+    StdDev,
+}
+
+/// What the crosshair reads off the active distribution at x
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CrosshairReadout {
+    x: f64,
+    density: f64,
+    cdf: f64,
+    z: f64,
+}
+
+/// Viridis colormap at t in [0, 1], interpolated between a few of its stops
+fn viridis(t: f64) -> egui::Color32 {
+    const STOPS: [[f64; 3]; 5] = [
+        [68.0, 1.0, 84.0],
+        [59.0, 82.0, 139.0],
+        [33.0, 145.0, 140.0],
+        [94.0, 201.0, 98.0],
+        [253.0, 231.0, 37.0],
+    ];
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let fraction = position - index as f64;
+    let channel = |c: usize| (STOPS[index][c] + (STOPS[index + 1][c] - STOPS[index][c]) * fraction).round() as u8;
+    egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Maps t ∈ [0, 1] to a colour, for heatmaps and colour bars
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    Grayscale,
+}
+
+impl Colormap {
+    const ALL: [Colormap; 3] = [Colormap::Viridis, Colormap::Magma, Colormap::Grayscale];
+
+    fn label(self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Grayscale => "Grayscale",
+        }
+    }
+
+    fn color(self, t: f64) -> egui::Color32 {
+        match self {
+            Colormap::Viridis => viridis(t),
+            Colormap::Magma => magma(t),
+            Colormap::Grayscale => {
+                let level = (255.0 * t.clamp(0.0, 1.0)).round() as u8;
+                egui::Color32::from_gray(level)
+            }
+        }
+    }
+}
+
+/// Black through purple and orange to pale yellow, five stops like `viridis`
+fn magma(t: f64) -> egui::Color32 {
+    const STOPS: [[f64; 3]; 5] = [
+        [0.0, 0.0, 4.0],
+        [81.0, 18.0, 124.0],
+        [183.0, 55.0, 121.0],
+        [252.0, 137.0, 97.0],
+        [252.0, 253.0, 191.0],
+    ];
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let fraction = position - index as f64;
+    let channel = |c: usize| (STOPS[index][c] + (STOPS[index + 1][c] - STOPS[index][c]) * fraction).round() as u8;
+    egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Parameter draws behind each uncertainty band; fixed seed per distribution so it does not flicker
+const BAND_DRAWS: usize = 200;
+
+/// Grid points of a bootstrap band
+const BOOTSTRAP_POINTS: usize = 400;
+
+/// Curve colors, assigned in turn to the distributions on the plot
+const PALETTE: [egui::Color32; 6] = [
+    egui::Color32::BLUE,
+    egui::Color32::RED,
+    egui::Color32::GREEN,
+    egui::Color32::from_rgb(255, 165, 0), // Orange
+    egui::Color32::from_rgb(128, 0, 128), // Purple
+    egui::Color32::from_rgb(255, 192, 203), // Pink
+];
+
+/// Lighter versions of `PALETTE` that stand out on a dark background
+const DARK_PALETTE: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(100, 160, 255),
+    egui::Color32::from_rgb(255, 110, 110),
+    egui::Color32::from_rgb(110, 220, 110),
+    egui::Color32::from_rgb(255, 185, 70),
+    egui::Color32::from_rgb(200, 130, 255),
+    egui::Color32::from_rgb(255, 170, 200),
+];
+
+const HIGH_CONTRAST_PALETTE: [egui::Color32; 6] = [
+    egui::Color32::YELLOW,
+    egui::Color32::from_rgb(0, 255, 255),
+    egui::Color32::from_rgb(255, 0, 255),
+    egui::Color32::WHITE,
+    egui::Color32::from_rgb(0, 255, 0),
+    egui::Color32::from_rgb(255, 128, 0),
+];
+
+/// Okabe–Ito: distinguishable with the common colour vision deficiencies and in greyscale print
+const PRINT_PALETTE: [egui::Color32; 7] = [
+    egui::Color32::from_rgb(0, 114, 178),
+    egui::Color32::from_rgb(213, 94, 0),
+    egui::Color32::from_rgb(0, 158, 115),
+    egui::Color32::from_rgb(230, 159, 0),
+    egui::Color32::from_rgb(204, 121, 167),
+    egui::Color32::from_rgb(86, 180, 233),
+    egui::Color32::BLACK,
+];
+
+/// Look of the window and plots, with a curve palette to match
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum PlotTheme {
+    /// egui's light or dark look, following the operating system
+    #[default]
+    System,
+    Light,
+    Dark,
+    /// Pure black background, white text and saturated curves
+    HighContrast,
+    /// White background and the colour-blind safe Okabe–Ito palette
+    Print,
+}
+
+impl PlotTheme {
+    const ALL: [PlotTheme; 5] = [PlotTheme::System, PlotTheme::Light, PlotTheme::Dark, PlotTheme::HighContrast, PlotTheme::Print];
+
+    fn label(self) -> &'static str {
+        match self {
+            PlotTheme::System => "System",
+            PlotTheme::Light => "Light",
+            PlotTheme::Dark => "Dark",
+            PlotTheme::HighContrast => "High contrast",
+            PlotTheme::Print => "Print",
+        }
+    }
+
+    fn palette(self) -> &'static [egui::Color32] {
+        match self {
+            PlotTheme::System | PlotTheme::Light => &PALETTE,
+            PlotTheme::Dark => &DARK_PALETTE,
+            PlotTheme::HighContrast => &HIGH_CONTRAST_PALETTE,
+            PlotTheme::Print => &PRINT_PALETTE,
+        }
+    }
+
+    fn apply(self, ctx: &egui::Context) {
+        use egui::{Color32, Theme, Visuals};
+        // Reset both looks first, so leaving a custom theme restores egui's own
+        ctx.set_visuals_of(Theme::Dark, Visuals::dark());
+        ctx.set_visuals_of(Theme::Light, Visuals::light());
+        match self {
+            PlotTheme::System => ctx.set_theme(egui::ThemePreference::System),
+            PlotTheme::Light => ctx.set_theme(Theme::Light),
+            PlotTheme::Dark => ctx.set_theme(Theme::Dark),
+            PlotTheme::HighContrast => {
+                let mut visuals = Visuals::dark();
+                visuals.panel_fill = Color32::BLACK;
+                visuals.window_fill = Color32::BLACK;
+                visuals.extreme_bg_color = Color32::BLACK;
+                visuals.override_text_color = Some(Color32::WHITE);
+                visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.0, Color32::WHITE);
+                ctx.set_visuals_of(Theme::Dark, visuals);
+                ctx.set_theme(Theme::Dark);
+            }
+            PlotTheme::Print => {
+                let mut visuals = Visuals::light();
+                visuals.panel_fill = Color32::WHITE;
+                visuals.window_fill = Color32::WHITE;
+                visuals.extreme_bg_color = Color32::WHITE;
+                visuals.override_text_color = Some(Color32::BLACK);
+                ctx.set_visuals_of(Theme::Light, visuals);
+                ctx.set_theme(Theme::Light);
+            }
+        }
+    }
+}
+
+/// How tick labels print their numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum NumberFormat {
+    /// egui_plot's own formatting
+    #[default]
+    Auto,
+    Fixed,
+    Scientific,
+}
+
+impl NumberFormat {
+    fn label(self) -> &'static str {
+        match self {
+            NumberFormat::Auto => "Auto",
+            NumberFormat::Fixed => "Fixed",
+            NumberFormat::Scientific => "Scientific",
+        }
+    }
+
+    fn format(self, value: f64, decimals: usize) -> String {
+        match self {
+            NumberFormat::Scientific => format!("{:.*e}", decimals, value),
+            // Auto only ends up here for values egui_plot does not format itself
+            NumberFormat::Fixed | NumberFormat::Auto => format!("{:.*}", decimals, value),
+        }
+    }
+}
+
+/// Labels, ticks and grid of the main plot, so exported figures do not have
+/// to live with egui_plot's defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct AxisStyle {
+    /// Empty keeps the label of the current view (e.g. "F(x)" or the z-scale)
+    x_label: String,
+    y_label: String,
+    /// Distance between ticks in plot units; None fits a power of ten to the zoom
+    x_step: Option<f64>,
+    y_step: Option<f64>,
+    number_format: NumberFormat,
+    decimals: usize,
+    show_grid: bool,
+    /// Four unlabeled lines between neighbouring ticks
+    minor_grid: bool,
+}
+
+impl Default for AxisStyle {
+    fn default() -> Self {
+        Self {
+            x_label: String::new(),
+            y_label: String::new(),
+            x_step: None,
+            y_step: None,
+            number_format: NumberFormat::Auto,
+            decimals: 2,
+            show_grid: true,
+            minor_grid: true,
+        }
+    }
+}
+
+impl AxisStyle {
+    /// Whether egui_plot's own grid spacing already gives what is asked for
+    fn default_spacing(&self) -> bool {
+        self.x_step.is_none() && self.y_step.is_none() && self.minor_grid
+    }
+}
+
+/// Grid lines every `step`, coarsened by powers of ten while finer than the
+/// zoom allows, plus four thinner lines in between when `minor` is set
+fn grid_marks(input: egui_plot::GridInput, step: Option<f64>, minor: bool) -> Vec<egui_plot::GridMark> {
+    let (lower, upper) = input.bounds;
+    let base = input.base_step_size;
+    if base <= 0.0 || !base.is_finite() || !lower.is_finite() || !upper.is_finite() {
+        return Vec::new();
+    }
+    let mut major = step
+        .filter(|step| *step > 0.0 && step.is_finite())
+        .unwrap_or_else(|| 10f64.powf(base.log10().ceil() + 1.0));
+    while major < base {
+        major *= 10.0;
+    }
+    let mut marks = Vec::new();
+    let mut add = |step: f64, skip_every: i64| {
+        for k in (lower / step).ceil() as i64..=(upper / step).floor() as i64 {
+            if skip_every == 0 || k % skip_every != 0 {
+                marks.push(egui_plot::GridMark { value: k as f64 * step, step_size: step });
+            }
+        }
+    };
+    add(major, 0);
+    if minor && major / 5.0 >= base {
+        add(major / 5.0, 5);
+    }
+    marks
+}
+
+/// How exported TikZ figures refer to colors and fonts, so they pick up the
+/// definitions of the LaTeX document they are dropped into
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct LatexTheme {
+    /// Document color name per palette slot (e.g. `MyBlue`); empty keeps the RGB value
+    color_names: Vec<String>,
+    /// Font commands for labels and ticks (e.g. `\sffamily\small`); empty inherits the document font
+    font: String,
+}
+
+impl LatexTheme {
+    /// xcolor names and expressions such as `MyBlue` or `MyBlue!60`
+    fn is_valid_color_name(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.!".contains(c))
+    }
+
+    /// Color of a palette slot as written into TikZ options
+    fn tikz_color(&self, slot: usize) -> String {
+        let slot = slot % PALETTE.len();
+        match self.color_names.get(slot).map(|name| name.trim()) {
+            Some(name) if Self::is_valid_color_name(name) => name.to_string(),
+            _ => tikz_rgb(PALETTE[slot]),
+        }
+    }
+
+    /// A drawn color as written into TikZ options: the document name of its
+    /// palette slot if it is one, else its RGB value
+    fn tikz_color_of(&self, color: egui::Color32) -> String {
+        match PALETTE.iter().position(|&slot| slot == color) {
+            Some(slot) => self.tikz_color(slot),
+            None => tikz_rgb(color),
+        }
+    }
+
+    /// pgfplots `font=` option, or None to inherit the document font
+    fn font_option(&self) -> Option<String> {
+        let font = self.font.trim();
+        (!font.is_empty()).then(|| format!("font={{{}}}", font))
+    }
+}
+
+fn tikz_rgb(color: egui::Color32) -> String {
+    format!("{{rgb,255:red,{};green,{};blue,{}}}", color.r(), color.g(), color.b())
+}
+
+/// Two distributions picked in the comparison window (optionally shading
+/// their overlap or drawing √(p q) on the plot), plus the two
+/// products/posteriors whose evidence ratio is the Bayes factor
+#[derive(Default)]
+struct ComparisonTool {
+    open: bool,
+    first: Option<u32>,
+    second: Option<u32>,
+    shade_overlap: bool,
+    show_bhattacharyya_curve: bool,
+    model_one: Option<u32>,
+    model_two: Option<u32>,
+}
+
+/// Joint density window: two distributions coupled with correlation ρ and
+/// drawn as a heatmap
+#[derive(Default)]
+struct JointTool {
+    open: bool,
+    x: Option<u32>,
+    y: Option<u32>,
+    rho: f64,
+    colormap: Colormap,
+    /// Cells along each axis
+    resolution: usize,
+    // (x fingerprint, y fingerprint, ρ, colormap, resolution) behind `texture`
+    computed_for: Option<(u64, u64, u64, usize, usize)>,
+    texture: Option<egui::TextureHandle>,
+}
+
+/// Parameter of an input distribution that a sweep animates
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum SweepParameter {
+    #[default]
+    Mean,
+    StdDev,
+}
+
+impl SweepParameter {
+    fn label(self) -> &'static str {
+        match self {
+            SweepParameter::Mean => "Mean",
+            SweepParameter::StdDev => "Std Dev",
+        }
+    }
+
+    /// Start and end offered when the parameter is picked
+    fn default_range(self) -> (f64, f64) {
+        match self {
+            SweepParameter::Mean => (-3.0, 3.0),
+            SweepParameter::StdDev => (0.5, 3.0),
+        }
+    }
+}
+
+/// Animation moving one parameter of an input distribution from `from` to
+/// `to` over `period` seconds, so derived curves can be watched following it
+struct ParameterSweep {
+    target: Option<u32>,
+    parameter: SweepParameter,
+    from: f64,
+    to: f64,
+    period: f64,
+    /// Run back and forth instead of jumping back to `from`
+    bounce: bool,
+    playing: bool,
+    elapsed: f64,
+}
+
+impl Default for ParameterSweep {
+    fn default() -> Self {
+        let (from, to) = SweepParameter::default().default_range();
+        Self {
+            target: None,
+            parameter: SweepParameter::default(),
+            from,
+            to,
+            period: 4.0,
+            bounce: true,
+            playing: false,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl ParameterSweep {
+    /// Position between `from` (0) and `to` (1) after `elapsed` seconds
+    fn progress(&self) -> f64 {
+        let cycles = self.elapsed / self.period.max(1e-3);
+        if self.bounce {
+            let t = cycles.rem_euclid(2.0);
+            if t > 1.0 { 2.0 - t } else { t }
+        } else {
+            cycles.rem_euclid(1.0)
+        }
+    }
+
+    fn value(&self) -> f64 {
+        self.from + (self.to - self.from) * self.progress()
+    }
+}
+
+/// Most keyframes a timeline keeps; the oldest are dropped first
+const MAX_KEYFRAMES: usize = 1000;
+
+/// Full app state at one moment of a recording
+struct Keyframe {
+    /// Seconds since the recording started
+    time: f64,
+    session: String,
+}
+
+/// Recorded history of parameter changes that can be scrubbed through
+#[derive(Default)]
+struct Timeline {
+    recording: bool,
+    started: Option<std::time::Instant>,
+    keyframes: Vec<Keyframe>,
+    /// Keyframe currently restored, or the latest one while recording
+    position: usize,
+    // State behind the latest keyframe, so unchanged frames are not stored
+    last_fingerprint: Option<u64>,
+    error: Option<String>,
+}
+
+/// Most undo steps kept; the oldest are dropped first
+const MAX_UNDO_STEPS: usize = 100;
+
+/// The distributions at one step of the edit history
+#[derive(Clone)]
+struct Snapshot {
+    distributions: HashMap<u32, GaussianDistribution>,
+    next_id: u32,
+    fingerprint: u64,
+}
+
+/// Undo and redo stacks. Every finished edit to the distributions (a slider
+/// released, an add, a delete, a multiplication) becomes one step, whatever
+/// control made it, so no code path has to remember to record itself.
+#[derive(Default)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// State as of the latest step, compared with the live one every frame
+    current: Option<Snapshot>,
+}
+
+/// Model specification pasted for import, and what the last import did
+#[derive(Default)]
+struct ModelSpecTool {
+    text: String,
+    status: Option<Result<String, String>>,
+}
+
+/// Share string window: the last one copied out and the one pasted in
+#[derive(Default)]
+struct ShareTool {
+    open: bool,
+    pasted: String,
+    status: Option<Result<String, String>>,
+}
+
+/// Inputs and latest result of the importance sampling window
+#[derive(Default)]
+struct ImportanceTool {
+    open: bool,
+    proposal: Option<u32>,
+    target: Option<u32>,
+    samples: usize,
+    seed: u64,
+    // (proposal fingerprint, target fingerprint, samples, seed) behind `report`
+    computed_for: Option<(u64, u64, usize, u64)>,
+    report: Option<sampling::ImportanceReport>,
+}
+
+/// Most draws the random sample window takes at once
+const MAX_SAMPLE_DRAWS: usize = 100_000;
+
+/// Draws shown as a rug under the sample histogram; more would be a solid bar
+const MAX_RUG_DRAWS: usize = 2000;
+
+/// Inputs and latest draws of the random sample window
+#[derive(Default)]
+struct SampleTool {
+    open: bool,
+    target: Option<u32>,
+    count: usize,
+    seed: u64,
+    /// CSV file the draws are exported to
+    path: String,
+    draws: Option<SampleDraws>,
+    // Outcome of the latest export: where it went, or what failed
+    status: Option<Result<String, String>>,
+}
+
+/// Size and destination of the "Export PNG" window
+#[derive(Default)]
+struct PngExportTool {
+    open: bool,
+    /// In pixels, independent of the window size
+    width: u32,
+    height: u32,
+    /// Pixels per point: how large text and lines come out relative to the image
+    scale: f32,
+    path: String,
+    // Outcome of the latest export: where it went, or what failed
+    status: Option<Result<String, String>>,
+    // Export being filled in and written on a worker thread
+    pending: Option<std::sync::mpsc::Receiver<Result<String, String>>>,
+}
+
+/// Destination of a text export such as the TikZ picture or the Python script
+#[derive(Default)]
+struct TextExport {
+    path: String,
+    // Outcome of the latest export: where it went, or what failed
+    status: Option<Result<String, String>>,
+}
+
+impl TextExport {
+    /// File name with Save and Copy buttons, then the outcome; `text` makes
+    /// the export when either is clicked
+    fn controls(&mut self, ui: &mut egui::Ui, text: impl FnOnce() -> Result<String, String>) {
+        let mut export = None;
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.add(egui::TextEdit::singleline(&mut self.path).desired_width(120.0));
+            if ui.add_enabled(!self.path.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                export = Some(true);
+            }
+            if ui.button("📋 Copy").clicked() {
+                export = Some(false);
+            }
+        });
+        if let Some(save) = export {
+            let path = std::path::PathBuf::from(self.path.trim());
+            self.status = Some(text().and_then(|text| {
+                if save {
+                    write_replacing(&path, text)
+                        .map(|()| format!("Wrote {}", path.display()))
+                        .map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+                } else {
+                    ui.output_mut(|o| o.copied_text = text);
+                    Ok("Copied to the clipboard".to_string())
+                }
+            }));
+        }
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.weak(message);
+            }
+            Some(Err(error)) => {
+                ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+            }
+            None => {}
+        }
+    }
+}
+
+/// Draws of the random sample window and where they came from
+struct SampleDraws {
+    /// Name of the distribution when it was sampled
+    name: String,
+    seed: u64,
+    values: Vec<f64>,
+    histogram: data::Histogram,
+}
+
+impl SampleDraws {
+    /// One row per draw, in the order drawn
+    fn csv(&self) -> String {
+        let mut csv = String::from("draw,x\n");
+        for (i, x) in self.values.iter().enumerate() {
+            csv.push_str(&format!("{},{}\n", i + 1, x));
+        }
+        csv
+    }
+}
+
+/// Most draws one Monte Carlo run takes
+const MAX_MONTE_CARLO_DRAWS: usize = 200_000;
+
+/// Monte Carlo propagation of distributions through a formula
+#[derive(Default)]
+struct MonteCarloTool {
+    open: bool,
+    /// E.g. "Z = X*Y + 2"; the part before '=' names the result
+    formula: String,
+    // Distribution picked for each variable; unpicked variables use the
+    // distribution of the same name
+    bindings: HashMap<String, u32>,
+    count: usize,
+    seed: u64,
+    result: Option<MonteCarloResult>,
+    error: Option<String>,
+}
+
+/// Draws of a formula and what they are compared with
+struct MonteCarloResult {
+    name: String,
+    /// In increasing order
+    samples: Vec<f64>,
+    /// Draws where the formula was undefined, left out of `samples`
+    undefined: usize,
+    histogram: data::Histogram,
+    bandwidth: f64,
+    /// Exact (mean, std dev) of the normal result when the formula is affine
+    /// in closed-form Gaussian inputs
+    exact: Option<(f64, f64)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionData {
+    /// Format version, see `session::migrate`
+    #[serde(default)]
+    version: u32,
+    distributions: HashMap<u32, GaussianDistribution>,
+    next_id: u32,
+    show_shading: bool,
+    shading_opacity: f32,
+    show_std_markers: bool,
+    #[serde(default)]
+    resolution: PlotResolution,
+    #[serde(default)]
+    view_lock: Option<[f64; 4]>,
+    #[serde(default)]
+    show_product_amplitude: bool,
+    #[serde(default)]
+    plot: PlotState,
+    #[serde(default)]
+    latex_theme: LatexTheme,
+    #[serde(default)]
+    groups: Vec<DistributionGroup>,
+    #[serde(default)]
+    curve_coloring: CurveColoring,
+    #[serde(default)]
+    reference_lines: Vec<ReferenceLine>,
+    #[serde(default)]
+    annotations: Vec<TextAnnotation>,
+    #[serde(default)]
+    theme: PlotTheme,
+    #[serde(default)]
+    custom_palette: Vec<[u8; 3]>,
+    #[serde(default)]
+    axis_style: AxisStyle,
+    /// Every tab of the window, in order; the active one is the rest of this file
+    #[serde(default)]
+    workspaces: Vec<SavedWorkspace>,
+    #[serde(default)]
+    active_workspace: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedWorkspace {
+    name: String,
+    /// A whole session of its own; left out for the active tab, and a tab
+    /// without one opens empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<serde_json::Value>,
+}
+
+/// Independent workspaces shown as tabs. Only the active one lives in the
+/// app's fields; the others wait as sessions and are swapped in on a switch.
+#[derive(Default)]
+struct Workspaces {
+    tabs: Vec<WorkspaceTab>,
+    active: usize,
+}
+
+#[derive(Default)]
+struct WorkspaceTab {
+    name: String,
+    /// None for the active tab and for a new, empty one
+    session: Option<serde_json::Value>,
+    // Kept in memory only, like the app's own selection and undo history
+    selection: Vec<u32>,
+    history: History,
+}
+
+impl Workspaces {
+    fn single() -> Self {
+        Workspaces { tabs: vec![WorkspaceTab { name: "Workspace 1".to_string(), ..Default::default() }], active: 0 }
+    }
+}
+
+/// Plot interaction state saved with a session, so reopening it restores
+/// the analysis view and not just the data
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct PlotState {
+    /// Visible [x_min, x_max, y_min, y_max] when the session was saved
+    bounds: Option<[f64; 4]>,
+    /// Interval picked for conditioning, drawn as two vertical lines
+    condition_interval: Option<(f64, f64)>,
+    /// Distribution whose z-scale the horizontal axis shows, if any
+    #[serde(default)]
+    z_reference: Option<u32>,
+    #[serde(default)]
+    log_y: bool,
+    #[serde(default)]
+    layout: PlotLayout,
+    #[serde(default)]
+    peak_normalized: bool,
+}
+
+/// How many samples are used to turn a density into a polyline
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct PlotResolution {
+    interactive: InteractiveResolution,
+    /// Samples per curve for exported data, independent of the window size
+    export_points: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum InteractiveResolution {
+    /// One sample per horizontal pixel of the plot area
+    PixelWidth,
+    /// A fixed number of samples regardless of the plot size
+    Fixed(usize),
+}
+
+impl Default for PlotResolution {
+    fn default() -> Self {
+        Self {
+            interactive: InteractiveResolution::PixelWidth,
+            export_points: 4000,
+        }
+    }
+}
+
+impl PlotResolution {
+    fn interactive_points(&self, plot_width_px: f32) -> usize {
+        match self.interactive {
+            InteractiveResolution::PixelWidth => (plot_width_px.round() as usize).clamp(64, 4096),
+            InteractiveResolution::Fixed(points) => points.max(2),
+        }
+    }
+}
+
+/// Per-curve sample cache for the interactive plot.
+///
+/// Samples sit on a global lattice x = k·step, so after a pan only the newly
+/// exposed interval is evaluated; zooming or editing the distribution
+/// invalidates the curve.
+#[derive(Default)]
+struct PointCache {
+    curves: HashMap<u32, CachedCurve>,
+    // Density evaluations performed so far, for diagnostics
+    evaluations: usize,
+}
+
+struct CachedCurve {
+    fingerprint: u64,
+    step: f64,
+    first_index: i64,
+    values: Vec<f64>,
+}
+
+impl PointCache {
+    fn points(&mut self, dist: &GaussianDistribution, x_min: f64, x_max: f64, num_points: usize) -> Vec<[f64; 2]> {
+        let fingerprint = dist.density_fingerprint();
+        let requested_step = (x_max - x_min) / (num_points.max(2) - 1) as f64;
+
+        // Pans keep the view width, but it can drift by rounding; accept a tiny
+        // relative difference so the lattice (and the cached samples) stay valid
+        let cached = self.curves.get(&dist.id).filter(|curve| {
+            curve.fingerprint == fingerprint
+                && (curve.step - requested_step).abs() <= curve.step * 1e-6
+        });
+        let step = cached.map_or(requested_step, |curve| curve.step);
+
+        let first_index = (x_min / step).floor() as i64;
+        let last_index = (x_max / step).ceil() as i64;
+        let mut values = Vec::with_capacity((last_index - first_index + 1) as usize);
+        for k in first_index..=last_index {
+            let reused = cached.and_then(|curve| {
+                usize::try_from(k - curve.first_index).ok()
+                    .and_then(|offset| curve.values.get(offset).copied())
+            });
+            values.push(reused.unwrap_or_else(|| {
+                self.evaluations += 1;
+                dist.evaluate(k as f64 * step)
+            }));
+        }
+
+        let points = values.iter().enumerate()
+            .map(|(i, &y)| [(first_index + i as i64) as f64 * step, y])
+            .collect();
+        self.curves.insert(dist.id, CachedCurve { fingerprint, step, first_index, values });
+        points
+    }
+
+    /// Drop cached curves for distributions that no longer exist
+    fn retain_ids(&mut self, distributions: &HashMap<u32, GaussianDistribution>) {
+        self.curves.retain(|id, _| distributions.contains_key(id));
+    }
+}
+
+impl PdfViewerApp {
+    fn new() -> Self {
+        Self {
+            show_shading: true,
+            shading_opacity: 0.3,
+            show_welcome: true,
+            show_hover_values: true,
+            show_std_markers: true,
+            custom_transform: "x^3".to_string(),
+            temper_beta: 0.5,
+            sum_copies: 2,
+            sync_port: sync::DEFAULT_PORT,
+            sync_interface: sync::DEFAULT_INTERFACE.to_string(),
+            sync_address: format!("192.168.1.2:{}", sync::DEFAULT_PORT),
+            follow_presenter: true,
+            view_input: [-6.0, 6.0, 0.0, 0.5],
+            importance: ImportanceTool { samples: 1000, ..Default::default() },
+            sampler: SampleTool { count: 1000, path: "samples.csv".to_string(), ..Default::default() },
+            png_export: PngExportTool { width: 1600, height: 900, scale: 2.0, path: "plot.png".to_string(), ..Default::default() },
+            workspaces: Workspaces::single(),
+            tikz_export: TextExport { path: "plot.tex".to_string(), ..Default::default() },
+            python_export: TextExport { path: "plot.py".to_string(), ..Default::default() },
+            r_export: TextExport { path: "plot.R".to_string(), ..Default::default() },
+            report_export: TextExport { path: "report.html".to_string(), ..Default::default() },
+            cheat_sheet_export: TextExport { path: "cheat_sheet.md".to_string(), ..Default::default() },
+            monte_carlo: MonteCarloTool { formula: "Z = X*Y + 2".to_string(), count: 10_000, ..Default::default() },
+            joint: JointTool { resolution: 150, ..Default::default() },
+            quantile_p: 0.975,
+            pvalue_x: 1.96,
+            credible_level: 0.95,
+            transform_t_max: 3.0,
+            auto_export: AutoExport { directory: "pdf_viewer_export".to_string(), ..Default::default() },
+            show_quantile_marker: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct GaussianDistribution {
+    id: u32,
+    name: String,
+    mean: f64,
+    std_dev: f64,
+    parent_ids: Vec<u32>,
+    is_product: bool,
+    #[serde(default)]
+    operation: Operation,
+    // Optional [lower, upper] outside of which the density is treated as zero,
+    // both when drawing and when integrating into derived distributions
+    #[serde(default)]
+    domain: Option<(f64, f64)>,
+    // Numerically evaluated density for derived distributions without a closed form.
+    // Rebuilt from the parents on every update, so it is never written to sessions.
+    #[serde(skip)]
+    grid: Option<DensityGrid>,
+    // The grid of a derived distribution whose parent was deleted; nothing can
+    // rebuild it any more, so this copy is saved with the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frozen_grid: Option<DensityGrid>,
+    // What the current derived values were computed from, so an unchanged
+    // derivation is not redone every frame
+    #[serde(skip)]
+    derived_from: Option<DerivationKey>,
+    #[serde(skip)]
+    warning: Option<String>,
+    // ∫ Π fᵢ(x) dx of the raw parent product, i.e. the evidence of a Bayes update
+    #[serde(skip)]
+    normalizing_constant: Option<f64>,
+    // Also draw the raw product S × density, whose amplitude is the honest one
+    #[serde(default)]
+    show_unnormalized: bool,
+    // Slider ranges for mean and std dev; widened on load to fit the saved values
+    #[serde(skip)]
+    ranges: ParameterRanges,
+    // What load-time reconciliation changed, shown until dismissed
+    #[serde(skip)]
+    load_notice: Option<String>,
+    // Uncertainty about the parameters of an input, drawn as a credible band
+    #[serde(default)]
+    uncertainty: Option<ParameterUncertainty>,
+    // Reference lines drawn on the curve when markers are shown
+    #[serde(default)]
+    markers: MarkerScheme,
+    // Left off the plot, but still listed and used by derived distributions
+    #[serde(default)]
+    hidden: bool,
+    #[serde(default)]
+    line_style: StrokeStyle,
+    #[serde(default = "default_line_width")]
+    line_width: f32,
+    // Drawn against the right-hand y-axis, which has its own scale
+    #[serde(default)]
+    secondary_axis: bool,
+    // Maximum-likelihood fit the parameters came from
+    #[serde(default)]
+    fit: Option<FitSummary>,
+}
+
+/// Family fitted to imported data
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum FitFamily {
+    #[default]
+    Normal,
+    /// ln X is normal; drawn as exp of the fitted normal
+    LogNormal,
+    /// Shape k and scale θ, on x > 0
+    Gamma,
+    /// Shapes α and β, on 0 < x < 1
+    Beta,
+}
+
+impl FitFamily {
+    const ALL: [FitFamily; 4] = [FitFamily::Normal, FitFamily::LogNormal, FitFamily::Gamma, FitFamily::Beta];
+
+    fn label(self) -> &'static str {
+        match self {
+            FitFamily::Normal => "Normal",
+            FitFamily::LogNormal => "Log-normal",
+            FitFamily::Gamma => "Gamma",
+            FitFamily::Beta => "Beta",
+        }
+    }
+
+    /// Names of the two parameters of `Operation::Family`
+    fn parameter_names(self) -> [&'static str; 2] {
+        match self {
+            FitFamily::Normal | FitFamily::LogNormal => ["μ", "σ"],
+            FitFamily::Gamma => ["k", "θ"],
+            FitFamily::Beta => ["α", "β"],
+        }
+    }
+
+    /// Whether the maximum-likelihood estimate has a closed form; Gamma and
+    /// Beta need an iterative solver and are fitted by moments only
+    fn has_closed_form_mle(self) -> bool {
+        matches!(self, FitFamily::Normal | FitFamily::LogNormal)
+    }
+
+    /// Estimate the two parameters from `values` with `method`, returning them
+    /// with their standard errors (when known) and the log-likelihood
+    fn estimate(self, method: FitMethod, values: &[f64]) -> Result<(Vec<FitParameter>, f64), String> {
+        use statrs::distribution::{Beta, Gamma};
+        match self {
+            FitFamily::LogNormal | FitFamily::Gamma if values.iter().any(|&x| x <= 0.0) => {
+                return Err(format!("A {} fit needs strictly positive data", self.label()));
+            }
+            FitFamily::Beta if values.iter().any(|&x| x <= 0.0 || x >= 1.0) => {
+                return Err("A Beta fit needs data strictly between 0 and 1".to_string());
+            }
+            _ => {}
+        }
+        if method == FitMethod::MaximumLikelihood && !self.has_closed_form_mle() {
+            return Err(format!("{} MLE needs an iterative solver; use the method of moments", self.label()));
+        }
+        let (mean, variance) = data::moments(values);
+        if variance <= 0.0 || !variance.is_finite() {
+            return Err("The data has no spread to fit".to_string());
+        }
+
+        let parameter = |name: &str, value: f64, se: Option<f64>| FitParameter { name: name.to_string(), value, se };
+        Ok(match self {
+            FitFamily::Normal | FitFamily::LogNormal => {
+                let logs: Vec<f64>;
+                let sample = if self == FitFamily::LogNormal {
+                    logs = values.iter().map(|x| x.ln()).collect();
+                    &logs
+                } else {
+                    values
+                };
+                let (mu, sigma, ses) = match method {
+                    FitMethod::MaximumLikelihood => {
+                        let fitted = data::normal_mle(sample);
+                        (fitted.mean, fitted.std_dev, Some((fitted.mean_se, fitted.std_dev_se)))
+                    }
+                    // Normal moments are the MLE; log-normal ones match E[X] and Var[X]
+                    FitMethod::Moments if self == FitFamily::LogNormal => {
+                        let log_variance = (1.0 + variance / (mean * mean)).ln();
+                        (mean.ln() - 0.5 * log_variance, log_variance.sqrt(), None)
+                    }
+                    FitMethod::Moments => (mean, variance.sqrt(), None),
+                };
+                let normal = Normal::new(mu, sigma).map_err(|e| e.to_string())?;
+                // The log-normal likelihood of x is that of ln x times the Jacobian 1/x
+                let jacobian: f64 = if self == FitFamily::LogNormal { sample.iter().sum() } else { 0.0 };
+                let log_likelihood = sample.iter().map(|&x| normal.ln_pdf(x)).sum::<f64>() - jacobian;
+                let [mu_name, sigma_name] = self.parameter_names();
+                let parameters = vec![
+                    parameter(&format!("{}̂", mu_name), mu, ses.map(|(se, _)| se)),
+                    parameter(&format!("{}̂", sigma_name), sigma, ses.map(|(_, se)| se)),
+                ];
+                (parameters, log_likelihood)
+            }
+            FitFamily::Gamma => {
+                // E = kθ, Var = kθ²
+                let (shape, scale) = (mean * mean / variance, variance / mean);
+                let gamma = Gamma::new(shape, 1.0 / scale).map_err(|e| e.to_string())?;
+                let log_likelihood = values.iter().map(|&x| gamma.ln_pdf(x)).sum();
+                (vec![parameter("k", shape, None), parameter("θ", scale, None)], log_likelihood)
+            }
+            FitFamily::Beta => {
+                // E = α/(α+β), Var = E(1−E)/(α+β+1)
+                let concentration = mean * (1.0 - mean) / variance - 1.0;
+                if concentration <= 0.0 {
+                    return Err("The data is too spread out for a Beta distribution".to_string());
+                }
+                let (alpha, beta) = (mean * concentration, (1.0 - mean) * concentration);
+                let distribution = Beta::new(alpha, beta).map_err(|e| e.to_string())?;
+                let log_likelihood = values.iter().map(|&x| distribution.ln_pdf(x)).sum();
+                (vec![parameter("α", alpha, None), parameter("β", beta, None)], log_likelihood)
+            }
+        })
+    }
+
+    /// (density, CDF) at each of `xs` with parameters `[a, b]` as listed by
+    /// `parameter_names`; None when they are not valid for the family
+    fn density_and_cdf(self, [a, b]: [f64; 2], xs: &[f64]) -> Option<Vec<(f64, f64)>> {
+        use statrs::distribution::{Beta, Gamma, LogNormal};
+        fn evaluate(distribution: impl Continuous<f64, f64> + ContinuousCDF<f64, f64>, xs: &[f64]) -> Vec<(f64, f64)> {
+            xs.iter().map(|&x| (finite_or_zero(distribution.pdf(x)), distribution.cdf(x))).collect()
+        }
+        match self {
+            FitFamily::Normal => Normal::new(a, b).ok().map(|d| evaluate(d, xs)),
+            FitFamily::LogNormal => LogNormal::new(a, b).ok().map(|d| evaluate(d, xs)),
+            FitFamily::Gamma => Gamma::new(a, 1.0 / b).ok().map(|d| evaluate(d, xs)),
+            FitFamily::Beta => Beta::new(a, b).ok().map(|d| evaluate(d, xs)),
+        }
+    }
+}
+
+/// How the parameters of a fit are estimated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum FitMethod {
+    #[default]
+    MaximumLikelihood,
+    /// Match the sample mean and variance
+    Moments,
+}
+
+impl FitMethod {
+    fn label(self) -> &'static str {
+        match self {
+            FitMethod::MaximumLikelihood => "MLE",
+            FitMethod::Moments => "Method of moments",
+        }
+    }
+}
+
+/// Estimated parameter with its standard error, when one is known
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct FitParameter {
+    name: String,
+    value: f64,
+    se: Option<f64>,
+}
+
+/// Fit a distribution came from, shown on its card
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct FitSummary {
+    family: FitFamily,
+    method: FitMethod,
+    /// Name of the imported column
+    data: String,
+    n: usize,
+    /// As fitted, of ln x for the log-normal family
+    parameters: Vec<FitParameter>,
+    log_likelihood: f64,
+}
+
+impl FitSummary {
+    fn lines(&self) -> Vec<String> {
+        let of = if self.family == FitFamily::LogNormal { " of ln x" } else { "" };
+        let mut lines = vec![format!("{} {} fit to {} (n = {})", self.family.label(), self.method.label(), self.data, self.n)];
+        for parameter in &self.parameters {
+            lines.push(match parameter.se {
+                Some(se) => format!("{}{} = {:.4} ± {:.4} (SE)", parameter.name, of, parameter.value, se),
+                None => format!("{}{} = {:.4}", parameter.name, of, parameter.value),
+            });
+        }
+        lines.push(format!("log-likelihood = {:.4}", self.log_likelihood));
+        lines
+    }
+}
+
+fn default_line_width() -> f32 {
+    1.5
+}
+
+/// What the numbers of a `MarkerScheme` mean
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum MarkerKind {
+    /// Multiples k of the std dev, drawn at μ + kσ
+    #[default]
+    Sigmas,
+    /// Cumulative probabilities in percent, drawn at the quantiles
+    Quantiles,
+    /// Fixed x positions
+    Values,
+}
+
+impl MarkerKind {
+    fn label(self) -> &'static str {
+        match self {
+            MarkerKind::Sigmas => "σ multiples",
+            MarkerKind::Quantiles => "Quantiles (%)",
+            MarkerKind::Values => "x values",
+        }
+    }
+
+    /// Starting values when a curve switches to this kind
+    fn default_values(self, dist: &GaussianDistribution) -> Vec<f64> {
+        match self {
+            MarkerKind::Sigmas => vec![-3.0, -2.0, -1.0, 0.0, 1.0, 2.0, 3.0],
+            MarkerKind::Quantiles => vec![2.5, 50.0, 97.5],
+            MarkerKind::Values => vec![dist.mean],
+        }
+    }
+}
+
+/// Vertical reference lines of one distribution
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct MarkerScheme {
+    kind: MarkerKind,
+    values: Vec<f64>,
+}
+
+impl Default for MarkerScheme {
+    fn default() -> Self {
+        Self { kind: MarkerKind::Sigmas, values: vec![-3.0, -2.0, -1.0, 0.0, 1.0, 2.0, 3.0] }
+    }
+}
+
+/// Parameter uncertainty of an input Gaussian: μ ~ N(mean, mean_sd²) and
+/// ln σ ~ N(ln std_dev, log_std_dev_sd²), i.e. σ is lognormal
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct ParameterUncertainty {
+    mean_sd: f64,
+    log_std_dev_sd: f64,
+    /// Probability covered by the band, e.g. 0.9 for the 5%–95% range
+    level: f64,
+}
+
+impl Default for ParameterUncertainty {
+    fn default() -> Self {
+        Self { mean_sd: 0.2, log_std_dev_sd: 0.1, level: 0.9 }
+    }
+}
+
+/// Ranges offered by the mean and std dev sliders of an input distribution
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ParameterRanges {
+    mean: (f64, f64),
+    std_dev: (f64, f64),
+}
+
+impl Default for ParameterRanges {
+    fn default() -> Self {
+        Self {
+            mean: (-10.0, 10.0),
+            std_dev: (0.1, 5.0),
+        }
+    }
+}
+
+impl ParameterRanges {
+    /// Make the ranges cover the given parameters; invalid values (non-finite
+    /// mean, non-positive std dev) cannot be shown and are clamped instead.
+    /// Returns a description of every adjustment.
+    fn reconcile(&mut self, mean: &mut f64, std_dev: &mut f64) -> Vec<String> {
+        let mut notes = Vec::new();
+        if !mean.is_finite() {
+            notes.push(format!("mean {} is not a number, reset to 0", mean));
+            *mean = 0.0;
+        }
+        if !std_dev.is_finite() || *std_dev <= 0.0 {
+            notes.push(format!("std dev {} must be positive, clamped to {}", std_dev, self.std_dev.0));
+            *std_dev = self.std_dev.0;
+        }
+        if !(self.mean.0..=self.mean.1).contains(mean) {
+            self.mean = (self.mean.0.min(mean.floor()), self.mean.1.max(mean.ceil()));
+            notes.push(format!("mean {} is outside the slider range, widened to [{}, {}]", mean, self.mean.0, self.mean.1));
+        }
+        if !(self.std_dev.0..=self.std_dev.1).contains(std_dev) {
+            self.std_dev = (self.std_dev.0.min(*std_dev), self.std_dev.1.max(std_dev.ceil()));
+            notes.push(format!(
+                "std dev {} is outside the slider range, widened to [{}, {}]",
+                std_dev, self.std_dev.0, self.std_dev.1
+            ));
+        }
+        notes
+    }
+}
+
+/// How a derived distribution is computed from its parents
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum Operation {
+    /// Renormalized product of all parent densities
+    #[default]
+    Product,
+    /// Distribution of X / Y where X is the first parent and Y the second
+    Quotient,
+    /// Largest of the independent parent variables: F(x) = Π Fᵢ(x)
+    Maximum,
+    /// Smallest of the independent parent variables: S(x) = Π Sᵢ(x)
+    Minimum,
+    /// Normalized posterior from a prior (first parent) and likelihood (second parent)
+    BayesUpdate,
+    /// Conditional distribution of the parent given lower ≤ X ≤ upper
+    Truncate { lower: f64, upper: f64 },
+    /// Distribution of g(X) for a function g applied to the parent
+    Transform(Transform),
+    /// Parent density raised to the power β and renormalized
+    Temper { beta: f64 },
+    /// Sum (or mean, when `average` is set) of n independent copies of the parent
+    IidSum { copies: u32, average: bool },
+    /// Weighted mixture Σ wᵢ fᵢ of the parents, e.g. Bayesian model averaging.
+    /// With `by_evidence` the weights follow each parent's evidence Zᵢ.
+    Mixture { weights: Vec<f64>, by_evidence: bool },
+    /// Gaussian kernel density estimate of imported samples; no parents.
+    /// Without a bandwidth Silverman's rule picks one.
+    Kde { samples: Vec<f64>, bandwidth: Option<f64> },
+    /// Gamma (k, θ) or Beta (α, β) density with the given parameters; no parents
+    Family { family: FitFamily, parameters: [f64; 2] },
+}
+
+impl Operation {
+    fn label(&self) -> &'static str {
+        match self {
+            Operation::Product => "Product",
+            Operation::Quotient => "Quotient",
+            Operation::Maximum => "Max",
+            Operation::Minimum => "Min",
+            Operation::BayesUpdate => "Posterior",
+            Operation::Truncate { .. } => "Conditional",
+            Operation::Transform(_) => "Transform",
+            Operation::Temper { .. } => "Tempered",
+            Operation::IidSum { average: false, .. } => "Sum",
+            Operation::IidSum { average: true, .. } => "Mean",
+            Operation::Mixture { .. } => "Mixture",
+            Operation::Kde { .. } => "KDE",
+            Operation::Family { family, .. } => family.label(),
+        }
+    }
+
+    fn accepts_parent_count(&self, count: usize) -> bool {
+        match self {
+            Operation::Product | Operation::Maximum | Operation::Minimum | Operation::Mixture { .. } => count >= 2,
+            Operation::Quotient | Operation::BayesUpdate => count == 2,
+            Operation::Truncate { .. }
+            | Operation::Transform(_)
+            | Operation::Temper { .. }
+            | Operation::IidSum { .. } => count == 1,
+            Operation::Kde { .. } | Operation::Family { .. } => count == 0,
+        }
+    }
+
+    /// Derived from stored data or parameters rather than from parents
+    fn is_standalone(&self) -> bool {
+        matches!(self, Operation::Kde { .. } | Operation::Family { .. })
+    }
+
+    /// Human readable formula in terms of the parent names
+    fn expression(&self, parent_names: &[String]) -> String {
+        match self {
+            Operation::Product => parent_names.join(" × "),
+            Operation::Quotient => parent_names.join(" / "),
+            Operation::Maximum => format!("max({})", parent_names.join(", ")),
+            Operation::Minimum => format!("min({})", parent_names.join(", ")),
+            Operation::BayesUpdate => format!("{} / evidence", parent_names.join(" × ")),
+            Operation::Truncate { lower, upper } => {
+                format!("{} | {:.3} ≤ X ≤ {:.3}", parent_names.join(", "), lower, upper)
+            }
+            Operation::Transform(transform) => {
+                format!("g({}) with g(x) = {}", parent_names.join(", "), transform.formula())
+            }
+            Operation::Temper { beta } => format!("{}^{:.3} / ∫ {}^{:.3}", parent_names.join(", "), beta, parent_names.join(", "), beta),
+            Operation::IidSum { copies, average } => {
+                let sum = format!("({}₁ + … + {}ₙ), n = {}", parent_names.join(", "), parent_names.join(", "), copies);
+                if *average { format!("{} / n", sum) } else { sum }
+            }
+            Operation::Mixture { weights, by_evidence } => {
+                let terms: Vec<String> = parent_names.iter().zip(normalized_weights(weights))
+                    .map(|(name, weight)| format!("{:.3}·{}", weight, name))
+                    .collect();
+                let sum = terms.join(" + ");
+                if *by_evidence { format!("{} (wᵢ ∝ evidence Zᵢ)", sum) } else { sum }
+            }
+            Operation::Kde { samples, bandwidth } => match bandwidth {
+                Some(bandwidth) => format!("KDE of {} samples, h = {:.4}", samples.len(), bandwidth),
+                None => format!("KDE of {} samples, Silverman bandwidth", samples.len()),
+            },
+            Operation::Family { family, parameters: [a, b] } => {
+                let [a_name, b_name] = family.parameter_names();
+                format!("{}({} = {:.4}, {} = {:.4})", family.label(), a_name, a, b_name, b)
+            }
+        }
+    }
+}
+
+/// Function g of a change-of-variables transform Y = g(X)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+enum Transform {
+    #[default]
+    Exp,
+    Log,
+    Square,
+    /// User formula in x, see `Expr` for the syntax
+    Expression(String),
+}
+
+impl Transform {
+    fn formula(&self) -> String {
+        match self {
+            Transform::Exp => "exp(x)".to_string(),
+            Transform::Log => "ln(x)".to_string(),
+            Transform::Square => "x^2".to_string(),
+            Transform::Expression(source) => source.clone(),
+        }
+    }
+
+    fn function(&self) -> Result<Expr, String> {
+        Expr::parse(&self.formula())
+    }
+}
+
+/// Real function of x parsed from a formula such as `2*x^2 - sqrt(abs(x)) / pi`.
+/// Supports + - * / ^, parentheses, the constants pi and e, and
+/// exp, ln (or log), sqrt, abs, sin, cos, tan, atan, sinh, cosh, tanh.
+/// Formulas over named variables such as `X*Y + 2` are parsed with
+/// `parse_with_variables` instead.
+#[derive(Clone, Debug)]
+enum Expr {
+    X,
+    /// Index into the variable names found while parsing
+    Variable(usize),
+    Constant(f64),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(fn(f64) -> f64, Box<Expr>),
+}
+
+impl Expr {
+    fn parse(source: &str) -> Result<Expr, String> {
+        Self::parse_tokens(source, None)
+    }
+
+    /// Parse a formula whose names other than functions and constants are
+    /// variables (x included), returned in order of first appearance
+    fn parse_with_variables(source: &str) -> Result<(Expr, Vec<String>), String> {
+        let mut variables = Vec::new();
+        let expr = Self::parse_tokens(source, Some(&mut variables))?;
+        Ok((expr, variables))
+    }
+
+    fn parse_tokens(source: &str, variables: Option<&mut Vec<String>>) -> Result<Expr, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = ExprParser { tokens: &tokens, position: 0, variables };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+
+    /// Value at x; NaN or infinite where the function is undefined
+    fn eval(&self, x: f64) -> f64 {
+        self.eval_at(x, &[])
+    }
+
+    /// Value with x and the variables set; a variable without a value is NaN
+    fn eval_at(&self, x: f64, variables: &[f64]) -> f64 {
+        match self {
+            Expr::X => x,
+            Expr::Variable(index) => variables.get(*index).copied().unwrap_or(f64::NAN),
+            Expr::Constant(value) => *value,
+            Expr::Negate(inner) => -inner.eval_at(x, variables),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval_at(x, variables), rhs.eval_at(x, variables));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(function, argument) => function(argument.eval_at(x, variables)),
+        }
+    }
+
+    /// Coefficients a and constant c when the formula is a₁v₁ + … + aₖvₖ + c
+    /// in its `count` variables; None when it is not affine in them
+    fn affine(&self, count: usize) -> Option<(Vec<f64>, f64)> {
+        let is_constant = |(coefficients, _): &(Vec<f64>, f64)| coefficients.iter().all(|&a| a == 0.0);
+        let scaled = |(coefficients, constant): (Vec<f64>, f64), factor: f64| {
+            (coefficients.iter().map(|a| a * factor).collect(), constant * factor)
+        };
+        match self {
+            Expr::X => None,
+            Expr::Variable(index) => {
+                let mut coefficients = vec![0.0; count];
+                coefficients[*index] = 1.0;
+                Some((coefficients, 0.0))
+            }
+            Expr::Constant(value) => Some((vec![0.0; count], *value)),
+            Expr::Negate(inner) => inner.affine(count).map(|affine| scaled(affine, -1.0)),
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.affine(count)?, rhs.affine(count)?);
+                match op {
+                    '+' | '-' => {
+                        let sign = if *op == '+' { 1.0 } else { -1.0 };
+                        let coefficients = lhs.0.iter().zip(&rhs.0).map(|(a, b)| a + sign * b).collect();
+                        Some((coefficients, lhs.1 + sign * rhs.1))
+                    }
+                    '*' if is_constant(&lhs) => Some(scaled(rhs, lhs.1)),
+                    '*' if is_constant(&rhs) => Some(scaled(lhs, rhs.1)),
+                    '/' if is_constant(&rhs) => Some(scaled(lhs, 1.0 / rhs.1)),
+                    '^' if is_constant(&lhs) && is_constant(&rhs) => Some((vec![0.0; count], lhs.1.powf(rhs.1))),
+                    _ => None,
+                }
+            }
+            Expr::Call(function, argument) => {
+                let argument = argument.affine(count)?;
+                is_constant(&argument).then(|| (argument.0, function(argument.1)))
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                // Exponent sign, as in 1e-3
+                let exponent_sign = (d == '-' || d == '+') && number.ends_with(['e', 'E']);
+                if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exponent_sign {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(number);
+        } else if c.is_alphabetic() {
+            let mut name = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_alphanumeric()) {
+                name.push(d);
+                chars.next();
+            }
+            tokens.push(name);
+        } else if "+-*/^()".contains(c) {
+            tokens.push(c.to_string());
+            chars.next();
+        } else {
+            return Err(format!("Unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct ExprParser<'a> {
+    tokens: &'a [String],
+    position: usize,
+    // Names of the variables seen so far, when the formula may have any
+    variables: Option<&'a mut Vec<String>>,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        self.position += 1;
+        self.tokens.get(self.position - 1).map(String::as_str)
+    }
+
+    fn binary(&mut self, operators: &str, operand: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        let mut expr = operand(self)?;
+        while let Some(op) = self.peek().and_then(|t| t.chars().next()).filter(|c| operators.contains(*c)) {
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary("+-", Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary("*/", Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some("-") {
+            self.position += 1;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    // Right associative, and binds tighter than unary minus: -x^2 = -(x^2)
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.peek() == Some("^") {
+            self.position += 1;
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.next().ok_or("Unexpected end of formula")?.to_string();
+        if token == "(" {
+            let inner = self.sum()?;
+            return match self.next() {
+                Some(")") => Ok(inner),
+                _ => Err("Missing ')'".to_string()),
+            };
+        }
+        if let Ok(value) = token.parse::<f64>() {
+            return Ok(Expr::Constant(value));
+        }
+        let function: fn(f64) -> f64 = match token.as_str() {
+            "x" if self.variables.is_none() => return Ok(Expr::X),
+            "pi" => return Ok(Expr::Constant(std::f64::consts::PI)),
+            "e" => return Ok(Expr::Constant(std::f64::consts::E)),
+            "exp" => f64::exp,
+            "ln" | "log" => f64::ln,
+            "sqrt" => f64::sqrt,
+            "abs" => f64::abs,
+            "sin" => f64::sin,
+            "cos" => f64::cos,
+            "tan" => f64::tan,
+            "atan" => f64::atan,
+            "sinh" => f64::sinh,
+            "cosh" => f64::cosh,
+            "tanh" => f64::tanh,
+            name => {
+                let Some(variables) = self.variables.as_deref_mut().filter(|_| name.starts_with(|c: char| c.is_alphabetic())) else {
+                    return Err(format!("Unknown name '{}'", token));
+                };
+                let index = variables.iter().position(|variable| variable == name).unwrap_or_else(|| {
+                    variables.push(name.to_string());
+                    variables.len() - 1
+                });
+                return Ok(Expr::Variable(index));
+            }
+        };
+        if self.next() != Some("(") {
+            return Err(format!("Expected '(' after {}", token));
+        }
+        let argument = self.sum()?;
+        if self.next() != Some(")") {
+            return Err("Missing ')'".to_string());
+        }
+        Ok(Expr::Call(function, Box::new(argument)))
+    }
+}
+
+/// Result of recomputing a derived distribution from its parents
+#[derive(Default)]
+struct Derivation {
+    mean: f64,
+    std_dev: f64,
+    grid: Option<DensityGrid>,
+    warning: Option<String>,
+    normalizing_constant: Option<f64>,
+}
+
+/// Inputs of a derivation: the operation and, per parent, the density
+/// fingerprint and the evidence that mixtures can be weighted by
+#[derive(Clone, Debug, PartialEq)]
+struct DerivationKey {
+    operation: Operation,
+    parents: Vec<(u64, Option<u64>)>,
+}
+
+impl DerivationKey {
+    fn new(operation: &Operation, parents: &[&GaussianDistribution]) -> Self {
+        Self {
+            operation: operation.clone(),
+            parents: parents.iter()
+                .map(|parent| (parent.density_fingerprint(), parent.normalizing_constant.map(f64::to_bits)))
+                .collect(),
+        }
+    }
+}
+
+/// Shape of a distribution beyond its mean and standard deviation
+#[derive(Clone, Copy, Debug)]
+struct MomentSummary {
+    mean: f64,
+    std_dev: f64,
+    skewness: f64,
+    /// Kurtosis minus 3, so 0 for a Gaussian
+    excess_kurtosis: f64,
+    mode: f64,
+    median: f64,
+}
+
+/// Density sampled on a uniform grid over [x_min, x_max].
+/// Values outside the grid are treated as zero.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "GridSamples", into = "GridSamples")]
+struct DensityGrid {
+    x_min: f64,
+    x_max: f64,
+    densities: Vec<f64>,
+    // Running trapezoidal integral of `densities`, same length
+    cumulative: Vec<f64>,
+}
+
+/// A grid as saved: the samples alone, the running integral is rebuilt on load
+#[derive(Serialize, Deserialize)]
+struct GridSamples {
+    x_min: f64,
+    x_max: f64,
+    densities: Vec<f64>,
+}
+
+impl From<DensityGrid> for GridSamples {
+    fn from(grid: DensityGrid) -> Self {
+        Self { x_min: grid.x_min, x_max: grid.x_max, densities: grid.densities }
+    }
+}
+
+impl TryFrom<GridSamples> for DensityGrid {
+    type Error = String;
+
+    fn try_from(samples: GridSamples) -> Result<Self, String> {
+        let GridSamples { x_min, x_max, densities } = samples;
+        let valid_range = x_min.is_finite() && x_max.is_finite() && x_min < x_max;
+        if densities.len() < 2 || !valid_range || densities.iter().any(|d| !(d.is_finite() && *d >= 0.0)) {
+            return Err("a saved density grid needs at least two finite, non-negative samples over a positive range".to_string());
+        }
+        Ok(Self::from_densities(x_min, x_max, densities))
+    }
+}
+
+impl DensityGrid {
+    const RESOLUTION: usize = 512;
+
+    fn from_fn(x_min: f64, x_max: f64, num_points: usize, f: impl Fn(f64) -> f64) -> Self {
+        let step = (x_max - x_min) / (num_points - 1) as f64;
+        let densities: Vec<f64> = (0..num_points)
+            .map(|i| f(x_min + step * i as f64).max(0.0))
+            .collect();
+        Self::from_densities(x_min, x_max, densities)
+    }
+
+    fn from_densities(x_min: f64, x_max: f64, densities: Vec<f64>) -> Self {
+        let step = (x_max - x_min) / (densities.len() - 1) as f64;
+        let mut cumulative = Vec::with_capacity(densities.len());
+        let mut total = 0.0;
+        cumulative.push(0.0);
+        for pair in densities.windows(2) {
+            total += (pair[0] + pair[1]) * step * 0.5;
+            cumulative.push(total);
+        }
+
+        Self { x_min, x_max, densities, cumulative }
+    }
+
+    fn step(&self) -> f64 {
+        (self.x_max - self.x_min) / (self.densities.len() - 1) as f64
+    }
+
+    // Fractional grid index of x, or None when x lies outside the grid
+    fn position(&self, x: f64) -> Option<(usize, f64)> {
+        if !(self.x_min..=self.x_max).contains(&x) {
+            return None;
+        }
+        let t = (x - self.x_min) / self.step();
+        let i = (t.floor() as usize).min(self.densities.len() - 2);
+        Some((i, t - i as f64))
+    }
+
+    fn pdf(&self, x: f64) -> f64 {
+        match self.position(x) {
+            Some((i, frac)) => self.densities[i] * (1.0 - frac) + self.densities[i + 1] * frac,
+            None => 0.0,
+        }
+    }
+
+    /// Cumulative probability, normalized to the mass captured by the grid
+    fn cdf(&self, x: f64) -> f64 {
+        let mass = self.mass();
+        if x < self.x_min || mass <= 0.0 {
+            return 0.0;
+        }
+        match self.position(x) {
+            Some((i, frac)) => {
+                (self.cumulative[i] + (self.cumulative[i + 1] - self.cumulative[i]) * frac) / mass
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Probability mass captured by the grid; below 1 when tails fall outside it
+    fn mass(&self) -> f64 {
+        *self.cumulative.last().unwrap_or(&0.0)
+    }
+
+    fn normalized(mut self) -> Self {
+        let mass = self.mass();
+        if mass > 0.0 {
+            self.densities.iter_mut().for_each(|d| *d /= mass);
+            self.cumulative.iter_mut().for_each(|c| *c /= mass);
+        }
+        self
+    }
+
+    /// Density of X + Y for independent X ~ self and Y ~ other:
+    /// (f * g)(z) = ∫ f(x) g(z − x) dx, kept to ±12 std devs around the mean
+    /// so repeated convolutions don't spread the grid over empty tails
+    fn convolve(&self, other: &DensityGrid) -> DensityGrid {
+        let (mean_a, sd_a) = self.moments();
+        let (mean_b, sd_b) = other.moments();
+        let (mean, sd) = (mean_a + mean_b, (sd_a.powi(2) + sd_b.powi(2)).sqrt());
+        let z_min = (self.x_min + other.x_min).max(mean - 12.0 * sd);
+        let z_max = (self.x_max + other.x_max).min(mean + 12.0 * sd);
+
+        let step = self.step();
+        let last = self.densities.len() - 1;
+        DensityGrid::from_fn(z_min, z_max, Self::RESOLUTION, |z| {
+            self.densities.iter().enumerate()
+                .filter(|(_, d)| **d > 0.0)
+                .map(|(i, d)| {
+                    let weight = if i == 0 || i == last { 0.5 } else { 1.0 };
+                    weight * d * other.pdf(z - (self.x_min + step * i as f64))
+                })
+                .sum::<f64>() * step
+        }).normalized()
+    }
+
+    /// Density of c·X for c > 0: f(x / c) / c
+    fn scaled(mut self, factor: f64) -> DensityGrid {
+        self.x_min *= factor;
+        self.x_max *= factor;
+        // Cumulative probabilities are unchanged by rescaling x
+        self.densities.iter_mut().for_each(|d| *d /= factor);
+        self
+    }
+
+    /// Mean and standard deviation of the density restricted to the grid
+    fn moments(&self) -> (f64, f64) {
+        let mass = self.mass();
+        if mass <= 0.0 {
+            return ((self.x_min + self.x_max) / 2.0, self.x_max - self.x_min);
+        }
+        let step = self.step();
+        let last = self.densities.len() - 1;
+        let x_at = |i: usize| self.x_min + step * i as f64;
+        // Trapezoidal weights, matching `mass`; truncated grids carry density at their edges
+        let weight = |i: usize| if i == 0 || i == last { 0.5 } else { 1.0 };
+
+        let mean = self.densities.iter().enumerate()
+            .map(|(i, d)| weight(i) * x_at(i) * d)
+            .sum::<f64>() * step / mass;
+        let variance = self.densities.iter().enumerate()
+            .map(|(i, d)| weight(i) * (x_at(i) - mean).powi(2) * d)
+            .sum::<f64>() * step / mass;
+
+        (mean, variance.sqrt().max(step))
+    }
+}
+
+impl Default for GaussianDistribution {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: "Gaussian 1".to_string(),
+            mean: 0.0,
+            std_dev: 1.0,
+            parent_ids: vec![],
+            is_product: false,
+            operation: Operation::default(),
+            domain: None,
+            grid: None,
+            frozen_grid: None,
+            derived_from: None,
+            warning: None,
+            normalizing_constant: None,
+            show_unnormalized: false,
+            ranges: ParameterRanges::default(),
+            load_notice: None,
+            uncertainty: None,
+            markers: MarkerScheme::default(),
+            hidden: false,
+            line_style: StrokeStyle::Solid,
+            line_width: default_line_width(),
+            secondary_axis: false,
+            fit: None,
+        }
+    }
+}
+
+impl GaussianDistribution {
+    fn new(id: u32, name: String, mean: f64, std_dev: f64) -> Self {
+        Self {
+            id,
+            name,
+            mean,
+            std_dev,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(test)]
+    fn new_product(id: u32, name: String, parent_ids: Vec<u32>, parents: &[&GaussianDistribution]) -> Self {
+        // For Gaussian distributions, multiplication results in another Gaussian
+        // with specific mean and variance relationships
+        Self::new_derived(id, name, Operation::Product, parent_ids, parents)
+    }
+
+    fn new_derived(
+        id: u32,
+        name: String,
+        operation: Operation,
+        parent_ids: Vec<u32>,
+        parents: &[&GaussianDistribution],
+    ) -> Self {
+        let mut dist = Self {
+            id,
+            name,
+            parent_ids,
+            is_product: operation == Operation::Product,
+            operation,
+            ..Default::default()
+        };
+        dist.apply_derivation(Self::derive(&dist.operation, parents));
+        dist
+    }
+
+    fn is_derived(&self) -> bool {
+        self.is_product || !self.parent_ids.is_empty() || self.operation.is_standalone()
+    }
+
+    /// Whether a definition read from outside (a session file, a sync peer)
+    /// can be derived and drawn without failing. Derived values are checked
+    /// too, as a frozen node draws from them.
+    fn validate(&self) -> Result<(), String> {
+        if self.is_derived() && !self.operation.accepts_parent_count(self.parent_ids.len()) {
+            return Err(format!("{} cannot take {} parents", self.operation.label(), self.parent_ids.len()));
+        }
+        if !(self.mean.is_finite() && self.std_dev.is_finite() && self.std_dev > 0.0) {
+            return Err(format!("mean {} and std dev {} must be finite, with a positive std dev", self.mean, self.std_dev));
+        }
+        if self.domain.is_some_and(|(lower, upper)| !(lower.is_finite() && upper.is_finite() && lower < upper)) {
+            return Err("the domain must be a finite interval".to_string());
+        }
+        Ok(())
+    }
+
+    /// Recompute mean, std dev and (when needed) the numeric density of a
+    /// derived distribution from its current parents
+    fn derive(operation: &Operation, parents: &[&GaussianDistribution]) -> Derivation {
+        match operation {
+            Operation::Product | Operation::BayesUpdate if parents.iter().all(|p| p.has_closed_form()) => {
+                let (mean, variance) = Self::multiply_gaussians(parents);
+                Derivation {
+                    mean,
+                    std_dev: variance.sqrt(),
+                    normalizing_constant: Some(Self::gaussian_product_constant(parents)),
+                    ..Default::default()
+                }
+            }
+            Operation::Product | Operation::BayesUpdate => Self::multiply_numeric(parents),
+            Operation::Quotient => Self::divide(parents[0], parents[1]),
+            Operation::Maximum => Self::order_statistic(parents, true),
+            Operation::Minimum => Self::order_statistic(parents, false),
+            Operation::Truncate { lower, upper } => Self::truncate(parents[0], *lower, *upper),
+            Operation::Transform(transform) => Self::transform(parents[0], transform),
+            Operation::Temper { beta } => Self::temper(parents[0], *beta),
+            Operation::IidSum { copies, average } => Self::iid_sum(parents[0], *copies, *average),
+            Operation::Mixture { weights, by_evidence } => Self::mixture(parents, weights, *by_evidence),
+            Operation::Kde { samples, bandwidth } => Self::kde(samples, *bandwidth),
+            Operation::Family { family, parameters } => Self::family(*family, *parameters),
+        }
+    }
+
+    fn apply_derivation(&mut self, derivation: Derivation) {
+        self.mean = derivation.mean;
+        self.std_dev = derivation.std_dev;
+        self.grid = derivation.grid;
+        self.warning = derivation.warning;
+        self.normalizing_constant = derivation.normalizing_constant;
+    }
+
+    fn multiply_gaussians(gaussians: &[&GaussianDistribution]) -> (f64, f64) {
+        if gaussians.is_empty() {
+            return (0.0, 1.0);
+        }
+        
+        // For multiplying Gaussian PDFs: 
+        // The product of two Gaussians N(μ₁,σ₁²) * N(μ₂,σ₂²) is proportional to
+        // N((μ₁/σ₁² + μ₂/σ₂²)/(1/σ₁² + 1/σ₂²), 1/(1/σ₁² + 1/σ₂²))
+        
+        let mut precision_sum = 0.0;  // sum of 1/σ²
+        let mut weighted_mean_sum = 0.0;  // sum of μ/σ²
+        
+        for gaussian in gaussians {
+            let precision = 1.0 / (gaussian.std_dev * gaussian.std_dev);
+            precision_sum += precision;
+            weighted_mean_sum += gaussian.mean * precision;
+        }
+        
+        let result_mean = weighted_mean_sum / precision_sum;
+        let result_variance = 1.0 / precision_sum;
+        
+        (result_mean, result_variance)
+    }
+
+    /// ∫ Π N(x; μᵢ, σᵢ²) dx, computed in log space:
+    /// log Z = −½ Σ log(2πσᵢ²) + ½ log(2π/P) − ½ Σ (μᵢ − μ)²/σᵢ²
+    /// where P = Σ 1/σᵢ² and μ is the product mean
+    fn gaussian_product_constant(gaussians: &[&GaussianDistribution]) -> f64 {
+        let (mean, variance) = Self::multiply_gaussians(gaussians);
+        let two_pi = 2.0 * std::f64::consts::PI;
+        let log_z = gaussians.iter()
+            .map(|g| {
+                let var = g.std_dev * g.std_dev;
+                -0.5 * (two_pi * var).ln() - 0.5 * (g.mean - mean).powi(2) / var
+            })
+            .sum::<f64>()
+            + 0.5 * (two_pi * variance).ln();
+        log_z.exp()
+    }
+
+    // Product of densities when at least one parent is only known numerically
+    fn multiply_numeric(parents: &[&GaussianDistribution]) -> Derivation {
+        let (x_min, x_max) = parents.iter()
+            .map(|p| p.support())
+            .fold((f64::NEG_INFINITY, f64::INFINITY), |(lo, hi), (a, b)| (lo.max(a), hi.min(b)));
+
+        let grid = DensityGrid::from_fn(x_min, x_max.max(x_min + 1e-9), DensityGrid::RESOLUTION, |x| {
+            parents.iter().map(|p| p.evaluate(x)).product()
+        });
+        if x_min >= x_max || grid.mass() <= 0.0 {
+            let (mean, variance) = Self::multiply_gaussians(parents);
+            return Derivation {
+                mean,
+                std_dev: variance.sqrt(),
+                warning: Some("Parents do not overlap; showing the Gaussian approximation".to_string()),
+                normalizing_constant: Some(0.0),
+                ..Default::default()
+            };
+        }
+
+        let raw_mass = grid.mass();
+        let grid = grid.normalized();
+        let (mean, std_dev) = grid.moments();
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            normalizing_constant: Some(raw_mass),
+            ..Default::default()
+        }
+    }
+
+    // Ratio distribution: f_Z(z) = ∫ |y| f_X(z·y) f_Y(y) dy
+    fn divide(numerator: &GaussianDistribution, denominator: &GaussianDistribution) -> Derivation {
+        const Y_STEPS: usize = 800;
+        let (y_min, y_max) = denominator.support();
+        let dy = (y_max - y_min) / Y_STEPS as f64;
+        let y_samples: Vec<(f64, f64)> = (0..=Y_STEPS)
+            .map(|j| {
+                let y = y_min + dy * j as f64;
+                let weight = if j == 0 || j == Y_STEPS { 0.5 } else { 1.0 };
+                (y, weight * dy * y.abs() * denominator.evaluate(y))
+            })
+            .filter(|&(_, w)| w > 0.0)
+            .collect();
+
+        // When the denominator has appreciable density at zero the ratio has
+        // Cauchy-like tails: no finite moments and mass far from the centre
+        let heavy_tailed = denominator.evaluate(0.0) * denominator.std_dev > 1e-3;
+
+        let (x_min, x_max) = if heavy_tailed {
+            let denom_power = denominator.mean.powi(2) + denominator.std_dev.powi(2);
+            let center = numerator.mean * denominator.mean / denom_power;
+            let scale = (numerator.mean.powi(2) + numerator.std_dev.powi(2)).sqrt() / denom_power.sqrt();
+            (center - 25.0 * scale, center + 25.0 * scale)
+        } else {
+            // Delta-method approximation of the ratio's spread, widened generously
+            // because the ratio is skewed when the denominator is only moderately far from 0
+            let center = numerator.mean / denominator.mean;
+            let spread = (numerator.std_dev.powi(2) + (center * denominator.std_dev).powi(2)).sqrt()
+                / denominator.mean.abs();
+            (center - 12.0 * spread, center + 12.0 * spread)
+        };
+
+        let grid = DensityGrid::from_fn(x_min, x_max, DensityGrid::RESOLUTION, |z| {
+            y_samples.iter().map(|&(y, w)| w * numerator.evaluate(z * y)).sum()
+        });
+        let (mean, std_dev) = grid.moments();
+
+        let warning = heavy_tailed.then(|| format!(
+            "Heavy-tailed ratio: the denominator has density near 0, so the result is Cauchy-like \
+             with undefined moments. Mean/std dev are over the plotted range ({:.1}% of the mass).",
+            100.0 * grid.mass().min(1.0)
+        ));
+
+        Derivation { mean, std_dev, grid: Some(grid), warning, ..Default::default() }
+    }
+
+    // Density of max (or min) of independent variables:
+    // f_max(x) = Σᵢ fᵢ(x) Π_{j≠i} Fⱼ(x),  f_min(x) = Σᵢ fᵢ(x) Π_{j≠i} (1 − Fⱼ(x))
+    fn order_statistic(parents: &[&GaussianDistribution], maximum: bool) -> Derivation {
+        let supports: Vec<(f64, f64)> = parents.iter().map(|p| p.support()).collect();
+        let pick = |a: f64, b: f64| if maximum { a.max(b) } else { a.min(b) };
+        let x_min = supports.iter().map(|s| s.0).reduce(pick).unwrap_or(-1.0);
+        let x_max = supports.iter().map(|s| s.1).reduce(pick).unwrap_or(1.0);
+
+        let grid = DensityGrid::from_fn(x_min, x_max, DensityGrid::RESOLUTION, |x| {
+            let tails: Vec<f64> = parents.iter()
+                .map(|p| if maximum { p.cdf(x) } else { 1.0 - p.cdf(x) })
+                .collect();
+            parents.iter().enumerate()
+                .map(|(i, p)| {
+                    let others: f64 = tails.iter().enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, t)| t)
+                        .product();
+                    p.evaluate(x) * others
+                })
+                .sum()
+        }).normalized();
+        let (mean, std_dev) = grid.moments();
+
+        Derivation { mean, std_dev, grid: Some(grid), ..Default::default() }
+    }
+
+    // Conditioning on an interval: f(x | a ≤ X ≤ b) = f(x) / P(a ≤ X ≤ b) on [a, b]
+    fn truncate(parent: &GaussianDistribution, lower: f64, upper: f64) -> Derivation {
+        let (support_min, support_max) = parent.support();
+        let (x_min, x_max) = (lower.max(support_min), upper.min(support_max));
+        let probability = parent.cdf(upper) - parent.cdf(lower);
+
+        if x_min >= x_max || probability <= 0.0 {
+            // An all-zero grid draws nothing, where no grid would draw the Gaussian
+            let (grid_min, grid_max) = if lower < upper { (lower, upper) } else { (lower, lower + 1.0) };
+            return Derivation {
+                mean: parent.mean.clamp(lower, upper),
+                std_dev: parent.std_dev,
+                grid: Some(DensityGrid::from_fn(grid_min, grid_max, 2, |_| 0.0)),
+                warning: Some("The interval holds no probability mass".to_string()),
+                normalizing_constant: Some(0.0),
+            };
+        }
+
+        let grid = DensityGrid::from_fn(x_min, x_max, DensityGrid::RESOLUTION, |x| parent.evaluate(x)).normalized();
+        let (mean, std_dev) = grid.moments();
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            normalizing_constant: Some(probability),
+            ..Default::default()
+        }
+    }
+
+    // Sum of n iid copies: mean nμ and variance nσ² add up exactly; the density is
+    // the n-fold convolution f^{*n}, built by repeated squaring. The mean of the
+    // copies is the sum scaled by 1/n.
+    fn iid_sum(parent: &GaussianDistribution, copies: u32, average: bool) -> Derivation {
+        let n = copies.max(1);
+        let scale = if average { 1.0 / n as f64 } else { 1.0 };
+        let mean = n as f64 * parent.mean * scale;
+        let std_dev = (n as f64).sqrt() * parent.std_dev * scale;
+        if parent.has_closed_form() {
+            return Derivation { mean, std_dev, ..Default::default() };
+        }
+
+        let (x_min, x_max) = parent.support();
+        let base = DensityGrid::from_fn(x_min, x_max, DensityGrid::RESOLUTION, |x| parent.evaluate(x)).normalized();
+        let mut power = base.clone();
+        let mut result: Option<DensityGrid> = None;
+        let mut remaining = n;
+        loop {
+            if remaining & 1 == 1 {
+                result = Some(match result {
+                    Some(sum) => sum.convolve(&power),
+                    None => power.clone(),
+                });
+            }
+            remaining >>= 1;
+            if remaining == 0 {
+                break;
+            }
+            power = power.convolve(&power);
+        }
+        let grid = result.unwrap_or(base).scaled(scale);
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            ..Default::default()
+        }
+    }
+
+    // Mixture Σ wᵢ fᵢ: mean Σ wᵢ μᵢ and second moment Σ wᵢ (σᵢ² + μᵢ²)
+    fn mixture(parents: &[&GaussianDistribution], weights: &[f64], by_evidence: bool) -> Derivation {
+        let evidence: Option<Vec<f64>> = parents.iter().map(|p| p.normalizing_constant).collect();
+        let (raw, warning) = match evidence {
+            Some(evidence) if by_evidence => (evidence, None),
+            _ => (
+                (0..parents.len()).map(|i| weights.get(i).copied().unwrap_or(1.0)).collect(),
+                by_evidence.then(|| "Not every component has an evidence; using the manual weights".to_string()),
+            ),
+        };
+        let weights = normalized_weights(&raw);
+        if weights.iter().all(|&w| w == 0.0) {
+            return Derivation {
+                mean: parents[0].mean,
+                std_dev: parents[0].std_dev,
+                warning: Some("Mixture weights must be positive".to_string()),
+                ..Default::default()
+            };
+        }
+
+        let (x_min, x_max) = parents.iter()
+            .map(|p| p.support())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (a, b)| (lo.min(a), hi.max(b)));
+        // Components can sit far apart, so sample the union of supports more finely
+        let grid = DensityGrid::from_fn(x_min, x_max, 4 * DensityGrid::RESOLUTION, |x| {
+            parents.iter().zip(&weights).map(|(p, w)| w * p.evaluate(x)).sum()
+        }).normalized();
+        let (mean, std_dev) = if parents.iter().all(|p| p.has_closed_form()) {
+            let mean: f64 = parents.iter().zip(&weights).map(|(p, w)| w * p.mean).sum();
+            let second: f64 = parents.iter().zip(&weights).map(|(p, w)| w * (p.std_dev.powi(2) + p.mean.powi(2))).sum();
+            (mean, (second - mean * mean).max(0.0).sqrt())
+        } else {
+            grid.moments()
+        };
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            warning,
+            ..Default::default()
+        }
+    }
+
+    // Gaussian kernels summed on a grid reaching a few bandwidths past the data,
+    // fine enough for several points per bandwidth
+    fn kde(samples: &[f64], bandwidth: Option<f64>) -> Derivation {
+        let mut sorted: Vec<f64> = samples.iter().copied().filter(|x| x.is_finite()).collect();
+        if sorted.len() < 2 {
+            // An all-zero grid draws nothing, where no grid would draw N(0, 1)
+            let center = sorted.first().copied().unwrap_or(0.0);
+            return Derivation {
+                mean: center,
+                std_dev: 1.0,
+                grid: Some(DensityGrid::from_fn(center - 1.0, center + 1.0, 2, |_| 0.0)),
+                warning: Some("A KDE needs at least two samples".to_string()),
+                ..Default::default()
+            };
+        }
+        sorted.sort_by(f64::total_cmp);
+        let bandwidth = bandwidth.filter(|h| *h > 0.0 && h.is_finite())
+            .unwrap_or_else(|| data::silverman_bandwidth(&sorted));
+        let (x_min, x_max) = (sorted[0] - 4.0 * bandwidth, sorted[sorted.len() - 1] + 4.0 * bandwidth);
+        let points = (((x_max - x_min) / bandwidth * 8.0) as usize).clamp(DensityGrid::RESOLUTION, 8192);
+        let grid = DensityGrid::from_fn(x_min, x_max, points, |x| data::kernel_density(&sorted, bandwidth, x)).normalized();
+        let (mean, std_dev) = grid.moments();
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            ..Default::default()
+        }
+    }
+
+    // Gamma and Beta densities on a grid over their support; an infinite
+    // density at the boundary (shape below 1) is left out of the grid
+    fn family(family: FitFamily, [a, b]: [f64; 2]) -> Derivation {
+        use statrs::distribution::{Beta, Gamma};
+        // Flagged with an all-zero grid, so the node draws nothing rather than N(0, 1)
+        let invalid = |message: String| Derivation {
+            std_dev: 1.0,
+            grid: Some(DensityGrid::from_fn(0.0, 1.0, 2, |_| 0.0)),
+            warning: Some(message),
+            ..Default::default()
+        };
+        let grid = match family {
+            FitFamily::Gamma => match Gamma::new(a, 1.0 / b) {
+                Ok(gamma) => {
+                    let upper = gamma.inverse_cdf(0.99995);
+                    DensityGrid::from_fn(0.0, upper, 4 * DensityGrid::RESOLUTION, |x| finite_or_zero(gamma.pdf(x)))
+                }
+                Err(_) => return invalid(format!("Gamma needs k > 0 and θ > 0, got k = {}, θ = {}", a, b)),
+            },
+            FitFamily::Beta => match Beta::new(a, b) {
+                Ok(beta) => DensityGrid::from_fn(0.0, 1.0, 4 * DensityGrid::RESOLUTION, |x| finite_or_zero(beta.pdf(x))),
+                Err(_) => return invalid(format!("Beta needs α > 0 and β > 0, got α = {}, β = {}", a, b)),
+            },
+            FitFamily::Normal | FitFamily::LogNormal => return invalid(format!("{} is not a grid family", family.label())),
+        };
+        let grid = grid.normalized();
+        let (mean, std_dev) = grid.moments();
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            ..Default::default()
+        }
+    }
+
+    // Tempering p(x)^β / ∫ p^β: flattens the density for β < 1, sharpens it for β > 1
+    fn temper(parent: &GaussianDistribution, beta: f64) -> Derivation {
+        if beta <= 0.0 || !beta.is_finite() {
+            return Derivation {
+                mean: parent.mean,
+                std_dev: parent.std_dev,
+                warning: Some(format!("β must be positive, got {}", beta)),
+                ..Default::default()
+            };
+        }
+        // N(μ, σ²)^β ∝ N(μ, σ²/β)
+        if parent.has_closed_form() {
+            return Derivation {
+                mean: parent.mean,
+                std_dev: parent.std_dev / beta.sqrt(),
+                ..Default::default()
+            };
+        }
+
+        let (x_min, x_max) = parent.support();
+        let grid = DensityGrid::from_fn(x_min, x_max, DensityGrid::RESOLUTION, |x| parent.evaluate(x).powf(beta));
+        if grid.mass() <= 0.0 {
+            return Derivation {
+                mean: parent.mean,
+                std_dev: parent.std_dev,
+                warning: Some("The tempered density has no mass".to_string()),
+                ..Default::default()
+            };
+        }
+        let grid = grid.normalized();
+        let (mean, std_dev) = grid.moments();
+        // Flattening lifts the tails, which are cut off at the parent's support
+        let warning = (beta < 1.0).then(|| format!(
+            "Tails beyond [{:.3}, {:.3}] are not included for β < 1", x_min, x_max
+        ));
+        Derivation {
+            mean,
+            std_dev,
+            grid: Some(grid),
+            warning,
+            ..Default::default()
+        }
+    }
+
+    // Change of variables Y = g(X): f_Y(y) = Σ f_X(xᵢ) / |g'(xᵢ)| over the roots g(xᵢ) = y.
+    // Evaluated in its discrete form: the mass of each short x-segment is spread
+    // evenly over the y-interval it maps to, which handles non-monotone g (x²)
+    // and integrable spikes (where g' = 0) without root finding.
+    fn transform(parent: &GaussianDistribution, transform: &Transform) -> Derivation {
+        const SEGMENTS: usize = 8192;
+        const TAIL: f64 = 1e-5;
+
+        let fallback = |warning: String| Derivation {
+            mean: parent.mean,
+            std_dev: parent.std_dev,
+            warning: Some(warning),
+            ..Default::default()
+        };
+        let g = match transform.function() {
+            Ok(g) => g,
+            Err(error) => return fallback(format!("Invalid formula: {}", error)),
+        };
+
+        let (x_min, x_max) = parent.support();
+        let dx = (x_max - x_min) / SEGMENTS as f64;
+        let xs: Vec<f64> = (0..=SEGMENTS).map(|i| x_min + dx * i as f64).collect();
+        let fs: Vec<f64> = xs.iter().map(|&x| parent.evaluate(x)).collect();
+        let gs: Vec<f64> = xs.iter().map(|&x| g.eval(x)).collect();
+
+        // (lowest y, highest y, mass) of every segment on which g is defined
+        let mut total_mass = 0.0;
+        let mut segments = Vec::with_capacity(SEGMENTS);
+        for i in 0..SEGMENTS {
+            let mass = (fs[i] + fs[i + 1]) * dx * 0.5;
+            total_mass += mass;
+            if gs[i].is_finite() && gs[i + 1].is_finite() && mass > 0.0 {
+                segments.push((gs[i].min(gs[i + 1]), gs[i].max(gs[i + 1]), mass));
+            }
+        }
+        let defined_mass: f64 = segments.iter().map(|s| s.2).sum();
+        if defined_mass <= 0.0 {
+            return fallback(format!("g(x) = {} is undefined wherever X has mass", transform.formula()));
+        }
+
+        // Moments treat each segment's mass as uniform over its y-interval
+        let mean = segments.iter().map(|(lo, hi, m)| m * (lo + hi) / 2.0).sum::<f64>() / defined_mass;
+        let variance = segments.iter()
+            .map(|(lo, hi, m)| m * (((lo + hi) / 2.0 - mean).powi(2) + (hi - lo).powi(2) / 12.0))
+            .sum::<f64>() / defined_mass;
+
+        // Plot range: central quantiles of Y, so heavy tails (exp) don't squash the bulk
+        let mut by_y: Vec<(f64, f64)> = segments.iter().map(|(lo, hi, m)| ((lo + hi) / 2.0, *m)).collect();
+        by_y.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let quantile = |p: f64| {
+            let mut cumulative = 0.0;
+            by_y.iter()
+                .find(|(_, m)| { cumulative += m; cumulative >= p * defined_mass })
+                .map_or(by_y[by_y.len() - 1].0, |(y, _)| *y)
+        };
+        let (y_min, y_max) = (quantile(TAIL), quantile(1.0 - TAIL));
+        if y_max - y_min <= 1e-12 * (1.0 + mean.abs()) {
+            return Derivation {
+                mean,
+                std_dev: variance.sqrt().max(1e-9),
+                warning: Some(format!("g(x) = {} is constant where X has mass, so g(X) is (nearly) a point mass", transform.formula())),
+                ..Default::default()
+            };
+        }
+
+        // Density at grid point j = mass falling in the cell [y_j − dy/2, y_j + dy/2] / dy
+        let cells = DensityGrid::RESOLUTION * 4;
+        let dy = (y_max - y_min) / (cells - 1) as f64;
+        let cell_of = |y: f64| ((y - y_min) / dy + 0.5).floor();
+        let mut masses = vec![0.0; cells];
+        for &(lo, hi, mass) in &segments {
+            let (first, last) = (cell_of(lo).max(0.0), cell_of(hi).min((cells - 1) as f64));
+            if first > last {
+                continue;
+            }
+            if hi - lo <= 0.0 {
+                masses[first as usize] += mass;
+                continue;
+            }
+            for (j, cell_mass) in masses.iter_mut().enumerate().take(last as usize + 1).skip(first as usize) {
+                let cell_lo = y_min + (j as f64 - 0.5) * dy;
+                let overlap = (hi.min(cell_lo + dy) - lo.max(cell_lo)).max(0.0);
+                *cell_mass += mass * overlap / (hi - lo);
+            }
+        }
+        let grid = DensityGrid::from_fn(y_min, y_max, cells, |y| masses[cell_of(y) as usize] / dy);
+
+        let undefined = 1.0 - defined_mass / total_mass;
+        let warning = (undefined > 1e-6).then(|| format!(
+            "g(x) = {} is undefined for {:.2}% of X's mass, which is dropped",
+            transform.formula(), undefined * 100.0
+        ));
+        Derivation {
+            mean,
+            std_dev: variance.sqrt(),
+            grid: Some(grid),
+            warning,
+            ..Default::default()
+        }
+    }
+
+    /// Hash of everything that shapes the density, used to invalidate cached samples
+    fn density_fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.mean.to_bits().hash(&mut hasher);
+        self.std_dev.to_bits().hash(&mut hasher);
+        self.domain.map(|(a, b)| (a.to_bits(), b.to_bits())).hash(&mut hasher);
+        if let Some(grid) = &self.grid {
+            grid.x_min.to_bits().hash(&mut hasher);
+            grid.x_max.to_bits().hash(&mut hasher);
+            for density in &grid.densities {
+                density.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// True when the density is an unclipped Gaussian, so closed-form identities apply
+    fn has_closed_form(&self) -> bool {
+        self.grid.is_none() && self.domain.is_none()
+    }
+
+    /// Largest value of the density: exact for a plain Gaussian, otherwise
+    /// the highest of 2000 samples over the support
+    fn peak_density(&self) -> f64 {
+        if self.has_closed_form() {
+            return 1.0 / (self.std_dev * (2.0 * std::f64::consts::PI).sqrt());
+        }
+        let (lower, upper) = self.support();
+        if lower >= upper {
+            return 0.0;
+        }
+        sample_curve(lower, upper, 2000, |x| self.evaluate(x)).iter()
+            .map(|[_, y]| *y)
+            .filter(|y| y.is_finite())
+            .fold(0.0, f64::max)
+    }
+
+    /// Interval holding essentially all of the probability mass
+    fn support(&self) -> (f64, f64) {
+        let (lower, upper) = match &self.grid {
+            Some(grid) => (grid.x_min, grid.x_max),
+            None => (self.mean - 8.0 * self.std_dev, self.mean + 8.0 * self.std_dev),
+        };
+        self.clip_to_domain(lower, upper)
+    }
+
+    /// Intersect [x_min, x_max] with the domain override, if any.
+    /// The result is empty (x_min > x_max) when they do not overlap.
+    fn clip_to_domain(&self, x_min: f64, x_max: f64) -> (f64, f64) {
+        match self.domain {
+            Some((lower, upper)) => (x_min.max(lower), x_max.min(upper)),
+            None => (x_min, x_max),
+        }
+    }
+
+    fn in_domain(&self, x: f64) -> bool {
+        self.domain.is_none_or(|(lower, upper)| (lower..=upper).contains(&x))
+    }
+
+    fn evaluate(&self, x: f64) -> f64 {
+        if !self.in_domain(x) {
+            return 0.0;
+        }
+        if let Some(grid) = &self.grid {
+            return grid.pdf(x);
+        }
+        let normal = Normal::new(self.mean, self.std_dev).unwrap();
+        normal.pdf(x)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        match self.domain {
+            Some((lower, _)) if x < lower => 0.0,
+            Some((lower, upper)) => self.unclipped_cdf(x.min(upper)) - self.unclipped_cdf(lower),
+            None => self.unclipped_cdf(x),
+        }
+    }
+
+    /// `cdf` renormalized to the domain, so it reaches 1; the inverse of `quantile`
+    fn quantile_level(&self, x: f64) -> f64 {
+        if self.has_closed_form() {
+            return self.cdf(x);
+        }
+        let (lower, upper) = self.support();
+        let (cdf_lower, cdf_upper) = (self.cdf(lower), self.cdf(upper));
+        if cdf_upper <= cdf_lower {
+            return 0.0;
+        }
+        ((self.cdf(x) - cdf_lower) / (cdf_upper - cdf_lower)).clamp(0.0, 1.0)
+    }
+
+    /// Inverse of `cdf`, renormalized to the domain: the x with P(X ≤ x) = p
+    fn quantile(&self, p: f64) -> f64 {
+        let p = p.clamp(0.0, 1.0);
+        if self.has_closed_form() {
+            return Normal::new(self.mean, self.std_dev).unwrap().inverse_cdf(p);
+        }
+        let (mut lower, mut upper) = self.support();
+        let (cdf_lower, cdf_upper) = (self.cdf(lower), self.cdf(upper));
+        let target = cdf_lower + p * (cdf_upper - cdf_lower);
+        for _ in 0..64 {
+            let middle = 0.5 * (lower + upper);
+            if self.cdf(middle) < target {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        0.5 * (lower + upper)
+    }
+
+    /// Intervals holding `level` of the mass: equal-tailed for closed-form
+    /// Gaussians, otherwise the highest-density region, which splits into
+    /// several intervals for multimodal curves
+    fn credible_regions(&self, level: f64) -> Vec<(f64, f64)> {
+        let level = level.clamp(0.0, 1.0);
+        if self.has_closed_form() {
+            let tail = (1.0 - level) / 2.0;
+            return vec![(self.quantile(tail), self.quantile(1.0 - tail))];
+        }
+        let (lower, upper) = self.support();
+        if lower >= upper {
+            return Vec::new();
+        }
+        const CELLS: usize = 4000;
+        let width = (upper - lower) / CELLS as f64;
+        let densities: Vec<f64> = (0..CELLS).map(|i| self.evaluate(lower + (i as f64 + 0.5) * width)).collect();
+        let total: f64 = densities.iter().sum();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        // Lowest density whose superlevel set still reaches the target mass
+        let mut sorted = densities.clone();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        let mut mass = 0.0;
+        let mut threshold = 0.0;
+        for density in sorted {
+            mass += density;
+            threshold = density;
+            if mass >= level * total {
+                break;
+            }
+        }
+
+        let mut regions: Vec<(f64, f64)> = Vec::new();
+        let mut inside = false;
+        for (i, &density) in densities.iter().enumerate() {
+            let x = lower + i as f64 * width;
+            match (density >= threshold && density > 0.0, inside) {
+                (true, false) => regions.push((x, x + width)),
+                (true, true) => regions.last_mut().unwrap().1 = x + width,
+                _ => {}
+            }
+            inside = density >= threshold && density > 0.0;
+        }
+        regions
+    }
+
+    fn unclipped_cdf(&self, x: f64) -> f64 {
+        if let Some(grid) = &self.grid {
+            return grid.cdf(x);
+        }
+        let normal = Normal::new(self.mean, self.std_dev).unwrap();
+        normal.cdf(x)
+    }
+    
+    #[cfg(test)]
+    fn generate_shading_polygon(&self, x_min: f64, x_max: f64, num_points: usize) -> PlotPoints {
+        let mut points = Vec::with_capacity(num_points + 2);
+        
+        // Create clean polygon: bottom-left -> curve points -> bottom-right
+        // Key insight: don't duplicate corner points in the curve sampling
+        
+        points.push([x_min, 0.0]);  // Bottom-left corner
+        
+        // Generate curve points excluding the exact boundaries to avoid duplication
+        if num_points == 1 {
+            // Single point case: use center
+            let x = (x_min + x_max) / 2.0;
+            let y = self.evaluate(x);
+            points.push([x, y]);
+        } else if num_points > 1 {
+            // Multiple points: space them between (but not including) the boundaries
+            for i in 1..=num_points {
+                let x = x_min + (x_max - x_min) * i as f64 / (num_points + 1) as f64;
+                let y = self.evaluate(x);
+                points.push([x, y]);
+            }
+        }
+        
+        points.push([x_max, 0.0]);  // Bottom-right corner
+        
+        // Let polygon fill algorithm automatically close from last point to first
+        PlotPoints::new(points)
+    }
+    
+    /// Mean, spread and shape; Gaussians are exact, anything else is integrated
+    /// numerically over the support (Simpson's rule), with the mode taken as the
+    /// highest sample
+    fn moment_summary(&self) -> MomentSummary {
+        const INTERVALS: usize = 4000;
+        if self.has_closed_form() {
+            return MomentSummary {
+                mean: self.mean,
+                std_dev: self.std_dev,
+                skewness: 0.0,
+                excess_kurtosis: 0.0,
+                mode: self.mean,
+                median: self.mean,
+            };
+        }
+
+        let (lower, upper) = self.support();
+        let step = (upper - lower) / INTERVALS as f64;
+        let samples: Vec<(f64, f64)> = (0..=INTERVALS)
+            .map(|i| {
+                let x = lower + step * i as f64;
+                let weight = if i == 0 || i == INTERVALS { 1.0 } else if i % 2 == 1 { 4.0 } else { 2.0 };
+                (x, weight * self.evaluate(x) * step / 3.0)
+            })
+            .collect();
+        let mass: f64 = samples.iter().map(|(_, w)| w).sum();
+        if upper <= lower || mass <= 0.0 {
+            return MomentSummary {
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+                skewness: f64::NAN,
+                excess_kurtosis: f64::NAN,
+                mode: f64::NAN,
+                median: f64::NAN,
+            };
+        }
+
+        let mean = samples.iter().map(|(x, w)| x * w).sum::<f64>() / mass;
+        let central = |power: i32| samples.iter().map(|(x, w)| (x - mean).powi(power) * w).sum::<f64>() / mass;
+        let variance = central(2);
+        let mode = (0..=INTERVALS)
+            .map(|i| lower + step * i as f64)
+            .max_by(|a, b| self.evaluate(*a).total_cmp(&self.evaluate(*b)))
+            .unwrap_or(mean);
+        MomentSummary {
+            mean,
+            std_dev: variance.sqrt(),
+            skewness: central(3) / variance.powf(1.5),
+            excess_kurtosis: central(4) / (variance * variance) - 3.0,
+            mode,
+            median: self.quantile(0.5),
+        }
+    }
+
+    /// Differential entropy −∫ p ln p dx in nats; ½ ln(2πeσ²) for a Gaussian
+    fn entropy(&self) -> f64 {
+        const INTERVALS: usize = 4000;
+        if self.has_closed_form() {
+            return 0.5 * (2.0 * std::f64::consts::PI * std::f64::consts::E * self.std_dev.powi(2)).ln();
+        }
+        let (lower, upper) = self.support();
+        if upper <= lower {
+            return f64::NAN;
+        }
+        let step = (upper - lower) / INTERVALS as f64;
+        let (mut mass, mut integral) = (0.0, 0.0);
+        for i in 0..=INTERVALS {
+            let density = self.evaluate(lower + step * i as f64);
+            let weight = if i == 0 || i == INTERVALS { 1.0 } else if i % 2 == 1 { 4.0 } else { 2.0 };
+            mass += weight * density;
+            if density > 0.0 {
+                integral -= weight * density * density.ln();
+            }
+        }
+        if mass <= 0.0 {
+            return f64::NAN;
+        }
+        // Renormalize: for p = f / m, −∫ p ln p = −∫ f ln f / m + ln m
+        let (mass, integral) = (mass * step / 3.0, integral * step / 3.0);
+        integral / mass + mass.ln()
+    }
+
+    /// P(X > x), renormalized like `quantile_level`. Gaussians use the exact
+    /// upper tail, which stays accurate far beyond where 1 − F(x) rounds to 0.
+    fn survival(&self, x: f64) -> f64 {
+        if self.has_closed_form() {
+            return Normal::new(self.mean, self.std_dev).unwrap().sf(x);
+        }
+        1.0 - self.quantile_level(x)
+    }
+
+    /// Data x where a parameter handle sits
+    fn handle_x(&self, handle: ParameterHandle) -> f64 {
+        match handle {
+            ParameterHandle::Mean => self.mean,
+            ParameterHandle::StdDev => self.mean + self.std_dev,
+        }
+    }
+
+    /// Move a parameter handle to x: the mean follows it, the spread is its
+    /// distance from the mean. Slider ranges widen to fit.
+    fn drag_handle(&mut self, handle: ParameterHandle, x: f64) {
+        match handle {
+            ParameterHandle::Mean => self.mean = x,
+            ParameterHandle::StdDev => self.std_dev = (x - self.mean).abs().max(1e-6),
+        }
+        self.ranges.reconcile(&mut self.mean, &mut self.std_dev);
+    }
+
+    /// ln f(x), computed directly for Gaussians so far tails do not underflow
+    /// to −∞ as exp(−z²/2) would
+    fn ln_density(&self, x: f64) -> f64 {
+        if self.has_closed_form() {
+            let z = (x - self.mean) / self.std_dev;
+            return -0.5 * z * z - (self.std_dev * (2.0 * std::f64::consts::PI).sqrt()).ln();
+        }
+        self.evaluate(x).ln()
+    }
+
+    /// h(x) = f(x) / S(x) with the density renormalized like `survival`;
+    /// NaN where nothing survives
+    fn hazard(&self, x: f64) -> f64 {
+        let survival = self.survival(x);
+        if survival <= 0.0 {
+            return f64::NAN;
+        }
+        let mass = if self.has_closed_form() {
+            1.0
+        } else {
+            let (lower, upper) = self.support();
+            self.cdf(upper) - self.cdf(lower)
+        };
+        self.evaluate(x) / mass / survival
+    }
+
+    /// E[e^{tX}], infinite when it diverges over the support
+    fn mgf(&self, t: f64) -> f64 {
+        if self.has_closed_form() {
+            return (self.mean * t + 0.5 * (self.std_dev * t).powi(2)).exp();
+        }
+        self.expectation(|x| (t * x).exp())
+    }
+
+    /// |E[e^{itX}]|, which is at most 1 and equals 1 at t = 0
+    fn characteristic_modulus(&self, t: f64) -> f64 {
+        if self.has_closed_form() {
+            return (-0.5 * (self.std_dev * t).powi(2)).exp();
+        }
+        let (cos, sin) = (self.expectation(|x| (t * x).cos()), self.expectation(|x| (t * x).sin()));
+        cos.hypot(sin).min(1.0)
+    }
+
+    /// E[g(X)] over the support with Simpson's rule, renormalized to the
+    /// curve's own mass; NaN when there is none
+    fn expectation(&self, g: impl Fn(f64) -> f64) -> f64 {
+        const INTERVALS: usize = 1000;
+        let (lower, upper) = self.support();
+        if upper <= lower {
+            return f64::NAN;
+        }
+        let step = (upper - lower) / INTERVALS as f64;
+        let (mut mass, mut total) = (0.0, 0.0);
+        for i in 0..=INTERVALS {
+            let x = lower + step * i as f64;
+            let weight = if i == 0 || i == INTERVALS { 1.0 } else if i % 2 == 1 { 4.0 } else { 2.0 };
+            let density = weight * self.evaluate(x);
+            mass += density;
+            if density > 0.0 {
+                total += density * g(x);
+            }
+        }
+        if mass > 0.0 { total / mass } else { f64::NAN }
+    }
+
+    /// x positions of the marker lines, each flagged when it marks the
+    /// centre (the mean or the median) and is drawn heavier
+    fn marker_positions(&self) -> Vec<(f64, bool)> {
+        let values = self.markers.values.iter().copied();
+        match self.markers.kind {
+            MarkerKind::Sigmas => values.map(|k| (self.mean + k * self.std_dev, k == 0.0)).collect(),
+            MarkerKind::Quantiles => values
+                .filter(|p| *p > 0.0 && *p < 100.0)
+                .map(|p| (self.quantile(p / 100.0), p == 50.0))
+                .collect(),
+            MarkerKind::Values => values.map(|x| (x, false)).collect(),
+        }
+    }
+}
+
+impl PdfViewerApp {
+    fn update_product_distributions(&mut self) {
+        // Derived distributions are always created after their parents, so
+        // updating in id order refreshes chains (products of products) in one pass
+        // KDEs and parametric families depend on their own data alone, so they
+        // are only built when their grid is missing
+        let mut derived_ids: Vec<u32> = self.distributions
+            .values()
+            .filter(|dist| !dist.parent_ids.is_empty() || (dist.operation.is_standalone() && dist.grid.is_none()))
+            .map(|dist| dist.id)
+            .collect();
+        derived_ids.sort_unstable();
+
+        for id in derived_ids {
+            let dist = &self.distributions[&id];
+            let parent_refs: Vec<&GaussianDistribution> = dist.parent_ids
+                .iter()
+                .filter_map(|parent_id| self.distributions.get(parent_id))
+                .collect();
+
+            // A deleted parent freezes the derived distribution at its last values
+            if parent_refs.len() == dist.parent_ids.len() {
+                let mut key = DerivationKey::new(&dist.operation, &parent_refs);
+                if dist.derived_from.as_ref() == Some(&key) {
+                    continue;
+                }
+                let derivation = GaussianDistribution::derive(&dist.operation, &parent_refs);
+                let evidence: Option<Vec<f64>> = parent_refs.iter().map(|p| p.normalizing_constant).collect();
+                if let Some(dist) = self.distributions.get_mut(&id) {
+                    dist.apply_derivation(derivation);
+                    dist.frozen_grid = None;
+                    // Evidence weights are mirrored so the panel shows them and unticking keeps them
+                    if let (Operation::Mixture { weights, by_evidence: true }, Some(evidence)) = (&mut dist.operation, evidence) {
+                        *weights = normalized_weights(&evidence);
+                    }
+                    key.operation = dist.operation.clone();
+                    dist.derived_from = Some(key);
+                }
+            } else if let Some(dist) = self.distributions.get_mut(&id) {
+                // Keep a copy of the grid to save, or bring back the saved one after a load
+                if dist.frozen_grid.is_none() {
+                    dist.frozen_grid = dist.grid.clone();
+                } else if dist.grid.is_none() {
+                    dist.grid = dist.frozen_grid.clone();
+                }
+            }
+        }
+    }
+
+    /// Create a derived distribution from the given parents, returning its id
+    fn add_derived(&mut self, operation: Operation, parent_ids: Vec<u32>) -> Option<u32> {
+        let parent_refs: Vec<&GaussianDistribution> = parent_ids
+            .iter()
+            .filter_map(|id| self.distributions.get(id))
+            .collect();
+
+        if parent_refs.len() != parent_ids.len() || !operation.accepts_parent_count(parent_refs.len()) {
+            return None;
+        }
+
+        let id = self.next_id;
+        let name = format!("{} {}", operation.label(), id + 1);
+        let dist = GaussianDistribution::new_derived(id, name, operation, parent_ids, &parent_refs);
+        self.distributions.insert(id, dist);
+        self.next_id += 1;
+        Some(id)
+    }
+
+    /// Name of a curve in the plot legend and hover labels
+    fn curve_label(&self, dist: &GaussianDistribution, density_view: bool) -> String {
+        let display_scale = if density_view { self.display_scale(dist) } else { 1.0 };
+        if display_scale != 1.0 {
+            format!("{} (raw, S = {:.3e})", dist.name, display_scale)
+        } else {
+            dist.name.clone()
+        }
+    }
+
+    /// Show only `id`, or everything again if it already is the only curve shown
+    fn isolate_curve(&mut self, id: u32) {
+        let isolated = self.distributions.values().all(|dist| dist.hidden == (dist.id != id));
+        for dist in self.distributions.values_mut() {
+            dist.hidden = !isolated && dist.id != id;
+        }
+    }
+
+    /// Factor from density to plotted height: the display scale, times the
+    /// secondary axis factor for curves on that axis
+    fn curve_scale(&self, dist: &GaussianDistribution) -> f64 {
+        if self.peak_normalized {
+            let peak = dist.peak_density();
+            return if peak > 0.0 { 1.0 / peak } else { 1.0 };
+        }
+        match self.secondary_scale {
+            Some(factor) if dist.secondary_axis => factor * self.display_scale(dist),
+            _ => self.display_scale(dist),
+        }
+    }
+
+    /// Factor that gives the tallest secondary-axis curve the height of the
+    /// tallest primary one over the visible range; None unless both axes have
+    /// visible curves. Only densities on a linear axis need it.
+    fn secondary_axis_scale(&self) -> Option<f64> {
+        if self.plot_mode != PlotMode::Density || self.log_y || self.peak_normalized {
+            return None;
+        }
+        let (x_min, x_max) = self.data_range();
+        let peak = |secondary: bool| -> f64 {
+            self.distributions.values()
+                .filter(|dist| !dist.hidden && dist.secondary_axis == secondary)
+                .flat_map(|dist| {
+                    // Sampled over the curve's own support, so narrow peaks are not missed
+                    let scale = self.display_scale(dist);
+                    let (lower, upper) = dist.support();
+                    let (lower, upper) = (lower.max(x_min), upper.min(x_max));
+                    if lower < upper { sample_curve(lower, upper, 400, |x| scale * dist.evaluate(x)) } else { Vec::new() }
+                })
+                .map(|[_, y]| y)
+                .filter(|y| y.is_finite())
+                .fold(0.0, f64::max)
+        };
+        let (primary, secondary) = (peak(false), peak(true));
+        (primary > 0.0 && secondary > 0.0).then(|| primary / secondary)
+    }
+
+    /// Factor applied to a curve when drawing: the normalizing constant S of
+    /// products and posteriors in amplitude view, 1 otherwise
+    fn display_scale(&self, dist: &GaussianDistribution) -> f64 {
+        match (&dist.operation, dist.normalizing_constant) {
+            (Operation::Product | Operation::BayesUpdate, Some(scale))
+                if self.show_product_amplitude && dist.is_derived() => scale,
+            _ => 1.0,
+        }
+    }
+    
+    /// Standardization of the horizontal axis; the identity unless a reference
+    /// distribution is picked
+    fn axis_map(&self) -> AxisMap {
+        self.z_reference
+            .and_then(|id| self.distributions.get(&id))
+            .filter(|reference| reference.std_dev > 0.0 && reference.std_dev.is_finite())
+            .map_or(AxisMap::IDENTITY, |reference| AxisMap { origin: reference.mean, unit: reference.std_dev, log_y: false })
+            .with_log_y(self.log_y)
+    }
+
+    /// Switch the z reference, carrying the current framing over to the new axis
+    fn set_z_reference(&mut self, reference: Option<u32>) {
+        self.remap_axes(|app| app.z_reference = reference);
+    }
+
+    /// Switch the y-axis between linear and log, keeping the visible range
+    fn set_log_y(&mut self, log_y: bool) {
+        self.remap_axes(|app| app.log_y = log_y);
+    }
+
+    fn remap_axes(&mut self, change: impl FnOnce(&mut Self)) {
+        let old = self.axis_map();
+        change(self);
+        let new = self.axis_map();
+        if let Some(bounds) = &self.plot_bounds {
+            let bounds = view_bounds(new.view_bounds(old.data_bounds(bounds_array(bounds))));
+            self.plot_bounds = Some(bounds);
+            self.requested_bounds = Some(bounds);
+        }
+    }
+
+    /// Visible range in data units, i.e. `get_plot_range` undone from z
+    fn data_range(&self) -> (f64, f64) {
+        let (view_min, view_max) = self.get_plot_range();
+        let axis = self.axis_map();
+        (axis.to_data(view_min), axis.to_data(view_max))
+    }
+
+    fn get_plot_range(&self) -> (f64, f64) {
+        if let Some([x_min, x_max, _, _]) = self.view_lock {
+            (x_min, x_max)
+        } else if let Some(bounds) = &self.plot_bounds {
+            (bounds.min()[0], bounds.max()[0])
+        } else {
+            (-6.0, 6.0)
+        }
+    }
+    
+    fn auto_fit_view(&mut self) {
+        if self.distributions.is_empty() {
+            return;
+        }
+        
+        let mut min_mean = f64::INFINITY;
+        let mut max_mean = f64::NEG_INFINITY;
+        let mut max_std_dev: f64 = 0.0;
+        
+        for dist in self.distributions.values() {
+            min_mean = min_mean.min(dist.mean);
+            max_mean = max_mean.max(dist.mean);
+            max_std_dev = max_std_dev.max(dist.std_dev);
+        }
+        
+        // Extend range by 4 standard deviations to show tails
+        let margin = 4.0 * max_std_dev;
+        let x_min = min_mean - margin;
+        let x_max = max_mean + margin;
+        
+        // Calculate reasonable y bounds
+        let y_max = 1.0 / (max_std_dev * (2.0 * std::f64::consts::PI).sqrt()) * 1.1;
+        
+        let bounds = view_bounds(self.axis_map().view_bounds([x_min, x_max, 0.0, y_max]));
+        self.plot_bounds = Some(bounds);
+        self.requested_bounds = Some(bounds);
+    }
+    
+    /// Save to `path`, in the binary format when its extension asks for it
+    fn save_session_to(&self, path: &std::path::Path) -> Result<(), String> {
+        let binary = path.extension().is_some_and(|extension| extension == session::BINARY_EXTENSION);
+        let contents = if binary { self.save_session_binary()? } else { self.save_session()?.into_bytes() };
+        write_replacing(path, contents).map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+    }
+
+    /// Replace the session with the one in `path`, in either format; a file
+    /// that does not parse leaves the current session alone
+    fn load_session_from(&mut self, path: &std::path::Path) -> Result<(), String> {
+        let bytes = std::fs::read(path).map_err(|error| format!("Cannot read {}: {}", path.display(), error))?;
+        self.load_session(bytes)
+    }
+
+    /// The session with every workspace tab
+    fn save_session(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.full_session_data())
+            .map_err(|e| format!("Failed to serialize session: {}", e))
+    }
+
+    /// `save_session` in the compact binary format
+    fn save_session_binary(&self) -> Result<Vec<u8>, String> {
+        let value = serde_json::to_value(self.full_session_data()).map_err(|e| format!("Failed to serialize session: {}", e))?;
+        session::encode_binary(&value)
+    }
+
+    fn full_session_data(&self) -> SessionData {
+        let mut session_data = self.session_data();
+        session_data.workspaces = self.workspaces.tabs.iter()
+            .map(|tab| SavedWorkspace { name: tab.name.clone(), session: tab.session.clone() })
+            .collect();
+        session_data.active_workspace = self.workspaces.active;
+        session_data
+    }
+
+    /// The session with every workspace tab as a share string
+    fn share_string(&self) -> Result<String, String> {
+        let value = serde_json::to_value(self.full_session_data()).map_err(|e| format!("Failed to serialize session: {}", e))?;
+        session::encode_share(&value)
+    }
+
+    /// Replace the session with the one shared in `text`; a string that does
+    /// not decode leaves the current session alone
+    fn load_share_string(&mut self, text: &str) -> Result<(), String> {
+        self.load_session_value(session::decode_share(text)?)
+    }
+
+    /// The active workspace alone, as a session
+    fn save_workspace(&self) -> Result<String, String> {
+        serde_json::to_string(&self.session_data()).map_err(|e| format!("Failed to serialize session: {}", e))
+    }
+
+    fn session_data(&self) -> SessionData {
+        SessionData {
+            version: session::VERSION,
+            distributions: self.distributions.clone(),
+            next_id: self.next_id,
+            show_shading: self.show_shading,
+            shading_opacity: self.shading_opacity,
+            show_std_markers: self.show_std_markers,
+            resolution: self.resolution,
+            view_lock: self.view_lock,
+            show_product_amplitude: self.show_product_amplitude,
+            plot: PlotState {
+                bounds: self.plot_bounds.as_ref().map(bounds_array),
+                condition_interval: self.condition_interval,
+                z_reference: self.z_reference,
+                log_y: self.log_y,
+                layout: self.plot_layout,
+                peak_normalized: self.peak_normalized,
+            },
+            latex_theme: self.latex_theme.clone(),
+            groups: self.groups.clone(),
+            curve_coloring: self.curve_coloring,
+            reference_lines: self.reference_lines.clone(),
+            annotations: self.annotations.clone(),
+            theme: self.theme,
+            custom_palette: self.custom_palette.clone(),
+            axis_style: self.axis_style.clone(),
+            workspaces: Vec::new(),
+            active_workspace: 0,
+        }
+    }
+
+    /// Replace everything, workspace tabs included, with the session in
+    /// `data`, JSON or binary
+    fn load_session(&mut self, data: impl AsRef<[u8]>) -> Result<(), String> {
+        self.load_session_value(session::decode(data.as_ref())?)
+    }
+
+    /// Like `load_session`, for a session decoded and migrated already
+    fn load_session_value(&mut self, value: serde_json::Value) -> Result<(), String> {
+        let mut session_data = session_from_value(value)?;
+        let saved = std::mem::take(&mut session_data.workspaces);
+        self.workspaces = if saved.is_empty() {
+            Workspaces::single()
+        } else {
+            Workspaces {
+                active: session_data.active_workspace.min(saved.len() - 1),
+                tabs: saved.into_iter()
+                    .map(|tab| WorkspaceTab { name: tab.name, session: tab.session, ..Default::default() })
+                    .collect(),
+            }
+        };
+        self.workspaces.tabs[self.workspaces.active].session = None;
+        self.history = History::default();
+        self.apply_session(session_data);
+        Ok(())
+    }
+
+    /// Replace the active workspace with the session in `data`; other tabs stay
+    fn load_workspace(&mut self, data: impl AsRef<[u8]>) -> Result<(), String> {
+        self.apply_session(parse_session(data.as_ref())?);
+        Ok(())
+    }
+
+    fn apply_session(&mut self, session_data: SessionData) {
+        self.distributions = session_data.distributions;
+        self.next_id = session_data.next_id;
+        self.show_shading = session_data.show_shading;
+        self.shading_opacity = session_data.shading_opacity;
+        self.show_std_markers = session_data.show_std_markers;
+        self.marker_text.clear();
+        self.resolution = session_data.resolution;
+        self.view_lock = session_data.view_lock;
+        self.show_product_amplitude = session_data.show_product_amplitude;
+        self.condition_interval = session_data.plot.condition_interval;
+        self.z_reference = session_data.plot.z_reference;
+        self.log_y = session_data.plot.log_y;
+        self.peak_normalized = session_data.plot.peak_normalized;
+        self.plot_layout = session_data.plot.layout;
+        self.latex_theme = session_data.latex_theme;
+        self.groups = session_data.groups;
+        self.curve_coloring = session_data.curve_coloring;
+        self.reference_lines = session_data.reference_lines;
+        self.annotations = session_data.annotations;
+        self.theme = session_data.theme;
+        self.custom_palette = session_data.custom_palette;
+        self.axis_style = session_data.axis_style;
+        self.placing_annotation = None;
+        if let Some(view) = self.view_lock.or(session_data.plot.bounds) {
+            self.view_input = view;
+            self.plot_bounds = Some(view_bounds(view));
+            self.requested_bounds = self.plot_bounds;
+        }
+        self.selected_for_multiplication.clear();
+        self.reconcile_loaded_parameters();
+        self.update_product_distributions();
+    }
+
+    /// Show tab `index`, keeping the one shown so far as it is
+    fn switch_workspace(&mut self, index: usize) -> Result<(), String> {
+        let active = self.workspaces.active;
+        if index == active || index >= self.workspaces.tabs.len() {
+            return Ok(());
+        }
+        let current = serde_json::to_value(self.session_data()).map_err(|e| format!("Failed to serialize session: {}", e))?;
+        let target = match &self.workspaces.tabs[index].session {
+            Some(session) => session.to_string(),
+            None => PdfViewerApp::new().save_workspace()?,
+        };
+        let selection = std::mem::take(&mut self.selected_for_multiplication);
+        let history = std::mem::take(&mut self.history);
+        if let Err(error) = self.load_workspace(&target) {
+            self.selected_for_multiplication = selection;
+            self.history = history;
+            return Err(error);
+        }
+        let tabs = &mut self.workspaces.tabs;
+        tabs[active] = WorkspaceTab { name: std::mem::take(&mut tabs[active].name), session: Some(current), selection, history };
+        let tab = &mut tabs[index];
+        tab.session = None;
+        self.selected_for_multiplication = std::mem::take(&mut tab.selection);
+        self.history = std::mem::take(&mut tab.history);
+        self.workspaces.active = index;
+        Ok(())
+    }
+
+    /// Open a new, empty workspace tab
+    fn add_workspace(&mut self) -> Result<(), String> {
+        let tabs = &mut self.workspaces.tabs;
+        let name = (1..).map(|n| format!("Workspace {}", n)).find(|name| tabs.iter().all(|tab| &tab.name != name)).unwrap();
+        tabs.push(WorkspaceTab { name, ..Default::default() });
+        self.switch_workspace(self.workspaces.tabs.len() - 1)
+    }
+
+    /// Drop tab `index` and what it holds; the last tab cannot be closed
+    fn close_workspace(&mut self, index: usize) -> Result<(), String> {
+        let count = self.workspaces.tabs.len();
+        if count < 2 || index >= count {
+            return Ok(());
+        }
+        if index == self.workspaces.active {
+            self.switch_workspace(if index + 1 < count { index + 1 } else { index - 1 })?;
+        }
+        self.workspaces.tabs.remove(index);
+        if index < self.workspaces.active {
+            self.workspaces.active -= 1;
+        }
+        Ok(())
+    }
+
+    /// Hand-edited sessions can hold values the sliders would silently clamp on
+    /// first drag; widen the slider ranges to fit them and flag what changed
+    fn reconcile_loaded_parameters(&mut self) {
+        for dist in self.distributions.values_mut().filter(|dist| !dist.is_derived()) {
+            let notes = dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+            if !notes.is_empty() {
+                dist.load_notice = Some(format!("Adjusted on load: {}", notes.join("; ")));
+            }
+        }
+    }
+
+    /// Connected to a host that only broadcasts
+    fn is_view_only(&self) -> bool {
+        self.sync.as_ref().is_some_and(|session| session.role() == sync::Role::Client && session.read_only())
+    }
+    
+    fn start_sync(&mut self, result: std::io::Result<sync::SyncSession>) {
+        match result {
+            Ok(session) => {
+                self.sync = Some(session);
+                self.sync_error = None;
+            }
+            Err(e) => self.sync_error = Some(format!("Sync failed: {}", e)),
+        }
+    }
+
+    /// Central 99.8% of a distribution, the range the joint heatmap covers
+    fn joint_range(dist: &GaussianDistribution) -> (f64, f64) {
+        (dist.quantile(0.001), dist.quantile(0.999))
+    }
+
+    /// Redraw the importance samples when the proposal, target, sample count or seed changed
+    fn refresh_importance(&mut self) {
+        let tool = &mut self.importance;
+        let (Some(proposal), Some(target)) = (
+            tool.proposal.and_then(|id| self.distributions.get(&id)),
+            tool.target.and_then(|id| self.distributions.get(&id)),
+        ) else {
+            tool.report = None;
+            tool.computed_for = None;
+            return;
+        };
+        let key = (proposal.density_fingerprint(), target.density_fingerprint(), tool.samples, tool.seed);
+        if tool.computed_for != Some(key) {
+            let samples = sampling::Sampler::new(tool.seed).sample(proposal, tool.samples);
+            tool.report = Some(sampling::ImportanceReport::new(proposal, target, samples));
+            tool.computed_for = Some(key);
+        }
+    }
+
+    /// Draw the tool's count of values from its distribution with its seed
+    fn draw_samples(&mut self) -> Result<(), String> {
+        let tool = &mut self.sampler;
+        let dist = tool.target.and_then(|id| self.distributions.get(&id)).ok_or("Pick a distribution")?;
+        let dataset = data::Dataset {
+            name: dist.name.clone(),
+            values: sampling::Sampler::new(tool.seed).sample(dist, tool.count.clamp(1, MAX_SAMPLE_DRAWS)),
+            skipped: 0,
+        };
+        let histogram = data::Histogram::new(&dataset.values, data::default_bin_width(&dataset));
+        tool.draws = Some(SampleDraws { name: dataset.name, seed: tool.seed, values: dataset.values, histogram });
+        tool.status = None;
+        Ok(())
+    }
+
+    /// Name of the result and the formula of "Z = X*Y + 2"; without '=' the
+    /// result is called "Result"
+    fn monte_carlo_formula(formula: &str) -> Result<(String, Expr, Vec<String>), String> {
+        let (name, body) = match formula.split_once('=') {
+            Some((name, body)) if !name.trim().is_empty() => (name.trim().to_string(), body),
+            Some(_) => return Err("Name the result before '='".to_string()),
+            None => ("Result".to_string(), formula),
+        };
+        let (expr, variables) = Expr::parse_with_variables(body)?;
+        Ok((name, expr, variables))
+    }
+
+    /// Distribution behind a variable: the one picked for it, or else the one
+    /// with the same name
+    fn monte_carlo_binding(&self, variable: &str) -> Option<u32> {
+        self.monte_carlo.bindings.get(variable)
+            .copied()
+            .filter(|id| self.distributions.contains_key(id))
+            .or_else(|| self.distributions.values().filter(|dist| dist.name == variable).map(|dist| dist.id).min())
+    }
+
+    fn run_monte_carlo(&mut self) -> Result<(), String> {
+        let (name, expr, variables) = Self::monte_carlo_formula(&self.monte_carlo.formula)?;
+        let inputs = variables.iter()
+            .map(|variable| {
+                self.monte_carlo_binding(variable)
+                    .map(|id| &self.distributions[&id])
+                    .ok_or_else(|| format!("Pick a distribution for {}", variable))
+            })
+            .collect::<Result<Vec<&GaussianDistribution>, String>>()?;
+        let tool = &self.monte_carlo;
+        let (mut samples, undefined) = sampling::propagate(&expr, &inputs, tool.count.clamp(1, MAX_MONTE_CARLO_DRAWS), tool.seed);
+        if samples.len() < 2 {
+            return Err("The formula is undefined for almost every draw".to_string());
+        }
+        samples.sort_by(f64::total_cmp);
+        let exact = expr.affine(inputs.len())
+            .filter(|_| inputs.iter().all(|dist| dist.has_closed_form()))
+            .map(|(coefficients, constant)| {
+                let mean = constant + coefficients.iter().zip(&inputs).map(|(a, dist)| a * dist.mean).sum::<f64>();
+                let variance: f64 = coefficients.iter().zip(&inputs).map(|(a, dist)| (a * dist.std_dev).powi(2)).sum();
+                (mean, variance.sqrt())
+            })
+            .filter(|&(_, std_dev)| std_dev > 0.0);
+        let dataset = data::Dataset { name, values: samples, skipped: undefined };
+        let histogram = data::Histogram::new(&dataset.values, data::default_bin_width(&dataset));
+        let bandwidth = data::silverman_bandwidth(&dataset.values);
+        self.monte_carlo.result = Some(MonteCarloResult {
+            name: dataset.name,
+            samples: dataset.values,
+            undefined,
+            histogram,
+            bandwidth,
+            exact,
+        });
+        Ok(())
+    }
+
+    /// Add the latest Monte Carlo result to the plot as a kernel density estimate
+    fn add_monte_carlo_curve(&mut self) -> Option<u32> {
+        let result = self.monte_carlo.result.as_ref()?;
+        let (samples, name) = (result.samples.clone(), format!("{} (Monte Carlo)", result.name));
+        let id = self.add_derived(Operation::Kde { samples, bandwidth: Some(result.bandwidth) }, Vec::new())?;
+        self.distributions.get_mut(&id).unwrap().name = name;
+        Some(id)
+    }
+
+    /// Crosshair values of the active distribution at data x, when the crosshair is on
+    fn crosshair_readout(&self, x: f64) -> Option<CrosshairReadout> {
+        if !self.crosshair || !x.is_finite() {
+            return None;
+        }
+        let dist = self.distributions.get(&self.crosshair_target?)?;
+        Some(CrosshairReadout {
+            x,
+            density: dist.evaluate(x),
+            cdf: dist.quantile_level(x),
+            z: (x - dist.mean) / dist.std_dev,
+        })
+    }
+
+    /// Height of every curve at data x in the current plot mode, highest first;
+    /// curves whose domain excludes x are left out
+    fn hover_values(&self, x: f64) -> Vec<(u32, f64)> {
+        let mut values: Vec<(u32, f64)> = self.distributions.values()
+            .filter(|dist| !dist.hidden && dist.in_domain(x))
+            .map(|dist| {
+                let value = match self.plot_mode {
+                    PlotMode::Density => self.display_scale(dist) * dist.evaluate(x),
+                    mode => mode.value(dist, x),
+                };
+                (dist.id, value)
+            })
+            .filter(|(_, value)| !value.is_nan())
+            .collect();
+        values.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        values
+    }
+
+    /// Titles and members of the small-multiple panels, in display order:
+    /// one per visible distribution, or one per group plus one for the
+    /// distributions outside every group
+    fn small_multiple_panels(&self) -> Vec<(String, Vec<u32>)> {
+        let mut ids: Vec<u32> = self.distributions.values()
+            .filter(|dist| !dist.hidden)
+            .map(|dist| dist.id)
+            .collect();
+        ids.sort_unstable();
+        if !self.small_multiples_by_group {
+            return ids.into_iter().map(|id| (self.distributions[&id].name.clone(), vec![id])).collect();
+        }
+        let mut panels: Vec<(String, Vec<u32>)> = self.groups.iter()
+            .map(|group| (group.name.clone(), ids.iter().copied().filter(|id| group.members.contains(id)).collect::<Vec<u32>>()))
+            .filter(|(_, members)| !members.is_empty())
+            .collect();
+        let ungrouped: Vec<u32> = ids.into_iter()
+            .filter(|id| !self.groups.iter().any(|group| group.members.contains(id)))
+            .collect();
+        if !ungrouped.is_empty() {
+            panels.push(("Ungrouped".to_string(), ungrouped));
+        }
+        panels
+    }
+
+    /// Closed outline of each visible density mirrored about its baseline: the
+    /// first distribution on the top row at y = n − 1, the last at y = 0.
+    /// The widest violin spans 0.9 of a row.
+    fn violin_shapes(&self, x_min: f64, x_max: f64, num_points: usize) -> Vec<(u32, f64, Vec<[f64; 2]>)> {
+        const HALF_WIDTH: f64 = 0.45;
+        let mut ids: Vec<u32> = self.distributions.values()
+            .filter(|dist| !dist.hidden)
+            .map(|dist| dist.id)
+            .collect();
+        ids.sort_unstable();
+        let curves: Vec<Vec<[f64; 2]>> = ids.iter()
+            .map(|id| {
+                let dist = &self.distributions[id];
+                let (lower, upper) = dist.clip_to_domain(x_min, x_max);
+                if lower >= upper {
+                    return Vec::new();
+                }
+                sample_curve(lower, upper, num_points, |x| dist.evaluate(x))
+                    .into_iter()
+                    .filter(|[_, y]| y.is_finite())
+                    .collect()
+            })
+            .collect();
+        let peak = |curve: &[[f64; 2]]| curve_peak(curve).map_or(0.0, |[_, y]| y);
+        let tallest = curves.iter().map(|curve| peak(curve)).fold(0.0, f64::max);
+
+        let rows = ids.len();
+        ids.into_iter()
+            .zip(curves)
+            .enumerate()
+            .map(|(row, (id, curve))| {
+                let baseline = (rows - 1 - row) as f64;
+                let peak = if self.violin_common_scale { tallest } else { peak(&curve) };
+                let scale = if peak > 0.0 { HALF_WIDTH / peak } else { 0.0 };
+                let upper = curve.iter().map(|&[x, y]| [x, baseline + scale * y]);
+                let lower = curve.iter().rev().map(|&[x, y]| [x, baseline - scale * y]);
+                (id, baseline, upper.chain(lower).collect())
+            })
+            .collect()
+    }
+
+    /// Probability levels compared by the probability plots: the plotting
+    /// positions (i − ½)/n of the sorted data, thinned to at most `MAX_POINTS`
+    /// for big samples, or an even grid when the sample is a distribution
+    fn probability_plot_levels(&self) -> Result<(Vec<f64>, Option<Vec<f64>>), String> {
+        const MAX_POINTS: usize = 2000;
+        let inputs = &self.probability_plot;
+        if inputs.sample_from_data {
+            let dataset = self.data.dataset.as_ref().ok_or("Import data or pick a sample distribution")?;
+            let sorted = dataset.sorted();
+            let n = sorted.len();
+            let stride = n.div_ceil(MAX_POINTS).max(1);
+            let indices: Vec<usize> = (0..n).step_by(stride).collect();
+            let levels = indices.iter().map(|&i| (i as f64 + 0.5) / n as f64).collect();
+            Ok((levels, Some(indices.into_iter().map(|i| sorted[i]).collect())))
+        } else {
+            const LEVELS: usize = 400;
+            Ok(((0..LEVELS).map(|k| (k as f64 + 0.5) / LEVELS as f64).collect(), None))
+        }
+    }
+
+    /// Probability levels, the sample's quantiles at them and the theoretical
+    /// distribution, which both probability plots are drawn from
+    fn probability_plot_samples(&self) -> Result<(Vec<f64>, Vec<f64>, &GaussianDistribution), String> {
+        let inputs = &self.probability_plot;
+        let reference = inputs.reference.and_then(|id| self.distributions.get(&id)).ok_or("Pick a theoretical distribution")?;
+        let (levels, data) = self.probability_plot_levels()?;
+        let sample: Vec<f64> = match data {
+            Some(values) => values,
+            None => {
+                let dist = inputs.sample.and_then(|id| self.distributions.get(&id)).ok_or("Pick a sample distribution")?;
+                levels.iter().map(|&p| dist.quantile(p)).collect()
+            }
+        };
+        Ok((levels, sample, reference))
+    }
+
+    /// (theoretical quantile, sample quantile) pairs of the Q-Q view
+    fn quantile_points(&self) -> Result<Vec<[f64; 2]>, String> {
+        let (levels, sample, reference) = self.probability_plot_samples()?;
+        Ok(levels.iter()
+            .zip(sample)
+            .map(|(&p, y)| [reference.quantile(p), y])
+            .filter(|[x, y]| x.is_finite() && y.is_finite())
+            .collect())
+    }
+
+    /// (theoretical probability, sample probability) pairs of the P-P view:
+    /// F(x) of the theoretical distribution at each sample quantile
+    fn probability_points(&self) -> Result<Vec<[f64; 2]>, String> {
+        let (levels, sample, reference) = self.probability_plot_samples()?;
+        Ok(levels.iter()
+            .zip(sample)
+            .filter(|(_, x)| x.is_finite())
+            .map(|(&p, x)| [reference.quantile_level(x), p])
+            .collect())
+    }
+
+    /// Keep templates in `directory`, created on the first save
+    fn enable_templates(&mut self, directory: std::path::PathBuf) {
+        self.templates.directory = Some(directory);
+        self.templates.refresh();
+    }
+
+    /// Save the active workspace as the template `name`, replacing one of that name
+    fn save_template(&mut self, name: &str) -> Result<(), String> {
+        let path = self.templates.path(name)?;
+        let contents = self.save_workspace()?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory).map_err(|error| format!("Cannot create {}: {}", directory.display(), error))?;
+        }
+        write_replacing(&path, contents).map_err(|error| format!("Cannot write {}: {}", path.display(), error))?;
+        self.templates.refresh();
+        Ok(())
+    }
+
+    /// Open the template `name` in the active workspace
+    fn load_template(&mut self, name: &str) -> Result<(), String> {
+        let path = self.templates.path(name)?;
+        let bytes = std::fs::read(&path).map_err(|error| format!("Cannot read {}: {}", path.display(), error))?;
+        self.load_workspace(bytes)
+    }
+
+    fn delete_template(&mut self, name: &str) -> Result<(), String> {
+        let path = self.templates.path(name)?;
+        let result = std::fs::remove_file(&path).map_err(|error| format!("Cannot delete {}: {}", path.display(), error));
+        self.templates.refresh();
+        result
+    }
+
+    /// Curve combine(p(x), q(x)) across the current view for the pair picked
+    /// in the comparison window, when it is open; in plot coordinates
+    fn comparison_curve(&self, num_points: usize, combine: impl Fn(f64, f64) -> f64) -> Option<Vec<[f64; 2]>> {
+        let tool = &self.comparison;
+        if !tool.open {
+            return None;
+        }
+        let p = self.distributions.get(&tool.first?)?;
+        let q = self.distributions.get(&tool.second?)?;
+        let (x_min, x_max) = self.data_range();
+        let axis = self.axis_map();
+        let step = (x_max - x_min) / (num_points.max(2) - 1) as f64;
+        Some((0..num_points.max(2))
+            .map(|i| {
+                let x = x_min + step * i as f64;
+                axis.view_point([x, combine(p.evaluate(x), q.evaluate(x))])
+            })
+            .filter(|[_, y]| y.is_finite())
+            .collect())
+    }
+
+    /// Plot position of every μ / σ handle, on its curve
+    fn parameter_handle_points(&self) -> Vec<(u32, ParameterHandle, [f64; 2])> {
+        let axis = self.axis_map();
+        let mut points = Vec::new();
+        for dist in self.distributions.values().filter(|dist| !dist.is_derived() && !dist.hidden) {
+            for handle in [ParameterHandle::Mean, ParameterHandle::StdDev] {
+                let x = dist.handle_x(handle);
+                let point = axis.view_point([x, self.curve_scale(dist) * dist.evaluate(x)]);
+                if point[1].is_finite() {
+                    points.push((dist.id, handle, point));
+                }
+            }
+        }
+        points
+    }
+
+    /// P(a ≤ X ≤ b) over the plot interval for every distribution, by id
+    fn interval_probabilities(&self) -> Vec<(u32, String, f64)> {
+        let Some((lower, upper)) = self.condition_interval else {
+            return Vec::new();
+        };
+        let mut rows: Vec<(u32, String, f64)> = self.distributions.values()
+            .map(|dist| (dist.id, dist.name.clone(), (dist.cdf(upper) - dist.cdf(lower)).max(0.0)))
+            .collect();
+        rows.sort_by_key(|(id, _, _)| *id);
+        rows
+    }
+
+    /// Every curve sampled over the current plot range at the export
+    /// resolution, as CSV with one column per distribution
+    fn export_curve_csv(&self) -> String {
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        dists.sort_by_key(|dist| dist.id);
+
+        let (x_min, x_max) = self.export_range();
+        let num_points = self.resolution.export_points.max(2);
+
+        let mut csv = String::from("x");
+        for dist in &dists {
+            csv.push_str(&format!(",\"{}\"", dist.name.replace('"', "\"\"")));
+        }
+        csv.push('\n');
+        for i in 0..num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+            csv.push_str(&format!("{}", x));
+            for dist in &dists {
+                csv.push_str(&format!(",{}", dist.evaluate(x)));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// One row of summary statistics per distribution, as CSV
+    fn export_stats_csv(&self) -> String {
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        dists.sort_by_key(|dist| dist.id);
+
+        let mut csv = String::from("id,name,kind,mean,std_dev,skewness,excess_kurtosis,median,mode\n");
+        for dist in dists {
+            let summary = dist.moment_summary();
+            let kind = if dist.is_derived() { dist.operation.label() } else { "Input" };
+            csv.push_str(&format!(
+                "{},\"{}\",{},{},{},{},{},{},{}\n",
+                dist.id, dist.name.replace('"', "\"\""), kind, summary.mean, summary.std_dev,
+                summary.skewness, summary.excess_kurtosis, summary.median, summary.mode
+            ));
+        }
+        csv
+    }
+
+    /// What the script exports share: the distributions in derivation order,
+    /// a unique name for each, and the grid they are all computed on
+    fn script_setup(&self) -> ScriptSetup<'_> {
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        // Parents always have lower ids than what is derived from them
+        dists.sort_by_key(|dist| dist.id);
+        let mut keys: HashMap<u32, String> = HashMap::new();
+        for dist in &dists {
+            let mut key = dist.name.clone();
+            if keys.values().any(|taken| taken == &key) {
+                key = format!("{} ({})", dist.name, dist.id);
+            }
+            keys.insert(dist.id, key);
+        }
+        // One grid holding every distribution's support and the view
+        let view = self.get_plot_range();
+        let (x_min, x_max) = dists.iter()
+            .map(|dist| dist.support())
+            .filter(|(lower, upper)| lower < upper)
+            .fold(view, |(a, b), (lower, upper)| (a.min(lower), b.max(upper)));
+        let points = self.resolution.export_points.max(SCRIPT_GRID_POINTS);
+        ScriptSetup { dists, keys, grid: (x_min, x_max, points), view }
+    }
+
+    /// Comment line and right-hand side defining `dist` on the grid. Derived
+    /// distributions are rebuilt from their parents where that is a pointwise
+    /// operation on densities, so editing an input in the script carries
+    /// through; the rest are tabulated from the viewer.
+    fn script_density(&self, dist: &GaussianDistribution, keys: &HashMap<u32, String>, language: ScriptLanguage) -> (String, String) {
+        let number = |value: f64| language.number(value);
+        let list = |values: &mut dyn Iterator<Item = f64>| language.list(values);
+        let parents: Option<Vec<String>> = dist.parent_ids.iter()
+            .map(|id| keys.get(id).map(|key| language.density(key)))
+            .collect();
+        let expression = match (&dist.operation, parents) {
+            (Operation::Product | Operation::BayesUpdate, Some(parents)) if !parents.is_empty() => {
+                format!("normalize({})", parents.join(" * "))
+            }
+            (Operation::Maximum, Some(parents)) if !parents.is_empty() => {
+                let cdfs: Vec<String> = parents.iter().map(|parent| format!("cdf({})", parent)).collect();
+                format!("{}({})", language.gradient(), cdfs.join(" * "))
+            }
+            (Operation::Minimum, Some(parents)) if !parents.is_empty() => {
+                let survivals: Vec<String> = parents.iter().map(|parent| format!("(1.0 - cdf({}))", parent)).collect();
+                format!("-{}({})", language.gradient(), survivals.join(" * "))
+            }
+            (Operation::Truncate { lower, upper }, Some(parents)) if parents.len() == 1 => {
+                format!("normalize({} * ((x >= {}) & (x <= {})))", parents[0], number(*lower), number(*upper))
+            }
+            (Operation::Temper { beta }, Some(parents)) if parents.len() == 1 => {
+                format!("normalize({} {} {})", parents[0], language.power(), number(*beta))
+            }
+            (Operation::Mixture { weights, .. }, Some(parents)) if parents.len() == weights.len() => {
+                let terms: Vec<String> = normalized_weights(weights).iter().zip(&parents)
+                    .map(|(weight, parent)| format!("{} * {}", number(*weight), parent))
+                    .collect();
+                terms.join(" + ")
+            }
+            (Operation::Kde { samples, bandwidth }, _) if samples.len() >= 2 => {
+                let mut sorted: Vec<f64> = samples.iter().copied().filter(|x| x.is_finite()).collect();
+                sorted.sort_by(f64::total_cmp);
+                let bandwidth = bandwidth.filter(|h| *h > 0.0 && h.is_finite()).unwrap_or_else(|| data::silverman_bandwidth(&sorted));
+                format!("kde({}, {})", list(&mut sorted.into_iter()), number(bandwidth))
+            }
+            (Operation::Family { family, parameters }, _) => language.family(*family, *parameters),
+            // Inputs, and results that are exactly Gaussian
+            _ if dist.grid.is_none() => language.family(FitFamily::Normal, [dist.mean, dist.std_dev]),
+            // Quotients, transforms and sums need integrals that are not worth rewriting here
+            _ => {
+                let grid = dist.grid.as_ref().unwrap();
+                format!("tabulated({}, {}, {})", number(grid.x_min), number(grid.x_max), list(&mut grid.densities.iter().copied()))
+            }
+        };
+        let expression = match dist.domain {
+            Some((lower, upper)) => format!("({}) * ((x >= {}) & (x <= {}))", expression, number(lower), number(upper)),
+            None => expression,
+        };
+        let description = if dist.is_derived() {
+            let parent_names: Vec<String> = dist.parent_ids.iter()
+                .map(|id| self.distributions.get(id).map_or_else(|| format!("#{}", id), |parent| parent.name.clone()))
+                .collect();
+            dist.operation.expression(&parent_names)
+        } else {
+            format!("N({}, {}²)", number(dist.mean), number(dist.std_dev))
+        };
+        (description.replace('\n', " "), expression)
+    }
+
+    /// Python script recreating every distribution on a common grid and
+    /// plotting the visible ones like the current view
+    fn export_python(&self) -> Result<String, String> {
+        let (y_value, y_label) = match self.plot_mode {
+            PlotMode::Density if self.peak_normalized => ("f / f.max()", "f(x) / max f"),
+            PlotMode::Density => ("f", "f(x)"),
+            PlotMode::Cdf => ("cdf(f)", "F(x)"),
+            PlotMode::Survival => ("1.0 - cdf(f)", "S(x)"),
+            PlotMode::Hazard => ("f / (1.0 - cdf(f))", "h(x)"),
+            PlotMode::LogDensity => ("np.log(f)", "ln f(x)"),
+            _ => return Err("Python export covers the density, CDF, survival, hazard and ln f views".to_string()),
+        };
+        let density_view = self.plot_mode == PlotMode::Density;
+        let language = ScriptLanguage::Python;
+        let number = python_number;
+        let ScriptSetup { dists, keys, grid: (x_min, x_max, points), view: (view_min, view_max) } = self.script_setup();
+
+        let mut script = String::from(PYTHON_PRELUDE);
+        script.push_str(&format!("x = np.linspace({}, {}, {})\npdf = {{}}\n", number(x_min), number(x_max), points));
+        for dist in &dists {
+            let (description, expression) = self.script_density(dist, &keys, language);
+            script.push_str(&format!("\n# {}\n{} = {}\n", description, language.density(&keys[&dist.id]), expression));
+        }
+
+        let (colors, _) = self.curve_colors();
+        script.push_str("\nfig, ax = plt.subplots()\n");
+        if let Some(histogram) = self.data.histogram.as_ref().filter(|_| self.data.show_histogram && density_view) {
+            let bins = histogram.densities();
+            let edges = bins.iter().map(|&(left, _, _)| left).chain(bins.last().map(|&(_, right, _)| right));
+            let name = self.data.dataset.as_ref().map_or("Data", |dataset| dataset.name.as_str());
+            script.push_str(&format!(
+                "ax.stairs({}, {}, fill=True, color=\"gray\", alpha=0.35, label={})\n",
+                language.list(&mut bins.iter().map(|&(_, _, density)| density)),
+                language.list(&mut edges.into_iter()),
+                quoted(name)
+            ));
+        }
+        for dist in dists.iter().filter(|dist| !dist.hidden) {
+            let color = quoted(&hex_color(colors[&dist.id]));
+            let style = match dist.line_style {
+                StrokeStyle::Solid => "-",
+                StrokeStyle::Dashed => "--",
+                StrokeStyle::Dotted => ":",
+            };
+            script.push_str(&format!("f = {}\n", language.density(&keys[&dist.id])));
+            script.push_str(&format!(
+                "ax.plot(x, {}, color={}, linestyle=\"{}\", linewidth={:?}, label={})\n",
+                y_value,
+                color,
+                style,
+                dist.line_width,
+                quoted(&dist.name)
+            ));
+            if self.show_shading && density_view {
+                script.push_str(&format!("ax.fill_between(x, {}, color={}, alpha={:?})\n", y_value, color, self.shading_opacity));
+            }
+        }
+
+        let (x_label, y_label) = self.script_axis_labels(y_label);
+        script.push_str(&format!("ax.set_xlabel({})\nax.set_ylabel({})\n", quoted(x_label), quoted(y_label)));
+        script.push_str(&format!("ax.set_xlim({}, {})\n", number(view_min), number(view_max)));
+        let log_y = self.log_y && self.plot_mode != PlotMode::LogDensity;
+        if let Some((y_min, y_max)) = self.script_y_range(log_y) {
+            script.push_str(&format!("ax.set_ylim({}, {})\n", number(y_min), number(y_max)));
+        }
+        if log_y {
+            script.push_str("ax.set_yscale(\"log\")\n");
+        }
+        if self.axis_style.show_grid {
+            script.push_str(&format!("ax.grid(True, which={})\n", if self.axis_style.minor_grid { "\"both\"" } else { "\"major\"" }));
+            if self.axis_style.minor_grid {
+                script.push_str("ax.minorticks_on()\n");
+            }
+        }
+        script.push_str("ax.legend()\nplt.show()\n");
+        Ok(script)
+    }
+
+    /// R script recreating every distribution on a common grid and plotting
+    /// the visible ones like the current view with ggplot2
+    fn export_r(&self) -> Result<String, String> {
+        let (y_value, y_label) = match self.plot_mode {
+            PlotMode::Density if self.peak_normalized => ("f / max(f)", "f(x) / max f"),
+            PlotMode::Density => ("f", "f(x)"),
+            PlotMode::Cdf => ("cdf(f)", "F(x)"),
+            PlotMode::Survival => ("1.0 - cdf(f)", "S(x)"),
+            PlotMode::Hazard => ("f / (1.0 - cdf(f))", "h(x)"),
+            PlotMode::LogDensity => ("log(f)", "ln f(x)"),
+            _ => return Err("R export covers the density, CDF, survival, hazard and ln f views".to_string()),
+        };
+        let density_view = self.plot_mode == PlotMode::Density;
+        let language = ScriptLanguage::R;
+        let number = |value: f64| language.number(value);
+        let ScriptSetup { dists, keys, grid: (x_min, x_max, points), view: (view_min, view_max) } = self.script_setup();
+
+        let mut script = String::from(R_PRELUDE);
+        script.push_str(&format!("x <- seq({}, {}, length.out = {})\npdf <- list()\n", number(x_min), number(x_max), points));
+        for dist in &dists {
+            let (description, expression) = self.script_density(dist, &keys, language);
+            script.push_str(&format!("\n# {}\n{} <- {}\n", description, language.density(&keys[&dist.id]), expression));
+        }
+
+        let visible: Vec<&&GaussianDistribution> = dists.iter().filter(|dist| !dist.hidden).collect();
+        if visible.is_empty() {
+            return Err("No visible curves to plot".to_string());
+        }
+        let (colors, _) = self.curve_colors();
+        script.push_str(&format!("\nview <- function(f) {}\ncurves <- rbind(\n", y_value));
+        let rows: Vec<String> = visible.iter()
+            .map(|dist| {
+                let key = &keys[&dist.id];
+                // ggplot2 widths are in mm
+                format!(
+                    "  data.frame(x = x, y = view({}), curve = {}, width = {:?} / .pt)",
+                    language.density(key),
+                    quoted(key),
+                    dist.line_width
+                )
+            })
+            .collect();
+        script.push_str(&rows.join(",\n"));
+        script.push_str("\n)\ncurves$curve <- factor(curves$curve, levels = unique(curves$curve))\n\n");
+        let scale = |value: &dyn Fn(&GaussianDistribution) -> String| -> String {
+            let pairs: Vec<String> = visible.iter().map(|dist| format!("{} = {}", quoted(&keys[&dist.id]), value(dist))).collect();
+            format!("c({})", pairs.join(", "))
+        };
+
+        script.push_str("plot <- ggplot(curves, aes(x, y, colour = curve, linetype = curve)) +\n");
+        if let Some(histogram) = self.data.histogram.as_ref().filter(|_| self.data.show_histogram && density_view) {
+            let bins = histogram.densities();
+            script.push_str(&format!(
+                "  geom_rect(aes(xmin = left, xmax = right, ymin = 0, ymax = density), data = data.frame(left = {}, right = {}, density = {}),\n            inherit.aes = FALSE, fill = \"gray\", alpha = 0.35) +\n",
+                language.list(&mut bins.iter().map(|&(left, _, _)| left)),
+                language.list(&mut bins.iter().map(|&(_, right, _)| right)),
+                language.list(&mut bins.iter().map(|&(_, _, density)| density))
+            ));
+        }
+        if self.show_shading && density_view {
+            script.push_str(&format!(
+                "  geom_area(aes(fill = curve), colour = NA, alpha = {:?}, position = \"identity\", show.legend = FALSE) +\n",
+                self.shading_opacity
+            ));
+        }
+        script.push_str("  geom_line(aes(linewidth = width)) +\n  scale_linewidth_identity() +\n");
+        let color = |dist: &GaussianDistribution| quoted(&hex_color(colors[&dist.id]));
+        script.push_str(&format!("  scale_colour_manual(values = {}) +\n", scale(&color)));
+        if self.show_shading && density_view {
+            script.push_str(&format!("  scale_fill_manual(values = {}) +\n", scale(&color)));
+        }
+        let line_type = |dist: &GaussianDistribution| match dist.line_style {
+            StrokeStyle::Solid => "\"solid\"",
+            StrokeStyle::Dashed => "\"dashed\"",
+            StrokeStyle::Dotted => "\"dotted\"",
+        }.to_string();
+        script.push_str(&format!("  scale_linetype_manual(values = {}) +\n", scale(&line_type)));
+
+        let (x_label, y_label) = self.script_axis_labels(y_label);
+        script.push_str(&format!("  labs(x = {}, y = {}, colour = NULL, linetype = NULL) +\n", quoted(x_label), quoted(y_label)));
+        let log_y = self.log_y && self.plot_mode != PlotMode::LogDensity;
+        if log_y {
+            script.push_str("  scale_y_log10() +\n");
+        }
+        let y_limits = match self.script_y_range(log_y) {
+            Some((y_min, y_max)) => format!(", ylim = c({}, {})", number(y_min), number(y_max)),
+            None => String::new(),
+        };
+        script.push_str(&format!("  coord_cartesian(xlim = c({}, {}){}) +\n", number(view_min), number(view_max), y_limits));
+        script.push_str(match (self.axis_style.show_grid, self.axis_style.minor_grid) {
+            (true, true) => "  theme_bw()\n",
+            (true, false) => "  theme_bw() + theme(panel.grid.minor = element_blank())\n",
+            (false, _) => "  theme_bw() + theme(panel.grid = element_blank())\n",
+        });
+        script.push_str("print(plot)\n");
+        Ok(script)
+    }
+
+    /// The axis labels set in the view, or the defaults
+    fn script_axis_labels<'a>(&'a self, y_default: &'a str) -> (&'a str, &'a str) {
+        let x_label = match self.axis_style.x_label.trim() {
+            "" => "x",
+            label => label,
+        };
+        let y_label = match self.axis_style.y_label.trim() {
+            "" => y_default,
+            label => label,
+        };
+        (x_label, y_label)
+    }
+
+    /// Vertical range of the view in data units, when the plot has one
+    fn script_y_range(&self, log_y: bool) -> Option<(f64, f64)> {
+        let [_, _, y_min, y_max] = self.view_lock.or(self.plot_bounds.as_ref().map(bounds_array))?;
+        Some(if log_y { (10f64.powf(y_min), 10f64.powf(y_max)) } else { (y_min, y_max) })
+    }
+
+    /// The current plot as a pgfplots `tikzpicture`: axes, histogram, shading,
+    /// curves, markers, reference lines and annotations, colored through the
+    /// LaTeX theme. Curves are sampled like the CSV export.
+    fn export_tikz(&self) -> Result<String, String> {
+        let y_label = match self.plot_mode {
+            PlotMode::Density if self.peak_normalized => "$f(x) / \\max f$",
+            PlotMode::Density => "$f(x)$",
+            PlotMode::Cdf => "$F(x)$",
+            PlotMode::Survival => "$S(x)$",
+            PlotMode::Hazard => "$h(x)$",
+            PlotMode::LogDensity => "$\\ln f(x)$",
+            _ => return Err("TikZ export covers the density, CDF, survival, hazard and ln f views".to_string()),
+        };
+        let density_view = self.plot_mode == PlotMode::Density;
+        // pgfplots draws the log axis itself, so y stays in data units
+        let log_y = self.log_y && self.plot_mode != PlotMode::LogDensity;
+        let axis = self.axis_map().with_log_y(false);
+        let point = |[x, y]: [f64; 2]| format!("({},{})", tikz_number(x), tikz_number(y));
+        let coordinates = |points: &[[f64; 2]]| -> String {
+            let points: Vec<String> = points.iter()
+                .filter(|[_, y]| y.is_finite() && (!log_y || *y > 0.0))
+                .map(|&xy| point(xy))
+                .collect();
+            format!("coordinates {{{}}}", points.join(" "))
+        };
+        // Full-height and full-width lines; y = 1 is valid on a log axis too
+        let vline = |x: f64, options: &str, label: Option<String>| {
+            let at = format!("{{axis cs:{},1}}", tikz_number(axis.to_view(x)));
+            let node = label.map_or(String::new(), |label| format!(" node[below right] {{{}}}", latex_escape(&label)));
+            format!("\\draw[{}] ({}|-{{rel axis cs:0,0}}) -- ({}|-{{rel axis cs:0,1}}){};\n", options, at, at, node)
+        };
+        let stroke = |style: StrokeStyle| match style {
+            StrokeStyle::Solid => "solid",
+            StrokeStyle::Dashed => "dashed",
+            StrokeStyle::Dotted => "dotted",
+        };
+
+        let mut options = Vec::new();
+        let x_label = match (self.axis_style.x_label.trim(), self.z_reference.and_then(|id| self.distributions.get(&id))) {
+            ("", Some(reference)) => format!("$z$ [{}]", latex_escape(&reference.name)),
+            ("", None) => "$x$".to_string(),
+            (label, _) => latex_escape(label),
+        };
+        let y_label = match self.axis_style.y_label.trim() {
+            "" => y_label.to_string(),
+            label => latex_escape(label),
+        };
+        options.push(format!("xlabel={{{}}}", x_label));
+        options.push(format!("ylabel={{{}}}", y_label));
+        let bounds = self.view_lock.or(self.plot_bounds.as_ref().map(bounds_array));
+        let (x_min, x_max) = self.get_plot_range();
+        options.push(format!("xmin={}, xmax={}", tikz_number(x_min), tikz_number(x_max)));
+        if let Some([_, _, y_min, y_max]) = bounds {
+            let (y_min, y_max) = if log_y { (10f64.powf(y_min), 10f64.powf(y_max)) } else { (y_min, y_max) };
+            options.push(format!("ymin={}, ymax={}", tikz_number(y_min), tikz_number(y_max)));
+        }
+        if log_y {
+            options.push("ymode=log".to_string());
+        }
+        match (self.axis_style.show_grid, self.axis_style.minor_grid) {
+            (true, true) => options.push("grid=both, minor tick num=4".to_string()),
+            (true, false) => options.push("grid=major".to_string()),
+            (false, _) => {}
+        }
+        options.push("legend pos=north east, legend cell align=left".to_string());
+        options.push("no markers".to_string());
+
+        let mut body = String::new();
+        if let Some(histogram) = self.data.histogram.as_ref().filter(|_| self.data.show_histogram && density_view) {
+            let bins = histogram.densities();
+            let tallest = bins.iter().map(|&(_, _, density)| density).fold(0.0, f64::max);
+            let scale = if self.peak_normalized && tallest > 0.0 { 1.0 / tallest } else { 1.0 };
+            // ybar interval reads each bar from its left edge; the last point closes the last bar
+            let mut edges: Vec<[f64; 2]> = bins.iter().map(|&(left, _, density)| axis.view_point([left, scale * density])).collect();
+            if let Some(&(_, right, density)) = bins.last() {
+                edges.push(axis.view_point([right, scale * density]));
+            }
+            let name = self.data.dataset.as_ref().map_or("Data", |dataset| dataset.name.as_str());
+            body.push_str(&format!("\\addplot[ybar interval, fill=gray, fill opacity=0.35, draw=gray] {};\n", coordinates(&edges)));
+            body.push_str(&format!("\\addlegendentry{{{}}}\n", latex_escape(name)));
+        }
+
+        let (colors, _) = self.curve_colors();
+        let (x_min, x_max) = self.export_range();
+        let num_points = self.resolution.export_points.max(2);
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().filter(|dist| !dist.hidden).collect();
+        dists.sort_by_key(|dist| dist.id);
+        for dist in dists {
+            let (lower, upper) = dist.clip_to_domain(x_min, x_max);
+            if lower >= upper {
+                continue;
+            }
+            let color = self.latex_theme.tikz_color_of(colors[&dist.id]);
+            let scale = if density_view { self.curve_scale(dist) } else { 1.0 };
+            let value = |x: f64| if density_view { scale * dist.evaluate(x) } else { self.plot_mode.value(dist, x) };
+            let to_view = |[x, y]: [f64; 2]| if density_view { axis.view_point([x, y]) } else { [axis.to_view(x), y] };
+            let curve: Vec<[f64; 2]> = sample_curve(lower, upper, num_points, value).into_iter().map(to_view).collect();
+
+            if self.show_shading && density_view {
+                let region = match self.condition_interval.filter(|_| self.show_interval_probability) {
+                    Some((a, b)) if a.max(lower) < b.min(upper) => {
+                        sample_curve(a.max(lower), b.min(upper), num_points, value).into_iter().map(to_view).collect()
+                    }
+                    Some(_) => Vec::new(),
+                    None => curve.clone(),
+                };
+                if !region.is_empty() {
+                    body.push_str(&format!(
+                        "\\addplot[draw=none, fill={}, fill opacity={:.2}, forget plot] {} \\closedcycle;\n",
+                        color,
+                        self.shading_opacity,
+                        coordinates(&region)
+                    ));
+                }
+            }
+            body.push_str(&format!(
+                "\\addplot[color={}, line width={:.1}pt, {}] {};\n",
+                color,
+                // egui points are 1/72 in at 1×, like TeX points near enough
+                dist.line_width,
+                stroke(dist.line_style),
+                coordinates(&curve)
+            ));
+            body.push_str(&format!("\\addlegendentry{{{}}}\n", latex_escape(&self.curve_label(dist, density_view))));
+
+            if self.show_std_markers {
+                for (x, center) in dist.marker_positions() {
+                    if x >= lower && x <= upper {
+                        let width = if center { "line width=1pt" } else { "line width=0.5pt, opacity=0.7" };
+                        body.push_str(&vline(x, &format!("color={}, dashed, {}", color, width), None));
+                    }
+                }
+            }
+        }
+
+        if let Some(x) = self.quantile_marker() {
+            body.push_str(&vline(x, "color={rgb,255:red,0;green,100;blue,0}, dashed, line width=0.75pt", Some(format!("p = {}", self.quantile_p))));
+        }
+        for line in &self.reference_lines {
+            let [r, g, b] = line.color;
+            let options = format!("color={}, {}, line width={:.1}pt", tikz_rgb(egui::Color32::from_rgb(r, g, b)), stroke(line.style), line.width);
+            if line.horizontal {
+                let at = format!("{{axis cs:0,{}}}", tikz_number(line.position));
+                body.push_str(&format!(
+                    "\\draw[{}] ({{rel axis cs:0,0}}|-{}) -- ({{rel axis cs:1,0}}|-{}) node[above left] {{{}}};\n",
+                    options,
+                    at,
+                    at,
+                    latex_escape(&line.caption())
+                ));
+            } else {
+                body.push_str(&vline(line.position, &options, Some(line.caption())));
+            }
+        }
+        for annotation in &self.annotations {
+            let [r, g, b] = annotation.color;
+            let color = tikz_rgb(egui::Color32::from_rgb(r, g, b));
+            let at = |[x, y]: [f64; 2]| format!("(axis cs:{},{})", tikz_number(axis.to_view(x)), tikz_number(y));
+            body.push_str(&format!("\\node[text={}, inner sep=1pt] (note) at {} {{{}}};\n", color, at(annotation.position), latex_escape(&annotation.text)));
+            if let Some(tip) = annotation.arrow_to {
+                body.push_str(&format!("\\draw[->, color={}, line width=0.75pt] (note) -- {};\n", color, at(tip)));
+            }
+        }
+
+        let picture = match self.latex_theme.font_option() {
+            Some(font) => format!("\\begin{{tikzpicture}}[{}]\n", font),
+            None => "\\begin{tikzpicture}\n".to_string(),
+        };
+        Ok(format!(
+            "% Needs \\usepackage{{pgfplots}} and \\pgfplotsset{{compat=1.17}} in the preamble\n{}\\begin{{axis}}[\n  {},\n]\n{}\\end{{axis}}\n\\end{{tikzpicture}}\n",
+            picture,
+            options.join(",\n  "),
+            body
+        ))
+    }
+
+    /// Hash of everything the exported files contain
+    fn export_fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        dists.sort_by_key(|dist| dist.id);
+        for dist in dists {
+            (dist.id, &dist.name, dist.density_fingerprint()).hash(&mut hasher);
+        }
+        let (x_min, x_max) = self.export_range();
+        (x_min.to_bits(), x_max.to_bits(), self.resolution.export_points).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Write the auto-export files when the trigger says so; true when written
+    fn run_auto_export(&mut self, now: std::time::Instant) -> bool {
+        let export = &self.auto_export;
+        if !export.enabled {
+            return false;
+        }
+        let fingerprint = self.export_fingerprint();
+        let due = match export.trigger {
+            ExportTrigger::OnChange => export.last_fingerprint != Some(fingerprint),
+            ExportTrigger::Every { seconds } => export.last_export
+                .is_none_or(|last| now.duration_since(last).as_secs() >= u64::from(seconds.max(1))),
+        };
+        if !due {
+            return false;
+        }
+
+        let directory = std::path::PathBuf::from(&export.directory);
+        let result = std::fs::create_dir_all(&directory)
+            .and_then(|_| write_replacing(&directory.join("curves.csv"), self.export_curve_csv()))
+            .and_then(|_| write_replacing(&directory.join("stats.csv"), self.export_stats_csv()));
+        let export = &mut self.auto_export;
+        export.last_export = Some(now);
+        export.last_fingerprint = Some(fingerprint);
+        match result {
+            Ok(()) => {
+                export.exports += 1;
+                export.error = None;
+                true
+            }
+            Err(e) => {
+                export.error = Some(format!("Export to {} failed: {}", directory.display(), e));
+                false
+            }
+        }
+    }
+
+    /// Start autosaving to the first free slot in `directory`, first offering
+    /// to restore whatever an earlier run left there
+    fn enable_autosave(&mut self, directory: &std::path::Path, now: std::time::Instant) {
+        let autosave = &mut self.autosave;
+        let slot = std::fs::create_dir_all(directory).ok().and_then(|()| {
+            (0..AUTOSAVE_SLOTS).find_map(|slot| {
+                let lock = std::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(false)
+                    .open(directory.join(format!("autosave-{}.lock", slot)))
+                    .ok()?;
+                lock.try_lock().ok()?;
+                Some((directory.join(format!("autosave-{}.json", slot)), lock))
+            })
+        });
+        let Some((path, lock)) = slot else {
+            autosave.error = Some(format!("Autosave is off: no free slot in {}", directory.display()));
+            return;
+        };
+        autosave.recovery = std::fs::read_to_string(&path).ok().map(|json| Recovery {
+            saved: std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok(),
+            json,
+            error: None,
+        });
+        autosave.path = Some(path);
+        autosave.lock = Some(lock);
+        autosave.last_save = Some(now);
+    }
+
+    /// Write the session to the autosave file when it changed and the
+    /// interval has passed; true when it was written
+    fn run_autosave(&mut self, now: std::time::Instant) -> bool {
+        let autosave = &self.autosave;
+        let Some(path) = autosave.path.clone().filter(|_| autosave.recovery.is_none()) else {
+            return false;
+        };
+        if autosave.last_save.is_some_and(|last| now.duration_since(last).as_secs() < AUTOSAVE_SECONDS) {
+            return false;
+        }
+        let json = match self.save_session() {
+            Ok(json) => json,
+            Err(error) => {
+                self.autosave.error = Some(error);
+                return false;
+            }
+        };
+        let fingerprint = {
+            use std::hash::{Hash, Hasher};
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            json.hash(&mut hasher);
+            hasher.finish()
+        };
+        let autosave = &mut self.autosave;
+        autosave.last_save = Some(now);
+        if autosave.last_fingerprint == Some(fingerprint) {
+            return false;
+        }
+        match write_replacing(&path, json) {
+            Ok(()) => {
+                autosave.last_fingerprint = Some(fingerprint);
+                autosave.error = None;
+                true
+            }
+            Err(error) => {
+                autosave.error = Some(format!("Autosave to {} failed: {}", path.display(), error));
+                false
+            }
+        }
+    }
+
+    /// Answer the recovery offer: load the autosaved session, or throw it away.
+    /// A session that fails to load stays on offer, and autosave stays paused
+    /// so the file is not overwritten.
+    fn resolve_recovery(&mut self, restore: bool) -> Result<(), String> {
+        let Some(recovery) = &self.autosave.recovery else {
+            return Ok(());
+        };
+        if restore {
+            let json = recovery.json.clone();
+            self.load_session(&json)?;
+        } else if let Some(path) = &self.autosave.path {
+            let _ = std::fs::remove_file(path);
+        }
+        self.autosave.recovery = None;
+        Ok(())
+    }
+
+    /// Take a delimited text file as the data source and show its first column
+    fn import_data(&mut self, source: String, text: String) {
+        let import = &mut self.data;
+        import.columns = data::column_names(&text);
+        import.source = Some(DataSource::Text { name: source, text });
+        import.error = None;
+        self.select_data_column(0);
+    }
+
+    /// Take a Parquet or Arrow file as the data source; only its schema is
+    /// read until a column is picked
+    fn import_columnar(&mut self, source: String, path: std::path::PathBuf, format: columnar::Format) {
+        let import = &mut self.data;
+        match columnar::numeric_columns(&path, format) {
+            Ok(columns) if columns.is_empty() => import.error = Some(format!("{} has no numeric columns", source)),
+            Ok(columns) => {
+                import.columns = columns;
+                import.source = Some(DataSource::Columnar { name: source, path, format });
+                import.error = None;
+                self.select_data_column(0);
+            }
+            Err(error) => import.error = Some(format!("Cannot read {} as {}: {}", source, format.label(), error)),
+        }
+    }
+
+    fn select_data_column(&mut self, column: usize) {
+        let import = &mut self.data;
+        let Some(source) = &import.source else {
+            return;
+        };
+        import.column = column;
+        import.gof_computed_for = None;
+        import.mixture.comparison.clear();
+        let parsed = match source {
+            DataSource::Text { name, text } => data::parse_column(text, column, name),
+            DataSource::Columnar { name, path, format } => match import.columns.get(column) {
+                Some(column) => columnar::read_column(path, *format, column, name),
+                None => Err(format!("{} has no column {}", name, column + 1)),
+            },
+        };
+        match parsed {
+            Ok(dataset) => {
+                import.bin_width = data::default_bin_width(&dataset);
+                import.histogram = Some(data::Histogram::new(&dataset.values, import.bin_width));
+                import.dataset = Some(dataset);
+                import.show_histogram = true;
+                import.show_ecdf = true;
+                import.error = None;
+            }
+            Err(error) => {
+                import.dataset = None;
+                import.histogram = None;
+                import.error = Some(error);
+            }
+        }
+    }
+
+    /// Take in whatever the live stream delivered; true when anything arrived
+    fn poll_stream(&mut self) -> bool {
+        let Some(stream) = &mut self.live_stream.stream else {
+            return false;
+        };
+        let values = stream.drain();
+        if values.is_empty() {
+            return false;
+        }
+        self.ingest_stream_values(values);
+        true
+    }
+
+    /// Slide `values` into the live window, which becomes the imported data
+    fn ingest_stream_values(&mut self, values: Vec<f64>) {
+        let live = &mut self.live_stream;
+        live.received += values.len() as u64;
+        live.buffer.extend(values);
+        let excess = live.buffer.len().saturating_sub(live.window.max(1));
+        live.buffer.drain(..excess);
+
+        let import = &mut self.data;
+        let first = import.dataset.as_ref().is_none_or(|dataset| dataset.name != LIVE_STREAM_NAME);
+        let dataset = data::Dataset {
+            name: LIVE_STREAM_NAME.to_string(),
+            values: live.buffer.iter().copied().collect(),
+            skipped: 0,
+        };
+        if first {
+            import.source = None;
+            import.columns.clear();
+            import.mixture.comparison.clear();
+            import.bin_width = data::default_bin_width(&dataset);
+            import.show_histogram = true;
+            import.show_ecdf = true;
+            import.error = None;
+        }
+        import.histogram = Some(data::Histogram::new(&dataset.values, import.bin_width));
+        import.dataset = Some(dataset);
+        import.gof_computed_for = None;
+        if self.live_stream.running_fit {
+            self.update_running_fit();
+        }
+    }
+
+    /// Refit the normal that follows the live window, creating it on first use
+    fn update_running_fit(&mut self) {
+        let Some(dataset) = &self.data.dataset else {
+            return;
+        };
+        // Too few or identical values so far
+        let Ok((parameters, log_likelihood)) = FitFamily::Normal.estimate(FitMethod::MaximumLikelihood, &dataset.values) else {
+            return;
+        };
+        let (mean, std_dev) = (parameters[0].value, parameters[1].value);
+        let summary = FitSummary {
+            family: FitFamily::Normal,
+            method: FitMethod::MaximumLikelihood,
+            data: dataset.name.clone(),
+            n: dataset.values.len(),
+            parameters,
+            log_likelihood,
+        };
+        let existing = self.live_stream.fit_id.filter(|id| self.distributions.get(id).is_some_and(|dist| !dist.is_derived()));
+        let id = existing.unwrap_or_else(|| {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.distributions.insert(id, GaussianDistribution::new(id, format!("Running fit of {}", LIVE_STREAM_NAME), mean, std_dev));
+            id
+        });
+        self.live_stream.fit_id = Some(id);
+        let dist = self.distributions.get_mut(&id).unwrap();
+        dist.mean = mean;
+        dist.std_dev = std_dev;
+        dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+        dist.fit = Some(summary);
+        self.update_product_distributions();
+    }
+
+    /// Add a kernel density estimate of the imported column as a new distribution
+    fn add_kde(&mut self, bandwidth: Option<f64>) -> Option<u32> {
+        let dataset = self.data.dataset.as_ref()?;
+        let (samples, name) = (dataset.values.clone(), format!("KDE of {}", dataset.name));
+        let id = self.add_derived(Operation::Kde { samples, bandwidth }, Vec::new())?;
+        self.distributions.get_mut(&id).unwrap().name = name;
+        Some(id)
+    }
+
+    /// Fit `family` to the imported column with `method` and add the result
+    /// as a new distribution, returning the id of the curve to look at
+    fn fit_to_data(&mut self, family: FitFamily, method: FitMethod) -> Result<u32, String> {
+        let dataset = self.data.dataset.as_ref().ok_or("No data imported")?;
+        let values = &dataset.values;
+        let (parameters, log_likelihood) = family.estimate(method, values)?;
+        let summary = FitSummary {
+            family,
+            method,
+            data: dataset.name.clone(),
+            n: values.len(),
+            parameters,
+            log_likelihood,
+        };
+        let name = format!("{} fit to {}", family.label(), dataset.name);
+        let [a, b] = [summary.parameters[0].value, summary.parameters[1].value];
+
+        let curve = match family {
+            FitFamily::Normal | FitFamily::LogNormal => {
+                let id = self.next_id;
+                let mut dist = GaussianDistribution::new(id, name.clone(), a, b);
+                dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+                dist.fit = Some(summary.clone());
+                self.next_id += 1;
+                if family == FitFamily::Normal {
+                    self.distributions.insert(id, dist);
+                    return Ok(id);
+                }
+                // Log-normal: exp of a normal in log units, which stays off the plot
+                dist.name = format!("ln of {}", name);
+                dist.hidden = true;
+                self.distributions.insert(id, dist);
+                self.add_derived(Operation::Transform(Transform::Exp), vec![id])
+            }
+            FitFamily::Gamma | FitFamily::Beta => self.add_derived(Operation::Family { family, parameters: [a, b] }, Vec::new()),
+        }
+        .ok_or("Could not create the fitted distribution")?;
+        let dist = self.distributions.get_mut(&curve).unwrap();
+        dist.name = name;
+        dist.fit = Some(summary);
+        Ok(curve)
+    }
+
+    /// Add a distribution for every sampling statement of a model spec,
+    /// returning their ids; nothing is added when any statement is bad
+    fn import_model_spec(&mut self, spec: &str) -> Result<Vec<u32>, String> {
+        let declarations = spec::parse(spec)?;
+        let mut ids = Vec::with_capacity(declarations.len());
+        for spec::Declaration { name, family, parameters: [a, b], .. } in declarations {
+            let id = match family {
+                FitFamily::Normal | FitFamily::LogNormal => {
+                    let id = self.next_id;
+                    let mut dist = GaussianDistribution::new(id, name.clone(), a, b);
+                    dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+                    self.next_id += 1;
+                    if family == FitFamily::Normal {
+                        self.distributions.insert(id, dist);
+                        ids.push(id);
+                        continue;
+                    }
+                    // Log-normal: exp of a normal in log units, as for fits
+                    dist.name = format!("ln of {}", name);
+                    dist.hidden = true;
+                    self.distributions.insert(id, dist);
+                    self.add_derived(Operation::Transform(Transform::Exp), vec![id])
+                }
+                FitFamily::Gamma | FitFamily::Beta => self.add_derived(Operation::Family { family, parameters: [a, b] }, Vec::new()),
+            }
+            .ok_or_else(|| format!("Could not create '{}'", name))?;
+            self.distributions.get_mut(&id).unwrap().name = name;
+            ids.push(id);
+        }
+        self.update_product_distributions();
+        Ok(ids)
+    }
+
+    /// Fit a k-component Gaussian mixture to the imported column by EM. The
+    /// components are added as hidden inputs of a new mixture, whose id is returned.
+    fn fit_mixture_to_data(&mut self, k: usize) -> Result<u32, String> {
+        if k < 2 {
+            return Err("A mixture needs at least two components; use the Normal fit for one".to_string());
+        }
+        let dataset = self.data.dataset.as_ref().ok_or("No data imported")?;
+        let fit = data::gaussian_mixture_em(&dataset.values, k)?;
+        let name = dataset.name.clone();
+        let mut components = Vec::with_capacity(k);
+        for (j, (&mean, &std_dev)) in fit.means.iter().zip(&fit.std_devs).enumerate() {
+            let id = self.next_id;
+            let mut dist = GaussianDistribution::new(id, format!("Component {} of {}", j + 1, name), mean, std_dev);
+            dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+            // Listed with their parameters, but only the mixture is drawn
+            dist.hidden = true;
+            self.distributions.insert(id, dist);
+            self.next_id += 1;
+            components.push(id);
+        }
+        let id = self.add_derived(Operation::Mixture { weights: fit.weights.clone(), by_evidence: false }, components)
+            .ok_or("Could not create the mixture")?;
+        self.distributions.get_mut(&id).unwrap().name = format!("{}-component mixture fit to {}", k, name);
+        self.data.mixture.record(fit);
+        Ok(id)
+    }
+
+    /// Fit every k up to `MAX_MIXTURE_COMPONENTS` for the BIC readout
+    fn compare_mixtures(&mut self) -> Result<(), String> {
+        let dataset = self.data.dataset.as_ref().ok_or("No data imported")?;
+        let fits: Vec<data::MixtureFit> = (1..=MAX_MIXTURE_COMPONENTS)
+            .map_while(|k| data::gaussian_mixture_em(&dataset.values, k).ok())
+            .collect();
+        if fits.is_empty() {
+            return Err("The data has no spread to fit".to_string());
+        }
+        for fit in fits {
+            self.data.mixture.record(fit);
+        }
+        Ok(())
+    }
+
+    /// Rerun the KS and Anderson–Darling tests when the data or the target's density changed
+    fn refresh_goodness_of_fit(&mut self) {
+        let import = &mut self.data;
+        let (Some(dataset), Some(target)) = (&import.dataset, import.gof_target.and_then(|id| self.distributions.get(&id))) else {
+            import.goodness_of_fit = None;
+            import.gof_computed_for = None;
+            import.residuals = None;
+            return;
+        };
+        let key = (target.id, target.density_fingerprint());
+        let cdf = |x: f64| target.quantile_level(x);
+        if import.gof_computed_for != Some(key) {
+            let sorted = dataset.sorted();
+            import.goodness_of_fit = Some((data::ks_test(&sorted, cdf), data::anderson_darling_test(&sorted, cdf)));
+        }
+        let bin_width = import.histogram.as_ref().map(|histogram| histogram.bin_width);
+        if import.gof_computed_for != Some(key) || import.residuals_bin_width != bin_width {
+            // Parameters fitted to this very data cost a degree of freedom each
+            let fitted = target.fit.as_ref()
+                .filter(|fit| fit.data == dataset.name && fit.n == dataset.values.len())
+                .map_or(0, |fit| fit.parameters.len());
+            import.residuals = import.histogram.as_ref().map(|histogram| {
+                let bins = data::binned_residuals(histogram, cdf);
+                let test = data::chi_square_test(&bins, fitted);
+                (bins, test)
+            });
+            import.residuals_bin_width = bin_width;
+        }
+        import.gof_computed_for = Some(key);
+    }
+
+    /// Start refitting resamples of the data behind the fitted curve `id`
+    fn start_bootstrap(&mut self, id: u32) -> Result<(), String> {
+        let dist = self.distributions.get(&id).ok_or("Pick a fitted distribution")?;
+        let fit = dist.fit.as_ref().ok_or("The curve was not fitted to data")?;
+        let dataset = self.data.dataset.as_ref()
+            .filter(|dataset| dataset.name == fit.data && dataset.values.len() == fit.n)
+            .ok_or_else(|| format!("Import {} again to resample it", fit.data))?;
+        // Over the bulk of the curve and all of the data
+        let (data_min, data_max) = dataset.range();
+        let (lower, upper) = (dist.quantile(0.0005).min(data_min), dist.quantile(0.9995).max(data_max));
+        let xs = (0..BOOTSTRAP_POINTS)
+            .map(|i| lower + (upper - lower) * i as f64 / (BOOTSTRAP_POINTS - 1) as f64)
+            .collect();
+        let tool = &mut self.data.bootstrap;
+        tool.job = bootstrap::BootstrapJob::start(dataset.values.clone(), fit.family, fit.method, xs, tool.replicates, tool.level, u64::from(id));
+        tool.computed_for = Some((id, dist.density_fingerprint()));
+        Ok(())
+    }
+
+    /// x-range of exported data: exactly the current view, or the union of
+    /// every curve's support
+    fn export_range(&self) -> (f64, f64) {
+        if !self.export_full_curves || self.distributions.is_empty() {
+            return self.data_range();
+        }
+        self.distributions.values()
+            .map(|dist| dist.support())
+            .filter(|(x_min, x_max)| x_min < x_max)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x_min, x_max)| (lo.min(x_min), hi.max(x_max)))
+    }
+
+    /// Total evidence of every product/posterior: its own scale S times the
+    /// evidence of any product parents, i.e. ∫ of the fully raw product of the
+    /// underlying input densities
+    fn chain_evidence(&self) -> HashMap<u32, f64> {
+        let mut ids: Vec<u32> = self.distributions.keys().copied().collect();
+        ids.sort_unstable();
+        let mut evidence = HashMap::new();
+        for id in ids {
+            let dist = &self.distributions[&id];
+            if let (Operation::Product | Operation::BayesUpdate, Some(scale)) = (&dist.operation, dist.normalizing_constant) {
+                let upstream: f64 = dist.parent_ids.iter()
+                    .map(|parent| evidence.get(parent).copied().unwrap_or(1.0))
+                    .product();
+                evidence.insert(id, scale * upstream);
+            }
+        }
+        evidence
+    }
+
+    /// x-intervals to shade for the p-value tool, in data units; the mirrored
+    /// tail of a two-tailed test holds the same probability as the observed one
+    fn pvalue_regions(&self) -> Vec<(f64, f64)> {
+        let Some(dist) = self.pvalue_target.and_then(|id| self.distributions.get(&id)) else {
+            return Vec::new();
+        };
+        let x = self.pvalue_x;
+        match self.pvalue_tail {
+            Tail::Lower => vec![(f64::NEG_INFINITY, x)],
+            Tail::Upper => vec![(x, f64::INFINITY)],
+            Tail::Two => {
+                let (lower, upper, _) = tail_probabilities(dist, x);
+                let tail = lower.min(upper);
+                vec![(f64::NEG_INFINITY, dist.quantile(tail)), (dist.quantile(1.0 - tail), f64::INFINITY)]
+            }
+        }
+    }
+
+    /// Replace a group's transform in one step: members go back through the old
+    /// transform and forward through the new one, so resetting to shift 0 and
+    /// scale 1 restores them exactly as they were
+    fn set_group_transform(&mut self, index: usize, shift: f64, scale: f64) {
+        let Some(group) = self.groups.get_mut(index) else {
+            return;
+        };
+        if !(scale > 0.0 && scale.is_finite() && shift.is_finite()) {
+            return;
+        }
+        let (old_shift, old_scale) = (group.shift, group.scale);
+        (group.shift, group.scale) = (shift, scale);
+        let map = |x: f64| (x - old_shift) / old_scale * scale + shift;
+        for id in &group.members {
+            let Some(dist) = self.distributions.get_mut(id).filter(|dist| !dist.is_derived()) else {
+                continue;
+            };
+            dist.mean = map(dist.mean);
+            dist.std_dev = dist.std_dev / old_scale * scale;
+            dist.domain = dist.domain.map(|(lower, upper)| (map(lower), map(upper)));
+            dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+        }
+        self.update_product_distributions();
+    }
+
+    /// Move the swept parameter on by `dt` seconds and recompute everything
+    /// derived from it; false when nothing is playing
+    fn advance_sweep(&mut self, dt: f64) -> bool {
+        if !self.sweep.playing {
+            return false;
+        }
+        let Some(dist) = self.sweep.target.and_then(|id| self.distributions.get_mut(&id)).filter(|dist| !dist.is_derived()) else {
+            self.sweep.playing = false;
+            return false;
+        };
+        self.sweep.elapsed += dt;
+        let value = self.sweep.value();
+        match self.sweep.parameter {
+            SweepParameter::Mean => dist.mean = value,
+            // A sweep through zero would leave the std dev invalid
+            SweepParameter::StdDev => dist.std_dev = value.max(1e-6),
+        }
+        dist.ranges.reconcile(&mut dist.mean, &mut dist.std_dev);
+        self.update_product_distributions();
+        true
+    }
+
+    /// Hash of every distribution's name and parameters, i.e. what a keyframe captures
+    fn parameter_fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        dists.sort_by_key(|dist| dist.id);
+        for dist in dists {
+            (dist.id, &dist.name, dist.density_fingerprint(), &dist.parent_ids).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Store the current state as a keyframe at `time` seconds when it differs
+    /// from the latest one; true when a keyframe was added
+    fn record_keyframe(&mut self, time: f64) -> bool {
+        let fingerprint = self.parameter_fingerprint();
+        if self.timeline.last_fingerprint == Some(fingerprint) {
+            return false;
+        }
+        let session = match self.save_workspace() {
+            Ok(session) => session,
+            Err(error) => {
+                self.timeline.error = Some(error);
+                return false;
+            }
+        };
+        let timeline = &mut self.timeline;
+        timeline.keyframes.push(Keyframe { time, session });
+        if timeline.keyframes.len() > MAX_KEYFRAMES {
+            timeline.keyframes.remove(0);
+        }
+        timeline.position = timeline.keyframes.len() - 1;
+        timeline.last_fingerprint = Some(fingerprint);
+        true
+    }
+
+    /// Restore the app to keyframe `index`; the timeline itself is kept
+    fn restore_keyframe(&mut self, index: usize) {
+        let Some(keyframe) = self.timeline.keyframes.get(index) else {
+            return;
+        };
+        let session = keyframe.session.clone();
+        self.timeline.error = self.load_workspace(&session).err();
+        self.timeline.position = index;
+        // Restoring is not an edit, so it must not be recorded again
+        self.timeline.last_fingerprint = Some(self.parameter_fingerprint());
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { distributions: self.distributions.clone(), next_id: self.next_id, fingerprint: self.parameter_fingerprint() }
+    }
+
+    /// Make the current distributions an undo step when they changed since the
+    /// last one; true when a step was added
+    fn record_history(&mut self) -> bool {
+        // A playing sweep or a running live fit rewrites a distribution every
+        // frame; like a drag, what they leave becomes one step once they stop
+        let live_fit = self.live_stream.stream.is_some() && self.live_stream.running_fit;
+        if self.sweep.playing || live_fit {
+            return false;
+        }
+        let fingerprint = self.parameter_fingerprint();
+        if self.history.current.as_ref().is_some_and(|current| current.fingerprint == fingerprint) {
+            return false;
+        }
+        let snapshot = self.snapshot();
+        let history = &mut self.history;
+        let Some(previous) = history.current.replace(snapshot) else {
+            // The first state seen is where the history starts
+            return false;
+        };
+        history.undo.push(previous);
+        if history.undo.len() > MAX_UNDO_STEPS {
+            history.undo.remove(0);
+        }
+        history.redo.clear();
+        true
+    }
+
+    /// Go back one step; false when there is nothing to undo
+    fn undo(&mut self) -> bool {
+        self.record_history();
+        let Some(snapshot) = self.history.undo.pop() else {
+            return false;
+        };
+        let current = self.history.current.replace(snapshot.clone());
+        self.history.redo.extend(current);
+        self.restore_snapshot(snapshot);
+        true
+    }
+
+    /// Reapply the last undone step; false when there is nothing to redo
+    fn redo(&mut self) -> bool {
+        // An edit since the undo starts a new branch and drops the redo stack
+        self.record_history();
+        let Some(snapshot) = self.history.redo.pop() else {
+            return false;
+        };
+        let current = self.history.current.replace(snapshot.clone());
+        self.history.undo.extend(current);
+        self.restore_snapshot(snapshot);
+        true
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.distributions = snapshot.distributions;
+        self.next_id = snapshot.next_id;
+        let distributions = &self.distributions;
+        self.selected_for_multiplication.retain(|id| distributions.contains_key(id));
+        self.update_product_distributions();
+    }
+
+    /// Curve colours in slot order: the custom palette, else the theme's
+    fn palette(&self) -> Vec<egui::Color32> {
+        if self.custom_palette.is_empty() {
+            self.theme.palette().to_vec()
+        } else {
+            self.custom_palette.iter().map(|&[r, g, b]| egui::Color32::from_rgb(r, g, b)).collect()
+        }
+    }
+
+    /// Colour of every curve, with the statistic's range when colouring by one
+    fn curve_colors(&self) -> (HashMap<u32, egui::Color32>, Option<(f64, f64)>) {
+        let statistics: HashMap<u32, f64> = self.distributions.iter()
+            .filter_map(|(&id, dist)| Some((id, self.curve_coloring.statistic(dist)?)))
+            .filter(|(_, value)| value.is_finite())
+            .collect();
+        let range = statistics.values()
+            .fold(None, |range: Option<(f64, f64)>, &value| match range {
+                Some((low, high)) => Some((low.min(value), high.max(value))),
+                None => Some((value, value)),
+            });
+        let palette = self.palette();
+        let colors = self.distributions.keys().enumerate()
+            .map(|(slot, &id)| {
+                let color = match (statistics.get(&id), range) {
+                    (Some(value), Some((low, high))) if high > low => viridis((value - low) / (high - low)),
+                    (Some(_), Some(_)) => viridis(0.5),
+                    _ => palette[slot % palette.len()],
+                };
+                (id, color)
+            })
+            .collect();
+        (colors, range)
+    }
+
+    /// (mean, std dev) the z-score panel standardizes with
+    fn zscore_reference(&self) -> Option<(f64, f64)> {
+        let dist = self.distributions.get(&self.zscore_target?)?;
+        Some((dist.mean, dist.std_dev)).filter(|&(_, std_dev)| std_dev > 0.0)
+    }
+
+    /// Position of the quantile lookup marker, in data units
+    fn quantile_marker(&self) -> Option<f64> {
+        if !self.show_quantile_marker {
+            return None;
+        }
+        let dist = self.distributions.get(&self.quantile_target?)?;
+        Some(dist.quantile(self.quantile_p)).filter(|x| x.is_finite())
+    }
+
+    /// Compact formula of a derived distribution in terms of the input
+    /// distributions it ultimately comes from, e.g. "A × B × C, renormalized".
+    /// None for input distributions.
+    fn recipe(&self, id: u32) -> Option<String> {
+        let dist = self.distributions.get(&id).filter(|dist| dist.is_derived())?;
+        let suffix = if dist.operation == Operation::Product { ", renormalized" } else { "" };
+        Some(format!("{}{}", self.recipe_expression(dist), suffix))
+    }
+
+    fn recipe_expression(&self, dist: &GaussianDistribution) -> String {
+        let operands: Vec<String> = dist.parent_ids.iter()
+            .map(|id| match self.distributions.get(id) {
+                None => format!("#{} (deleted)", id),
+                Some(parent) if !parent.is_derived() => parent.name.clone(),
+                // Renormalizing an inner product changes nothing once it is multiplied again
+                Some(parent) if dist.operation == Operation::Product && parent.operation == Operation::Product => {
+                    self.recipe_expression(parent)
+                }
+                Some(parent) if matches!(dist.operation, Operation::Maximum | Operation::Minimum | Operation::Transform(_)) => {
+                    self.recipe_expression(parent)
+                }
+                Some(parent) => format!("({})", self.recipe_expression(parent)),
+            })
+            .collect();
+        dist.operation.expression(&operands)
+    }
+
+    /// (id, name) pairs sorted by id, for selection widgets
+    fn distribution_choices(&self) -> Vec<(u32, String)> {
+        let mut choices: Vec<(u32, String)> = self.distributions
+            .values()
+            .map(|dist| (dist.id, dist.name.clone()))
+            .collect();
+        choices.sort_by_key(|(id, _)| *id);
+        choices
+    }
+
+    /// Markdown summary of the closed-form identities behind every derived
+    /// distribution, with the current parameter values filled in
+    fn algebra_cheat_sheet(&self) -> String {
+        let mut derived: Vec<&GaussianDistribution> = self.distributions
+            .values()
+            .filter(|dist| dist.is_derived())
+            .collect();
+        derived.sort_by_key(|dist| dist.id);
+
+        let mut md = String::from("# Distribution Algebra Cheat Sheet\n");
+        if derived.is_empty() {
+            md.push_str("\nNo derived distributions yet. Multiply or divide distributions to build a derivation graph.\n");
+            return md;
+        }
+
+        for dist in derived {
+            let parents: Vec<Option<&GaussianDistribution>> = dist.parent_ids
+                .iter()
+                .map(|id| self.distributions.get(id))
+                .collect();
+            let parent_names: Vec<String> = parents.iter().zip(&dist.parent_ids)
+                .map(|(parent, id)| match parent {
+                    Some(parent) => parent.name.clone(),
+                    None => format!("#{} (deleted)", id),
+                })
+                .collect();
+            md.push_str(&format!("\n## {}\n\n", dist.name));
+            md.push_str(&format!("{} = {}\n\n", dist.name, dist.operation.expression(&parent_names)));
+
+            let parents: Vec<&GaussianDistribution> = parents.into_iter().flatten().collect();
+            if parents.len() != dist.parent_ids.len() {
+                md.push_str(&format!(
+                    "- A parent was deleted; values are frozen at {}\n",
+                    gaussian_label(dist)
+                ));
+                continue;
+            }
+            for line in Self::identity_lines(dist, &parents) {
+                md.push_str(&format!("- {}\n", line));
+            }
+        }
+        md
+    }
+
+    fn identity_lines(dist: &GaussianDistribution, parents: &[&GaussianDistribution]) -> Vec<String> {
+        let all_gaussian = parents.iter().all(|p| p.has_closed_form());
+        match &dist.operation {
+            Operation::Product if all_gaussian => {
+                let precisions: Vec<f64> = parents.iter().map(|p| 1.0 / p.std_dev.powi(2)).collect();
+                let precision_sum: f64 = precisions.iter().sum();
+                let variance = 1.0 / precision_sum;
+                let operands: Vec<String> = parents.iter().map(|p| gaussian_label(p)).collect();
+                let weighted: Vec<String> = parents.iter().zip(&precisions)
+                    .map(|(p, precision)| format!("{:.4}·{:.4}", p.mean, precision))
+                    .collect();
+                let precision_terms: Vec<String> = precisions.iter().map(|p| format!("{:.4}", p)).collect();
+                let mut lines = vec![
+                    "Product of Gaussians: Π N(μᵢ, σᵢ²) ∝ N(μ, σ²) with 1/σ² = Σ 1/σᵢ² and μ = σ² Σ μᵢ/σᵢ²".to_string(),
+                    format!("{} ∝ {}", operands.join(" × "), gaussian_label(dist)),
+                    format!("1/σ² = {} = {:.4} → σ² = {:.4}, σ = {:.4}",
+                        precision_terms.join(" + "), precision_sum, variance, variance.sqrt()),
+                    format!("μ = {:.4} × ({}) = {:.4}", variance, weighted.join(" + "), dist.mean),
+                ];
+                let scale = dist.normalizing_constant.unwrap_or(0.0);
+                if let [a, b] = parents {
+                    lines.push(format!(
+                        "Scale: S = N(μ₁; μ₂, σ₁² + σ₂²) = N({:.4}; {:.4}, {:.4}) = {:.6}, so the raw product is S × {}",
+                        a.mean, b.mean, a.std_dev.powi(2) + b.std_dev.powi(2), scale, gaussian_label(dist)
+                    ));
+                } else {
+                    lines.push(format!(
+                        "Scale: S = ∫ Π fᵢ(x) dx = {:.6}, so the raw product is S × {}",
+                        scale, gaussian_label(dist)
+                    ));
+                }
+                lines
+            }
+            Operation::BayesUpdate => {
+                let (prior, likelihood) = (parents[0], parents[1]);
+                let evidence = dist.normalizing_constant.unwrap_or(0.0);
+                let mut lines = vec![
+                    "Bayes' rule: p(x | D) = p(x) L(x) / Z with evidence Z = ∫ p(x) L(x) dx".to_string(),
+                ];
+                if all_gaussian {
+                    let predictive_var = prior.std_dev.powi(2) + likelihood.std_dev.powi(2);
+                    lines.push(format!(
+                        "Gaussian evidence: Z = N(μ_prior; μ_L, σ_prior² + σ_L²) = N({:.4}; {:.4}, {:.4}) = {:.6}",
+                        prior.mean, likelihood.mean, predictive_var, evidence
+                    ));
+                } else {
+                    lines.push(format!("Evidence (numerical): Z = {:.6}", evidence));
+                }
+                lines.push(format!(
+                    "Posterior: {} × {} / {:.6} = {}",
+                    gaussian_label(prior), gaussian_label(likelihood), evidence, gaussian_label(dist)
+                ));
+                lines
+            }
+            Operation::Truncate { lower, upper } => {
+                let parent = parents[0];
+                let probability = dist.normalizing_constant.unwrap_or(0.0);
+                let mut lines = vec![
+                    "Conditioning on an interval: f(x | a ≤ X ≤ b) = f(x) / P(a ≤ X ≤ b) for a ≤ x ≤ b".to_string(),
+                ];
+                if all_gaussian {
+                    lines.push(format!(
+                        "P = Φ((b − μ)/σ) − Φ((a − μ)/σ) = Φ({:.4}) − Φ({:.4}) = {:.6}",
+                        (upper - parent.mean) / parent.std_dev,
+                        (lower - parent.mean) / parent.std_dev,
+                        probability
+                    ));
+                } else {
+                    lines.push(format!("P(a ≤ X ≤ b) (numerical) = {:.6}", probability));
+                }
+                lines.push(format!(
+                    "Result moments: mean = {:.4}, std dev = {:.4}",
+                    dist.mean, dist.std_dev
+                ));
+                lines
+            }
+            Operation::IidSum { copies, average } => {
+                let parent = parents[0];
+                let n = *copies as f64;
+                let mut lines = vec![
+                    format!(
+                        "Sum of n = {} iid copies: S = X₁ + … + Xₙ has mean nμ and variance nσ², density f^(*n) (n-fold convolution)",
+                        copies
+                    ),
+                ];
+                if *average {
+                    lines.push("Mean of the copies: S/n has mean μ and variance σ²/n".to_string());
+                }
+                if parent.has_closed_form() {
+                    lines.push(format!(
+                        "Gaussian copies stay Gaussian: {} → {}",
+                        gaussian_label(parent), gaussian_label(dist)
+                    ));
+                } else {
+                    lines.push(format!(
+                        "Central limit theorem: (S − nμ) / (σ√n) → N(0, 1); here mean = {:.4}, std dev = {:.4} (σ√n = {:.4})",
+                        dist.mean, dist.std_dev, parent.std_dev * n.sqrt()
+                    ));
+                }
+                lines
+            }
+            Operation::Mixture { weights, by_evidence } => {
+                let mut lines = vec![
+                    "Mixture: f(x) = Σ wᵢ fᵢ(x) with Σ wᵢ = 1; mean = Σ wᵢ μᵢ, variance = Σ wᵢ (σᵢ² + μᵢ²) − mean²".to_string(),
+                ];
+                if *by_evidence {
+                    lines.push("Bayesian model averaging: wᵢ = Zᵢ / Σ Zⱼ, the posterior model probabilities under equal model priors".to_string());
+                }
+                for (parent, weight) in parents.iter().zip(normalized_weights(weights)) {
+                    lines.push(format!("{:.4} × {} ({})", weight, parent.name, gaussian_label(parent)));
+                }
+                lines.push(format!("Result moments: mean = {:.4}, std dev = {:.4}", dist.mean, dist.std_dev));
+                lines
+            }
+            Operation::Temper { beta } => {
+                let parent = parents[0];
+                let mut lines = vec![
+                    format!("Tempering: f(x) = p(x)^β / ∫ p(t)^β dt with β = {:.4}", beta),
+                ];
+                if parent.has_closed_form() {
+                    lines.push(format!(
+                        "N(μ, σ²)^β ∝ N(μ, σ²/β): {} → σ² = {:.4} / {:.4} = {:.4}",
+                        gaussian_label(parent), parent.std_dev.powi(2), beta, dist.std_dev.powi(2)
+                    ));
+                } else {
+                    lines.push(format!("Result moments (numerical): mean = {:.4}, std dev = {:.4}", dist.mean, dist.std_dev));
+                }
+                lines
+            }
+            Operation::Transform(transform) => {
+                let mut lines = vec![
+                    format!("Change of variables Y = g(X) with g(x) = {}", transform.formula()),
+                    "f_Y(y) = Σᵢ f_X(xᵢ) / |g'(xᵢ)| summed over the roots g(xᵢ) = y (one root when g is monotone)".to_string(),
+                ];
+                let parent = parents[0];
+                match transform {
+                    Transform::Exp if parent.has_closed_form() => lines.push(format!(
+                        "exp of a Gaussian is LogNormal: f_Y(y) = N(ln y; μ, σ²) / y, mean = exp(μ + σ²/2) = {:.4}, \
+                         std dev = √((exp(σ²) − 1) exp(2μ + σ²)) = {:.4}",
+                        (parent.mean + parent.std_dev.powi(2) / 2.0).exp(),
+                        ((parent.std_dev.powi(2).exp() - 1.0) * (2.0 * parent.mean + parent.std_dev.powi(2)).exp()).sqrt()
+                    )),
+                    Transform::Square if parent.has_closed_form() && parent.mean == 0.0 => lines.push(format!(
+                        "Square of a centered Gaussian is σ² χ²₁: f_Y(y) = 2 N(√y; 0, σ²) / (2√y) for y > 0, mean = σ² = {:.4}",
+                        parent.std_dev.powi(2)
+                    )),
+                    _ => {}
+                }
+                lines.push(format!("Result moments: mean = {:.4}, std dev = {:.4}", dist.mean, dist.std_dev));
+                lines
+            }
+            Operation::Product => vec![
+                "Product of densities: f(x) ∝ Π fᵢ(x), renormalized numerically (no closed form)".to_string(),
+                format!("Result moments: mean = {:.4}, std dev = {:.4}", dist.mean, dist.std_dev),
+            ],
+            Operation::Quotient => {
+                let (x, y) = (parents[0], parents[1]);
+                let mut lines = vec![
+                    "Ratio of random variables: f_Z(z) = ∫ |y| f_X(zy) f_Y(y) dy, evaluated numerically".to_string(),
+                ];
+                if all_gaussian && x.mean == 0.0 && y.mean == 0.0 {
+                    lines.push("Ratio of centered Gaussians: N(0, σx²) / N(0, σy²) = Cauchy(0, σx/σy)".to_string());
+                    lines.push(format!(
+                        "{} / {} = Cauchy(0, {:.4}/{:.4}) = Cauchy(0, {:.4}); mean and variance are undefined",
+                        gaussian_label(x), gaussian_label(y), x.std_dev, y.std_dev, x.std_dev / y.std_dev
+                    ));
+                } else {
+                    lines.push(format!(
+                        "Result moments over the plotted range: mean = {:.4}, std dev = {:.4}",
+                        dist.mean, dist.std_dev
+                    ));
+                }
+                lines
+            }
+            Operation::Kde { samples, bandwidth } => vec![
+                "Kernel density estimate: f̂(x) = 1/(nh) Σ φ((x − xᵢ)/h) with the standard normal kernel φ".to_string(),
+                match bandwidth {
+                    Some(h) => format!("n = {}, bandwidth h = {:.4}", samples.len(), h),
+                    None => format!("n = {}, bandwidth from Silverman's rule h = 0.9 min(s, IQR/1.34) n^(−1/5)", samples.len()),
+                },
+                format!("Result moments: mean = {:.4}, std dev = {:.4} (the sample variance plus h²)", dist.mean, dist.std_dev),
+            ],
+            Operation::Family { family, parameters: [a, b] } => vec![
+                match family {
+                    FitFamily::Gamma => format!("Gamma: f(x) = x^(k−1) e^(−x/θ) / (Γ(k) θ^k), mean kθ = {:.4}, variance kθ² = {:.4}", a * b, a * b * b),
+                    FitFamily::Beta => format!(
+                        "Beta: f(x) = x^(α−1) (1−x)^(β−1) / B(α, β), mean α/(α+β) = {:.4}",
+                        a / (a + b)
+                    ),
+                    family => family.label().to_string(),
+                },
+                format!("Result moments: mean = {:.4}, std dev = {:.4}", dist.mean, dist.std_dev),
+            ],
+            Operation::Maximum | Operation::Minimum => {
+                let (name, cdf_identity, density_identity) = if dist.operation == Operation::Maximum {
+                    ("Maximum", "F_max(x) = Π Fᵢ(x)", "f_max(x) = Σᵢ fᵢ(x) Π_{j≠i} Fⱼ(x)")
+                } else {
+                    ("Minimum", "1 − F_min(x) = Π (1 − Fᵢ(x))", "f_min(x) = Σᵢ fᵢ(x) Π_{j≠i} (1 − Fⱼ(x))")
+                };
+                vec![
+                    format!("{} of independent variables: {}, so {}", name, cdf_identity, density_identity),
+                    format!(
+                        "Inputs: {}; result moments: mean = {:.4}, std dev = {:.4}",
+                        parents.iter().map(|p| gaussian_label(p)).collect::<Vec<_>>().join(", "),
+                        dist.mean, dist.std_dev
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+/// Densities that blow up at a boundary are left out of numeric grids
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+/// Marker values as the comma-separated text they are edited in
+fn format_marker_values(values: &[f64]) -> String {
+    values.iter().map(f64::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Comma-separated numbers; None if any entry is not a finite number
+fn parse_marker_values(text: &str) -> Option<Vec<f64>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.parse::<f64>().ok().filter(|value| value.is_finite()))
+        .collect()
+}
+
+/// Denominator densities below this fraction of the denominator's largest
+/// sampled value leave a gap in the likelihood ratio instead of a spike
+const RATIO_DENOMINATOR_FLOOR: f64 = 1e-9;
+
+/// log₁₀ of the likelihood ratio f(x)/g(x) over [x_min, x_max], split into
+/// segments wherever it is undefined: g is numerically zero, f is zero, or x
+/// is outside either domain
+fn likelihood_ratio_segments(
+    numerator: &GaussianDistribution,
+    denominator: &GaussianDistribution,
+    x_min: f64,
+    x_max: f64,
+    num_points: usize,
+) -> Vec<Vec<[f64; 2]>> {
+    let denominators = sample_curve(x_min, x_max, num_points, |x| denominator.evaluate(x));
+    let floor = RATIO_DENOMINATOR_FLOOR * denominators.iter().map(|[_, g]| *g).fold(0.0, f64::max);
+    let mut segments = vec![Vec::new()];
+    for [x, g] in denominators {
+        let f = numerator.evaluate(x);
+        let ratio = (f / g).log10();
+        if g > floor && ratio.is_finite() {
+            segments.last_mut().unwrap().push([x, ratio]);
+        } else if !segments.last().unwrap().is_empty() {
+            segments.push(Vec::new());
+        }
+    }
+    segments.retain(|segment| segment.len() > 1);
+    segments
+}
+
+/// Convex pieces of the region between two curves sampled at the same xs,
+/// each flagged with whether the first curve is the upper one. Slices where
+/// the curves cross are split at the crossing, so every piece is a triangle
+/// or a quadrilateral that egui_plot can fill.
+fn between_regions(first: &[[f64; 2]], second: &[[f64; 2]]) -> Vec<(Vec<[f64; 2]>, bool)> {
+    let mut pieces = Vec::new();
+    for i in 1..first.len().min(second.len()) {
+        let ([x0, f0], [x1, f1]) = (first[i - 1], first[i]);
+        let (g0, g1) = (second[i - 1][1], second[i][1]);
+        let (d0, d1) = (f0 - g0, f1 - g1);
+        if d0 * d1 < 0.0 {
+            let t = d0 / (d0 - d1);
+            let crossing = [x0 + t * (x1 - x0), f0 + t * (f1 - f0)];
+            pieces.push((vec![[x0, f0], crossing, [x0, g0]], d0 > 0.0));
+            pieces.push((vec![crossing, [x1, f1], [x1, g1]], d1 > 0.0));
+        } else if d0 != 0.0 || d1 != 0.0 {
+            pieces.push((vec![[x0, f0], [x1, f1], [x1, g1], [x0, g0]], d0 + d1 > 0.0));
+        }
+    }
+    pieces
+}
+
+/// `num_points` (at least 2) evenly spaced points [x, f(x)] from x_min to x_max
+fn sample_curve(x_min: f64, x_max: f64, num_points: usize, f: impl Fn(f64) -> f64) -> Vec<[f64; 2]> {
+    let last = num_points.max(2) - 1;
+    (0..=last)
+        .map(|i| {
+            let x = x_min + (x_max - x_min) * i as f64 / last as f64;
+            [x, f(x)]
+        })
+        .collect()
+}
+
+/// Highest sampled point of a curve
+fn curve_peak(curve: &[[f64; 2]]) -> Option<[f64; 2]> {
+    curve.iter().copied().filter(|[_, y]| y.is_finite()).max_by(|a, b| a[1].total_cmp(&b[1]))
+}
+
+/// Same x-range, with the top raised so a peak sits just below it
+fn rescale_to_peak(bounds: egui_plot::PlotBounds, peak: f64) -> egui_plot::PlotBounds {
+    let y_min = bounds.min()[1];
+    egui_plot::PlotBounds::from_min_max(
+        [bounds.min()[0], y_min],
+        [bounds.max()[0], y_min + (peak - y_min) * 1.05],
+    )
+}
+
+/// Metric value with its unit, spelling out infinity
+fn format_metric(value: f64, unit: &str) -> String {
+    if value.is_infinite() {
+        "∞ (P has mass where Q has none)".to_string()
+    } else {
+        format!("{:.6} {}", value, unit)
+    }
+}
+
+/// Weights rescaled to sum to 1, with negative or non-finite entries as 0.
+/// All zeros when nothing positive is left.
+fn normalized_weights(weights: &[f64]) -> Vec<f64> {
+    let clean: Vec<f64> = weights.iter()
+        .map(|&w| if w.is_finite() { w.max(0.0) } else { 0.0 })
+        .collect();
+    let total: f64 = clean.iter().sum();
+    if total > 0.0 {
+        clean.iter().map(|w| w / total).collect()
+    } else {
+        clean
+    }
+}
+
+/// (P(X ≤ x), P(X ≥ x), two-tailed p-value) under a distribution
+fn tail_probabilities(dist: &GaussianDistribution, x: f64) -> (f64, f64, f64) {
+    let (lower, upper) = (dist.quantile_level(x), 1.0 - dist.quantile_level(x));
+    (lower, upper, (2.0 * lower.min(upper)).min(1.0))
+}
+
+/// Move one endpoint (0 = lower, 1 = upper) of an interval to x. Dragging past
+/// the other endpoint swaps their roles, so the returned handle keeps
+/// following the pointer.
+fn move_interval_endpoint((lower, upper): (f64, f64), handle: usize, x: f64) -> ((f64, f64), usize) {
+    let other = if handle == 0 { upper } else { lower };
+    if x <= other {
+        ((x, other), 0)
+    } else {
+        ((other, x), 1)
+    }
+}
+
+/// Number for TikZ coordinates and options: fixed-point where that is exact
+/// enough, scientific for tiny values such as far tails on a log axis
+fn tikz_number(value: f64) -> String {
+    if value == 0.0 || (1e-3..1e9).contains(&value.abs()) {
+        let fixed = format!("{:.6}", value);
+        fixed.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        format!("{:.4e}", value)
+    }
+}
+
+/// Fewest grid points of the script exports, so derived densities computed
+/// on the grid stay accurate when the supports are far apart
+const SCRIPT_GRID_POINTS: usize = 4001;
+
+/// Distributions of a script export and the grid they are computed on
+struct ScriptSetup<'a> {
+    /// In derivation order, parents first
+    dists: Vec<&'a GaussianDistribution>,
+    /// Names made unique, by id
+    keys: HashMap<u32, String>,
+    /// (x_min, x_max, points)
+    grid: (f64, f64, usize),
+    /// Horizontal range of the view
+    view: (f64, f64),
+}
+
+/// Target language of a script export; each prelude defines the same helpers
+/// (`normalize`, `cdf`, `kde`, `tabulated`) so expressions carry over
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScriptLanguage {
+    Python,
+    R,
+}
+
+impl ScriptLanguage {
+    fn number(self, value: f64) -> String {
+        match self {
+            ScriptLanguage::Python => python_number(value),
+            ScriptLanguage::R if value.is_nan() => "NaN".to_string(),
+            ScriptLanguage::R if value.is_infinite() => if value > 0.0 { "Inf" } else { "-Inf" }.to_string(),
+            ScriptLanguage::R => format!("{:?}", value),
+        }
+    }
+
+    fn list(self, values: &mut dyn Iterator<Item = f64>) -> String {
+        let values: Vec<String> = values.map(|value| self.number(value)).collect();
+        match self {
+            ScriptLanguage::Python => format!("[{}]", values.join(", ")),
+            ScriptLanguage::R => format!("c({})", values.join(", ")),
+        }
+    }
+
+    /// The density named `key`
+    fn density(self, key: &str) -> String {
+        match self {
+            ScriptLanguage::Python => format!("pdf[{}]", quoted(key)),
+            ScriptLanguage::R => format!("pdf[[{}]]", quoted(key)),
+        }
+    }
+
+    fn power(self) -> &'static str {
+        match self {
+            ScriptLanguage::Python => "**",
+            ScriptLanguage::R => "^",
+        }
+    }
+
+    /// Derivative on the grid
+    fn gradient(self) -> &'static str {
+        match self {
+            ScriptLanguage::Python => "np.gradient",
+            ScriptLanguage::R => "gradient",
+        }
+    }
+
+    /// Density of a parametric family evaluated on the grid
+    fn family(self, family: FitFamily, [a, b]: [f64; 2]) -> String {
+        let (a, b) = (self.number(a), self.number(b));
+        match (self, family) {
+            (ScriptLanguage::Python, FitFamily::Normal) => format!("stats.norm(loc={}, scale={}).pdf(x)", a, b),
+            (ScriptLanguage::Python, FitFamily::LogNormal) => format!("stats.lognorm(s={}, scale=np.exp({})).pdf(x)", b, a),
+            (ScriptLanguage::Python, FitFamily::Gamma) => format!("stats.gamma(a={}, scale={}).pdf(x)", a, b),
+            (ScriptLanguage::Python, FitFamily::Beta) => format!("stats.beta(a={}, b={}).pdf(x)", a, b),
+            (ScriptLanguage::R, FitFamily::Normal) => format!("dnorm(x, mean = {}, sd = {})", a, b),
+            (ScriptLanguage::R, FitFamily::LogNormal) => format!("dlnorm(x, meanlog = {}, sdlog = {})", a, b),
+            (ScriptLanguage::R, FitFamily::Gamma) => format!("dgamma(x, shape = {}, scale = {})", a, b),
+            (ScriptLanguage::R, FitFamily::Beta) => format!("dbeta(x, {}, {})", a, b),
+        }
+    }
+}
+
+/// Imports and grid helpers at the top of every Python export
+const PYTHON_PRELUDE: &str = r#"# Distributions from PDF Viewer, rebuilt with NumPy and SciPy.
+# Each density lives on the grid x in the dictionary pdf, keyed by name.
+import numpy as np
+import matplotlib.pyplot as plt
+from scipy import stats
+from scipy.integrate import cumulative_trapezoid, trapezoid
+
+
+def normalize(f):
+    """Scale a density on the grid so that it integrates to 1."""
+    return f / trapezoid(f, x)
+
+
+def cdf(f):
+    """Running integral of a density on the grid."""
+    return cumulative_trapezoid(f, x, initial=0.0)
+
+
+def kde(samples, bandwidth):
+    """Gaussian kernel density estimate with kernel std dev `bandwidth`."""
+    samples = np.asarray(samples)
+    return stats.gaussian_kde(samples, bw_method=bandwidth / samples.std(ddof=1))(x)
+
+
+def tabulated(lower, upper, values):
+    """Density the viewer computed on an even grid over [lower, upper]."""
+    return np.interp(x, np.linspace(lower, upper, len(values)), values, left=0.0, right=0.0)
+
+
+"#;
+
+/// Imports and grid helpers at the top of every R export
+const R_PRELUDE: &str = r#"# Distributions from PDF Viewer, rebuilt in base R and drawn with ggplot2.
+# Each density lives on the grid x in the list pdf, keyed by name.
+library(ggplot2)
+
+trapezoids <- function(f) diff(x) * (head(f, -1) + tail(f, -1)) / 2
+
+# Scale a density on the grid so that it integrates to 1
+normalize <- function(f) f / sum(trapezoids(f))
+
+# Running integral of a density on the grid
+cdf <- function(f) c(0, cumsum(trapezoids(f)))
+
+# Derivative on the grid: central differences inside, one-sided at the ends
+gradient <- function(f) {
+  n <- length(f)
+  c((f[2] - f[1]) / (x[2] - x[1]),
+    (f[3:n] - f[1:(n - 2)]) / (x[3:n] - x[1:(n - 2)]),
+    (f[n] - f[n - 1]) / (x[n] - x[n - 1]))
+}
+
+# Gaussian kernel density estimate with kernel std dev `bandwidth`
+kde <- function(samples, bandwidth) {
+  density(samples, bw = bandwidth, from = min(x), to = max(x), n = length(x))$y
+}
+
+# Density the viewer computed on an even grid over [lower, upper]
+tabulated <- function(lower, upper, values) {
+  approx(seq(lower, upper, length.out = length(values)), values, xout = x, yleft = 0, yright = 0)$y
+}
+
+"#;
+
+/// Look of the HTML report
+const REPORT_STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; }
+img { max-width: 100%; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+pre { background: #f6f6f6; padding: 1em; overflow-x: auto; }";
+
+fn is_markdown_path(path: &str) -> bool {
+    std::path::Path::new(path.trim())
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown"))
+}
+
+/// Plain text made safe for HTML
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// "#rrggbb" of a color, for the script exports
+fn hex_color(color: egui::Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+/// A float as Python source
+fn python_number(value: f64) -> String {
+    if value.is_nan() {
+        "np.nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "np.inf" } else { "-np.inf" }.to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+/// Text as a double-quoted string literal; JSON escapes are valid in Python and R
+fn quoted(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+/// Plain text made safe for LaTeX
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replace a file through a temporary sibling, so readers never see it half written
+fn write_replacing(path: &std::path::Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)
+}
+
+/// Recipe of a plotted series: its name is the distribution name, possibly
+/// followed by a parenthesized variant such as " (raw, S = …)"
+fn curve_recipe<'a>(recipes: &'a HashMap<String, String>, series: &str) -> Option<&'a String> {
+    recipes.get(series).or_else(|| {
+        recipes.iter()
+            .filter(|(name, _)| series.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with(" (")))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, recipe)| recipe)
+    })
+}
+
+/// Jeffreys' verbal scale for a Bayes factor, read on whichever side of 1 it falls
+fn jeffreys_strength(bayes_factor: f64) -> &'static str {
+    let strength = bayes_factor.max(1.0 / bayes_factor);
+    if strength < 10f64.sqrt() {
+        "Barely worth mentioning"
+    } else if strength < 10.0 {
+        "Substantial"
+    } else if strength < 10f64.powf(1.5) {
+        "Strong"
+    } else if strength < 100.0 {
+        "Very strong"
+    } else {
+        "Decisive"
+    }
+}
+
+/// Plot bounds from [x_min, x_max, y_min, y_max]
+fn view_bounds([x_min, x_max, y_min, y_max]: [f64; 4]) -> egui_plot::PlotBounds {
+    egui_plot::PlotBounds::from_min_max([x_min, y_min], [x_max, y_max])
+}
+
+/// Inverse of `view_bounds`
+fn bounds_array(bounds: &egui_plot::PlotBounds) -> [f64; 4] {
+    [bounds.min()[0], bounds.max()[0], bounds.min()[1], bounds.max()[1]]
+}
+
+fn gaussian_label(dist: &GaussianDistribution) -> String {
+    format!("N(μ={:.4}, σ={:.4})", dist.mean, dist.std_dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::f64::consts::PI;
+
+    const EPSILON: f64 = 1e-10;
+    const APPROX_EPSILON: f64 = 1e-6;
+
+    #[test]
+    fn test_gaussian_distribution_creation() {
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        assert_eq!(dist.id, 1);
+        assert_eq!(dist.name, "Test");
+        assert_eq!(dist.mean, 0.0);
+        assert_eq!(dist.std_dev, 1.0);
+        assert!(dist.parent_ids.is_empty());
+        assert!(!dist.is_product);
+    }
+
+    #[test]
+    fn test_gaussian_pdf_evaluation() {
+        let dist = GaussianDistribution::new(1, "Standard Normal".to_string(), 0.0, 1.0);
+        
+        // Test at mean (should be maximum)
+        let at_mean = dist.evaluate(0.0);
+        let expected_at_mean = 1.0 / (2.0 * PI).sqrt();
+        assert_abs_diff_eq!(at_mean, expected_at_mean, epsilon = APPROX_EPSILON);
+        
+        // Test at one standard deviation
+        let at_one_std = dist.evaluate(1.0);
+        let expected_at_one_std = (1.0 / (2.0 * PI).sqrt()) * (-0.5_f64).exp();
+        assert_abs_diff_eq!(at_one_std, expected_at_one_std, epsilon = APPROX_EPSILON);
+        
+        // Test symmetry
+        assert_abs_diff_eq!(dist.evaluate(-1.0), dist.evaluate(1.0), epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_gaussian_pdf_different_parameters() {
+        let dist = GaussianDistribution::new(1, "Custom".to_string(), 2.0, 0.5);
+        
+        // Test at mean
+        let at_mean = dist.evaluate(2.0);
+        let expected = 1.0 / (0.5 * (2.0 * PI).sqrt());
+        assert_abs_diff_eq!(at_mean, expected, epsilon = APPROX_EPSILON);
+        
+        // Test symmetry around mean
+        assert_abs_diff_eq!(dist.evaluate(1.5), dist.evaluate(2.5), epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_gaussian_multiplication_two_distributions() {
+        let dist1 = GaussianDistribution::new(1, "Dist1".to_string(), 0.0, 1.0);
+        let dist2 = GaussianDistribution::new(2, "Dist2".to_string(), 2.0, 1.0);
+        
+        let parents = vec![&dist1, &dist2];
+        let (result_mean, result_variance) = GaussianDistribution::multiply_gaussians(&parents);
+        
+        // For N(0,1) * N(2,1):
+        // precision1 = 1, precision2 = 1
+        // weighted_mean_sum = 0*1 + 2*1 = 2
+        // precision_sum = 1 + 1 = 2
+        // result_mean = 2/2 = 1
+        // result_variance = 1/2 = 0.5
+        assert_abs_diff_eq!(result_mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(result_variance, 0.5, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_gaussian_multiplication_three_distributions() {
+        let dist1 = GaussianDistribution::new(1, "D1".to_string(), 0.0, 1.0);
+        let dist2 = GaussianDistribution::new(2, "D2".to_string(), 3.0, 1.0);
+        let dist3 = GaussianDistribution::new(3, "D3".to_string(), 6.0, 2.0);
+        
+        let parents = vec![&dist1, &dist2, &dist3];
+        let (result_mean, result_variance) = GaussianDistribution::multiply_gaussians(&parents);
+        
+        // precision1 = 1, precision2 = 1, precision3 = 1/4 = 0.25
+        // weighted_mean_sum = 0*1 + 3*1 + 6*0.25 = 4.5
+        // precision_sum = 1 + 1 + 0.25 = 2.25
+        // result_mean = 4.5/2.25 = 2.0
+        // result_variance = 1/2.25 = 4/9
+        assert_abs_diff_eq!(result_mean, 2.0, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(result_variance, 4.0/9.0, epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_gaussian_multiplication_empty_list() {
+        let parents: Vec<&GaussianDistribution> = vec![];
+        let (result_mean, result_variance) = GaussianDistribution::multiply_gaussians(&parents);
+        assert_eq!(result_mean, 0.0);
+        assert_eq!(result_variance, 1.0);
+    }
+
+    #[test]
+    fn test_gaussian_product_creation() {
+        let dist1 = GaussianDistribution::new(1, "Parent1".to_string(), 1.0, 2.0);
+        let dist2 = GaussianDistribution::new(2, "Parent2".to_string(), 3.0, 1.0);
+        
+        let parents = vec![&dist1, &dist2];
+        let parent_ids = vec![1, 2];
+        let product = GaussianDistribution::new_product(
+            10, 
+            "Product".to_string(), 
+            parent_ids.clone(), 
+            &parents
+        );
+        
+        assert_eq!(product.id, 10);
+        assert_eq!(product.name, "Product");
+        assert_eq!(product.parent_ids, parent_ids);
+        assert!(product.is_product);
+        
+        // Verify mathematical correctness
+        // precision1 = 1/4 = 0.25, precision2 = 1
+        // weighted_mean_sum = 1*0.25 + 3*1 = 3.25
+        // precision_sum = 0.25 + 1 = 1.25
+        // result_mean = 3.25/1.25 = 2.6
+        // result_std_dev = sqrt(1/1.25) = sqrt(0.8) ≈ 0.894
+        assert_abs_diff_eq!(product.mean, 2.6, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(product.std_dev, (0.8_f64).sqrt(), epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_generate_points_basic() {
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        
+        // Test the individual point generation logic instead
+        let x_values = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let y_values: Vec<f64> = x_values.iter().map(|&x| dist.evaluate(x)).collect();
+        
+        assert_eq!(y_values.len(), 5);
+        
+        // Check that y values are positive (valid PDF values)
+        for &y in &y_values {
+            assert!(y > 0.0);
+        }
+        
+        // Check that maximum is at mean (x=0) - middle value should be largest
+        assert!(y_values[2] > y_values[0]);
+        assert!(y_values[2] > y_values[4]);
+        
+        // Test symmetry
+        assert_abs_diff_eq!(y_values[0], y_values[4], epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(y_values[1], y_values[3], epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_generate_shading_polygon() {
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        
+        let x_min = -2.0;
+        let x_max = 2.0;
+        let num_points = 5;
+        
+        // Generate points manually to test the algorithm since PlotPoints is opaque
+        let mut expected_points = Vec::with_capacity(num_points + 2);
+        
+        // Start from the bottom left corner
+        expected_points.push([x_min, 0.0]);
+        
+        // Generate curve points from left to right
+        for i in 0..num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+            let y = dist.evaluate(x);
+            expected_points.push([x, y]);
+        }
+        
+        // End at the bottom right corner
+        expected_points.push([x_max, 0.0]);
+        
+        // Now test the properties using our expected points
+        assert_eq!(expected_points.len(), num_points + 2);
+        
+        // First point should be bottom left corner
+        assert_abs_diff_eq!(expected_points[0][0], x_min, epsilon = EPSILON);
+        assert_abs_diff_eq!(expected_points[0][1], 0.0, epsilon = EPSILON);
+        
+        // Last point should be bottom right corner  
+        let last_idx = expected_points.len() - 1;
+        assert_abs_diff_eq!(expected_points[last_idx][0], x_max, epsilon = EPSILON);
+        assert_abs_diff_eq!(expected_points[last_idx][1], 0.0, epsilon = EPSILON);
+        
+        // Middle points should have positive y values (above x-axis)
+        for (i, point) in expected_points.iter().enumerate().take(expected_points.len() - 1).skip(1) {
+            assert!(point[1] > 0.0, "Point {} should be above x-axis, got y={}", i, point[1]);
+            assert!(point[0] >= x_min && point[0] <= x_max, "Point {} x-coordinate should be in range", i);
+        }
+        
+        // Points should be ordered by x-coordinate (left to right)
+        for i in 1..expected_points.len() {
+            assert!(expected_points[i][0] >= expected_points[i-1][0], "Points should be ordered by x-coordinate");
+        }
+        
+        // The curve points should form a proper bell shape (maximum near center)
+        let center_idx = expected_points.len() / 2;
+        let center_y = expected_points[center_idx][1];
+        let edge_y = expected_points[1][1]; // First curve point
+        assert!(center_y >= edge_y, "Center of distribution should be at least as high as edges");
+    }
+
+    #[test]
+    fn test_std_markers() {
+        let mut dist = GaussianDistribution::new(1, "Test".to_string(), 5.0, 2.0);
+        let markers = dist.marker_positions();
+        
+        assert_eq!(markers.len(), 7);
+        
+        let expected = [
+            5.0 - 3.0 * 2.0, // -1.0
+            5.0 - 2.0 * 2.0, // 1.0
+            5.0 - 1.0 * 2.0, // 3.0
+            5.0,              // 5.0 (mean)
+            5.0 + 1.0 * 2.0, // 7.0
+            5.0 + 2.0 * 2.0, // 9.0
+            5.0 + 3.0 * 2.0, // 11.0
+        ];
+        
+        for (i, &(marker, center)) in markers.iter().enumerate() {
+            assert_abs_diff_eq!(marker, expected[i], epsilon = EPSILON);
+            assert_eq!(center, i == 3);
+        }
+
+        // Quantiles in percent; 0 and 100 (infinite quantiles) are dropped
+        dist.markers = MarkerScheme { kind: MarkerKind::Quantiles, values: vec![0.0, 2.5, 50.0, 97.5, 100.0] };
+        let markers = dist.marker_positions();
+        assert_eq!(markers.len(), 3);
+        assert_abs_diff_eq!(markers[0].0, 5.0 - 2.0 * 1.959963984540054, epsilon = 1e-6);
+        assert_eq!(markers[1], (5.0, true));
+        assert_abs_diff_eq!(markers[2].0, 5.0 + 2.0 * 1.959963984540054, epsilon = 1e-6);
+
+        dist.markers = MarkerScheme { kind: MarkerKind::Values, values: vec![3.2] };
+        assert_eq!(dist.marker_positions(), vec![(3.2, false)]);
+
+        // The scheme is saved with the distribution
+        let restored: GaussianDistribution = serde_json::from_str(&serde_json::to_string(&dist).unwrap()).unwrap();
+        assert_eq!(restored.markers, dist.markers);
+
+        assert_eq!(parse_marker_values("2.5, 50 ,97.5,"), Some(vec![2.5, 50.0, 97.5]));
+        assert_eq!(parse_marker_values("1, x"), None);
+        assert_eq!(format_marker_values(&[-1.0, 0.5]), "-1, 0.5");
+    }
+
+    #[test]
+    fn test_pdf_viewer_app_creation() {
+        let app = PdfViewerApp::new();
+        assert!(app.distributions.is_empty());
+        assert_eq!(app.next_id, 0);
+        assert!(app.selected_for_multiplication.is_empty());
+        assert!(app.show_shading);
+        assert_abs_diff_eq!(app.shading_opacity, 0.3_f32, epsilon = 1e-6_f32);
+        assert!(app.show_std_markers);
+    }
+
+    #[test]
+    fn test_session_save_load_roundtrip() {
+        let mut app = PdfViewerApp::new();
+        
+        // Add some distributions
+        let dist1 = GaussianDistribution::new(0, "Test1".to_string(), 1.0, 0.5);
+        let dist2 = GaussianDistribution::new(1, "Test2".to_string(), -1.0, 2.0);
+        
+        app.distributions.insert(0, dist1);
+        app.distributions.insert(1, dist2);
+        app.next_id = 2;
+        app.show_shading = false;
+        app.shading_opacity = 0.7;
+        app.show_std_markers = false;
+        
+        // Save session
+        let json = app.save_session().expect("Save should succeed");
+        assert!(json.contains("Test1"));
+        assert!(json.contains("Test2"));
+        
+        // Create new app and load session
+        let mut new_app = PdfViewerApp::new();
+        new_app.load_session(&json).expect("Load should succeed");
+        
+        // Verify all data was restored
+        assert_eq!(new_app.distributions.len(), 2);
+        assert_eq!(new_app.next_id, 2);
+        assert!(!new_app.show_shading);
+        assert_abs_diff_eq!(new_app.shading_opacity, 0.7_f32, epsilon = 1e-6_f32);
+        assert!(!new_app.show_std_markers);
+        
+        // Verify distribution details
+        let loaded_dist1 = new_app.distributions.get(&0).unwrap();
+        assert_eq!(loaded_dist1.name, "Test1");
+        assert_abs_diff_eq!(loaded_dist1.mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(loaded_dist1.std_dev, 0.5, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_session_save_with_products() {
+        let mut app = PdfViewerApp::new();
+        
+        // Create parent distributions
+        let parent1 = GaussianDistribution::new(0, "Parent1".to_string(), 0.0, 1.0);
+        let parent2 = GaussianDistribution::new(1, "Parent2".to_string(), 2.0, 1.0);
+        
+        // Create product distribution
+        let parents = vec![&parent1, &parent2];
+        let product = GaussianDistribution::new_product(
+            2, 
+            "Product".to_string(), 
+            vec![0, 1], 
+            &parents
+        );
+        
+        app.distributions.insert(0, parent1);
+        app.distributions.insert(1, parent2);
+        app.distributions.insert(2, product);
+        app.next_id = 3;
+        
+        // Test save/load
+        let json = app.save_session().expect("Save should succeed");
+        let mut new_app = PdfViewerApp::new();
+        new_app.load_session(&json).expect("Load should succeed");
+        
+        // Verify product distribution was preserved
+        let loaded_product = new_app.distributions.get(&2).unwrap();
+        assert!(loaded_product.is_product);
+        assert_eq!(loaded_product.parent_ids, vec![0, 1]);
+        assert_eq!(loaded_product.name, "Product");
+    }
+
+    #[test]
+    fn test_update_product_distributions() {
+        let mut app = PdfViewerApp::new();
+        
+        // Create parent distributions
+        let parent1 = GaussianDistribution::new(0, "Parent1".to_string(), 0.0, 1.0);
+        let parent2 = GaussianDistribution::new(1, "Parent2".to_string(), 2.0, 1.0);
+        
+        // Create product distribution
+        let parents = vec![&parent1, &parent2];
+        let product = GaussianDistribution::new_product(
+            2, 
+            "Product".to_string(), 
+            vec![0, 1], 
+            &parents
+        );
+        
+        app.distributions.insert(0, parent1);
+        app.distributions.insert(1, parent2);
+        app.distributions.insert(2, product);
+        
+        // Modify a parent distribution
+        app.distributions.get_mut(&0).unwrap().mean = 1.0;
+        app.distributions.get_mut(&0).unwrap().std_dev = 0.5;
+        
+        // Update products
+        app.update_product_distributions();
+        
+        // Verify product was updated
+        let updated_product = app.distributions.get(&2).unwrap();
+        
+        // Calculate expected values manually
+        // Parent1: mean=1.0, std_dev=0.5, precision=4
+        // Parent2: mean=2.0, std_dev=1.0, precision=1
+        // Expected mean = (1.0*4 + 2.0*1) / (4+1) = 6/5 = 1.2
+        // Expected variance = 1/(4+1) = 0.2
+        // Expected std_dev = sqrt(0.2) ≈ 0.447
+        assert_abs_diff_eq!(updated_product.mean, 1.2, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(updated_product.std_dev, (0.2_f64).sqrt(), epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_invalid_json_load() {
+        let mut app = PdfViewerApp::new();
+        let result = app.load_session("invalid json");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Failed to parse"));
+    }
+
+    #[test]
+    fn test_very_small_std_dev() {
+        let dist = GaussianDistribution::new(1, "Narrow".to_string(), 0.0, 0.01);
+        let at_mean = dist.evaluate(0.0);
+        
+        // Very narrow distribution should have very high peak
+        assert!(at_mean > 30.0); // Much higher than standard normal
+        
+        // Test integration manually instead of using PlotPoints
+        let x_min = -0.05;
+        let x_max = 0.05;
+        let num_points = 100;
+        
+        let dx = (x_max - x_min) / (num_points - 1) as f64;
+        let mut integral = 0.0;
+        
+        for i in 0..(num_points - 1) {
+            let x1 = x_min + i as f64 * dx;
+            let x2 = x_min + (i + 1) as f64 * dx;
+            let y1 = dist.evaluate(x1);
+            let y2 = dist.evaluate(x2);
+            integral += (y1 + y2) * dx * 0.5;
+        }
+        
+        // Should be close to 1, but we're only integrating a small range
+        assert!(integral > 0.8); // Most of the mass should be in this range
+    }
+
+    #[test]
+    fn test_large_std_dev() {
+        let dist = GaussianDistribution::new(1, "Wide".to_string(), 0.0, 10.0);
+        let at_mean = dist.evaluate(0.0);
+        
+        // Very wide distribution should have very low peak
+        assert!(at_mean < 0.05);
+        
+        // Should still be symmetric
+        assert_abs_diff_eq!(dist.evaluate(-5.0), dist.evaluate(5.0), epsilon = APPROX_EPSILON);
+    }
+
+    #[test]
+    fn test_plot_range_calculation() {
+        let app = PdfViewerApp::new();
+        
+        // Test default range
+        let (x_min, x_max) = app.get_plot_range();
+        assert_abs_diff_eq!(x_min, -6.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(x_max, 6.0, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_auto_fit_view() {
+        let mut app = PdfViewerApp::new();
+        
+        // Add distributions with different means and std devs
+        let dist1 = GaussianDistribution::new(0, "D1".to_string(), -2.0, 0.5);
+        let dist2 = GaussianDistribution::new(1, "D2".to_string(), 5.0, 2.0);
+        
+        app.distributions.insert(0, dist1);
+        app.distributions.insert(1, dist2);
+        
+        app.auto_fit_view();
+        
+        // Should fit range to include all distributions with margin
+        assert!(app.plot_bounds.is_some());
+        let bounds = app.plot_bounds.unwrap();
+        
+        // Expected range: min_mean=-2, max_mean=5, max_std_dev=2
+        // Margin = 4 * 2 = 8
+        // x_min = -2 - 8 = -10, x_max = 5 + 8 = 13
+        assert_abs_diff_eq!(bounds.min()[0], -10.0, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(bounds.max()[0], 13.0, epsilon = APPROX_EPSILON);
+        
+        // Y bounds should be reasonable
+        assert_abs_diff_eq!(bounds.min()[1], 0.0, epsilon = EPSILON);
+        assert!(bounds.max()[1] > 0.0);
+    }
+
+    #[test]
+    fn test_auto_fit_empty_distributions() {
+        let mut app = PdfViewerApp::new();
+        
+        // Should not crash with empty distributions
+        app.auto_fit_view();
+        // Function should return early without setting bounds
+    }
+
+    #[test]
+    fn test_mathematical_properties() {
+        // Test that multiplying identical distributions gives expected result
+        let dist = GaussianDistribution::new(1, "Original".to_string(), 3.0, 2.0);
+        let parents = vec![&dist, &dist];
+        let (mean, variance) = GaussianDistribution::multiply_gaussians(&parents);
+        
+        // When multiplying identical N(μ,σ²) distributions:
+        // Result should be N(μ, σ²/2)
+        assert_abs_diff_eq!(mean, 3.0, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(variance, 2.0, epsilon = APPROX_EPSILON); // σ²/2 = 4/2 = 2
+    }
+
+    #[test]
+    fn test_precision_edge_case() {
+        // Test with very different precisions
+        let high_precision = GaussianDistribution::new(1, "HP".to_string(), 1.0, 0.1);
+        let low_precision = GaussianDistribution::new(2, "LP".to_string(), 5.0, 10.0);
+        
+        let parents = vec![&high_precision, &low_precision];
+        let (mean, _variance) = GaussianDistribution::multiply_gaussians(&parents);
+        
+        // High precision distribution should dominate
+        // precision_hp = 1/0.01 = 100, precision_lp = 1/100 = 0.01
+        // Expected mean ≈ (1.0 * 100 + 5.0 * 0.01) / (100 + 0.01) ≈ 1.0005
+        assert!(mean > 1.0);
+        assert!(mean < 1.1); // Should be very close to high precision mean
+    }
+
+    #[test]
+    fn test_shading_polygon_different_distributions() {
+        // Test shading polygons for distributions with different parameters
+        let distributions = vec![
+            GaussianDistribution::new(1, "Narrow".to_string(), 0.0, 0.5),
+            GaussianDistribution::new(2, "Wide".to_string(), 0.0, 2.0),
+            GaussianDistribution::new(3, "Shifted".to_string(), 3.0, 1.0),
+        ];
+        
+        let x_min = -6.0;
+        let x_max = 6.0;
+        let num_points = 100;
+        
+        for dist in &distributions {
+            // Generate expected points manually to test the algorithm
+            let mut expected_points = Vec::with_capacity(num_points + 2);
+            expected_points.push([x_min, 0.0]);
+            
+            for i in 0..num_points {
+                let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+                let y = dist.evaluate(x);
+                expected_points.push([x, y]);
+            }
+            expected_points.push([x_max, 0.0]);
+            
+            // Validate basic structure
+            assert_eq!(expected_points.len(), num_points + 2);
+            
+            // Validate boundary points
+            assert_abs_diff_eq!(expected_points[0][1], 0.0, epsilon = EPSILON);
+            assert_abs_diff_eq!(expected_points[expected_points.len()-1][1], 0.0, epsilon = EPSILON);
+            
+            // Find the maximum y value in the polygon (should be near the mean)
+            let max_y = expected_points.iter().map(|p| p[1]).fold(0.0, f64::max);
+            let expected_max_y = dist.evaluate(dist.mean);
+            
+            // The maximum in the polygon should be close to the theoretical maximum
+            let tolerance = expected_max_y * 0.01; // 1% tolerance
+            assert!((max_y - expected_max_y).abs() < tolerance, 
+                   "Distribution {}: polygon max y={:.6}, expected max y={:.6}", 
+                   dist.name, max_y, expected_max_y);
+        }
+    }
+
+    #[test]
+    fn test_shading_polygon_edge_cases() {
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        
+        // Test with minimal points
+        let polygon_points = dist.generate_shading_polygon(-1.0, 1.0, 2);
+        let points = polygon_points.points();
+        assert_eq!(points.len(), 4); // 2 curve points + 2 boundary points
+        
+        // Test with large range
+        let polygon_points = dist.generate_shading_polygon(-10.0, 10.0, 1000);
+        let points = polygon_points.points();
+        assert_eq!(points.len(), 1002); // 1000 curve points + 2 boundary points
+        
+        // Test with single point
+        let polygon_points = dist.generate_shading_polygon(-1.0, 1.0, 1);
+        let points = polygon_points.points();
+        assert_eq!(points.len(), 3); // 1 curve point + 2 boundary points
+        
+        // Ensure all edge cases still maintain proper structure
+        for test_points in [2, 1000, 1] {
+            // Generate expected points manually
+            let mut expected_points = Vec::with_capacity(test_points + 2);
+            expected_points.push([-2.0, 0.0]);
+            
+            for i in 0..test_points {
+                let x = if test_points == 1 {
+                    // Special case: single point should be at the center of the range
+                    (-2.0 + 2.0) / 2.0  // Center of [-2.0, 2.0]
+                } else {
+                    -2.0 + (4.0) * i as f64 / (test_points - 1) as f64
+                };
+                let y = dist.evaluate(x);
+                expected_points.push([x, y]);
+            }
+            expected_points.push([2.0, 0.0]);
+            
+            // First and last should be on x-axis
+            assert_abs_diff_eq!(expected_points[0][1], 0.0, epsilon = EPSILON);
+            assert_abs_diff_eq!(expected_points[expected_points.len()-1][1], 0.0, epsilon = EPSILON);
+            
+            // All curve points should be above or on x-axis (boundary points are exactly 0)
+            for (i, point) in expected_points.iter().enumerate() {
+                assert!(point[1] >= 0.0, 
+                       "Point {} has negative y value: ({}, {}) for test_points={}", 
+                       i, point[0], point[1], test_points);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shading_polygon_area_approximation() {
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        
+        // Test that the polygon area approximates the integral reasonably well
+        let x_min = -3.0;
+        let x_max = 3.0;
+        let num_points = 1000; // High resolution for better approximation
+        
+        // Generate expected points manually
+        let mut expected_points = Vec::with_capacity(num_points + 2);
+        expected_points.push([x_min, 0.0]);
+        
+        for i in 0..num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+            let y = dist.evaluate(x);
+            expected_points.push([x, y]);
+        }
+        expected_points.push([x_max, 0.0]);
+        
+        // Calculate polygon area using trapezoidal rule
+        let mut polygon_area = 0.0;
+        for i in 0..expected_points.len()-1 {
+            let x1 = expected_points[i][0];
+            let y1 = expected_points[i][1];
+            let x2 = expected_points[i+1][0];
+            let y2 = expected_points[i+1][1];
+            
+            // Trapezoidal area between points
+            polygon_area += (x2 - x1) * (y1 + y2) * 0.5;
+        }
+        
+        // Calculate theoretical integral using numerical integration
+        let dx = (x_max - x_min) / (num_points - 1) as f64;
+        let mut theoretical_area = 0.0;
+        for i in 0..(num_points - 1) {
+            let x1 = x_min + i as f64 * dx;
+            let x2 = x_min + (i + 1) as f64 * dx;
+            let y1 = dist.evaluate(x1);
+            let y2 = dist.evaluate(x2);
+            theoretical_area += (x2 - x1) * (y1 + y2) * 0.5;
+        }
+        
+        // The polygon area should be very close to the theoretical area
+        let relative_error = (polygon_area - theoretical_area).abs() / theoretical_area;
+        assert!(relative_error < 0.01, "Polygon area {:.6} should closely match theoretical area {:.6}, relative error: {:.6}",
+               polygon_area, theoretical_area, relative_error);
+        
+        // For a Gaussian from -3σ to +3σ, we should capture ~99.7% of the total area
+        // Total area under normal distribution is 1.0, so this range should be ~0.997
+        assert!(theoretical_area > 0.995, "Should capture most of the distribution area");
+        assert!(polygon_area > 0.995, "Polygon should capture most of the distribution area");
+    }
+
+    #[test]
+    fn test_shading_polygon_product_distributions() {
+        // Test that product distributions also generate valid shading polygons
+        let parent1 = GaussianDistribution::new(1, "Parent1".to_string(), -1.0, 1.0);
+        let parent2 = GaussianDistribution::new(2, "Parent2".to_string(), 1.0, 1.0);
+        
+        let parents = vec![&parent1, &parent2];
+        let product = GaussianDistribution::new_product(
+            3,
+            "Product".to_string(),
+            vec![1, 2],
+            &parents
+        );
+        
+        let x_min = -4.0;
+        let x_max = 4.0;
+        let num_points = 100;
+        
+        // Generate expected points manually
+        let mut expected_points = Vec::with_capacity(num_points + 2);
+        expected_points.push([x_min, 0.0]);
+        
+        for i in 0..num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+            let y = product.evaluate(x);
+            expected_points.push([x, y]);
+        }
+        expected_points.push([x_max, 0.0]);
+        
+        // Validate structure
+        assert_eq!(expected_points.len(), num_points + 2);
+        
+        // Validate boundaries
+        assert_abs_diff_eq!(expected_points[0][1], 0.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(expected_points[expected_points.len()-1][1], 0.0, epsilon = EPSILON);
+        
+        // All curve points should be positive
+        for point in &expected_points[1..expected_points.len() - 1] {
+            assert!(point[1] > 0.0);
+        }
+        
+        // The maximum should be near the product distribution's mean
+        let max_y = expected_points.iter().map(|p| p[1]).fold(0.0, f64::max);
+        let expected_max_y = product.evaluate(product.mean);
+        let tolerance = expected_max_y * 0.05; // 5% tolerance for product distributions
+        
+        assert!((max_y - expected_max_y).abs() < tolerance,
+               "Product distribution polygon max should be close to theoretical max");
+    }
+
+    #[test] 
+    fn test_shading_consistency_with_curve_points() {
+        // Test that shading polygon points are consistent with curve generation
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 2.0, 1.5);
+        
+        let x_min = -2.0;
+        let x_max = 6.0;
+        let num_points = 50;
+        
+        // Generate expected curve points manually
+        let mut expected_curve_points = Vec::with_capacity(num_points);
+        for i in 0..num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points - 1) as f64;
+            let y = dist.evaluate(x);
+            expected_curve_points.push([x, y]);
+        }
+        
+        // Generate expected polygon points manually
+        let mut expected_polygon_points = Vec::with_capacity(num_points + 2);
+        expected_polygon_points.push([x_min, 0.0]);
+        for point in &expected_curve_points {
+            expected_polygon_points.push(*point);
+        }
+        expected_polygon_points.push([x_max, 0.0]);
+        
+        // Polygon should have 2 more points than curve (the boundary points)
+        assert_eq!(expected_polygon_points.len(), expected_curve_points.len() + 2);
+        
+        // The middle points of the polygon should match the curve points
+        for i in 0..expected_curve_points.len() {
+            let curve_point = expected_curve_points[i];
+            let polygon_point = expected_polygon_points[i + 1]; // Offset by 1 due to boundary point
+            
+            assert_abs_diff_eq!(curve_point[0], polygon_point[0], epsilon = EPSILON);
+            assert_abs_diff_eq!(curve_point[1], polygon_point[1], epsilon = EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_shading_polygon_no_duplicate_boundary_points() {
+        // Test that the corrected polygon generation doesn't create duplicate boundary points
+        let dist = GaussianDistribution::new(1, "Test".to_string(), 0.0, 1.0);
+        
+        let x_min = -2.0;
+        let x_max = 2.0;
+        let num_points = 5;
+        
+        // Generate expected points manually to verify the corrected logic
+        let mut expected_points = Vec::with_capacity(num_points + 2);
+        
+        expected_points.push([x_min, 0.0]);  // Bottom-left corner
+        
+        // Curve points should NOT be at exact boundaries
+        for i in 1..=num_points {
+            let x = x_min + (x_max - x_min) * i as f64 / (num_points + 1) as f64;
+            let y = dist.evaluate(x);
+            expected_points.push([x, y]);
+        }
+        
+        expected_points.push([x_max, 0.0]);  // Bottom-right corner
+        
+        // Verify structure
+        assert_eq!(expected_points.len(), num_points + 2);
+        
+        // Verify no duplicate x-coordinates
+        for i in 1..expected_points.len() {
+            assert!(
+                expected_points[i][0] > expected_points[i-1][0], 
+                "Point {} x-coord ({}) should be greater than previous point x-coord ({})",
+                i, expected_points[i][0], expected_points[i-1][0]
+            );
+        }
+        
+        // Verify boundary points are exactly at boundaries
+        assert_abs_diff_eq!(expected_points[0][0], x_min, epsilon = EPSILON);
+        assert_abs_diff_eq!(expected_points[0][1], 0.0, epsilon = EPSILON);
+        
+        let last_idx = expected_points.len() - 1;
+        assert_abs_diff_eq!(expected_points[last_idx][0], x_max, epsilon = EPSILON);
+        assert_abs_diff_eq!(expected_points[last_idx][1], 0.0, epsilon = EPSILON);
+        
+        // Verify curve points are strictly between boundaries
+        for (i, point) in expected_points.iter().enumerate().take(expected_points.len() - 1).skip(1) {
+            let x = point[0];
+            assert!(x > x_min && x < x_max, "Curve point {} x-coordinate should be strictly between boundaries", i);
+            assert!(point[1] > 0.0, "Curve point {} should be above x-axis", i);
+        }
+        
+        // Test single point case
+        let single_point_expected = [
+            [x_min, 0.0],
+            [(x_min + x_max) / 2.0, dist.evaluate((x_min + x_max) / 2.0)],
+            [x_max, 0.0],
+        ];
+        
+        assert_eq!(single_point_expected.len(), 3);
+        assert!(single_point_expected[1][0] > x_min && single_point_expected[1][0] < x_max);
+        assert!(single_point_expected[1][1] > 0.0);
+    }
+
+    #[test]
+    fn test_quotient_of_centered_normals_is_cauchy() {
+        let x = GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0);
+        let y = GaussianDistribution::new(1, "Y".to_string(), 0.0, 1.0);
+        let quotient = GaussianDistribution::new_derived(
+            2, "Quotient".to_string(), Operation::Quotient, vec![0, 1], &[&x, &y]);
+
+        // N(0,1) / N(0,1) is the standard Cauchy distribution 1 / (π(1 + z²))
+        for z in [0.0, 0.5, 1.0, 3.0] {
+            let expected = 1.0 / (PI * (1.0 + z * z));
+            assert_abs_diff_eq!(quotient.evaluate(z), expected, epsilon = 1e-3);
+        }
+        assert!(!quotient.is_product);
+        assert!(quotient.warning.is_some(), "Cauchy-like ratios should carry a heavy-tail warning");
+    }
+
+    #[test]
+    fn test_quotient_with_denominator_far_from_zero() {
+        let x = GaussianDistribution::new(0, "X".to_string(), 4.0, 0.4);
+        let y = GaussianDistribution::new(1, "Y".to_string(), 2.0, 0.05);
+        let quotient = GaussianDistribution::new_derived(
+            2, "Quotient".to_string(), Operation::Quotient, vec![0, 1], &[&x, &y]);
+
+        assert!(quotient.warning.is_none());
+        let grid = quotient.grid.as_ref().expect("Quotients are evaluated numerically");
+        assert_abs_diff_eq!(grid.mass(), 1.0, epsilon = 1e-3);
+
+        // Delta method: mean ≈ 2, sd ≈ sqrt(0.4² + (2·0.05)²) / 2 ≈ 0.206
+        assert_abs_diff_eq!(quotient.mean, 2.0, epsilon = 0.01);
+        assert_abs_diff_eq!(quotient.std_dev, 0.206, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_product_of_numeric_parent_updates_in_order() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 4.0, 0.4));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Y".to_string(), 2.0, 0.05));
+        app.distributions.insert(2, GaussianDistribution::new(2, "W".to_string(), 2.0, 0.2));
+        app.next_id = 3;
+
+        let quotient_id = app.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+        let product_id = app.add_derived(Operation::Product, vec![quotient_id, 2]).unwrap();
+        assert!(app.add_derived(Operation::Quotient, vec![0, 1, 2]).is_none());
+
+        // Product of two near-Gaussian densities sits between the two means
+        let product = &app.distributions[&product_id];
+        assert!(product.grid.is_some());
+        assert!(product.mean > 1.95 && product.mean < 2.05);
+        assert!(product.std_dev < 0.2);
+
+        // Changing the root parent propagates through the whole chain
+        app.distributions.get_mut(&0).unwrap().mean = 5.0;
+        app.update_product_distributions();
+        assert_abs_diff_eq!(app.distributions[&quotient_id].mean, 2.5, epsilon = 0.02);
+        assert!(app.distributions[&product_id].mean > 2.05);
+    }
+
+    #[test]
+    fn test_algebra_cheat_sheet_fills_in_product_identity() {
+        let mut app = PdfViewerApp::new();
+        assert!(app.algebra_cheat_sheet().contains("No derived distributions"));
+
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        let product_id = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+
+        let sheet = app.algebra_cheat_sheet();
+        assert!(sheet.starts_with("# Distribution Algebra Cheat Sheet"));
+        assert!(sheet.contains("Product 3 = A × B"));
+        assert!(sheet.contains("N(μ=0.0000, σ=1.0000) × N(μ=2.0000, σ=1.0000) ∝ N(μ=1.0000, σ=0.7071)"));
+        assert!(sheet.contains("1/σ² = 1.0000 + 1.0000 = 2.0000"));
+
+        // Deleting a parent is reported rather than silently dropped
+        app.distributions.remove(&1);
+        let sheet = app.algebra_cheat_sheet();
+        assert!(sheet.contains("A × #1 (deleted)"));
+        assert!(sheet.contains(&format!("frozen at {}", gaussian_label(&app.distributions[&product_id]))));
+    }
+
+    #[test]
+    fn test_algebra_cheat_sheet_cauchy_identity() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 2.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Y".to_string(), 0.0, 1.0));
+        app.next_id = 2;
+        app.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+
+        let sheet = app.algebra_cheat_sheet();
+        assert!(sheet.contains("Quotient 3 = X / Y"));
+        assert!(sheet.contains("Cauchy(0, 2.0000)"));
+    }
+
+    #[test]
+    fn test_max_and_min_of_standard_normals() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 0.0, 1.0));
+        app.next_id = 2;
+        let max_id = app.add_derived(Operation::Maximum, vec![0, 1]).unwrap();
+        let min_id = app.add_derived(Operation::Minimum, vec![0, 1]).unwrap();
+
+        // max of two iid N(0,1): mean 1/√π, variance 1 − 1/π; min is the mirror image
+        let expected_mean = 1.0 / PI.sqrt();
+        let expected_std = (1.0 - 1.0 / PI).sqrt();
+        assert_abs_diff_eq!(app.distributions[&max_id].mean, expected_mean, epsilon = 1e-3);
+        assert_abs_diff_eq!(app.distributions[&max_id].std_dev, expected_std, epsilon = 1e-3);
+        assert_abs_diff_eq!(app.distributions[&min_id].mean, -expected_mean, epsilon = 1e-3);
+
+        // F_max = Φ²
+        let phi = app.distributions[&0].cdf(0.5);
+        assert_abs_diff_eq!(app.distributions[&max_id].cdf(0.5), phi * phi, epsilon = 1e-3);
+
+        // Shifting one parent far to the right makes the max follow it
+        app.distributions.get_mut(&1).unwrap().mean = 10.0;
+        app.update_product_distributions();
+        assert_abs_diff_eq!(app.distributions[&max_id].mean, 10.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(app.distributions[&min_id].mean, 0.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_bayes_update_posterior_and_evidence() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Likelihood".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        let posterior_id = app.add_derived(Operation::BayesUpdate, vec![0, 1]).unwrap();
+        let posterior = &app.distributions[&posterior_id];
+
+        assert_eq!(posterior.name, "Posterior 3");
+        assert_abs_diff_eq!(posterior.mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(posterior.std_dev, 0.5_f64.sqrt(), epsilon = EPSILON);
+
+        // Z = N(0; 2, 1 + 1) = exp(−1) / √(4π)
+        let expected_evidence = (-1.0_f64).exp() / (4.0 * PI).sqrt();
+        assert_abs_diff_eq!(posterior.normalizing_constant.unwrap(), expected_evidence, epsilon = EPSILON);
+
+        // The posterior integrates to one
+        let area: f64 = (0..=2000)
+            .map(|i| posterior.evaluate(-4.0 + 10.0 * i as f64 / 2000.0) * 10.0 / 2000.0)
+            .sum();
+        assert_abs_diff_eq!(area, 1.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_gaussian_product_constant_matches_quadrature() {
+        let d1 = GaussianDistribution::new(1, "D1".to_string(), 0.0, 1.0);
+        let d2 = GaussianDistribution::new(2, "D2".to_string(), 3.0, 1.0);
+        let d3 = GaussianDistribution::new(3, "D3".to_string(), 6.0, 2.0);
+        let parents = [&d1, &d2, &d3];
+
+        let dx = 1e-3;
+        let numeric: f64 = (0..20_000)
+            .map(|i| {
+                let x = -8.0 + i as f64 * dx;
+                parents.iter().map(|p| p.evaluate(x)).product::<f64>() * dx
+            })
+            .sum();
+        assert_abs_diff_eq!(GaussianDistribution::gaussian_product_constant(&parents), numeric, epsilon = 1e-8);
+    }
+
+    #[test]
+    fn test_domain_override_clips_evaluation_and_integration() {
+        let mut clipped = GaussianDistribution::new(0, "Clipped".to_string(), 0.0, 1.0);
+        clipped.domain = Some((0.0, 2.0));
+
+        assert_eq!(clipped.evaluate(-0.5), 0.0);
+        assert_eq!(clipped.evaluate(2.5), 0.0);
+        assert_abs_diff_eq!(clipped.evaluate(1.0), (-0.5_f64).exp() / (2.0 * PI).sqrt(), epsilon = EPSILON);
+        assert_eq!(clipped.support(), (0.0, 2.0));
+        assert_eq!(clipped.clip_to_domain(-6.0, 1.0), (0.0, 1.0));
+
+        // CDF only accumulates mass inside the domain: Φ(2) − Φ(0) ≈ 0.4772
+        assert_eq!(clipped.cdf(-1.0), 0.0);
+        assert_abs_diff_eq!(clipped.cdf(5.0), 0.47725, epsilon = 1e-4);
+
+        // Products with a clipped parent are integrated over the domain only
+        let other = GaussianDistribution::new(1, "Other".to_string(), 0.0, 1.0);
+        let product = GaussianDistribution::new_product(2, "Product".to_string(), vec![0, 1], &[&clipped, &other]);
+        assert!(product.grid.is_some());
+        assert!(product.mean > 0.0, "Mass left of the domain is excluded");
+        assert_eq!(product.evaluate(-0.1), 0.0);
+    }
+
+    #[test]
+    fn test_domain_override_roundtrips_through_session() {
+        let mut app = PdfViewerApp::new();
+        let mut dist = GaussianDistribution::new(0, "Clipped".to_string(), 1.0, 0.5);
+        dist.domain = Some((0.0, 3.0));
+        app.distributions.insert(0, dist);
+        app.next_id = 1;
+
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.distributions[&0].domain, Some((0.0, 3.0)));
+
+        // Sessions saved before the field existed still load
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy["distributions"]["0"].as_object_mut().unwrap().remove("domain");
+        loaded.load_session(legacy.to_string()).unwrap();
+        assert_eq!(loaded.distributions[&0].domain, None);
+    }
+
+    #[test]
+    fn test_interactive_and_export_resolutions_are_independent() {
+        let mut resolution = PlotResolution::default();
+        assert_eq!(resolution.interactive, InteractiveResolution::PixelWidth);
+        assert_eq!(resolution.interactive_points(812.4), 812);
+        assert_eq!(resolution.interactive_points(10.0), 64);
+
+        resolution.interactive = InteractiveResolution::Fixed(300);
+        assert_eq!(resolution.interactive_points(812.4), 300);
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B \"wide\"".to_string(), 1.0, 2.0));
+        app.resolution = resolution;
+        app.resolution.export_points = 1234;
+
+        let csv = app.export_curve_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "x,\"A\",\"B \"\"wide\"\"\"");
+        assert_eq!(lines.len(), 1 + 1234);
+        assert!(lines[1].starts_with("-6,"));
+        assert!(lines[1234].starts_with("6,"));
+
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.resolution, app.resolution);
+    }
+
+    #[test]
+    fn test_product_scale_constant_and_unnormalized_curve() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 3.0, 2.0));
+        app.next_id = 2;
+        let id = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let product = &app.distributions[&id];
+
+        // S = N(0; 3, 1 + 4)
+        let expected = (-9.0_f64 / 10.0).exp() / (2.0 * PI * 5.0).sqrt();
+        let scale = product.normalizing_constant.unwrap();
+        assert_abs_diff_eq!(scale, expected, epsilon = EPSILON);
+
+        // The raw product f_A · f_B equals S times the displayed density everywhere
+        for x in [-1.0, 0.5, 2.0] {
+            let raw = app.distributions[&0].evaluate(x) * app.distributions[&1].evaluate(x);
+            assert_abs_diff_eq!(raw, scale * product.evaluate(x), epsilon = 1e-12);
+        }
+
+        assert!(app.algebra_cheat_sheet().contains(&format!("= {:.6}, so the raw product is S ×", expected)));
+    }
+
+    #[test]
+    fn test_point_cache_reuses_samples_when_panning() {
+        let mut cache = PointCache::default();
+        let mut dist = GaussianDistribution::new(7, "Test".to_string(), 0.0, 1.0);
+
+        let first = cache.points(&dist, -5.0, 5.0, 101);
+        assert_eq!(first.len(), 101);
+        assert_eq!(cache.evaluations, 101);
+        for &[x, y] in &first {
+            assert_abs_diff_eq!(y, dist.evaluate(x), epsilon = EPSILON);
+        }
+
+        // Panning right by 2.5 units only evaluates the 25 newly exposed lattice points
+        let panned = cache.points(&dist, -2.5, 7.5, 101);
+        assert_eq!(cache.evaluations, 101 + 25);
+        assert_abs_diff_eq!(panned[0][0], -2.5, epsilon = 1e-9);
+        for &[x, y] in &panned {
+            assert_abs_diff_eq!(y, dist.evaluate(x), epsilon = EPSILON);
+        }
+
+        // Zooming changes the lattice and editing the distribution invalidates it
+        cache.points(&dist, -1.0, 1.0, 101);
+        assert_eq!(cache.evaluations, 101 + 25 + 101);
+        dist.mean = 0.5;
+        let moved = cache.points(&dist, -1.0, 1.0, 101);
+        assert_eq!(cache.evaluations, 101 + 25 + 101 + 101);
+        assert_abs_diff_eq!(moved[50][1], dist.evaluate(moved[50][0]), epsilon = EPSILON);
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(1, dist.clone());
+        cache.retain_ids(&app.distributions);
+        assert!(cache.curves.is_empty());
+    }
+
+    #[test]
+    fn test_truncation_to_interval() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+
+        // Symmetric truncation to [-1, 1]: variance 1 − 2φ(1) / (2Φ(1) − 1)
+        let id = app.add_derived(Operation::Truncate { lower: -1.0, upper: 1.0 }, vec![0]).unwrap();
+        let conditional = &app.distributions[&id];
+        assert_eq!(conditional.name, "Conditional 2");
+        assert_eq!(conditional.parent_ids, vec![0]);
+        assert_abs_diff_eq!(conditional.mean, 0.0, epsilon = APPROX_EPSILON);
+        assert_abs_diff_eq!(conditional.std_dev, 0.29112_f64.sqrt(), epsilon = 1e-3);
+        assert_abs_diff_eq!(conditional.normalizing_constant.unwrap(), 0.682689, epsilon = APPROX_EPSILON);
+        assert_eq!(conditional.evaluate(1.5), 0.0);
+        assert_abs_diff_eq!(conditional.evaluate(0.0), app.distributions[&0].evaluate(0.0) / 0.682689, epsilon = 1e-3);
+
+        // Half-normal: mean √(2/π), and the child follows edits to the parent
+        let half = app.add_derived(Operation::Truncate { lower: 0.0, upper: 100.0 }, vec![0]).unwrap();
+        assert_abs_diff_eq!(app.distributions[&half].mean, (2.0 / PI).sqrt(), epsilon = 1e-3);
+        app.distributions.get_mut(&0).unwrap().mean = 1.0;
+        app.update_product_distributions();
+        assert!(app.distributions[&half].mean > 1.0);
+
+        // An interval with no mass is flagged instead of producing NaNs
+        let empty = app.add_derived(Operation::Truncate { lower: 50.0, upper: 60.0 }, vec![0]).unwrap();
+        assert!(app.distributions[&empty].warning.is_some());
+        assert!(app.distributions[&empty].mean.is_finite());
+    }
+
+    #[test]
+    fn test_expression_parser() {
+        let g = Expr::parse("2*x^2 - 3/(1 + x)").unwrap();
+        assert_abs_diff_eq!(g.eval(1.0), 0.5, epsilon = EPSILON);
+        assert_abs_diff_eq!(Expr::parse("-x^2").unwrap().eval(3.0), -9.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(Expr::parse("2^3^2").unwrap().eval(0.0), 512.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(Expr::parse("exp(ln(x)) + sqrt(abs(-4)) * pi").unwrap().eval(2.5), 2.5 + 2.0 * PI, epsilon = EPSILON);
+        assert_abs_diff_eq!(Expr::parse("1e-3 * x").unwrap().eval(2.0), 0.002, epsilon = EPSILON);
+        assert!(Expr::parse("ln(x)").unwrap().eval(-1.0).is_nan());
+
+        assert!(Expr::parse("2x").is_err());
+        assert!(Expr::parse("foo(x)").is_err());
+        assert!(Expr::parse("(x + 1").is_err());
+        assert!(Expr::parse("x $ 2").is_err());
+        assert!(Expr::parse("").is_err());
+    }
+
+    #[test]
+    fn test_exp_of_normal_is_lognormal() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+
+        let id = app.add_derived(Operation::Transform(Transform::Exp), vec![0]).unwrap();
+        let y = &app.distributions[&id];
+        assert_eq!(y.name, "Transform 2");
+        assert!(y.warning.is_none());
+        assert_abs_diff_eq!(y.mean, 0.5_f64.exp(), epsilon = 1e-4);
+        assert_abs_diff_eq!(y.std_dev, ((1.0_f64.exp() - 1.0) * 1.0_f64.exp()).sqrt(), epsilon = 1e-3);
+
+        // f_Y(y) = φ(ln y) / y
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        for &point in &[0.5, 1.0, 2.0, 4.0] {
+            assert_abs_diff_eq!(y.evaluate(point), normal.pdf(f64::ln(point)) / point, epsilon = 2e-3);
+        }
+        assert_eq!(y.evaluate(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_non_monotone_and_partial_transforms() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+
+        // X² of a standard normal is χ²₁: mean 1, variance 2, both branches counted
+        let square = app.add_derived(Operation::Transform(Transform::Square), vec![0]).unwrap();
+        let chi_square = &app.distributions[&square];
+        assert_abs_diff_eq!(chi_square.mean, 1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(chi_square.std_dev, 2.0_f64.sqrt(), epsilon = 1e-3);
+        assert_abs_diff_eq!(chi_square.evaluate(1.0), (-0.5_f64).exp() / (2.0 * PI).sqrt(), epsilon = 2e-3);
+        assert_abs_diff_eq!(chi_square.cdf(1.0), 0.682689, epsilon = 1e-2);
+
+        // ln is undefined for the negative half, which is reported
+        let log = app.add_derived(Operation::Transform(Transform::Log), vec![0]).unwrap();
+        assert!(app.distributions[&log].warning.as_deref().unwrap().contains("undefined for 50."));
+
+        // Linear custom formula: 2x + 1 ~ N(1, 4)
+        let linear = app.add_derived(Operation::Transform(Transform::Expression("2*x + 1".to_string())), vec![0]).unwrap();
+        assert_abs_diff_eq!(app.distributions[&linear].mean, 1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(app.distributions[&linear].std_dev, 2.0, epsilon = 1e-3);
+
+        // A bad formula keeps the node but flags it
+        let bad = app.add_derived(Operation::Transform(Transform::Expression("x +".to_string())), vec![0]).unwrap();
+        assert!(app.distributions[&bad].warning.as_deref().unwrap().starts_with("Invalid formula"));
+    }
+
+    #[test]
+    fn test_load_widens_slider_ranges() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Wide".to_string(), 25.5, 0.05));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Invalid".to_string(), 0.0, -2.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Normal".to_string(), 1.0, 2.0));
+        app.next_id = 3;
+        let json = app.save_session().unwrap();
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+
+        // Out-of-range values are kept and the ranges grow to include them
+        let wide = &loaded.distributions[&0];
+        assert_eq!(wide.mean, 25.5);
+        assert_eq!(wide.std_dev, 0.05);
+        assert_eq!(wide.ranges.mean, (-10.0, 26.0));
+        assert_eq!(wide.ranges.std_dev, (0.05, 5.0));
+        let notice = wide.load_notice.as_deref().unwrap();
+        assert!(notice.contains("mean 25.5") && notice.contains("std dev 0.05"));
+
+        // Values no slider could represent are clamped, and flagged as well
+        let invalid = &loaded.distributions[&1];
+        assert_eq!(invalid.std_dev, 0.1);
+        assert!(invalid.load_notice.as_deref().unwrap().contains("must be positive"));
+
+        let normal = &loaded.distributions[&2];
+        assert_eq!(normal.ranges, ParameterRanges::default());
+        assert!(normal.load_notice.is_none());
+    }
+
+    #[test]
+    fn test_lan_sync_between_host_and_client() {
+        fn wait_for(mut condition: impl FnMut() -> bool) {
+            let start = std::time::Instant::now();
+            while !condition() {
+                assert!(start.elapsed() < std::time::Duration::from_secs(5), "sync timed out");
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+
+        let mut host = PdfViewerApp::new();
+        host.distributions.insert(0, GaussianDistribution::new(0, "Prior".to_string(), 1.0, 2.0));
+        host.distributions.insert(1, GaussianDistribution::new(1, "Data".to_string(), 3.0, 1.0));
+        host.next_id = 2;
+        host.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let mut host_sync = sync::SyncSession::host(sync::DEFAULT_INTERFACE, 0).unwrap();
+        host_sync.publish(&host.distributions);
+
+        let port = host_sync.address().rsplit(':').next().unwrap().to_string();
+        let mut client = PdfViewerApp::new();
+        let mut client_sync = sync::SyncSession::connect(&format!("127.0.0.1:{}", port)).unwrap();
+
+        // The joining client receives the host's session, derived nodes included
+        wait_for(|| {
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            client_sync.poll(&mut client.distributions, &mut client.next_id);
+            client.distributions.len() == 3
+        });
+        client.update_product_distributions();
+        assert_eq!(client.next_id, 3);
+        assert_abs_diff_eq!(client.distributions[&2].mean, host.distributions[&2].mean, epsilon = EPSILON);
+
+        // Edits flow back to the host, and derived values are recomputed there
+        // (published every frame like the UI does, since edits made before the
+        // end of the snapshot arrives are held back until then)
+        client.distributions.get_mut(&0).unwrap().mean = 5.0;
+        wait_for(|| {
+            client_sync.poll(&mut client.distributions, &mut client.next_id);
+            client_sync.publish(&client.distributions);
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            host.distributions[&0].mean == 5.0
+        });
+        host.update_product_distributions();
+        host_sync.publish(&host.distributions);
+
+        // Removals propagate too
+        host.distributions.remove(&1);
+        host_sync.publish(&host.distributions);
+        wait_for(|| {
+            client_sync.poll(&mut client.distributions, &mut client.next_id);
+            !client.distributions.contains_key(&1)
+        });
+        assert_eq!(client.distributions[&0].mean, 5.0);
+    }
+
+    #[test]
+    fn test_tempered_distribution() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 1.0, 2.0));
+        app.next_id = 1;
+
+        // Closed form: N(μ, σ²)^β ∝ N(μ, σ²/β)
+        let id = app.add_derived(Operation::Temper { beta: 4.0 }, vec![0]).unwrap();
+        assert_eq!(app.distributions[&id].name, "Tempered 2");
+        assert_abs_diff_eq!(app.distributions[&id].mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&id].std_dev, 1.0, epsilon = EPSILON);
+
+        // Editing β in place re-derives the distribution
+        app.distributions.get_mut(&id).unwrap().operation = Operation::Temper { beta: 0.25 };
+        app.update_product_distributions();
+        assert_abs_diff_eq!(app.distributions[&id].std_dev, 4.0, epsilon = EPSILON);
+
+        // Numerical path agrees with the closed form for a grid-backed parent
+        app.distributions.get_mut(&id).unwrap().operation = Operation::Temper { beta: 2.0 };
+        app.update_product_distributions();
+        let sharpened = app.add_derived(Operation::Temper { beta: 0.5 }, vec![id]).unwrap();
+        let numeric = app.add_derived(Operation::Truncate { lower: -100.0, upper: 100.0 }, vec![0]).unwrap();
+        let numeric_tempered = app.add_derived(Operation::Temper { beta: 2.0 }, vec![numeric]).unwrap();
+        assert_abs_diff_eq!(app.distributions[&sharpened].std_dev, 2.0, epsilon = EPSILON);
+        assert!(app.distributions[&numeric_tempered].grid.is_some());
+        assert_abs_diff_eq!(app.distributions[&numeric_tempered].mean, 1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(app.distributions[&numeric_tempered].std_dev, 2.0_f64.sqrt(), epsilon = 1e-3);
+
+        let invalid = app.add_derived(Operation::Temper { beta: 0.0 }, vec![0]).unwrap();
+        assert!(app.distributions[&invalid].warning.is_some());
+    }
+
+    #[test]
+    fn test_sum_of_iid_copies() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 1.0, 2.0));
+        app.next_id = 1;
+
+        // Gaussian copies: N(nμ, nσ²), and the mean N(μ, σ²/n)
+        let sum = app.add_derived(Operation::IidSum { copies: 4, average: false }, vec![0]).unwrap();
+        let mean = app.add_derived(Operation::IidSum { copies: 4, average: true }, vec![0]).unwrap();
+        assert_eq!(app.distributions[&sum].name, "Sum 2");
+        assert_eq!(app.distributions[&mean].name, "Mean 3");
+        assert_abs_diff_eq!(app.distributions[&sum].mean, 4.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&sum].std_dev, 4.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&mean].mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&mean].std_dev, 1.0, epsilon = EPSILON);
+
+        // Numerical convolution of a grid-backed Gaussian matches the closed form
+        let grid_backed = app.add_derived(Operation::Truncate { lower: -100.0, upper: 100.0 }, vec![0]).unwrap();
+        let numeric = app.add_derived(Operation::IidSum { copies: 3, average: false }, vec![grid_backed]).unwrap();
+        let expected = Normal::new(3.0, 12.0_f64.sqrt()).unwrap();
+        let numeric = &app.distributions[&numeric];
+        for &x in &[-3.0, 0.0, 3.0, 7.0] {
+            assert_abs_diff_eq!(numeric.evaluate(x), expected.pdf(x), epsilon = 1e-4);
+        }
+        let (grid_mean, grid_sd) = numeric.grid.as_ref().unwrap().moments();
+        assert_abs_diff_eq!(grid_mean, 3.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(grid_sd, 12.0_f64.sqrt(), epsilon = 1e-3);
+
+        // A skewed base (half-normal) loses its skew as n grows; the grid keeps unit mass
+        let half = app.add_derived(Operation::Truncate { lower: 1.0, upper: 100.0 }, vec![0]).unwrap();
+        let average = app.add_derived(Operation::IidSum { copies: 30, average: true }, vec![half]).unwrap();
+        let average = &app.distributions[&average];
+        assert_abs_diff_eq!(average.grid.as_ref().unwrap().mass(), 1.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(average.cdf(average.mean), 0.5, epsilon = 0.02);
+        assert_abs_diff_eq!(average.std_dev, app.distributions[&half].std_dev / 30.0_f64.sqrt(), epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_broadcast_only_sync() {
+        fn wait_for(mut condition: impl FnMut() -> bool) {
+            let start = std::time::Instant::now();
+            while !condition() {
+                assert!(start.elapsed() < std::time::Duration::from_secs(5), "sync timed out");
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+
+        let mut host = PdfViewerApp::new();
+        host.distributions.insert(0, GaussianDistribution::new(0, "Demo".to_string(), 0.0, 1.0));
+        host.next_id = 1;
+        let mut host_sync = sync::SyncSession::host(sync::DEFAULT_INTERFACE, 0).unwrap();
+        host_sync.set_read_only(true);
+        host_sync.publish(&host.distributions);
+        host_sync.publish_view([-2.0, 2.0, 0.0, 0.5]);
+
+        let port = host_sync.address().rsplit(':').next().unwrap().to_string();
+        let mut viewer = PdfViewerApp::new();
+        viewer.sync = Some(sync::SyncSession::connect(&format!("127.0.0.1:{}", port)).unwrap());
+
+        // The viewer learns it is read-only and receives the presenter's view
+        let mut view = None;
+        wait_for(|| {
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            let session = viewer.sync.as_mut().unwrap();
+            session.poll(&mut viewer.distributions, &mut viewer.next_id);
+            view = view.or(session.take_presenter_view());
+            view.is_some() && session.read_only() && viewer.distributions.contains_key(&0)
+        });
+        assert!(viewer.is_view_only());
+        assert_eq!(view, Some([-2.0, 2.0, 0.0, 0.5]));
+
+        // Local edits on a viewer are never sent, so the host keeps its value
+        viewer.distributions.get_mut(&0).unwrap().mean = 3.0;
+        viewer.sync.as_mut().unwrap().publish(&viewer.distributions);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        host_sync.poll(&mut host.distributions, &mut host.next_id);
+        assert_eq!(host.distributions[&0].mean, 0.0);
+
+        // Presenter edits still reach the viewer
+        host.distributions.get_mut(&0).unwrap().std_dev = 2.0;
+        host_sync.publish(&host.distributions);
+        wait_for(|| {
+            let session = viewer.sync.as_mut().unwrap();
+            session.poll(&mut viewer.distributions, &mut viewer.next_id);
+            viewer.distributions[&0].std_dev == 2.0
+        });
+    }
+
+    #[test]
+    fn test_view_lock_and_export_range() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Wide".to_string(), 0.0, 2.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Shifted".to_string(), 10.0, 1.0));
+        app.next_id = 2;
+        app.resolution.export_points = 3;
+
+        // The lock is saved, and restores the exact framing
+        app.view_lock = Some([-1.0, 4.0, 0.0, 0.25]);
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.view_lock, Some([-1.0, 4.0, 0.0, 0.25]));
+        assert_eq!(loaded.view_input, [-1.0, 4.0, 0.0, 0.25]);
+        assert_eq!(loaded.get_plot_range(), (-1.0, 4.0));
+
+        // Exports cover exactly the current view by default...
+        let rows: Vec<f64> = loaded.export_curve_csv().lines().skip(1)
+            .map(|line| line.split(',').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(rows, vec![-1.0, 1.5, 4.0]);
+
+        // ...or the full support of every curve
+        loaded.export_full_curves = true;
+        assert_eq!(loaded.export_range(), (-16.0, 18.0));
+    }
+
+    #[test]
+    fn test_peak_rescaling() {
+        let narrow = GaussianDistribution::new(0, "Narrow".to_string(), 1.0, 0.05);
+        let curve: Vec<[f64; 2]> = (0..=200).map(|i| {
+            let x = i as f64 / 100.0;
+            [x, narrow.evaluate(x)]
+        }).collect();
+        let peak = curve_peak(&curve).unwrap();
+        assert_eq!(peak[0], 1.0);
+        assert_abs_diff_eq!(peak[1], 1.0 / (0.05 * (2.0 * PI).sqrt()), epsilon = EPSILON);
+        assert_eq!(curve_peak(&[]), None);
+
+        // Rescaling keeps x and the y floor, and leaves 5% headroom above the peak
+        let bounds = egui_plot::PlotBounds::from_min_max([-3.0, 0.0], [3.0, 0.5]);
+        let rescaled = rescale_to_peak(bounds, peak[1]);
+        assert_eq!(rescaled.min(), [-3.0, 0.0]);
+        assert_eq!(rescaled.max()[0], 3.0);
+        assert_abs_diff_eq!(rescaled.max()[1], peak[1] * 1.05, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_sampler_and_quantile() {
+        let normal = GaussianDistribution::new(0, "N".to_string(), 2.0, 3.0);
+        let draws = sampling::Sampler::new(7).sample(&normal, 20_000);
+        assert_eq!(draws, sampling::Sampler::new(7).sample(&normal, 20_000));
+        let mean = draws.iter().sum::<f64>() / draws.len() as f64;
+        let sd = (draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / draws.len() as f64).sqrt();
+        assert_abs_diff_eq!(mean, 2.0, epsilon = 0.1);
+        assert_abs_diff_eq!(sd, 3.0, epsilon = 0.1);
+
+        // Domain-restricted and grid-backed distributions invert their own CDF
+        let mut clipped = GaussianDistribution::new(1, "Clipped".to_string(), 0.0, 1.0);
+        clipped.domain = Some((0.0, 10.0));
+        assert_abs_diff_eq!(clipped.quantile(0.5), 0.674490, epsilon = 1e-5);
+        let draws = sampling::Sampler::new(1).sample(&clipped, 1000);
+        assert!(draws.iter().all(|&x| (0.0..=10.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_importance_reweighting() {
+        let target = GaussianDistribution::new(0, "p".to_string(), 1.0, 1.0);
+
+        // Sampling from the target itself: uniform weights and ESS = N
+        let draws = sampling::Sampler::new(3).sample(&target, 500);
+        let report = sampling::ImportanceReport::new(&target, &target, draws);
+        assert_abs_diff_eq!(report.effective_sample_size, 500.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(report.max_weight(), 1.0 / 500.0, epsilon = 1e-12);
+
+        // A wider proposal recovers the target mean with a reduced ESS
+        let wide = GaussianDistribution::new(1, "q".to_string(), 0.0, 2.0);
+        let report = sampling::ImportanceReport::new(&wide, &target, sampling::Sampler::new(3).sample(&wide, 20_000));
+        assert_abs_diff_eq!(report.weights.iter().sum::<f64>(), 1.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(report.mean_estimate, 1.0, epsilon = 0.05);
+        assert!(report.effective_sample_size < 20_000.0 * 0.8);
+
+        // A narrow, offset proposal collapses onto a handful of draws
+        let narrow = GaussianDistribution::new(2, "q".to_string(), -2.0, 0.5);
+        let report = sampling::ImportanceReport::new(&narrow, &target, sampling::Sampler::new(3).sample(&narrow, 1000));
+        assert!(report.effective_sample_size < 50.0);
+    }
+
+    #[test]
+    fn test_product_amplitude_view() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let quotient = app.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+
+        // Area-normalized by default: every curve is drawn as is
+        assert_eq!(app.display_scale(&app.distributions[&product]), 1.0);
+
+        // Raw amplitude scales products by S = N(0; 2, 2), and nothing else
+        app.show_product_amplitude = true;
+        let expected = Normal::new(2.0, 2.0_f64.sqrt()).unwrap().pdf(0.0);
+        assert_abs_diff_eq!(app.display_scale(&app.distributions[&product]), expected, epsilon = EPSILON);
+        assert_eq!(app.display_scale(&app.distributions[&0]), 1.0);
+        assert_eq!(app.display_scale(&app.distributions[&quotient]), 1.0);
+
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert!(loaded.show_product_amplitude);
+    }
+
+    #[test]
+    fn test_kl_divergence() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "P".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Q".to_string(), 1.0, 2.0));
+        app.next_id = 2;
+        let (p, q) = (&app.distributions[&0], &app.distributions[&1]);
+
+        // ln 2 + (1 + 1) / 8 − ½
+        let expected = 2.0_f64.ln() + 0.25 - 0.5;
+        assert_abs_diff_eq!(divergence::kl_divergence(p, q), expected, epsilon = EPSILON);
+        assert_abs_diff_eq!(divergence::kl_divergence(p, p), 0.0, epsilon = EPSILON);
+        assert!(divergence::kl_divergence(q, p) > expected);
+
+        // Numerical integration agrees for grid-backed copies
+        let numeric_p = app.add_derived(Operation::Truncate { lower: -100.0, upper: 100.0 }, vec![0]).unwrap();
+        let numeric_q = app.add_derived(Operation::Truncate { lower: -100.0, upper: 100.0 }, vec![1]).unwrap();
+        let (numeric_p, numeric_q) = (&app.distributions[&numeric_p], &app.distributions[&numeric_q]);
+        assert_abs_diff_eq!(divergence::kl_divergence(numeric_p, numeric_q), expected, epsilon = 1e-4);
+
+        // Q without support where P has mass gives an infinite divergence
+        let mut restricted = GaussianDistribution::new(2, "R".to_string(), 0.0, 1.0);
+        restricted.domain = Some((0.0, 5.0));
+        assert!(divergence::kl_divergence(&app.distributions[&0], &restricted).is_infinite());
+        assert!(divergence::kl_divergence(&restricted, &app.distributions[&0]).is_finite());
+    }
+
+    #[test]
+    fn test_hellinger_distance() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "P".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Q".to_string(), 1.0, 2.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Far".to_string(), 100.0, 1.0));
+        app.next_id = 3;
+        let (p, q) = (&app.distributions[&0], &app.distributions[&1]);
+
+        // BC = √(2·1·2 / 5) · exp(−1 / 20)
+        let expected = (1.0 - (0.8_f64).sqrt() * (-0.05_f64).exp()).sqrt();
+        assert_abs_diff_eq!(divergence::hellinger_distance(p, q), expected, epsilon = EPSILON);
+        assert_abs_diff_eq!(divergence::hellinger_distance(q, p), expected, epsilon = EPSILON);
+        assert_abs_diff_eq!(divergence::hellinger_distance(p, p), 0.0, epsilon = 1e-7);
+        assert_abs_diff_eq!(divergence::hellinger_distance(p, &app.distributions[&2]), 1.0, epsilon = EPSILON);
+
+        let numeric_p = app.add_derived(Operation::Truncate { lower: -100.0, upper: 100.0 }, vec![0]).unwrap();
+        let numeric_p = &app.distributions[&numeric_p];
+        assert_abs_diff_eq!(divergence::hellinger_distance(numeric_p, &app.distributions[&1]), expected, epsilon = 1e-4);
+        assert_abs_diff_eq!(divergence::hellinger_distance(numeric_p, &app.distributions[&2]), 1.0, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_chain_evidence_of_nested_products() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 3.0, 2.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "C".to_string(), -1.0, 0.5));
+        app.next_id = 3;
+        let inner = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let outer = app.add_derived(Operation::Product, vec![inner, 2]).unwrap();
+
+        let evidence = app.chain_evidence();
+        assert_eq!(evidence.len(), 2);
+        assert_abs_diff_eq!(evidence[&inner], app.distributions[&inner].normalizing_constant.unwrap(), epsilon = EPSILON);
+
+        // (A × B) × C carries the same total evidence as the flat product A × B × C
+        let (a, b, c) = (&app.distributions[&0], &app.distributions[&1], &app.distributions[&2]);
+        let flat = GaussianDistribution::gaussian_product_constant(&[a, b, c]);
+        assert_abs_diff_eq!(evidence[&outer], flat, epsilon = 1e-14);
+        assert!(evidence[&outer] < app.distributions[&outer].normalizing_constant.unwrap());
+    }
+
+    #[test]
+    fn test_bayes_factor_between_priors() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Likelihood".to_string(), 2.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Prior near".to_string(), 2.0, 1.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Prior far".to_string(), -4.0, 1.0));
+        app.next_id = 3;
+        let near = app.add_derived(Operation::BayesUpdate, vec![1, 0]).unwrap();
+        let far = app.add_derived(Operation::BayesUpdate, vec![2, 0]).unwrap();
+
+        // Z = N(μ_L; μ_prior, 1 + 1), so log BF = (6² − 0²) / (2 · 2) = 9
+        let evidence = app.chain_evidence();
+        let log_factor = evidence[&near].ln() - evidence[&far].ln();
+        assert_abs_diff_eq!(log_factor, 9.0, epsilon = 1e-9);
+        assert_eq!(jeffreys_strength(log_factor.exp()), "Decisive");
+        assert_eq!(jeffreys_strength((-log_factor).exp()), "Decisive");
+
+        assert_eq!(jeffreys_strength(1.0), "Barely worth mentioning");
+        assert_eq!(jeffreys_strength(5.0), "Substantial");
+        assert_eq!(jeffreys_strength(1.0 / 20.0), "Strong");
+        assert_eq!(jeffreys_strength(50.0), "Very strong");
+    }
+
+    #[test]
+    fn test_wasserstein_distance() {
+        let p = GaussianDistribution::new(0, "P".to_string(), 0.0, 1.0);
+        let q = GaussianDistribution::new(1, "Q".to_string(), 1.5, 2.0);
+
+        // Equal spreads: W₁ is just the shift. Equal means: |Δσ| E|Z| = |Δσ| √(2/π)
+        let shifted = GaussianDistribution::new(2, "P shifted".to_string(), 3.0, 1.0);
+        assert_abs_diff_eq!(divergence::wasserstein_distance(&p, &shifted), 3.0, epsilon = EPSILON);
+        let wide = GaussianDistribution::new(3, "P wide".to_string(), 0.0, 2.0);
+        assert_abs_diff_eq!(divergence::wasserstein_distance(&p, &wide), (2.0 / PI).sqrt(), epsilon = EPSILON);
+
+        // A domain so wide it changes nothing forces the quantile integral, which matches the closed form
+        let closed = divergence::wasserstein_distance(&p, &q);
+        let mut p_numeric = p.clone();
+        p_numeric.domain = Some((-40.0, 40.0));
+        assert_abs_diff_eq!(divergence::wasserstein_distance(&p_numeric, &q), closed, epsilon = 1e-3);
+        assert_abs_diff_eq!(divergence::wasserstein_distance(&q, &p_numeric), closed, epsilon = 1e-3);
+
+        // Disjoint supports: KL is infinite but W₁ is the distance the mass moves
+        let mut left = p.clone();
+        left.domain = Some((-1.0, 1.0));
+        let mut right = p.clone();
+        right.mean = 10.0;
+        right.domain = Some((9.0, 11.0));
+        assert!(divergence::kl_divergence(&left, &right).is_infinite());
+        assert_abs_diff_eq!(divergence::wasserstein_distance(&left, &right), 10.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_model_averaged_mixture() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Likelihood".to_string(), 1.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Prior A".to_string(), 0.0, 1.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Prior B".to_string(), 3.0, 2.0));
+        app.next_id = 3;
+        let first = app.add_derived(Operation::BayesUpdate, vec![1, 0]).unwrap();
+        let second = app.add_derived(Operation::BayesUpdate, vec![2, 0]).unwrap();
+        let averaged = app.add_derived(Operation::Mixture { weights: vec![1.0, 1.0], by_evidence: true }, vec![first, second]).unwrap();
+        app.update_product_distributions();
+
+        let (z1, z2) = (
+            app.distributions[&first].normalizing_constant.unwrap(),
+            app.distributions[&second].normalizing_constant.unwrap(),
+        );
+        let (w1, w2) = (z1 / (z1 + z2), z2 / (z1 + z2));
+        let mixture = &app.distributions[&averaged];
+        let Operation::Mixture { weights, .. } = &mixture.operation else { panic!("not a mixture") };
+        assert_abs_diff_eq!(weights[0], w1, epsilon = EPSILON);
+        assert_abs_diff_eq!(weights[1], w2, epsilon = EPSILON);
+
+        let (p1, p2) = (&app.distributions[&first], &app.distributions[&second]);
+        assert_abs_diff_eq!(mixture.mean, w1 * p1.mean + w2 * p2.mean, epsilon = EPSILON);
+        assert_abs_diff_eq!(mixture.evaluate(1.0), w1 * p1.evaluate(1.0) + w2 * p2.evaluate(1.0), epsilon = 1e-4);
+        let (grid_mean, grid_std) = mixture.grid.as_ref().unwrap().moments();
+        assert_abs_diff_eq!(grid_mean, mixture.mean, epsilon = 1e-4);
+        assert_abs_diff_eq!(grid_std, mixture.std_dev, epsilon = 1e-4);
+
+        // Manual weights; plain inputs have no evidence, so evidence weighting falls back with a warning
+        let manual = app.add_derived(Operation::Mixture { weights: vec![3.0, 1.0], by_evidence: false }, vec![1, 2]).unwrap();
+        assert_abs_diff_eq!(app.distributions[&manual].mean, 0.75 * 0.0 + 0.25 * 3.0, epsilon = EPSILON);
+        let fallback = app.add_derived(Operation::Mixture { weights: vec![1.0, 1.0], by_evidence: true }, vec![1, 2]).unwrap();
+        assert!(app.distributions[&fallback].warning.is_some());
+        assert!(app.add_derived(Operation::Mixture { weights: vec![1.0], by_evidence: false }, vec![1]).is_none());
+    }
+
+    #[test]
+    fn test_total_variation_and_overlap_curve() {
+        use statrs::distribution::{ContinuousCDF, Normal};
+
+        // Equal spreads: TV = 2Φ(|Δμ| / 2σ) − 1
+        let p = GaussianDistribution::new(0, "P".to_string(), 0.0, 1.0);
+        let q = GaussianDistribution::new(1, "Q".to_string(), 1.0, 1.0);
+        let expected = 2.0 * Normal::new(0.0, 1.0).unwrap().cdf(0.5) - 1.0;
+        assert_abs_diff_eq!(divergence::total_variation_distance(&p, &q), expected, epsilon = 1e-6);
+        assert_abs_diff_eq!(divergence::total_variation_distance(&q, &p), expected, epsilon = 1e-6);
+        assert_abs_diff_eq!(divergence::total_variation_distance(&p, &p), 0.0, epsilon = 1e-9);
+
+        // The overlap curve only exists while the comparison window is open with two picks
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, p.clone());
+        app.distributions.insert(1, q.clone());
+        app.next_id = 2;
+
+        // Conditionals on disjoint intervals share no mass
+        let left = app.add_derived(Operation::Truncate { lower: -1.0, upper: 0.0 }, vec![0]).unwrap();
+        let right = app.add_derived(Operation::Truncate { lower: 0.5, upper: 1.0 }, vec![0]).unwrap();
+        let (left, right) = (&app.distributions[&left], &app.distributions[&right]);
+        assert_abs_diff_eq!(divergence::total_variation_distance(left, right), 1.0, epsilon = 1e-9);
+
+        app.comparison.first = Some(0);
+        app.comparison.second = Some(1);
+        assert!(app.comparison_curve(101, f64::min).is_none());
+        app.comparison.open = true;
+        let overlap = app.comparison_curve(101, f64::min).unwrap();
+        assert_eq!(overlap.len(), 101);
+        for [x, y] in overlap {
+            assert_abs_diff_eq!(y, p.evaluate(x).min(q.evaluate(x)), epsilon = EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_bhattacharyya_readout() {
+        // Equal spreads: D_B = Δμ² / (8σ²)
+        let p = GaussianDistribution::new(0, "P".to_string(), 0.0, 1.0);
+        let q = GaussianDistribution::new(1, "Q".to_string(), 2.0, 1.0);
+        assert_abs_diff_eq!(divergence::bhattacharyya_distance(&p, &q), 0.5, epsilon = EPSILON);
+        assert_abs_diff_eq!(divergence::bhattacharyya_coefficient(&p, &q), (-0.5_f64).exp(), epsilon = EPSILON);
+        assert_abs_diff_eq!(divergence::bhattacharyya_distance(&p, &p), 0.0, epsilon = EPSILON);
+
+        // The numerical path agrees with the closed form, and the √(p q) curve integrates to BC
+        let mut app = PdfViewerApp::new();
+        let mut wide_domain = q.clone();
+        wide_domain.domain = Some((-40.0, 40.0));
+        app.distributions.insert(0, p.clone());
+        app.distributions.insert(1, wide_domain.clone());
+        assert_abs_diff_eq!(
+            divergence::bhattacharyya_coefficient(&p, &wide_domain),
+            divergence::bhattacharyya_coefficient(&p, &q),
+            epsilon = 1e-6
+        );
+        app.comparison = ComparisonTool { open: true, first: Some(0), second: Some(1), ..Default::default() };
+        app.plot_bounds = Some(view_bounds([-8.0, 10.0, 0.0, 0.5]));
+        let curve = app.comparison_curve(2001, |p, q| (p * q).sqrt()).unwrap();
+        let area: f64 = curve.windows(2).map(|w| 0.5 * (w[0][1] + w[1][1]) * (w[1][0] - w[0][0])).sum();
+        assert_abs_diff_eq!(area, (-0.5_f64).exp(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_session_restores_plot_state() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.plot_bounds = Some(view_bounds([-2.0, 3.0, 0.0, 0.6]));
+        app.condition_interval = Some((-0.5, 1.5));
+
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.get_plot_range(), (-2.0, 3.0));
+        assert_eq!(loaded.requested_bounds.as_ref().map(bounds_array), Some([-2.0, 3.0, 0.0, 0.6]));
+        assert_eq!(loaded.condition_interval, Some((-0.5, 1.5)));
+
+        // Sessions saved before the plot state existed still load
+        let mut legacy: serde_json::Value = serde_json::from_str(&json).unwrap();
+        legacy.as_object_mut().unwrap().remove("plot");
+        let legacy = legacy.to_string();
+        let mut old = PdfViewerApp::new();
+        old.load_session(&legacy).unwrap();
+        assert_eq!(old.condition_interval, None);
+    }
+
+    #[test]
+    fn test_latex_theme_colors_and_font() {
+        let mut theme = LatexTheme::default();
+        assert_eq!(theme.tikz_color(0), "{rgb,255:red,0;green,0;blue,255}");
+        assert_eq!(theme.font_option(), None);
+
+        theme.color_names = vec!["MyBlue".to_string(), " MyRed!60 ".to_string(), "bad name{".to_string()];
+        theme.font = "\\sffamily\\small".to_string();
+        assert_eq!(theme.tikz_color(0), "MyBlue");
+        assert_eq!(theme.tikz_color(1), "MyRed!60");
+        assert_eq!(theme.tikz_color(2), "{rgb,255:red,0;green,255;blue,0}");
+        assert_eq!(theme.tikz_color(PALETTE.len()), "MyBlue");
+        assert_eq!(theme.font_option().as_deref(), Some("font={\\sffamily\\small}"));
+
+        // The theme travels with the session
+        let mut app = PdfViewerApp::new();
+        app.latex_theme = theme.clone();
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.latex_theme, theme);
+    }
+
+    #[test]
+    fn test_moment_summary() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Z".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+
+        let gaussian = app.distributions[&0].moment_summary();
+        assert_eq!((gaussian.mode, gaussian.median, gaussian.skewness), (0.0, 0.0, 0.0));
+
+        // Half-normal: Z conditioned on Z ≥ 0
+        let half = app.add_derived(Operation::Truncate { lower: 0.0, upper: 10.0 }, vec![0]).unwrap();
+        let summary = app.distributions[&half].moment_summary();
+        assert_abs_diff_eq!(summary.mean, (2.0 / PI).sqrt(), epsilon = 1e-4);
+        assert_abs_diff_eq!(summary.std_dev, (1.0 - 2.0 / PI).sqrt(), epsilon = 1e-4);
+        assert_abs_diff_eq!(summary.skewness, 2.0_f64.sqrt() * (4.0 - PI) / (PI - 2.0).powf(1.5), epsilon = 1e-3);
+        assert_abs_diff_eq!(summary.excess_kurtosis, 8.0 * (PI - 3.0) / (PI - 2.0).powi(2), epsilon = 1e-3);
+        assert_abs_diff_eq!(summary.median, 0.674_489_75, epsilon = 1e-3);
+        assert_abs_diff_eq!(summary.mode, 0.0, epsilon = 1e-2);
+    }
+
+    #[test]
+    fn test_standardized_x_axis() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Reference".to_string(), 2.0, 0.5));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Other".to_string(), 3.0, 1.0));
+        app.next_id = 2;
+        app.plot_bounds = Some(view_bounds([0.0, 4.0, 0.0, 1.0]));
+
+        // Switching to z keeps the framing: x ∈ [0, 4] is z ∈ [−4, 4]
+        app.set_z_reference(Some(0));
+        assert_eq!(app.axis_map(), AxisMap { origin: 2.0, unit: 0.5, log_y: false });
+        assert_eq!(app.get_plot_range(), (-4.0, 4.0));
+        assert_eq!(app.data_range(), (0.0, 4.0));
+        assert_eq!(app.plot_bounds.as_ref().map(bounds_array), Some([-4.0, 4.0, 0.0, 0.5]));
+
+        // Curves keep unit area in z: the reference becomes the standard normal
+        let axis = app.axis_map();
+        let [z, y] = axis.view_point([2.0, app.distributions[&0].evaluate(2.0)]);
+        assert_abs_diff_eq!(z, 0.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(y, 1.0 / (2.0 * PI).sqrt(), epsilon = EPSILON);
+
+        // Exports stay in data units, and the distributions themselves are untouched
+        assert_eq!(app.export_range(), (0.0, 4.0));
+        assert_eq!((app.distributions[&0].mean, app.distributions[&0].std_dev), (2.0, 0.5));
+
+        // The axis mode is part of the saved plot state
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.axis_map(), axis);
+        assert_eq!(loaded.get_plot_range(), (-4.0, 4.0));
+
+        app.set_z_reference(None);
+        assert_eq!(app.axis_map(), AxisMap::IDENTITY);
+        assert_eq!(app.get_plot_range(), (0.0, 4.0));
+    }
+
+    #[test]
+    fn test_recipe_breadcrumbs() {
+        let mut app = PdfViewerApp::new();
+        for (id, name) in [(0, "A"), (1, "B"), (2, "C")] {
+            app.distributions.insert(id, GaussianDistribution::new(id, name.to_string(), id as f64, 1.0));
+        }
+        app.next_id = 3;
+        let inner = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let outer = app.add_derived(Operation::Product, vec![inner, 2]).unwrap();
+        let maximum = app.add_derived(Operation::Maximum, vec![outer, 0]).unwrap();
+        let posterior = app.add_derived(Operation::BayesUpdate, vec![maximum, 2]).unwrap();
+
+        assert_eq!(app.recipe(0), None);
+        assert_eq!(app.recipe(outer).unwrap(), "A × B × C, renormalized");
+        assert_eq!(app.recipe(maximum).unwrap(), "max(A × B × C, A)");
+        assert_eq!(app.recipe(posterior).unwrap(), "(max(A × B × C, A)) × C / evidence");
+
+        // Plot series carry suffixes after the distribution name
+        let recipes: HashMap<String, String> = [
+            ("Product 5".to_string(), "outer".to_string()),
+            ("Product 5 (x)".to_string(), "tricky".to_string()),
+        ].into();
+        assert_eq!(curve_recipe(&recipes, "Product 5").unwrap(), "outer");
+        assert_eq!(curve_recipe(&recipes, "Product 5 (raw, S = 1e-3)").unwrap(), "outer");
+        assert_eq!(curve_recipe(&recipes, "Product 5 (x) (unnormalized)").unwrap(), "tricky");
+        assert_eq!(curve_recipe(&recipes, "Product 50"), None);
+
+        app.distributions.remove(&1);
+        assert_eq!(app.recipe(inner).unwrap(), "A × #1 (deleted), renormalized");
+    }
+
+    #[test]
+    fn test_quantile_lookup() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Z".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        assert_eq!(app.quantile_marker(), None);
+
+        app.quantile_target = Some(0);
+        assert_abs_diff_eq!(app.quantile_marker().unwrap(), 1.959_963_985, epsilon = 1e-8);
+
+        // Numeric distributions invert their own CDF: the median of Z | Z ≥ 0 is Φ⁻¹(0.75)
+        let half = app.add_derived(Operation::Truncate { lower: 0.0, upper: 10.0 }, vec![0]).unwrap();
+        app.quantile_target = Some(half);
+        app.quantile_p = 0.5;
+        assert_abs_diff_eq!(app.quantile_marker().unwrap(), 0.674_489_75, epsilon = 1e-3);
+
+        // The tails of a Gaussian have no finite marker position
+        app.quantile_target = Some(0);
+        app.quantile_p = 1.0;
+        assert_eq!(app.quantile_marker(), None);
+        app.quantile_p = 0.5;
+        app.show_quantile_marker = false;
+        assert_eq!(app.quantile_marker(), None);
+    }
+
+    #[test]
+    fn test_interval_probability() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Z".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Wide".to_string(), 0.0, 2.0));
+        app.next_id = 2;
+        assert!(app.interval_probabilities().is_empty());
+
+        app.condition_interval = Some((-1.959_963_985, 1.959_963_985));
+        let rows = app.interval_probabilities();
+        assert_eq!(rows.len(), 2);
+        assert_abs_diff_eq!(rows[0].2, 0.95, epsilon = 1e-9);
+        assert_abs_diff_eq!(rows[1].2, 2.0 * Normal::new(0.0, 2.0).unwrap().cdf(1.959_963_985) - 1.0, epsilon = 1e-9);
+
+        // Handles keep the interval ordered, swapping roles when dragged past each other
+        assert_eq!(move_interval_endpoint((-1.0, 1.0), 0, -2.0), ((-2.0, 1.0), 0));
+        assert_eq!(move_interval_endpoint((-1.0, 1.0), 1, 0.5), ((-1.0, 0.5), 1));
+        assert_eq!(move_interval_endpoint((-1.0, 1.0), 0, 3.0), ((1.0, 3.0), 1));
+        assert_eq!(move_interval_endpoint((-1.0, 1.0), 1, -3.0), ((-3.0, -1.0), 0));
+    }
+
+    #[test]
+    fn test_auto_export_on_change_and_timer() {
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_auto_export_{}", std::process::id()));
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A \"quoted\"".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.resolution.export_points = 5;
+        app.auto_export.directory = directory.to_string_lossy().into_owned();
+        let start = std::time::Instant::now();
+
+        assert!(!app.run_auto_export(start));
+        app.auto_export.enabled = true;
+        assert!(app.run_auto_export(start));
+        assert!(!app.run_auto_export(start));
+        let stats = std::fs::read_to_string(directory.join("stats.csv")).unwrap();
+        assert_eq!(stats.lines().nth(1).unwrap(), "0,\"A \"\"quoted\"\"\",Input,0,1,0,0,0,0");
+        assert_eq!(std::fs::read_to_string(directory.join("curves.csv")).unwrap().lines().count(), 6);
+
+        // Editing an input is a change; so is anything derived from it
+        app.distributions.get_mut(&0).unwrap().mean = 1.0;
+        assert!(app.run_auto_export(start));
+        assert_eq!(app.auto_export.exports, 2);
+
+        // Timed mode rewrites on schedule even without changes
+        app.auto_export.trigger = ExportTrigger::Every { seconds: 60 };
+        assert!(!app.run_auto_export(start + std::time::Duration::from_secs(30)));
+        assert!(app.run_auto_export(start + std::time::Duration::from_secs(61)));
+        assert!(!directory.join("stats.tmp").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_parameter_uncertainty_band() {
+        let xs: Vec<f64> = (-40..=40).map(|i| i as f64 / 10.0).collect();
+        let dist = GaussianDistribution::new(0, "A".to_string(), 0.5, 1.0);
+        let band_at = |mean_sd, log_std_dev_sd, level, draws| {
+            let uncertainty = ParameterUncertainty { mean_sd, log_std_dev_sd, level };
+            sampling::gaussian_density_band(&dist, &uncertainty, &xs, draws, 7)
+        };
+
+        // Without uncertainty the band collapses onto the density
+        let exact = band_at(0.0, 0.0, 0.9, 50);
+        let normal = Normal::new(0.5, 1.0).unwrap();
+        for (&x, &[lower, upper]) in xs.iter().zip(&exact) {
+            assert_abs_diff_eq!(lower, normal.pdf(x), epsilon = EPSILON);
+            assert_abs_diff_eq!(upper, normal.pdf(x), epsilon = EPSILON);
+        }
+
+        // With uncertainty it brackets the plug-in density, widens with the level, and is reproducible
+        let band = band_at(0.3, 0.2, 0.9, 400);
+        let wider = band_at(0.3, 0.2, 0.99, 400);
+        assert_eq!(band, band_at(0.3, 0.2, 0.9, 400));
+        for ((&x, &[lower, upper]), &[wide_lower, wide_upper]) in xs.iter().zip(&band).zip(&wider) {
+            assert!(lower <= upper);
+            assert!(wide_lower <= lower && upper <= wide_upper);
+            if (x - 0.5).abs() > 0.05 {
+                assert!(lower < normal.pdf(x) && normal.pdf(x) < upper, "x = {}", x);
+            }
+        }
+
+        // The setting is saved with the distribution
+        let mut dist = dist.clone();
+        dist.uncertainty = Some(ParameterUncertainty::default());
+        let restored: GaussianDistribution = serde_json::from_str(&serde_json::to_string(&dist).unwrap()).unwrap();
+        assert_eq!(restored.uncertainty, Some(ParameterUncertainty::default()));
+    }
+
+    #[test]
+    fn test_p_values_and_tail_regions() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Z".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Shifted".to_string(), 1.0, 2.0));
+        app.next_id = 2;
+        assert!(app.pvalue_regions().is_empty());
+
+        let (lower, upper, two) = tail_probabilities(&app.distributions[&0], 1.959_963_985);
+        assert_abs_diff_eq!(lower, 0.975, epsilon = 1e-9);
+        assert_abs_diff_eq!(upper, 0.025, epsilon = 1e-9);
+        assert_abs_diff_eq!(two, 0.05, epsilon = 1e-9);
+
+        // The mirrored tail holds the same probability as the observed one
+        app.pvalue_target = Some(1);
+        app.pvalue_x = -2.0;
+        let regions = app.pvalue_regions();
+        assert_eq!(regions[0].0, f64::NEG_INFINITY);
+        assert_abs_diff_eq!(regions[0].1, -2.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(regions[1].0, 4.0, epsilon = 1e-9);
+        app.pvalue_tail = Tail::Upper;
+        assert_eq!(app.pvalue_regions(), vec![(-2.0, f64::INFINITY)]);
+
+        // Numeric distributions are measured against their own (renormalized) mass
+        let half = app.add_derived(Operation::Truncate { lower: 0.0, upper: 10.0 }, vec![0]).unwrap();
+        let (lower, upper, two) = tail_probabilities(&app.distributions[&half], 0.674_489_75);
+        assert_abs_diff_eq!(lower, 0.5, epsilon = 1e-3);
+        assert_abs_diff_eq!(upper, 0.5, epsilon = 1e-3);
+        assert_abs_diff_eq!(two, 1.0, epsilon = 2e-3);
+    }
+
+    #[test]
+    fn test_group_transform_is_one_reversible_step() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Length A".to_string(), 1200.0, 30.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Length B".to_string(), 1250.0, 40.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Other".to_string(), 5.0, 1.0));
+        app.next_id = 3;
+        app.reconcile_loaded_parameters();
+        app.distributions.get_mut(&1).unwrap().domain = Some((1100.0, 1400.0));
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.groups.push(DistributionGroup { name: "Lengths".to_string(), members: vec![0, 1], shift: 0.0, scale: 1.0 });
+
+        // mm → m
+        app.set_group_transform(0, 0.0, 0.001);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 1.2, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&0].std_dev, 0.03, epsilon = EPSILON);
+        let (lower, upper) = app.distributions[&1].domain.unwrap();
+        assert_abs_diff_eq!(lower, 1.1, epsilon = EPSILON);
+        assert_abs_diff_eq!(upper, 1.4, epsilon = EPSILON);
+        assert_eq!(app.distributions[&2].mean, 5.0);
+        assert!(app.distributions[&product].mean < 1.3, "derived distributions follow");
+        assert!(app.distributions[&0].ranges.std_dev.0 <= 0.03, "slider ranges fit the new units");
+
+        // Editing the transform re-applies from the original values, and resetting undoes it
+        app.set_group_transform(0, 10.0, 0.01);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 22.0, epsilon = 1e-9);
+        app.set_group_transform(0, 0.0, 1.0);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 1200.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(app.distributions[&1].std_dev, 40.0, epsilon = 1e-9);
+
+        // Groups travel with the session
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.groups, app.groups);
+    }
+
+    #[test]
+    fn test_credible_regions() {
+        let normal = GaussianDistribution::new(0, "N".to_string(), 1.0, 2.0);
+        let regions = normal.credible_regions(0.95);
+        assert_eq!(regions.len(), 1);
+        assert_abs_diff_eq!(regions[0].0, 1.0 - 1.959963984540054 * 2.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(regions[0].1, 1.0 + 1.959963984540054 * 2.0, epsilon = 1e-6);
+
+        // A skewed truncation: the HPD interval starts at the edge where the density peaks
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        let truncated = app.add_derived(Operation::Truncate { lower: 0.0, upper: 3.0 }, vec![0]).unwrap();
+        let dist = &app.distributions[&truncated];
+        let regions = dist.credible_regions(0.5);
+        assert_eq!(regions.len(), 1);
+        let (lower, upper) = regions[0];
+        assert_abs_diff_eq!(lower, 0.0, epsilon = 0.01);
+        let mass = (dist.quantile_level(upper) - dist.quantile_level(lower)).abs();
+        assert_abs_diff_eq!(mass, 0.5, epsilon = 0.005);
+
+        // Two well separated modes give two intervals
+        app.distributions.insert(2, GaussianDistribution::new(2, "B".to_string(), 10.0, 1.0));
+        let mixture = app.add_derived(Operation::Mixture { weights: vec![1.0, 1.0], by_evidence: false }, vec![0, 2]).unwrap();
+        let regions = app.distributions[&mixture].credible_regions(0.8);
+        assert_eq!(regions.len(), 2);
+        assert!(regions[0].1 < 5.0 && regions[1].0 > 5.0);
+    }
+
+    #[test]
+    fn test_color_by_statistic() {
+        let narrow = GaussianDistribution::new(0, "Narrow".to_string(), 0.0, 0.5);
+        assert_abs_diff_eq!(narrow.entropy(), 0.5 * (2.0 * PI * std::f64::consts::E * 0.25).ln(), epsilon = EPSILON);
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, narrow);
+        app.distributions.insert(1, GaussianDistribution::new(1, "Middle".to_string(), 0.0, 1.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Wide".to_string(), 0.0, 3.0));
+        app.next_id = 3;
+
+        // Numeric entropy of a Gaussian-shaped product matches the closed form
+        let product = app.add_derived(Operation::Product, vec![1, 1]).unwrap();
+        let sigma = 1.0 / 2f64.sqrt();
+        assert_abs_diff_eq!(app.distributions[&product].entropy(), 0.5 * (2.0 * PI * std::f64::consts::E * sigma * sigma).ln(), epsilon = 1e-3);
+        app.distributions.remove(&product);
+
+        let (colors, range) = app.curve_colors();
+        assert!(range.is_none());
+        assert_eq!(colors.len(), 3);
+        assert!(colors.values().all(|color| PALETTE.contains(color)));
+
+        app.curve_coloring = CurveColoring::Variance;
+        let (colors, range) = app.curve_colors();
+        let (low, high) = range.unwrap();
+        assert_abs_diff_eq!(low, 0.25, epsilon = EPSILON);
+        assert_abs_diff_eq!(high, 9.0, epsilon = EPSILON);
+        assert_eq!(colors[&0], viridis(0.0));
+        assert_eq!(colors[&2], viridis(1.0));
+        assert_ne!(colors[&1], viridis(0.0));
+
+        app.curve_coloring = CurveColoring::Entropy;
+        let (colors, _) = app.curve_colors();
+        assert_eq!(colors[&0], viridis(0.0));
+        assert_eq!(colors[&2], viridis(1.0));
+    }
+
+    #[test]
+    fn test_zscore_reference() {
+        let mut app = PdfViewerApp::new();
+        assert!(app.zscore_reference().is_none());
+        app.distributions.insert(0, GaussianDistribution::new(0, "Heights".to_string(), 170.0, 8.0));
+        app.next_id = 1;
+        app.zscore_target = Some(0);
+        let (mean, std_dev) = app.zscore_reference().unwrap();
+        app.zscore_x = 186.0;
+        assert_abs_diff_eq!((app.zscore_x - mean) / std_dev, 2.0, epsilon = EPSILON);
+        // Setting z moves x
+        app.zscore_x = mean + -1.5 * std_dev;
+        assert_abs_diff_eq!(app.zscore_x, 158.0, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_cli_ops_and_stats() {
+        let dir = std::env::temp_dir().join(format!("pdf_viewer_cli_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("s.json"), dir.join("s2.json"));
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(1, GaussianDistribution::new(1, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(2, GaussianDistribution::new(2, "B".to_string(), 2.0, 1.0));
+        app.next_id = 3;
+        std::fs::write(&input, app.save_session().unwrap()).unwrap();
+
+        let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        let mut stdout = Vec::new();
+        cli::run(&args(&format!("ops multiply --session {} --ids 1,2 --out {} --name AB", input.display(), output.display())), &mut stdout).unwrap();
+        let mut result = PdfViewerApp::new();
+        result.load_session(std::fs::read_to_string(&output).unwrap()).unwrap();
+        let product = &result.distributions[&3];
+        assert_eq!(product.name, "AB");
+        assert_eq!(product.parent_ids, vec![1, 2]);
+        assert_abs_diff_eq!(product.mean, 1.0, epsilon = 1e-6);
+
+        cli::run(&args(&format!("stats --session {}", output.display())), &mut stdout).unwrap();
+        let csv = String::from_utf8(stdout).unwrap();
+        assert!(csv.starts_with("id,name,kind,mean"));
+        assert!(csv.contains("3,\"AB\",Product,"));
+
+        let mut stdout = Vec::new();
+        cli::run(&args(&format!("stats --session {} --format json", output.display())), &mut stdout).unwrap();
+        let rows: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 3);
+
+        assert!(cli::run(&args(&format!("ops multiply --session {} --ids 1,9", input.display())), &mut Vec::new()).is_err());
+        assert!(cli::run(&args(&format!("ops divide --session {} --ids 1", input.display())), &mut Vec::new()).is_err());
+        assert!(cli::run(&args("frobnicate"), &mut Vec::new()).is_err());
+
+        // Only subcommands bypass the GUI; launcher arguments such as macOS's -psn_ do not
+        assert!(cli::is_command("ops") && cli::is_command("stats") && cli::is_command("--help"));
+        assert!(!cli::is_command("-psn_0_12345") && !cli::is_command("session.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mgf_and_characteristic_function() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.5, 1.0));
+        app.next_id = 1;
+        let x = &app.distributions[&0];
+        assert_abs_diff_eq!(x.mgf(0.0), 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(x.mgf(1.0), (0.5f64 + 0.5).exp(), epsilon = EPSILON);
+        assert_abs_diff_eq!(x.characteristic_modulus(2.0), (-2.0f64).exp(), epsilon = EPSILON);
+
+        // Sum of two iid copies: its transforms are the squares of the parent's
+        let sum = app.add_derived(Operation::IidSum { copies: 2, average: false }, vec![0]).unwrap();
+        app.update_product_distributions();
+        let (x, sum) = (&app.distributions[&0], &app.distributions[&sum]);
+        for t in [-1.0, 0.3, 1.2] {
+            assert_abs_diff_eq!(sum.mgf(t), x.mgf(t).powi(2), epsilon = 1e-3 * x.mgf(t).powi(2));
+            assert_abs_diff_eq!(sum.characteristic_modulus(t), x.characteristic_modulus(t).powi(2), epsilon = 1e-3);
+        }
+
+        // A numeric curve agrees with the closed form of the same Gaussian
+        app.distributions.get_mut(&0).unwrap().domain = Some((-20.0, 20.0));
+        let restricted = &app.distributions[&0];
+        assert!(!restricted.has_closed_form());
+        assert_abs_diff_eq!(restricted.mgf(1.0), 1f64.exp(), epsilon = 1e-6);
+        assert_abs_diff_eq!(restricted.characteristic_modulus(2.0), (-2.0f64).exp(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_cdf_points() {
+        let dist = GaussianDistribution::new(0, "N".to_string(), 1.0, 2.0);
+        let points = sample_curve(-5.0, 7.0, 121, |x| PlotMode::Cdf.value(&dist, x));
+        assert_eq!(points.len(), 121);
+        assert_eq!(points[0][0], -5.0);
+        assert_eq!(points[120][0], 7.0);
+        let normal = Normal::new(1.0, 2.0).unwrap();
+        for [x, y] in &points {
+            assert_abs_diff_eq!(*y, normal.cdf(*x), epsilon = EPSILON);
+        }
+        assert!(points.windows(2).all(|pair| pair[1][1] >= pair[0][1]));
+
+        // Restricted curves still run from 0 to 1 over their domain
+        let mut restricted = dist.clone();
+        restricted.domain = Some((0.0, 2.0));
+        let points = sample_curve(0.0, 2.0, 50, |x| PlotMode::Cdf.value(&restricted, x));
+        assert_abs_diff_eq!(points[0][1], 0.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(points[49][1], 1.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(restricted.quantile_level(1.0), 0.5, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_release_version_comparison() {
+        assert!(updates::is_newer("v0.2.0", "0.1.0"));
+        assert!(updates::is_newer("1.0", "0.9.9"));
+        assert!(updates::is_newer("v0.1.1-rc1", "0.1.0"));
+        assert!(!updates::is_newer("v0.1.0", "0.1.0"));
+        assert!(!updates::is_newer("0.1", "0.1.0"));
+        assert!(!updates::is_newer("v0.0.9", "0.1.0"));
+        assert!(!updates::is_newer("nightly", "0.1.0"));
+
+        let release = updates::parse_release(r#"{
+            "tag_name": "v0.2.0",
+            "name": "PDF Viewer 0.2",
+            "body": "- CDF view",
+            "html_url": "https://github.com/ShabbirHasan1/pdf_viewer/releases/tag/v0.2.0",
+            "draft": false
+        }"#).unwrap();
+        assert_eq!(release.tag_name, "v0.2.0");
+        assert_eq!(release.body.as_deref(), Some("- CDF view"));
+        assert!(updates::parse_release(r#"{"message": "Not Found"}"#).is_err());
+
+        // Nothing is fetched until a check is requested
+        let mut status = updates::UpdateStatus::default();
+        assert!(!status.poll());
+        assert!(matches!(status, updates::UpdateStatus::Idle));
+    }
+
+    #[test]
+    fn test_gallery_examples_load() {
+        for example in &GALLERY {
+            let mut app = PdfViewerApp::new();
+            app.load_session(example.session).unwrap_or_else(|e| panic!("{}: {}", example.title, e));
+            assert!(app.distributions.len() >= 3, "{}", example.title);
+            assert!(app.distributions.values().any(|dist| dist.is_derived()), "{}", example.title);
+            assert!(app.distributions.values().all(|dist| dist.warning.is_none()), "{}", example.title);
+
+            let thumbnail = gallery_thumbnail(example.session).unwrap();
+            assert_eq!(thumbnail.len(), app.distributions.len());
+            assert!(thumbnail.iter().all(|curve| curve.iter().any(|[_, y]| *y > 0.0)), "{}", example.title);
+        }
+
+        let mut app = PdfViewerApp::new();
+        app.load_session(GALLERY[0].session).unwrap();
+        let posterior = app.distributions.values().find(|dist| dist.name == "Posterior").unwrap();
+        // N(0, 2²) prior with N(3, 1) likelihood
+        assert_abs_diff_eq!(posterior.mean, 2.4, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_survival_function() {
+        let dist = GaussianDistribution::new(0, "N".to_string(), 0.0, 1.0);
+        for [x, y] in sample_curve(-3.0, 3.0, 61, |x| PlotMode::Survival.value(&dist, x)) {
+            assert_abs_diff_eq!(y, 1.0 - dist.quantile_level(x), epsilon = 1e-12);
+        }
+        // Far in the tail the exact upper tail is still resolved
+        let tail = dist.survival(10.0);
+        assert!(tail > 0.0 && tail < 1e-22, "{}", tail);
+
+        let mut restricted = dist.clone();
+        restricted.domain = Some((-1.0, 1.0));
+        assert_abs_diff_eq!(restricted.survival(-1.0), 1.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(restricted.survival(0.0), 0.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(restricted.survival(1.0), 0.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_log_y_axis() {
+        let axis = AxisMap::IDENTITY.with_log_y(true);
+        assert_eq!(axis.view_point([1.0, 1e-3]), [1.0, -3.0]);
+        assert_eq!(axis.view_point([1.0, 0.0])[1], f64::NEG_INFINITY);
+        assert_abs_diff_eq!(axis.y_to_data(axis.y_to_view(0.25)), 0.25, epsilon = EPSILON);
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "N".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.plot_bounds = Some(view_bounds([-4.0, 4.0, 0.0, 0.5]));
+        app.set_log_y(true);
+        // x stays; the linear floor at 0 becomes a few decades below the top
+        let [x_min, x_max, y_min, y_max] = bounds_array(app.plot_bounds.as_ref().unwrap());
+        assert_eq!((x_min, x_max), (-4.0, 4.0));
+        assert_abs_diff_eq!(y_max, 0.5f64.log10(), epsilon = EPSILON);
+        assert_abs_diff_eq!(y_min, y_max - AxisMap::LOG_DECADES, epsilon = EPSILON);
+        assert_eq!(app.data_range(), (-4.0, 4.0));
+
+        // Saved with the plot state
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert!(loaded.log_y);
+        assert!(loaded.axis_map().log_y);
+
+        app.set_log_y(false);
+        let [_, _, y_min, y_max] = bounds_array(app.plot_bounds.as_ref().unwrap());
+        assert_abs_diff_eq!(y_max, 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(y_min, 0.5e-6, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_hazard_rate() {
+        // Standard normal: h(x) = φ(x) / (1 − Φ(x)), increasing and ~x in the right tail
+        let dist = GaussianDistribution::new(0, "N".to_string(), 0.0, 1.0);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        assert_abs_diff_eq!(dist.hazard(0.0), 2.0 * normal.pdf(0.0), epsilon = EPSILON);
+        let rates: Vec<f64> = (-30..=30).map(|i| dist.hazard(i as f64 * 0.2)).collect();
+        assert!(rates.windows(2).all(|pair| pair[1] > pair[0]));
+        assert_abs_diff_eq!(dist.hazard(20.0) / 20.0, 1.0, epsilon = 0.01);
+
+        // Restricted to [0, ∞) the density is renormalized first
+        let mut half = dist.clone();
+        half.domain = Some((0.0, 50.0));
+        assert_abs_diff_eq!(half.hazard(0.0), 2.0 * normal.pdf(0.0), epsilon = 1e-6);
+        assert!(half.hazard(60.0).is_nan());
+        assert_eq!(PlotMode::Hazard.value(&dist, 1.0), dist.hazard(1.0));
+    }
+
+    #[test]
+    fn test_log_density() {
+        let dist = GaussianDistribution::new(0, "N".to_string(), 1.0, 2.0);
+        let normal = Normal::new(1.0, 2.0).unwrap();
+        for x in [-3.0, 0.0, 1.0, 4.5] {
+            assert_abs_diff_eq!(dist.ln_density(x), normal.ln_pdf(x), epsilon = 1e-12);
+        }
+        // 60σ out the density underflows, the log density does not
+        let far = 1.0 + 60.0 * 2.0;
+        assert_eq!(dist.evaluate(far), 0.0);
+        assert_abs_diff_eq!(dist.ln_density(far), -1800.0 - (2.0 * (2.0 * PI).sqrt()).ln(), epsilon = 1e-9);
+
+        // A parabola: second differences are constant, −1/σ²
+        let points = sample_curve(-5.0, 7.0, 13, |x| PlotMode::LogDensity.value(&dist, x));
+        for window in points.windows(3) {
+            assert_abs_diff_eq!(window[0][1] - 2.0 * window[1][1] + window[2][1], -0.25, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_plot_layout_heights() {
+        assert_eq!(PlotLayout::Single.heights(500.0), vec![500.0]);
+        let heights = PlotLayout::PdfOverCdf.heights(508.0);
+        assert_eq!(heights.len(), 2);
+        assert_abs_diff_eq!(heights[0] + heights[1], 500.0, epsilon = 1e-3);
+        assert!(heights[0] > heights[1]);
+
+        let mut app = PdfViewerApp::new();
+        app.plot_layout = PlotLayout::PdfOverCdf;
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.plot_layout, PlotLayout::PdfOverCdf);
+    }
+
+    #[test]
+    fn test_hover_values_sorted() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Wide".to_string(), 0.0, 3.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Narrow".to_string(), 0.0, 0.5));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Shifted".to_string(), 2.0, 1.0));
+        app.next_id = 3;
+        app.distributions.get_mut(&2).unwrap().domain = Some((1.0, 5.0));
+
+        let values = app.hover_values(0.0);
+        let ids: Vec<u32> = values.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 0], "outside its domain the shifted curve is left out");
+        assert_abs_diff_eq!(values[0].1, Normal::new(0.0, 0.5).unwrap().pdf(0.0), epsilon = EPSILON);
+
+        let ids: Vec<u32> = app.hover_values(2.0).iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 0, 1]);
+
+        app.plot_mode = PlotMode::Cdf;
+        let values = app.hover_values(0.0);
+        assert_abs_diff_eq!(values[0].1, 0.5, epsilon = EPSILON);
+        assert_abs_diff_eq!(values[1].1, 0.5, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_crosshair_readout() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "N".to_string(), 10.0, 2.0));
+        app.next_id = 1;
+        app.crosshair_target = Some(0);
+        assert!(app.crosshair_readout(12.0).is_none(), "off until toggled");
+
+        app.crosshair = true;
+        let readout = app.crosshair_readout(12.0).unwrap();
+        let normal = Normal::new(10.0, 2.0).unwrap();
+        assert_abs_diff_eq!(readout.density, normal.pdf(12.0), epsilon = EPSILON);
+        assert_abs_diff_eq!(readout.cdf, normal.cdf(12.0), epsilon = EPSILON);
+        assert_abs_diff_eq!(readout.z, 1.0, epsilon = EPSILON);
+
+        app.crosshair_target = Some(7);
+        assert!(app.crosshair_readout(12.0).is_none());
+    }
+
+    #[test]
+    fn test_parameter_handles() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 4.0, 1.0));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+
+        // Handles only for inputs, sitting on their curves
+        let points = app.parameter_handle_points();
+        assert_eq!(points.len(), 4);
+        assert!(points.iter().all(|(id, _, _)| *id != product));
+        let (_, _, sigma_point) = points.iter().find(|(id, handle, _)| *id == 0 && *handle == ParameterHandle::StdDev).unwrap();
+        assert_abs_diff_eq!(sigma_point[0], 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(sigma_point[1], Normal::new(0.0, 1.0).unwrap().pdf(1.0), epsilon = EPSILON);
+
+        let dist = app.distributions.get_mut(&0).unwrap();
+        dist.drag_handle(ParameterHandle::Mean, 2.0);
+        assert_eq!(dist.mean, 2.0);
+        dist.drag_handle(ParameterHandle::StdDev, 14.0);
+        assert_abs_diff_eq!(dist.std_dev, 12.0, epsilon = EPSILON);
+        assert!(dist.ranges.std_dev.1 >= 12.0, "slider range widened");
+        // Dragging σ past the mean mirrors instead of going negative
+        dist.drag_handle(ParameterHandle::StdDev, 1.0);
+        assert_abs_diff_eq!(dist.std_dev, 1.0, epsilon = EPSILON);
+
+        app.update_product_distributions();
+        assert_abs_diff_eq!(app.distributions[&product].mean, 3.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_between_regions() {
+        // Crossing inside the middle slice: split into two triangles
+        let first = [[0.0, 1.0], [1.0, 1.0], [2.0, 0.0], [3.0, 0.0]];
+        let second = [[0.0, 0.0], [1.0, 0.5], [2.0, 0.5], [3.0, 0.0]];
+        let pieces = between_regions(&first, &second);
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(pieces[0], (vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.5], [0.0, 0.0]], true));
+        let (triangle, on_top) = &pieces[1];
+        assert!(on_top);
+        assert_eq!(triangle.len(), 3);
+        assert_abs_diff_eq!(triangle[1][0], 1.5, epsilon = EPSILON);
+        assert_abs_diff_eq!(triangle[1][1], 0.5, epsilon = EPSILON);
+        assert!(!pieces[2].1);
+        // The slice where the curves touch at both ends still fills the lower wedge
+        assert!(!pieces[3].1);
+
+        // Identical curves enclose nothing
+        assert!(between_regions(&first, &first).is_empty());
+
+        // Total area of the pieces is ∫|f − g|
+        let area = |polygon: &Vec<[f64; 2]>| {
+            let n = polygon.len();
+            (0..n).map(|i| {
+                let ([x0, y0], [x1, y1]) = (polygon[i], polygon[(i + 1) % n]);
+                x0 * y1 - x1 * y0
+            }).sum::<f64>().abs() / 2.0
+        };
+        let total: f64 = pieces.iter().map(|(piece, _)| area(piece)).sum();
+        assert_abs_diff_eq!(total, 0.75 + 0.125 + 0.125 + 0.25, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_likelihood_ratio_segments() {
+        let f = GaussianDistribution::new(0, "f".to_string(), 1.0, 1.0);
+        let g = GaussianDistribution::new(1, "g".to_string(), 0.0, 1.0);
+        // Equal variances: ln Λ = x − ½, a straight line
+        let segments = likelihood_ratio_segments(&f, &g, -3.0, 3.0, 61);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].len(), 61);
+        for [x, y] in &segments[0] {
+            assert_abs_diff_eq!(*y, (x - 0.5) / std::f64::consts::LN_10, epsilon = 1e-9);
+        }
+
+        // A narrow denominator vanishes in the tails: gaps there, not spikes
+        let narrow = GaussianDistribution::new(2, "narrow".to_string(), 0.0, 0.2);
+        let segments = likelihood_ratio_segments(&f, &narrow, -5.0, 5.0, 101);
+        assert_eq!(segments.len(), 1);
+        let (first, last) = (segments[0][0][0], segments[0].last().unwrap()[0]);
+        assert!(first > -5.0 && last < 5.0);
+        assert!(segments[0].iter().all(|[_, y]| y.is_finite()));
+
+        // A numerator restricted to x ≥ 0 leaves the left half out
+        let mut half = f.clone();
+        half.domain = Some((0.0, f64::INFINITY));
+        let segments = likelihood_ratio_segments(&half, &g, -2.0, 2.0, 41);
+        assert!(segments.iter().flatten().all(|[x, _]| *x >= 0.0));
+    }
+
+    #[test]
+    fn test_reference_lines_saved() {
+        let mut app = PdfViewerApp::new();
+        let mut spec = ReferenceLine::new(3.2);
+        spec.label = "spec limit".to_string();
+        let mut threshold = ReferenceLine::new(0.1);
+        threshold.horizontal = true;
+        threshold.style = StrokeStyle::Dotted;
+        threshold.color = [0, 0, 255];
+        app.reference_lines = vec![spec, threshold];
+        assert_eq!(app.reference_lines[0].caption(), "spec limit = 3.2");
+        assert_eq!(app.reference_lines[1].caption(), "y = 0.1");
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.reference_lines, app.reference_lines);
+
+        // Sessions from before reference lines still load
+        let json = r#"{"distributions": {}, "next_id": 0, "show_shading": true, "shading_opacity": 0.3, "show_std_markers": true}"#;
+        loaded.load_session(json).unwrap();
+        assert!(loaded.reference_lines.is_empty());
+    }
+
+    #[test]
+    fn test_text_annotations_saved() {
+        let mut app = PdfViewerApp::new();
+        let mut peak = TextAnnotation::new([0.0, 0.45]);
+        peak.text = "mode".to_string();
+        peak.arrow_to = Some([0.0, 0.399]);
+        app.annotations = vec![peak, TextAnnotation::new([2.0, 0.1])];
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.annotations, app.annotations);
+        assert_eq!(loaded.annotations[0].arrow_to, Some([0.0, 0.399]));
+        assert_eq!(loaded.annotations[1].arrow_to, None);
+    }
+
+    #[test]
+    fn test_hidden_curves_and_isolation() {
+        let mut app = PdfViewerApp::new();
+        for (id, name) in ["A", "B", "C"].into_iter().enumerate() {
+            app.distributions.insert(id as u32, GaussianDistribution::new(id as u32, name.to_string(), id as f64, 1.0));
+        }
+        app.next_id = 3;
+
+        // Hidden curves drop out of the hover readout
+        app.distributions.get_mut(&2).unwrap().hidden = true;
+        assert!(app.hover_values(1.0).iter().all(|(id, _)| *id != 2));
+
+        // Isolating shows one curve; isolating it again shows all of them
+        app.isolate_curve(1);
+        assert!(app.distributions.values().all(|dist| dist.hidden == (dist.id != 1)));
+        app.isolate_curve(1);
+        assert!(app.distributions.values().all(|dist| !dist.hidden));
+
+        app.distributions.get_mut(&0).unwrap().hidden = true;
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert!(loaded.distributions[&0].hidden);
+        assert!(!loaded.distributions[&1].hidden);
+    }
+
+    #[test]
+    fn test_line_style_saved() {
+        let mut dist = GaussianDistribution::new(0, "Theory".to_string(), 0.0, 1.0);
+        assert_eq!((dist.line_style, dist.line_width), (StrokeStyle::Solid, 1.5));
+        dist.line_style = StrokeStyle::Dashed;
+        dist.line_width = 3.0;
+        let restored: GaussianDistribution = serde_json::from_str(&serde_json::to_string(&dist).unwrap()).unwrap();
+        assert_eq!((restored.line_style, restored.line_width), (StrokeStyle::Dashed, 3.0));
+
+        // Distributions saved before styles existed get the defaults
+        let old = r#"{"id": 1, "name": "Old", "mean": 0.0, "std_dev": 1.0, "parent_ids": [], "is_product": false}"#;
+        let restored: GaussianDistribution = serde_json::from_str(old).unwrap();
+        assert_eq!((restored.line_style, restored.line_width), (StrokeStyle::Solid, 1.5));
+    }
+
+    #[test]
+    fn test_themes_and_custom_palette() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        assert_eq!(app.palette(), PALETTE.to_vec());
+
+        app.theme = PlotTheme::Print;
+        let (colors, _) = app.curve_colors();
+        assert_eq!(colors[&0], PlotTheme::Print.palette()[0]);
+
+        // A custom palette wins over the theme's
+        app.custom_palette = vec![[10, 20, 30]];
+        let (colors, _) = app.curve_colors();
+        assert_eq!(colors[&0], egui::Color32::from_rgb(10, 20, 30));
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.theme, PlotTheme::Print);
+        assert_eq!(loaded.custom_palette, vec![[10, 20, 30]]);
+    }
+
+    #[test]
+    fn test_grid_marks_and_number_format() {
+        let input = |bounds: (f64, f64), base_step_size: f64| egui_plot::GridInput { bounds, base_step_size };
+
+        // Fixed step with minor lines: majors at multiples of 0.5, four minors between
+        let marks = grid_marks(input((-1.0, 1.0), 0.01), Some(0.5), true);
+        let majors: Vec<f64> = marks.iter().filter(|mark| mark.step_size == 0.5).map(|mark| mark.value).collect();
+        assert_eq!(majors, vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(marks.len(), 5 + 16);
+        assert!(marks.iter().filter(|mark| mark.step_size != 0.5).all(|mark| (mark.value / 0.1).round() % 5.0 != 0.0));
+
+        // Without minor lines only the majors are left
+        assert_eq!(grid_marks(input((-1.0, 1.0), 0.01), Some(0.5), false).len(), 5);
+
+        // A step finer than the zoom allows is coarsened by powers of ten
+        let marks = grid_marks(input((0.0, 100.0), 2.0), Some(0.5), false);
+        assert!(marks.iter().all(|mark| mark.step_size == 5.0));
+
+        // Auto: a power of ten above the finest step
+        let marks = grid_marks(input((0.0, 10.0), 0.03), None, false);
+        assert!(marks.iter().all(|mark| mark.step_size == 1.0));
+
+        assert_eq!(NumberFormat::Fixed.format(0.12345, 3), "0.123");
+        assert_eq!(NumberFormat::Scientific.format(1234.5, 2), "1.23e3");
+
+        let mut app = PdfViewerApp::new();
+        app.axis_style.x_label = "Length (mm)".to_string();
+        app.axis_style.y_step = Some(0.05);
+        app.axis_style.minor_grid = false;
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert_eq!(loaded.axis_style, app.axis_style);
+    }
+
+    #[test]
+    fn test_secondary_axis_scale() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Wide".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Narrow".to_string(), 0.0, 0.01));
+        app.next_id = 2;
+        assert_eq!(app.secondary_axis_scale(), None);
+
+        // The narrow peak (≈ 39.9) is brought down to the wide one (≈ 0.399)
+        app.distributions.get_mut(&1).unwrap().secondary_axis = true;
+        let factor = app.secondary_axis_scale().unwrap();
+        assert_abs_diff_eq!(factor, 0.01, epsilon = 1e-4);
+        app.secondary_scale = Some(factor);
+        assert_abs_diff_eq!(app.curve_scale(&app.distributions[&1]), factor, epsilon = EPSILON);
+        assert_eq!(app.curve_scale(&app.distributions[&0]), 1.0);
+
+        // Only densities on a linear axis use it
+        app.log_y = true;
+        assert_eq!(app.secondary_axis_scale(), None);
+    }
+
+    #[test]
+    fn test_peak_normalized_scale() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 3.0, 0.25));
+        app.next_id = 2;
+        app.peak_normalized = true;
+        for dist in app.distributions.values() {
+            assert_abs_diff_eq!(app.curve_scale(dist) * dist.evaluate(dist.mean), 1.0, epsilon = 1e-12);
+        }
+        // Hovering still reports the true density
+        let values = app.hover_values(0.0);
+        let a = values.iter().find(|(id, _)| *id == 0).unwrap().1;
+        assert_abs_diff_eq!(a, 1.0 / (2.0 * PI).sqrt(), epsilon = EPSILON);
+
+        // Numeric shapes use the sampled maximum
+        let mut restricted = GaussianDistribution::new(2, "C".to_string(), 0.0, 1.0);
+        restricted.domain = Some((1.0, 4.0));
+        let peak = restricted.peak_density();
+        assert_abs_diff_eq!(peak, restricted.evaluate(1.0), epsilon = 1e-9);
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        assert!(loaded.peak_normalized);
+    }
+
+    #[test]
+    fn test_small_multiple_panels() {
+        let mut app = PdfViewerApp::new();
+        for (id, name) in ["A", "B", "C", "D"].into_iter().enumerate() {
+            app.distributions.insert(id as u32, GaussianDistribution::new(id as u32, name.to_string(), 0.0, 1.0));
+        }
+        app.next_id = 4;
+        app.distributions.get_mut(&3).unwrap().hidden = true;
+
+        let panels = app.small_multiple_panels();
+        assert_eq!(panels, vec![
+            ("A".to_string(), vec![0]),
+            ("B".to_string(), vec![1]),
+            ("C".to_string(), vec![2]),
+        ]);
+
+        app.groups.push(DistributionGroup { name: "Pair".to_string(), members: vec![1, 2, 3], shift: 0.0, scale: 1.0 });
+        app.small_multiples_by_group = true;
+        assert_eq!(app.small_multiple_panels(), vec![
+            ("Pair".to_string(), vec![1, 2]),
+            ("Ungrouped".to_string(), vec![0]),
+        ]);
+    }
+
+    #[test]
+    fn test_joint_density() {
+        let x = GaussianDistribution::new(0, "X".to_string(), 1.0, 2.0);
+        let y = GaussianDistribution::new(1, "Y".to_string(), -1.0, 0.5);
+
+        // Two Gaussians under the copula give the bivariate normal
+        let rho: f64 = 0.6;
+        for (px, py) in [(1.0, -1.0), (3.0, -0.5), (-2.0, 0.2)] {
+            let (zx, zy) = ((px - 1.0) / 2.0, (py + 1.0) / 0.5);
+            let quadratic = (zx * zx - 2.0 * rho * zx * zy + zy * zy) / (1.0 - rho * rho);
+            let expected = (-0.5 * quadratic).exp() / (2.0 * PI * 2.0 * 0.5 * (1.0 - rho * rho).sqrt());
+            assert_abs_diff_eq!(bivariate::joint_density(&x, &y, rho, px, py), expected, epsilon = 1e-9);
+        }
+        // Independence: the product of the marginals
+        assert_abs_diff_eq!(bivariate::joint_density(&x, &y, 0.0, 0.5, -1.2), x.evaluate(0.5) * y.evaluate(-1.2), epsilon = EPSILON);
+
+        // The raster integrates to about 1 over the central ranges and peaks at the means
+        let raster = bivariate::density_raster(&x, &y, rho, (-7.0, 9.0), (-3.0, 1.0), 160, 80);
+        let cell = (16.0 / 160.0) * (4.0 / 80.0);
+        assert_abs_diff_eq!(raster.values.iter().sum::<f64>() * cell, 1.0, epsilon = 1e-3);
+        let peak = raster.values.iter().position(|v| *v == raster.max).unwrap();
+        let (row, column) = (peak / 160, peak % 160);
+        assert_abs_diff_eq!(-7.0 + 0.1 * (column as f64 + 0.5), 1.0, epsilon = 0.1);
+        assert_abs_diff_eq!(1.0 - 0.05 * (row as f64 + 0.5), -1.0, epsilon = 0.05);
+
+        assert_eq!(Colormap::Grayscale.color(1.0), egui::Color32::WHITE);
+        assert_eq!(Colormap::Viridis.color(0.0), viridis(0.0));
+    }
+
+    #[test]
+    fn test_parameter_sweep() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 0.0, 1.0));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+
+        // Nothing moves until the sweep plays
+        assert!(!app.advance_sweep(1.0));
+        app.sweep.target = Some(0);
+        app.sweep.playing = true;
+
+        // Mean −3 → 3 over 4 s: a quarter of the way after 1 s, at the end after 4 s, back after 6 s
+        assert!(app.advance_sweep(1.0));
+        assert_abs_diff_eq!(app.distributions[&0].mean, -1.5, epsilon = EPSILON);
+        // The product of N(−1.5, 1) and N(0, 1) sits halfway, at −0.75
+        assert_abs_diff_eq!(app.distributions[&product].mean, -0.75, epsilon = EPSILON);
+        app.advance_sweep(3.0);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 3.0, epsilon = EPSILON);
+        app.advance_sweep(2.0);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 0.0, epsilon = EPSILON);
+
+        // Without bouncing it wraps around to the start
+        app.sweep.bounce = false;
+        app.sweep.elapsed = 0.0;
+        app.advance_sweep(5.0);
+        assert_abs_diff_eq!(app.distributions[&0].mean, -1.5, epsilon = EPSILON);
+
+        // Std dev sweeps past the slider range widen it
+        app.sweep.parameter = SweepParameter::StdDev;
+        (app.sweep.from, app.sweep.to) = (1.0, 8.0);
+        app.sweep.elapsed = 0.0;
+        app.advance_sweep(4.0 - 1e-9);
+        assert_abs_diff_eq!(app.distributions[&0].std_dev, 8.0, epsilon = 1e-6);
+        assert!(app.distributions[&0].ranges.std_dev.1 >= 8.0);
+
+        // Removing the target stops the animation
+        app.distributions.remove(&0);
+        assert!(!app.advance_sweep(0.1));
+        assert!(!app.sweep.playing);
+    }
+
+    #[test]
+    fn test_timeline_keyframes() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+
+        assert!(app.record_keyframe(0.0));
+        // Unchanged state adds nothing
+        assert!(!app.record_keyframe(0.5));
+        app.distributions.get_mut(&0).unwrap().mean = -2.0;
+        app.update_product_distributions();
+        assert!(app.record_keyframe(1.0));
+        app.distributions.get_mut(&1).unwrap().std_dev = 3.0;
+        app.show_shading = false;
+        app.update_product_distributions();
+        assert!(app.record_keyframe(2.0));
+        assert_eq!(app.timeline.keyframes.len(), 3);
+        assert_eq!(app.timeline.position, 2);
+
+        // Scrubbing back restores parameters, derived curves and other settings
+        app.restore_keyframe(0);
+        assert_abs_diff_eq!(app.distributions[&0].mean, 0.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&1].std_dev, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&product].mean, 1.0, epsilon = EPSILON);
+        assert!(app.show_shading);
+        // ...without recording the restored state as a new keyframe
+        assert!(!app.record_keyframe(3.0));
+
+        app.restore_keyframe(1);
+        assert_abs_diff_eq!(app.distributions[&0].mean, -2.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&product].mean, 0.0, epsilon = EPSILON);
+        app.restore_keyframe(2);
+        assert!(!app.show_shading);
+        assert_abs_diff_eq!(app.distributions[&1].std_dev, 3.0, epsilon = EPSILON);
+        assert_eq!(app.timeline.keyframes.len(), 3);
+    }
+
+    #[test]
+    fn test_violin_shapes() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Wide".to_string(), 0.0, 2.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Narrow".to_string(), 1.0, 0.5));
+        app.distributions.insert(2, GaussianDistribution::new(2, "Hidden".to_string(), 0.0, 1.0));
+        app.distributions.get_mut(&2).unwrap().hidden = true;
+        app.next_id = 3;
+
+        let shapes = app.violin_shapes(-8.0, 8.0, 401);
+        assert_eq!(shapes.len(), 2);
+        // First distribution on the top row
+        assert_eq!((shapes[0].0, shapes[0].1), (0, 1.0));
+        assert_eq!((shapes[1].0, shapes[1].1), (1, 0.0));
+        for (_, baseline, outline) in &shapes {
+            // Mirror image: the second half retraces the first below the baseline
+            let half = outline.len() / 2;
+            for (top, bottom) in outline[..half].iter().zip(outline[half..].iter().rev()) {
+                assert_abs_diff_eq!(top[0], bottom[0], epsilon = EPSILON);
+                assert_abs_diff_eq!(top[1] - baseline, baseline - bottom[1], epsilon = EPSILON);
+            }
+            // Each violin fills its row by default
+            let widest = outline.iter().map(|[_, y]| y - baseline).fold(0.0, f64::max);
+            assert_abs_diff_eq!(widest, 0.45, epsilon = 1e-6);
+        }
+
+        // On a common scale widths follow the densities: the wide one peaks 4× lower
+        app.violin_common_scale = true;
+        let shapes = app.violin_shapes(-8.0, 8.0, 401);
+        let widest = |outline: &[[f64; 2]], baseline: f64| outline.iter().map(|[_, y]| y - baseline).fold(0.0, f64::max);
+        assert_abs_diff_eq!(widest(&shapes[1].2, 0.0), 0.45, epsilon = 1e-6);
+        assert_abs_diff_eq!(widest(&shapes[0].2, 1.0), 0.45 / 4.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_csv_import_and_histogram() {
+        let csv = "id;height;note\n1;1,5;x\n2;2.5;\"a;b\"\n3;NA;y\n4;0.5;z\n\n5;1.0;w\n";
+        assert_eq!(data::detect_delimiter(csv), ';');
+        assert_eq!(data::column_names(csv), vec!["id", "height", "note"]);
+        assert_eq!(data::split_fields("1;\"a;b\";\"say \"\"hi\"\"\"", ';'), vec!["1", "a;b", "say \"hi\""]);
+
+        // "1,5" and "NA" are not numbers: skipped and counted
+        let dataset = data::parse_column(csv, 1, "people.csv").unwrap();
+        assert_eq!(dataset.name, "people.csv: height");
+        assert_eq!(dataset.values, vec![2.5, 0.5, 1.0]);
+        assert_eq!(dataset.skipped, 2);
+        assert!(data::parse_column(csv, 2, "people.csv").is_err());
+        assert!(data::parse_column(csv, 5, "people.csv").is_err());
+
+        // Headerless files get numbered columns
+        assert_eq!(data::column_names("1,2\n3,4\n"), vec!["Column 1", "Column 2"]);
+        assert_eq!(data::parse_column("1,2\n3,4\n", 1, "f").unwrap().values, vec![2.0, 4.0]);
+
+        // Bins sit on multiples of the width, hold every value and integrate to 1
+        let values = [0.1, 0.2, 0.35, 0.9, 1.0, -0.3];
+        let histogram = data::Histogram::new(&values, 0.25);
+        assert_abs_diff_eq!(histogram.start, -0.5, epsilon = EPSILON);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), values.len());
+        assert_eq!(histogram.counts, vec![1, 0, 2, 1, 0, 1, 1]);
+        let area: f64 = histogram.densities().iter().map(|(left, right, density)| (right - left) * density).sum();
+        assert_abs_diff_eq!(area, 1.0, epsilon = EPSILON);
+
+        let mut app = PdfViewerApp::new();
+        let text: String = (0..1000).map(|i| format!("{}\n", (i as f64 + 0.5) / 1000.0)).collect();
+        app.import_data("uniform.csv".to_string(), text);
+        let dataset = app.data.dataset.as_ref().unwrap();
+        assert_eq!(dataset.values.len(), 1000);
+        assert!(app.data.show_histogram);
+        // Freedman–Diaconis: 2 · 0.5 / ∛1000
+        assert_abs_diff_eq!(app.data.bin_width, 0.1, epsilon = 1e-3);
+        assert!(app.data.histogram.as_ref().unwrap().densities().iter().all(|(_, _, density)| *density < 1.2));
+    }
+
+    #[test]
+    fn test_kernel_density_estimate() {
+        let samples = vec![-1.0, 0.0, 0.5, 2.0];
+        let mut sorted = samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        // Direct sum of the four kernels
+        let h = 0.7;
+        let expected: f64 = samples.iter()
+            .map(|s| Normal::new(*s, h).unwrap().pdf(0.3))
+            .sum::<f64>() / samples.len() as f64;
+        assert_abs_diff_eq!(data::kernel_density(&sorted, h, 0.3), expected, epsilon = 1e-12);
+
+        // Silverman for a regular sample: 0.9 · min(s, IQR/1.34) · n^(−1/5)
+        let regular: Vec<f64> = (0..100).map(f64::from).collect();
+        let sd = (100.0f64 * 101.0 / 12.0).sqrt();
+        assert_abs_diff_eq!(data::silverman_bandwidth(&regular), 0.9 * sd.min(49.5 / 1.34) * 100f64.powf(-0.2), epsilon = 1e-9);
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        assert!(app.add_kde(None).is_none());
+        app.import_data("s.csv".to_string(), "x\n-1\n0\n0.5\n2\n".to_string());
+        let kde = app.add_kde(Some(h)).unwrap();
+        let dist = &app.distributions[&kde];
+        assert_eq!(dist.name, "KDE of s.csv: x");
+        assert!(dist.is_derived());
+        assert_abs_diff_eq!(dist.evaluate(0.3), expected, epsilon = 1e-4);
+        // Mean of the samples; variance of the samples (1/n) plus h²
+        assert_abs_diff_eq!(dist.mean, 0.375, epsilon = 1e-4);
+        assert_abs_diff_eq!(dist.std_dev, (1.171875f64 + h * h).sqrt(), epsilon = 1e-3);
+
+        // Multiplied numerically like any other grid
+        let product = app.add_derived(Operation::Product, vec![0, kde]).unwrap();
+        app.update_product_distributions();
+        assert!(app.distributions[&product].grid.is_some());
+
+        // The samples travel with the session and the grid is rebuilt on load
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_abs_diff_eq!(loaded.distributions[&kde].evaluate(0.3), expected, epsilon = 1e-4);
+        assert_abs_diff_eq!(loaded.distributions[&product].mean, app.distributions[&product].mean, epsilon = 1e-9);
+
+        // Without two usable samples the node is flagged and draws nothing
+        let empty = app.add_derived(Operation::Kde { samples: vec![f64::NAN, 1.0], bandwidth: None }, Vec::new()).unwrap();
+        let dist = &app.distributions[&empty];
+        assert!(dist.warning.as_deref().unwrap().contains("two samples"));
+        assert!(!dist.has_closed_form());
+        assert!([-1.0, 0.0, 1.0, 2.0].iter().all(|&x| dist.evaluate(x) == 0.0));
+        assert!(dist.validate().is_ok());
+    }
+
+    #[test]
+    fn test_maximum_likelihood_fit() {
+        let values = [1.0, 2.0, 4.0, 5.0];
+        let fit = data::normal_mle(&values);
+        // μ̂ = 3, σ̂² = (4 + 1 + 1 + 4) / 4 = 2.5
+        assert_abs_diff_eq!(fit.mean, 3.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(fit.std_dev, 2.5f64.sqrt(), epsilon = EPSILON);
+        assert_abs_diff_eq!(fit.mean_se, 2.5f64.sqrt() / 2.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(fit.std_dev_se, 2.5f64.sqrt() / 8f64.sqrt(), epsilon = EPSILON);
+        let normal = Normal::new(fit.mean, fit.std_dev).unwrap();
+        let direct: f64 = values.iter().map(|&x| normal.ln_pdf(x)).sum();
+        assert_abs_diff_eq!(fit.log_likelihood, direct, epsilon = 1e-10);
+
+        let mut app = PdfViewerApp::new();
+        assert!(app.fit_to_data(FitFamily::Normal, FitMethod::MaximumLikelihood).is_err());
+        app.import_data("d.csv".to_string(), "v\n1\n2\n4\n5\n".to_string());
+        let id = app.fit_to_data(FitFamily::Normal, FitMethod::MaximumLikelihood).unwrap();
+        let dist = &app.distributions[&id];
+        assert_eq!(dist.name, "Normal fit to d.csv: v");
+        assert!(!dist.is_derived());
+        assert_abs_diff_eq!(dist.mean, 3.0, epsilon = EPSILON);
+        assert_eq!(dist.fit.as_ref().unwrap().n, 4);
+
+        // Log-normal: a hidden normal of ln x and its exp transform
+        let id = app.fit_to_data(FitFamily::LogNormal, FitMethod::MaximumLikelihood).unwrap();
+        let curve = &app.distributions[&id];
+        assert_eq!(curve.operation, Operation::Transform(Transform::Exp));
+        let underlying = &app.distributions[&curve.parent_ids[0]];
+        assert!(underlying.hidden);
+        let logs: Vec<f64> = values.iter().map(|x: &f64| x.ln()).collect();
+        assert_abs_diff_eq!(underlying.mean, logs.iter().sum::<f64>() / 4.0, epsilon = EPSILON);
+        let summary = curve.fit.as_ref().unwrap();
+        let lognormal = statrs::distribution::LogNormal::new(summary.parameters[0].value, summary.parameters[1].value).unwrap();
+        let direct: f64 = values.iter().map(|&x| lognormal.ln_pdf(x)).sum();
+        assert_abs_diff_eq!(summary.log_likelihood, direct, epsilon = 1e-10);
+
+        app.import_data("n.csv".to_string(), "v\n-1\n2\n".to_string());
+        assert!(app.fit_to_data(FitFamily::LogNormal, FitMethod::MaximumLikelihood).is_err());
+    }
+
+    #[test]
+    fn test_method_of_moments_fit() {
+        let mut app = PdfViewerApp::new();
+        // Mean 2, variance (1 + 0 + 0 + 1) / 4 = 0.5
+        app.import_data("g.csv".to_string(), "v\n1\n2\n2\n3\n".to_string());
+        assert!(app.fit_to_data(FitFamily::Gamma, FitMethod::MaximumLikelihood).is_err());
+
+        // Gamma: k = 2² / 0.5 = 8, θ = 0.5 / 2
+        let id = app.fit_to_data(FitFamily::Gamma, FitMethod::Moments).unwrap();
+        let dist = &app.distributions[&id];
+        assert_eq!(dist.operation, Operation::Family { family: FitFamily::Gamma, parameters: [8.0, 0.25] });
+        assert!(dist.is_derived());
+        assert_abs_diff_eq!(dist.mean, 2.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(dist.std_dev, 0.5f64.sqrt(), epsilon = 1e-3);
+        let gamma = statrs::distribution::Gamma::new(8.0, 4.0).unwrap();
+        assert_abs_diff_eq!(dist.evaluate(1.7), gamma.pdf(1.7), epsilon = 1e-4);
+        assert!(dist.fit.as_ref().unwrap().parameters.iter().all(|parameter| parameter.se.is_none()));
+
+        // Log-normal moments reproduce the sample mean and variance
+        let id = app.fit_to_data(FitFamily::LogNormal, FitMethod::Moments).unwrap();
+        let fit = app.distributions[&id].fit.clone().unwrap();
+        let (mu, sigma) = (fit.parameters[0].value, fit.parameters[1].value);
+        assert_abs_diff_eq!((mu + 0.5 * sigma * sigma).exp(), 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(((sigma * sigma).exp() - 1.0) * (2.0 * mu + sigma * sigma).exp(), 0.5, epsilon = 1e-12);
+
+        // Beta: mean 0.4, variance 0.02 → α + β = 0.4 · 0.6 / 0.02 − 1 = 11
+        app.import_data("b.csv".to_string(), "v\n0.2\n0.4\n0.6\n0.4\n0.2\n0.6\n".to_string());
+        let (mean, variance) = data::moments(&app.data.dataset.as_ref().unwrap().values);
+        assert_abs_diff_eq!(mean, 0.4, epsilon = 1e-12);
+        let concentration = mean * (1.0 - mean) / variance - 1.0;
+        let id = app.fit_to_data(FitFamily::Beta, FitMethod::Moments).unwrap();
+        let dist = &app.distributions[&id];
+        let Operation::Family { parameters: [alpha, beta], .. } = dist.operation else { panic!("not a family") };
+        assert_abs_diff_eq!(alpha, 0.4 * concentration, epsilon = 1e-12);
+        assert_abs_diff_eq!(beta, 0.6 * concentration, epsilon = 1e-12);
+        assert_abs_diff_eq!(dist.mean, 0.4, epsilon = 1e-3);
+
+        // Saved by parameters and rebuilt on load
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_abs_diff_eq!(loaded.distributions[&id].evaluate(0.3), app.distributions[&id].evaluate(0.3), epsilon = 1e-12);
+
+        app.import_data("o.csv".to_string(), "v\n0.5\n1.5\n".to_string());
+        assert!(app.fit_to_data(FitFamily::Beta, FitMethod::Moments).is_err());
+
+        // Impossible parameters (e.g. from a hand-edited session) flag the node and draw nothing
+        let bad = app.add_derived(Operation::Family { family: FitFamily::Gamma, parameters: [-1.0, 2.0] }, Vec::new()).unwrap();
+        let dist = &app.distributions[&bad];
+        assert!(dist.warning.as_deref().unwrap().starts_with("Gamma needs"));
+        assert!(!dist.has_closed_form());
+        assert!([-1.0, 0.0, 0.5, 2.0].iter().all(|&x| dist.evaluate(x) == 0.0));
+    }
+
+    #[test]
+    fn test_goodness_of_fit_tests() {
+        // Uniform CDF on [0, 1] against points with a known largest gap
+        let uniform = |x: f64| x.clamp(0.0, 1.0);
+        let ks = data::ks_test(&[0.1, 0.2, 0.3, 0.9], uniform);
+        // After 0.3 the ECDF is 3/4 while F = 0.3
+        assert_abs_diff_eq!(ks.statistic, 0.45, epsilon = EPSILON);
+        assert_abs_diff_eq!(ks.location, 0.3, epsilon = EPSILON);
+        assert_abs_diff_eq!(ks.empirical, 0.75, epsilon = EPSILON);
+
+        // Evenly spread quantiles fit well; a shifted sample does not
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let good: Vec<f64> = (0..200).map(|i| normal.inverse_cdf((i as f64 + 0.5) / 200.0)).collect();
+        let bad: Vec<f64> = good.iter().map(|x| x + 0.5).collect();
+        let (good_ks, bad_ks) = (data::ks_test(&good, |x| normal.cdf(x)), data::ks_test(&bad, |x| normal.cdf(x)));
+        assert_abs_diff_eq!(good_ks.statistic, 0.0025, epsilon = 1e-9);
+        assert!(good_ks.p_value > 0.99);
+        assert!(bad_ks.p_value < 1e-6);
+        let (good_ad, bad_ad) = (data::anderson_darling_test(&good, |x| normal.cdf(x)), data::anderson_darling_test(&bad, |x| normal.cdf(x)));
+        assert!(good_ad.statistic < 0.1 && good_ad.p_value > 0.99);
+        assert!(bad_ad.p_value < 1e-6);
+
+        // The app reruns the tests only when the target's density changes
+        let mut app = PdfViewerApp::new();
+        let text: String = good.iter().map(|x| format!("{}\n", x)).collect();
+        app.import_data("z.csv".to_string(), text);
+        let id = app.fit_to_data(FitFamily::Normal, FitMethod::MaximumLikelihood).unwrap();
+        app.data.gof_target = Some(id);
+        app.refresh_goodness_of_fit();
+        let (ks, _) = app.data.goodness_of_fit.unwrap();
+        assert!(ks.p_value > 0.99);
+        app.distributions.get_mut(&id).unwrap().mean = 0.5;
+        app.refresh_goodness_of_fit();
+        let (ks, _) = app.data.goodness_of_fit.unwrap();
+        assert!(ks.p_value < 1e-6);
+        app.distributions.remove(&id);
+        app.refresh_goodness_of_fit();
+        assert!(app.data.goodness_of_fit.is_none());
+    }
+
+    #[test]
+    fn test_quantile_quantile_points() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Standard".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Scaled".to_string(), 1.0, 2.0));
+        app.next_id = 2;
+        assert!(app.quantile_points().is_err());
+
+        // Two normals: sample quantiles are an affine map of the theoretical ones
+        app.probability_plot = ProbabilityPlot { sample_from_data: false, sample: Some(1), reference: Some(0) };
+        let points = app.quantile_points().unwrap();
+        assert_eq!(points.len(), 400);
+        for [x, y] in points {
+            assert_abs_diff_eq!(y, 1.0 + 2.0 * x, epsilon = 1e-9);
+        }
+
+        // Data: sorted values against the plotting positions (i − ½)/n
+        app.import_data("d.csv".to_string(), "v\n3\n1\n2\n".to_string());
+        app.probability_plot.sample_from_data = true;
+        let points = app.quantile_points().unwrap();
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        assert_eq!(points.len(), 3);
+        for (i, [x, y]) in points.into_iter().enumerate() {
+            assert_abs_diff_eq!(x, normal.inverse_cdf((i as f64 + 0.5) / 3.0), epsilon = 1e-12);
+            assert_abs_diff_eq!(y, (i + 1) as f64, epsilon = EPSILON);
+        }
+
+        // Big samples are thinned
+        let text: String = (0..10_000).map(|i| format!("{}\n", i)).collect();
+        app.import_data("big.csv".to_string(), text);
+        assert!(app.quantile_points().unwrap().len() <= 2000);
+    }
+
+    #[test]
+    fn test_probability_probability_points() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Standard".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Shifted".to_string(), 0.5, 1.0));
+        app.next_id = 2;
+
+        // A distribution against itself lies on the diagonal
+        app.probability_plot = ProbabilityPlot { sample_from_data: false, sample: Some(0), reference: Some(0) };
+        for [x, y] in app.probability_points().unwrap() {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-9);
+        }
+        // Shifted right, the sample's p-quantile sits at a higher theoretical probability
+        app.probability_plot.sample = Some(1);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        for [x, y] in app.probability_points().unwrap() {
+            assert!(x > y);
+            assert_abs_diff_eq!(x, normal.cdf(0.5 + normal.inverse_cdf(y)), epsilon = 1e-9);
+        }
+
+        // Data: F at each sorted value against (i − ½)/n
+        app.import_data("d.csv".to_string(), "v\n0.5\n-1\n0\n1.5\n".to_string());
+        app.probability_plot.sample_from_data = true;
+        let points = app.probability_points().unwrap();
+        let sorted = [-1.0, 0.0, 0.5, 1.5];
+        for (i, [x, y]) in points.into_iter().enumerate() {
+            assert_abs_diff_eq!(x, normal.cdf(sorted[i]), epsilon = 1e-12);
+            assert_abs_diff_eq!(y, (i as f64 + 0.5) / 4.0, epsilon = EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_ecdf_steps() {
+        let steps = data::ecdf_steps(&[1.0, 2.0, 2.0, 4.0], 0.0, 5.0);
+        assert_eq!(steps, vec![
+            [0.0, 0.0],
+            [1.0, 0.0], [1.0, 0.25],
+            [2.0, 0.25], [2.0, 0.75],
+            [4.0, 0.75], [4.0, 1.0],
+            [5.0, 1.0],
+        ]);
+        // The ends reach past a view narrower than the data
+        let steps = data::ecdf_steps(&[1.0, 3.0], 2.0, 2.5);
+        assert_eq!(steps.first(), Some(&[1.0, 0.0]));
+        assert_eq!(steps.last(), Some(&[3.0, 1.0]));
+        assert!(data::ecdf_steps(&[], 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_bootstrap_band() {
+        let values: Vec<f64> = (0..200).map(|i| Normal::new(1.0, 2.0).unwrap().inverse_cdf((i as f64 + 0.5) / 200.0)).collect();
+        let xs: Vec<f64> = (0..41).map(|i| -5.0 + 0.3 * i as f64).collect();
+        let band = bootstrap::bootstrap_band(&values, FitFamily::Normal, FitMethod::MaximumLikelihood, &xs, 300, 0.95, 3).unwrap();
+        assert_eq!(band.replicates, 300);
+        let (parameters, _) = FitFamily::Normal.estimate(FitMethod::MaximumLikelihood, &values).unwrap();
+        let fitted = FitFamily::Normal.density_and_cdf([parameters[0].value, parameters[1].value], &xs).unwrap();
+        for ((density, cdf), (&(f, big_f), &x)) in band.density.iter().zip(&band.cdf).zip(fitted.iter().zip(&xs)) {
+            assert!(density[0] <= density[1] && cdf[0] <= cdf[1]);
+            assert!((0.0..=1.0).contains(&cdf[0]) && (0.0..=1.0).contains(&cdf[1]));
+            // The fit to the full data lies inside its own band near the centre
+            if (x - 1.0).abs() < 2.0 {
+                assert!(density[0] <= f && f <= density[1], "density at {}", x);
+                assert!(cdf[0] <= big_f && big_f <= cdf[1], "CDF at {}", x);
+            }
+        }
+        // Same seed, same band
+        let again = bootstrap::bootstrap_band(&values, FitFamily::Normal, FitMethod::MaximumLikelihood, &xs, 300, 0.95, 3).unwrap();
+        assert_eq!(again.density, band.density);
+
+        // A constant column cannot be refitted
+        assert!(bootstrap::bootstrap_band(&[2.0; 10], FitFamily::Normal, FitMethod::Moments, &xs, 50, 0.95, 3).is_err());
+    }
+
+    #[test]
+    fn test_bootstrap_job_for_fitted_curve() {
+        let mut app = PdfViewerApp::new();
+        app.import_data("d.csv".to_string(), "v\n1.2\n0.7\n2.5\n1.9\n0.4\n3.1\n1.1\n".to_string());
+        let id = app.fit_to_data(FitFamily::Gamma, FitMethod::Moments).unwrap();
+        app.data.bootstrap.replicates = 50;
+        app.start_bootstrap(id).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        while !app.data.bootstrap.job.poll() {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let band = app.data.bootstrap.band_for(&app.distributions[&id]).unwrap();
+        assert_eq!(band.xs.len(), BOOTSTRAP_POINTS);
+        assert!(band.replicates > 40);
+
+        // A band is only drawn for the curve it was computed for
+        app.distributions.get_mut(&id).unwrap().operation = Operation::Family { family: FitFamily::Gamma, parameters: [2.0, 1.0] };
+        app.distributions.get_mut(&id).unwrap().grid = None;
+        app.update_product_distributions();
+        assert!(app.data.bootstrap.band_for(&app.distributions[&id]).is_none());
+
+        // Fits need the data they came from
+        app.import_data("other.csv".to_string(), "w\n1\n2\n3\n".to_string());
+        assert!(app.start_bootstrap(id).is_err());
+    }
+
+    #[test]
+    fn test_gaussian_mixture_em() {
+        // Two well-separated clusters: 300 values like N(−3, 1) and 200 like N(4, 0.5²)
+        let standard = Normal::new(0.0, 1.0).unwrap();
+        let mut values: Vec<f64> = (0..300).map(|i| -3.0 + standard.inverse_cdf((i as f64 + 0.5) / 300.0)).collect();
+        values.extend((0..200).map(|i| 4.0 + 0.5 * standard.inverse_cdf((i as f64 + 0.5) / 200.0)));
+
+        let fit = data::gaussian_mixture_em(&values, 2).unwrap();
+        assert!(fit.converged);
+        assert_abs_diff_eq!(fit.weights[0], 0.6, epsilon = 1e-3);
+        assert_abs_diff_eq!(fit.means[0], -3.0, epsilon = 0.01);
+        assert_abs_diff_eq!(fit.means[1], 4.0, epsilon = 0.01);
+        assert_abs_diff_eq!(fit.std_devs[0], 1.0, epsilon = 0.02);
+        assert_abs_diff_eq!(fit.std_devs[1], 0.5, epsilon = 0.02);
+
+        // One component is the normal MLE
+        let single = data::gaussian_mixture_em(&values, 1).unwrap();
+        assert_abs_diff_eq!(single.log_likelihood, data::normal_mle(&values).log_likelihood, epsilon = 1e-6);
+        assert!(fit.bic < single.bic);
+        assert!(fit.bic < data::gaussian_mixture_em(&values, 3).unwrap().bic);
+
+        assert!(data::gaussian_mixture_em(&values[..3], 2).is_err());
+        assert!(data::gaussian_mixture_em(&[1.0; 10], 2).is_err());
+    }
+
+    #[test]
+    fn test_fit_mixture_to_data() {
+        let mut app = PdfViewerApp::new();
+        app.import_data("d.csv".to_string(), "v\n-2.1\n-1.9\n-2.3\n-1.7\n3.0\n3.2\n2.8\n3.1\n".to_string());
+        let id = app.fit_mixture_to_data(2).unwrap();
+        let mixture = &app.distributions[&id];
+        assert_eq!(mixture.parent_ids.len(), 2);
+        assert!(mixture.parent_ids.iter().all(|parent| app.distributions[parent].hidden));
+        assert_abs_diff_eq!(mixture.mean, 0.5125, epsilon = 1e-6);
+        assert!(app.fit_mixture_to_data(1).is_err());
+
+        app.compare_mixtures().unwrap();
+        let ks: Vec<usize> = app.data.mixture.comparison.iter().map(|fit| fit.weights.len()).collect();
+        assert_eq!(ks, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_stream_parse_values() {
+        assert_eq!(stream::parse_values("1 2.5,-3;4e2\tNaN x inf 7"), vec![1.0, 2.5, -3.0, 400.0, 7.0]);
+        assert!(stream::parse_values("temperature").is_empty());
+    }
+
+    #[test]
+    fn test_stream_over_tcp_and_udp() {
+        use std::io::Write;
+        let drain_until = |stream: &mut stream::DataStream, count: usize| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+            let mut values = Vec::new();
+            while values.len() < count {
+                assert!(std::time::Instant::now() < deadline, "only got {:?}", values);
+                values.extend(stream.drain());
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            values
+        };
+        // The description ends with the address actually bound
+        let port = |stream: &stream::DataStream| -> u16 { stream.description().rsplit(':').next().unwrap().parse().unwrap() };
+
+        let mut tcp = stream::DataStream::open(stream::StreamSource::Tcp, stream::DEFAULT_INTERFACE, 0).unwrap();
+        assert!(tcp.description().starts_with("TCP 127.0.0.1:"));
+        let mut sender = std::net::TcpStream::connect(("127.0.0.1", port(&tcp))).unwrap();
+        sender.write_all(b"1 2 3\nnot a number\n4,5\n").unwrap();
+        assert_eq!(drain_until(&mut tcp, 5), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        // A line the reader's timeout cuts in two is still read whole
+        sender.write_all(b"6 1").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        sender.write_all(b"7\n").unwrap();
+        assert_eq!(drain_until(&mut tcp, 2), vec![6.0, 17.0]);
+
+        // An endless line closes its connection, and stopping closes idle ones
+        let closed = |connection: &mut std::net::TcpStream| {
+            use std::io::Read;
+            connection.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+            let result = connection.read(&mut [0; 16]);
+            assert!(result.as_ref().map_or_else(|error| !matches!(error.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut), |read| *read == 0), "{:?}", result);
+        };
+        let mut flood = std::net::TcpStream::connect(("127.0.0.1", port(&tcp))).unwrap();
+        let _ = flood.write_all(&vec![b'1'; 4 << 20]);
+        closed(&mut flood);
+        drop(tcp);
+        closed(&mut sender);
+
+        let mut udp = stream::DataStream::open(stream::StreamSource::Udp, stream::DEFAULT_INTERFACE, 0).unwrap();
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.send_to(b"0.5;1.5", ("127.0.0.1", port(&udp))).unwrap();
+        assert_eq!(drain_until(&mut udp, 2), vec![0.5, 1.5]);
+    }
+
+    #[test]
+    fn test_live_stream_window_and_running_fit() {
+        let mut app = PdfViewerApp::new();
+        app.live_stream.window = 4;
+        app.ingest_stream_values(vec![10.0]);
+        // One value cannot be fitted yet
+        assert!(app.live_stream.fit_id.is_none());
+        app.ingest_stream_values(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(app.live_stream.received, 6);
+        let dataset = app.data.dataset.as_ref().unwrap();
+        assert_eq!(dataset.values, vec![2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(app.data.histogram.as_ref().unwrap().total, 4);
+
+        let id = app.live_stream.fit_id.unwrap();
+        assert_abs_diff_eq!(app.distributions[&id].mean, 3.5, epsilon = EPSILON);
+        assert_abs_diff_eq!(app.distributions[&id].std_dev, 1.25f64.sqrt(), epsilon = EPSILON);
+
+        // The same curve follows later values
+        app.ingest_stream_values(vec![6.0]);
+        assert_eq!(app.live_stream.fit_id, Some(id));
+        assert_eq!(app.distributions.len(), 1);
+        assert_abs_diff_eq!(app.distributions[&id].mean, 4.5, epsilon = EPSILON);
+    }
+
+    #[test]
+    fn test_import_parquet_and_arrow_columns() {
+        use arrow_array::{ArrayRef, Float64Array, Int32Array, RecordBatch, StringArray};
+        use std::sync::Arc;
+        let batch = RecordBatch::try_from_iter(vec![
+            ("label", Arc::new(StringArray::from(vec!["a", "b", "c", "d"])) as ArrayRef),
+            ("x", Arc::new(Float64Array::from(vec![Some(1.5), None, Some(2.5), Some(f64::NAN)])) as ArrayRef),
+            ("n", Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef),
+        ]).unwrap();
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_columnar_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let parquet_path = directory.join("data.parquet");
+        let mut writer = parquet::arrow::ArrowWriter::try_new(std::fs::File::create(&parquet_path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let arrow_path = directory.join("data.arrow");
+        let mut writer = arrow_ipc::writer::FileWriter::try_new(std::fs::File::create(&arrow_path).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let csv_path = directory.join("data.csv");
+        std::fs::write(&csv_path, "x\n1\n2\n").unwrap();
+
+        assert_eq!(columnar::Format::detect(&parquet_path), Some(columnar::Format::Parquet));
+        assert_eq!(columnar::Format::detect(&arrow_path), Some(columnar::Format::ArrowIpc));
+        assert_eq!(columnar::Format::detect(&csv_path), None);
+
+        for (path, format) in [(parquet_path, columnar::Format::Parquet), (arrow_path, columnar::Format::ArrowIpc)] {
+            // Text columns are not offered
+            assert_eq!(columnar::numeric_columns(&path, format).unwrap(), vec!["x", "n"]);
+            let mut app = PdfViewerApp::new();
+            app.import_columnar("data".to_string(), path.clone(), format);
+            let dataset = app.data.dataset.as_ref().unwrap();
+            assert_eq!(dataset.name, "data: x");
+            assert_eq!(dataset.values, vec![1.5, 2.5]);
+            assert_eq!(dataset.skipped, 2);
+            // Integers are read as numbers too
+            app.select_data_column(1);
+            assert_eq!(app.data.dataset.as_ref().unwrap().values, vec![1.0, 2.0, 3.0, 4.0]);
+            assert!(columnar::read_column(&path, format, "missing", "data").is_err());
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_draw_samples() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Y".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+
+        assert!(app.draw_samples().is_err());
+        app.sampler.target = Some(product);
+        app.sampler.count = 20_000;
+        app.sampler.seed = 42;
+        app.draw_samples().unwrap();
+        let draws = app.sampler.draws.as_ref().unwrap();
+        assert_eq!(draws.values.len(), 20_000);
+        assert_eq!(draws.histogram.total, 20_000);
+        // N(0, 1) · N(2, 1) ∝ N(1, ½)
+        let (mean, variance) = data::moments(&draws.values);
+        assert_abs_diff_eq!(mean, 1.0, epsilon = 0.02);
+        assert_abs_diff_eq!(variance, 0.5, epsilon = 0.02);
+
+        // Same seed, same draws
+        let first = draws.values.clone();
+        app.draw_samples().unwrap();
+        assert_eq!(app.sampler.draws.as_ref().unwrap().values, first);
+        app.sampler.seed = 43;
+        app.draw_samples().unwrap();
+        assert_ne!(app.sampler.draws.as_ref().unwrap().values, first);
+
+        app.sampler.count = 2;
+        app.draw_samples().unwrap();
+        let draws = app.sampler.draws.as_ref().unwrap();
+        assert_eq!(draws.csv(), format!("draw,x\n1,{}\n2,{}\n", draws.values[0], draws.values[1]));
+    }
+
+    #[test]
+    fn test_expression_variables() {
+        let (expr, variables) = Expr::parse_with_variables("X*Y + 2 - X / sqrt(x)").unwrap();
+        assert_eq!(variables, vec!["X", "Y", "x"]);
+        assert_abs_diff_eq!(expr.eval_at(f64::NAN, &[3.0, 2.0, 4.0]), 6.5, epsilon = EPSILON);
+        assert!(expr.eval(1.0).is_nan());
+        // Without variables, unknown names are still errors
+        assert!(Expr::parse("X + 1").is_err());
+        assert!(Expr::parse_with_variables("foo(X)").is_err());
+
+        let affine = |source: &str| {
+            let (expr, variables) = Expr::parse_with_variables(source).unwrap();
+            expr.affine(variables.len())
+        };
+        assert_eq!(affine("2*A - (B - 1)/4 + exp(0)"), Some((vec![2.0, -0.25], 1.25)));
+        assert_eq!(affine("-A*3^2"), Some((vec![-9.0], 0.0)));
+        assert_eq!(affine("A*B"), None);
+        assert_eq!(affine("sqrt(A)"), None);
+        assert_eq!(affine("1/A"), None);
+    }
+
+    #[test]
+    fn test_monte_carlo_propagation() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 1.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Second".to_string(), 0.0, 2.0));
+        app.next_id = 2;
+
+        app.monte_carlo = MonteCarloTool { formula: "Z = X + 2*Y".to_string(), count: 50_000, seed: 1, ..Default::default() };
+        // Y matches no distribution until it is picked
+        assert_eq!(app.run_monte_carlo(), Err("Pick a distribution for Y".to_string()));
+        app.monte_carlo.bindings.insert("Y".to_string(), 1);
+        app.run_monte_carlo().unwrap();
+        let result = app.monte_carlo.result.as_ref().unwrap();
+        assert_eq!(result.name, "Z");
+        assert_eq!(result.samples.len(), 50_000);
+        let (exact_mean, exact_std_dev) = result.exact.unwrap();
+        assert_abs_diff_eq!(exact_mean, 1.0, epsilon = EPSILON);
+        assert_abs_diff_eq!(exact_std_dev, 17f64.sqrt(), epsilon = EPSILON);
+        let (mean, variance) = data::moments(&result.samples);
+        assert_abs_diff_eq!(mean, exact_mean, epsilon = 0.06);
+        assert_abs_diff_eq!(variance.sqrt(), exact_std_dev, epsilon = 0.06);
+
+        // Products have no exact normal; E[XY] = E[X] E[Y] for independent inputs
+        app.monte_carlo.formula = "X*Y + 2".to_string();
+        app.run_monte_carlo().unwrap();
+        let result = app.monte_carlo.result.as_ref().unwrap();
+        assert_eq!(result.name, "Result");
+        assert!(result.exact.is_none());
+        assert_abs_diff_eq!(data::moments(&result.samples).0, 2.0, epsilon = 0.06);
+
+        // ln of a normal around 0 is undefined about half the time
+        app.monte_carlo.formula = "W = ln(Y)".to_string();
+        app.run_monte_carlo().unwrap();
+        let undefined = app.monte_carlo.result.as_ref().unwrap().undefined as f64 / 50_000.0;
+        assert_abs_diff_eq!(undefined, 0.5, epsilon = 0.02);
+
+        let id = app.add_monte_carlo_curve().unwrap();
+        assert_eq!(app.distributions[&id].name, "W (Monte Carlo)");
+        assert!(PdfViewerApp::monte_carlo_formula(" = X").is_err());
+    }
+
+    #[test]
+    fn test_binned_residuals() {
+        // Uniform on [0, 4] in four unit bins, the outer bins taking the tails
+        let uniform = |x: f64| (x / 4.0).clamp(0.0, 1.0);
+        let values: Vec<f64> = (0..40).map(|i| i as f64 / 10.0).collect();
+        let bins = data::binned_residuals(&data::Histogram::new(&values, 1.0), uniform);
+        assert_eq!(bins.len(), 4);
+        for bin in &bins {
+            assert_eq!(bin.observed, 10);
+            assert_abs_diff_eq!(bin.expected, 10.0, epsilon = EPSILON);
+            assert_abs_diff_eq!(bin.residual, 0.0, epsilon = EPSILON);
+        }
+        let test = data::chi_square_test(&bins, 0).unwrap();
+        assert_eq!((test.degrees_of_freedom, test.pooled_bins), (3, 4));
+        assert_abs_diff_eq!(test.p_value, 1.0, epsilon = EPSILON);
+        // One fitted parameter per degree of freedom; none left means no test
+        assert_eq!(data::chi_square_test(&bins, 2).unwrap().degrees_of_freedom, 1);
+        assert!(data::chi_square_test(&bins, 3).is_none());
+
+        // Skewed counts: χ² = Σ (O − E)²/E with E = 10 per bin
+        let values: Vec<f64> = [0.5; 20].into_iter().chain([1.5; 10]).chain([2.5; 6]).chain([3.5; 4]).collect();
+        let bins = data::binned_residuals(&data::Histogram::new(&values, 1.0), uniform);
+        assert_abs_diff_eq!(bins[0].residual, 10.0 / 10f64.sqrt(), epsilon = EPSILON);
+        let test = data::chi_square_test(&bins, 0).unwrap();
+        assert_abs_diff_eq!(test.statistic, (100.0 + 0.0 + 16.0 + 36.0) / 10.0, epsilon = EPSILON);
+        assert!(test.p_value < 0.01);
+
+        // Small expected counts are pooled with the next bin
+        let values: Vec<f64> = (0..12).map(|i| i as f64 / 3.0).collect();
+        let bins = data::binned_residuals(&data::Histogram::new(&values, 1.0), uniform);
+        assert_eq!(data::chi_square_test(&bins, 0).unwrap().pooled_bins, 2);
+
+        // The app fits, then bins the data against the fit
+        let mut app = PdfViewerApp::new();
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let text: String = (0..500).map(|i| format!("{}\n", normal.inverse_cdf((i as f64 + 0.5) / 500.0))).collect();
+        app.import_data("z.csv".to_string(), text);
+        let id = app.fit_to_data(FitFamily::Normal, FitMethod::MaximumLikelihood).unwrap();
+        app.data.gof_target = Some(id);
+        app.refresh_goodness_of_fit();
+        let (bins, test) = app.data.residuals.clone().unwrap();
+        assert_eq!(bins.iter().map(|bin| bin.observed).sum::<usize>(), 500);
+        assert_abs_diff_eq!(bins.iter().map(|bin| bin.expected).sum::<f64>(), 500.0, epsilon = 1e-6);
+        let test = test.unwrap();
+        assert_eq!(test.degrees_of_freedom, test.pooled_bins - 3);
+        assert!(test.p_value > 0.9);
+        // Rebinned when the bin width changes
+        app.data.bin_width *= 2.0;
+        app.data.histogram = Some(data::Histogram::new(&app.data.dataset.as_ref().unwrap().values, app.data.bin_width));
+        app.refresh_goodness_of_fit();
+        assert!(app.data.residuals.as_ref().unwrap().0.len() < bins.len());
+    }
+
+    #[test]
+    fn test_offscreen_rendering() {
+        // A filled rectangle comes out in its color, blended over the background
+        let image = raster::render(40, 20, 2.0, egui::Style::default(), |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::WHITE))
+                .show(ctx, |ui| {
+                    ui.painter().rect_filled(egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(10.0, 10.0)), 0.0, egui::Color32::RED);
+                    ui.painter().rect_filled(egui::Rect::from_min_max(egui::pos2(10.0, 0.0), egui::pos2(20.0, 10.0)), 0.0, egui::Color32::from_black_alpha(128));
+                });
+        })
+        .unwrap()
+        .rasterize();
+        assert_eq!(image.size, [40, 20]);
+        assert_eq!(image.pixels[10 * 40 + 10], egui::Color32::RED);
+        assert_eq!(image.pixels[10 * 40 + 30], egui::Color32::from_gray(127));
+        assert!(raster::render(0, 10, 1.0, egui::Style::default(), |_| {}).is_err());
+
+        // The exported plot has the requested size whatever the window, and shows the curve
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Standard".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.plot_bounds = Some(view_bounds([-4.0, 4.0, 0.0, 0.5]));
+        let bytes = app.export_png(320, 180, 1.0, egui::Style::default()).unwrap();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height, info.color_type), (320, 180, png::ColorType::Rgba));
+        let (colors, _) = app.curve_colors();
+        let curve = colors[&0];
+        assert!(pixels.chunks_exact(4).any(|pixel| pixel[..3] == [curve.r(), curve.g(), curve.b()]));
+        // The view the screen plot asked for and the one it shows are left to it
+        assert!(app.requested_bounds.is_none());
+        app.plot_bounds = None;
+        app.export_png(320, 180, 1.0, egui::Style::default()).unwrap();
+        assert!(app.plot_bounds.is_none());
+    }
+
+    #[test]
+    fn test_tikz_export() {
+        assert_eq!(tikz_number(0.25), "0.25");
+        assert_eq!(tikz_number(-3.0), "-3");
+        assert_eq!(tikz_number(0.0), "0");
+        assert_eq!(tikz_number(1.5e-7), "1.5000e-7");
+        assert_eq!(latex_escape("A_1 & 50% {x}"), "A\\_1 \\& 50\\% \\{x\\}");
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior_1".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.plot_bounds = Some(view_bounds([-4.0, 4.0, 0.0, 0.5]));
+        app.resolution.export_points = 101;
+        app.latex_theme.color_names = PALETTE.iter().map(|_| "MyBlue".to_string()).collect();
+        app.reference_lines.push(ReferenceLine { label: "Limit".to_string(), ..ReferenceLine::new(1.5) });
+        let tikz = app.export_tikz().unwrap();
+        assert!(tikz.starts_with("% Needs \\usepackage{pgfplots}"));
+        assert!(tikz.contains("xmin=-4, xmax=4,\n  ymin=0, ymax=0.5"));
+        assert!(tikz.contains("ylabel={$f(x)$}"));
+        assert!(tikz.trim_end().ends_with("\\end{axis}\n\\end{tikzpicture}"));
+        // Shading, then the curve in the theme's color with its legend entry
+        assert!(tikz.contains("\\addplot[draw=none, fill=MyBlue, fill opacity=0.30, forget plot] coordinates {(-4,"));
+        assert!(tikz.contains("\\closedcycle;"));
+        assert!(tikz.contains("\\addplot[color=MyBlue, line width="));
+        assert!(tikz.contains("(0,0.398942)"));
+        assert!(tikz.contains("\\addlegendentry{Prior\\_1}"));
+        // σ markers and the reference line span the axis
+        assert!(tikz.contains("\\draw[color=MyBlue, dashed, line width=1pt] ({axis cs:0,1}|-{rel axis cs:0,0})"));
+        assert!(tikz.contains("node[below right] {Limit = 1.5}"));
+        assert_eq!(tikz.matches("\\addplot").count(), 2);
+
+        // Hidden curves are left out, and the log axis is pgfplots' own
+        app.set_log_y(true);
+        let tikz = app.export_tikz().unwrap();
+        assert!(tikz.contains("ymode=log"));
+        assert!(!tikz.contains("(-4,0)"));
+        app.distributions.get_mut(&0).unwrap().hidden = true;
+        assert_eq!(app.export_tikz().unwrap().matches("\\addplot").count(), 0);
+
+        app.plot_mode = PlotMode::QuantileQuantile;
+        assert!(app.export_tikz().is_err());
+    }
+
+    #[test]
+    fn test_session_files() {
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_session_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("session.json");
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(3, GaussianDistribution::new(3, "Saved".to_string(), 1.5, 0.5));
+        app.next_id = 4;
+        app.save_session_to(&path).unwrap();
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session_from(&path).unwrap();
+        assert_eq!(loaded.distributions[&3].name, "Saved");
+        assert_eq!(loaded.next_id, 4);
+
+        // A file that is not a session leaves the current one alone
+        std::fs::write(&path, "not json").unwrap();
+        assert!(loaded.load_session_from(&path).unwrap_err().starts_with("Failed to parse session"));
+        assert_eq!(loaded.distributions.len(), 1);
+        assert!(loaded.load_session_from(&directory.join("missing.json")).unwrap_err().starts_with("Cannot read"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_autosave_and_recovery() {
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_autosave_{}", std::process::id()));
+        let start = std::time::Instant::now();
+        let later = |seconds: u64| start + std::time::Duration::from_secs(seconds);
+
+        // Nothing to recover on a first launch; the first save waits an interval
+        let mut app = PdfViewerApp::new();
+        assert!(!app.run_autosave(start));
+        app.enable_autosave(&directory, start);
+        assert!(app.autosave.recovery.is_none());
+        let path = app.autosave.path.clone().unwrap();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Tweaked".to_string(), 0.25, 1.0));
+        assert!(!app.run_autosave(later(10)));
+        assert!(app.run_autosave(later(AUTOSAVE_SECONDS)));
+        // An unchanged session is not written again
+        assert!(!app.run_autosave(later(2 * AUTOSAVE_SECONDS)));
+        app.distributions.get_mut(&0).unwrap().mean = 0.5;
+        assert!(app.run_autosave(later(3 * AUTOSAVE_SECONDS)));
+
+        // A second viewer running at the same time gets a slot of its own
+        let mut other = PdfViewerApp::new();
+        other.enable_autosave(&directory, start);
+        assert!(other.autosave.recovery.is_none());
+        assert_ne!(other.autosave.path.as_ref(), Some(&path));
+        drop(other);
+
+        // A "crash" leaves the file; the next launch offers it and pauses autosave
+        drop(app);
+        let mut next = PdfViewerApp::new();
+        next.enable_autosave(&directory, start);
+        assert_eq!(next.autosave.path.as_ref(), Some(&path));
+        assert!(next.autosave.recovery.is_some());
+        assert!(!next.run_autosave(later(AUTOSAVE_SECONDS)));
+        next.resolve_recovery(true).unwrap();
+        assert_eq!(next.distributions[&0].mean, 0.5);
+        assert!(next.run_autosave(later(AUTOSAVE_SECONDS)));
+        drop(next);
+
+        // A session that fails to load stays on offer, with autosave still paused
+        std::fs::write(&path, "{ not a session").unwrap();
+        let mut broken = PdfViewerApp::new();
+        broken.enable_autosave(&directory, start);
+        assert!(broken.resolve_recovery(true).is_err());
+        assert!(broken.autosave.recovery.is_some());
+        assert!(!broken.run_autosave(later(AUTOSAVE_SECONDS)));
+        drop(broken);
+
+        // Discarding removes the file, so nothing is offered after that
+        let mut third = PdfViewerApp::new();
+        third.enable_autosave(&directory, start);
+        third.resolve_recovery(false).unwrap();
+        assert!(third.distributions.is_empty());
+        assert!(!path.exists());
+        drop(third);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_session_versions() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+        let json = app.save_session().unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(saved["version"], session::VERSION);
+
+        // Unversioned files from before derived operations existed
+        let mut legacy = saved.clone();
+        legacy.as_object_mut().unwrap().remove("version");
+        legacy["distributions"]["2"].as_object_mut().unwrap().remove("operation");
+        let migrated = session::migrate(legacy.clone()).unwrap();
+        assert_eq!(migrated["version"], session::VERSION);
+        assert_eq!(migrated["distributions"]["2"]["operation"], "Product");
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(legacy.to_string()).unwrap();
+        assert_eq!(loaded.distributions[&2].operation, Operation::Product);
+        assert_eq!(loaded.distributions[&2].mean, app.distributions[&2].mean);
+
+        // Files from a newer viewer are refused rather than half understood
+        let mut newer = saved.clone();
+        newer["version"] = (session::VERSION + 1).into();
+        let error = loaded.load_session(newer.to_string()).unwrap_err();
+        assert!(error.contains("newer viewer"), "{}", error);
+        assert_eq!(loaded.distributions.len(), 3);
+        let mut broken = saved.clone();
+        broken["version"] = "one".into();
+        assert!(loaded.load_session(broken.to_string()).unwrap_err().starts_with("Failed to parse session"));
+        assert!(loaded.load_session("[1, 2]").unwrap_err().starts_with("Failed to parse session"));
+
+        // Definitions that would fail to derive or draw are refused on load
+        let mut one_parent = saved.clone();
+        one_parent["distributions"]["2"]["operation"] = "Quotient".into();
+        one_parent["distributions"]["2"]["parent_ids"] = serde_json::json!([0]);
+        let error = loaded.load_session(one_parent.to_string()).unwrap_err();
+        assert!(error.contains("distribution 2"), "{}", error);
+        let mut orphan = saved;
+        orphan["distributions"]["2"]["parent_ids"] = serde_json::json!([99]);
+        orphan["distributions"]["2"]["std_dev"] = 0.0.into();
+        assert!(loaded.load_session(orphan.to_string()).unwrap_err().starts_with("Failed to parse session"));
+        assert_eq!(loaded.distributions.len(), 3);
+        loaded.update_product_distributions();
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut app = PdfViewerApp::new();
+        assert!(!app.undo());
+        app.record_history();
+        app.distributions.insert(0, GaussianDistribution::new(0, "A".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B".to_string(), 2.0, 1.0));
+        app.next_id = 2;
+        assert!(app.record_history());
+        assert!(!app.record_history());
+
+        // Parameter edit, then a multiplication
+        app.distributions.get_mut(&0).unwrap().mean = 1.0;
+        app.update_product_distributions();
+        assert!(app.record_history());
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+        let product_mean = app.distributions[&product].mean;
+        assert!(app.record_history());
+
+        assert!(app.undo());
+        assert!(!app.distributions.contains_key(&product));
+        assert_eq!(app.next_id, 2);
+        assert!(app.undo());
+        assert_eq!(app.distributions[&0].mean, 0.0);
+        assert!(app.redo());
+        assert!(app.redo());
+        assert_eq!(app.distributions[&0].mean, 1.0);
+        assert_eq!(app.distributions[&product].mean, product_mean);
+        assert!(!app.redo());
+
+        // A delete is undone too, and an unrecorded edit is taken in before undoing it
+        app.distributions.remove(&1);
+        assert!(app.undo());
+        assert!(app.distributions.contains_key(&1));
+        assert!(app.redo());
+        assert!(!app.distributions.contains_key(&1));
+
+        // A new edit after an undo drops what could be redone
+        assert!(app.undo());
+        app.distributions.get_mut(&0).unwrap().std_dev = 3.0;
+        assert!(app.record_history());
+        assert!(!app.redo());
+        assert!(app.history.undo.len() <= MAX_UNDO_STEPS);
+    }
+
+    #[test]
+    fn test_workspace_tabs() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Lecture 1".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.selected_for_multiplication = vec![0];
+        app.log_y = true;
+        app.record_history();
+
+        app.add_workspace().unwrap();
+        assert_eq!(app.workspaces.active, 1);
+        assert_eq!(app.workspaces.tabs[1].name, "Workspace 2");
+        assert!(app.distributions.is_empty());
+        assert!(app.selected_for_multiplication.is_empty() && !app.log_y);
+        app.distributions.insert(0, GaussianDistribution::new(0, "Lecture 2".to_string(), 5.0, 2.0));
+        app.next_id = 1;
+
+        // Each tab keeps its own distributions, selection and view
+        app.switch_workspace(0).unwrap();
+        assert_eq!(app.distributions[&0].name, "Lecture 1");
+        assert_eq!(app.selected_for_multiplication, vec![0]);
+        assert!(app.log_y);
+        assert!(!app.undo());
+
+        // All tabs are saved together, and loading brings them all back
+        app.workspaces.tabs[1].name = "Bayes".to_string();
+        let json = app.save_session().unwrap();
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&json).unwrap();
+        assert_eq!(loaded.workspaces.tabs.len(), 2);
+        assert_eq!(loaded.workspaces.active, 0);
+        assert_eq!(loaded.distributions[&0].name, "Lecture 1");
+        loaded.switch_workspace(1).unwrap();
+        assert_eq!(loaded.distributions[&0].name, "Lecture 2");
+        assert_eq!(loaded.distributions[&0].mean, 5.0);
+
+        // Closing the active tab shows a neighbour; the last tab stays
+        loaded.close_workspace(1).unwrap();
+        assert_eq!(loaded.workspaces.tabs.len(), 1);
+        assert_eq!(loaded.distributions[&0].name, "Lecture 1");
+        loaded.close_workspace(0).unwrap();
+        assert_eq!(loaded.workspaces.tabs.len(), 1);
+
+        // Files from before tabs open as a single workspace
+        let mut single = PdfViewerApp::new();
+        single.load_session(app.save_workspace().unwrap()).unwrap();
+        assert_eq!(single.workspaces.tabs.len(), 1);
+        assert_eq!(single.workspaces.tabs[0].name, "Workspace 1");
+    }
+
+    #[test]
+    fn test_python_export() {
+        assert_eq!(python_number(0.5), "0.5");
+        assert_eq!(python_number(-2.0), "-2.0");
+        assert_eq!(python_number(1e-7), "1e-7");
+        assert_eq!(python_number(f64::NEG_INFINITY), "-np.inf");
+        assert_eq!(quoted("Prior \"A\"\\n"), "\"Prior \\\"A\\\"\\\\n\"");
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Likelihood".to_string(), 2.0, 0.5));
+        app.next_id = 2;
+        let product = app.add_derived(Operation::Product, vec![0, 1]).unwrap();
+        let conditional = app.add_derived(Operation::Truncate { lower: -1.0, upper: 1.0 }, vec![0]).unwrap();
+        let quotient = app.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+        app.update_product_distributions();
+        app.distributions.get_mut(&1).unwrap().hidden = true;
+        app.distributions.get_mut(&conditional).unwrap().name = "Prior".to_string();
+        app.plot_bounds = Some(view_bounds([-4.0, 4.0, 0.0, 0.8]));
+        let script = app.export_python().unwrap();
+        assert!(script.starts_with("# Distributions from PDF Viewer"));
+        assert!(script.contains("from scipy import stats"));
+        assert!(script.contains("pdf[\"Prior\"] = stats.norm(loc=0.0, scale=1.0).pdf(x)"));
+        // Derived distributions follow their parents in the script
+        assert!(script.contains(&format!("pdf[\"Product {}\"] = normalize(pdf[\"Prior\"] * pdf[\"Likelihood\"])", product + 1)));
+        assert!(script.contains(&format!("pdf[\"Prior ({})\"] = normalize(pdf[\"Prior\"] * ((x >= -1.0) & (x <= 1.0)))", conditional)));
+        assert!(script.contains(&format!("pdf[\"Quotient {}\"] = tabulated(", quotient + 1)));
+        // Visible curves only, in the view of the plot
+        assert_eq!(script.matches("ax.plot(").count(), 4);
+        assert!(!script.contains("label=\"Likelihood\""));
+        assert!(script.contains("ax.set_xlim(-4.0, 4.0)\nax.set_ylim(0.0, 0.8)"));
+        assert!(script.contains("linestyle=\"-\", linewidth=1.5, label=\"Prior\")\nax.fill_between(x, f, color=\"#"));
+        assert!(script.contains(", alpha=0.3)\n"));
+        assert!(script.trim_end().ends_with("plt.show()"));
+
+        app.plot_mode = PlotMode::Cdf;
+        assert!(app.export_python().unwrap().contains("ax.plot(x, cdf(f), "));
+        app.plot_mode = PlotMode::QuantileQuantile;
+        assert!(app.export_python().is_err());
+    }
+
+    #[test]
+    fn test_r_export() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior".to_string(), 0.0, 1.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Likelihood".to_string(), 2.0, 0.5));
+        app.next_id = 2;
+        let posterior = app.add_derived(Operation::BayesUpdate, vec![0, 1]).unwrap();
+        let tempered = app.add_derived(Operation::Temper { beta: 0.5 }, vec![0]).unwrap();
+        app.update_product_distributions();
+        app.distributions.get_mut(&1).unwrap().line_style = StrokeStyle::Dashed;
+        app.plot_bounds = Some(view_bounds([-4.0, 4.0, 0.0, 0.8]));
+        let script = app.export_r().unwrap();
+        assert!(script.starts_with("# Distributions from PDF Viewer"));
+        assert!(script.contains("library(ggplot2)"));
+        assert!(script.contains(", length.out = 4001)\npdf <- list()\n"));
+        assert!(script.contains("pdf[[\"Prior\"]] <- dnorm(x, mean = 0.0, sd = 1.0)"));
+        assert!(script.contains(&format!("pdf[[\"Posterior {}\"]] <- normalize(pdf[[\"Prior\"]] * pdf[[\"Likelihood\"]])", posterior + 1)));
+        assert!(script.contains(&format!("pdf[[\"Tempered {}\"]] <- normalize(pdf[[\"Prior\"]] ^ 0.5)", tempered + 1)));
+        // One data frame row block per visible curve, with its style in the manual scales
+        assert_eq!(script.matches("  data.frame(x = x, y = view(").count(), 4);
+        assert!(script.contains("width = 1.5 / .pt)"));
+        assert!(script.contains("scale_linetype_manual(values = c(\"Prior\" = \"solid\", \"Likelihood\" = \"dashed\""));
+        assert!(script.contains("geom_area(aes(fill = curve), colour = NA, alpha = 0.3"));
+        assert!(script.contains("coord_cartesian(xlim = c(-4.0, 4.0), ylim = c(0.0, 0.8))"));
+        assert!(script.trim_end().ends_with("print(plot)"));
+
+        app.set_log_y(true);
+        assert!(app.export_r().unwrap().contains("scale_y_log10()"));
+        assert_eq!(ScriptLanguage::R.number(f64::INFINITY), "Inf");
+        for dist in app.distributions.values_mut() {
+            dist.hidden = true;
+        }
+        assert!(app.export_r().is_err());
+        app.plot_mode = PlotMode::QuantileQuantile;
+        assert!(app.export_r().is_err());
+    }
+
+    #[test]
+    fn test_binary_sessions() {
+        let mut app = PdfViewerApp::new();
+        let samples: Vec<f64> = (0..2000).map(|i| (f64::from(i) * 0.618).sin() * 3.0 + f64::from(i) / 997.0).collect();
+        let kde = GaussianDistribution::new_derived(0, "Data".to_string(), Operation::Kde { samples: samples.clone(), bandwidth: None }, Vec::new(), &[]);
+        app.distributions.insert(0, kde);
+        app.distributions.insert(1, GaussianDistribution::new(1, "Fit".to_string(), 0.25, 1.5));
+        app.next_id = 2;
+        app.update_product_distributions();
+
+        let binary = app.save_session_binary().unwrap();
+        let json = app.save_session().unwrap();
+        assert!(binary.starts_with(session::BINARY_MAGIC));
+        assert!(binary.len() * 2 < json.len(), "{} vs {} bytes", binary.len(), json.len());
+
+        // Loading tells the formats apart by their first bytes
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(&binary).unwrap();
+        assert!(matches!(&loaded.distributions[&0].operation, Operation::Kde { samples: loaded, .. } if *loaded == samples));
+        assert_eq!(loaded.distributions[&1].mean, 0.25);
+        assert_eq!(loaded.distributions[&0].mean, app.distributions[&0].mean);
+
+        // The extension picks the format on save
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_binary_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(format!("session.{}", session::BINARY_EXTENSION));
+        app.save_session_to(&path).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(session::BINARY_MAGIC));
+        app.save_session_to(&directory.join("session.json")).unwrap();
+        assert!(std::fs::read(directory.join("session.json")).unwrap().starts_with(b"{"));
+        loaded.load_session_from(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // A damaged file leaves the session alone
+        let error = loaded.load_session(&binary[..binary.len() / 2]).unwrap_err();
+        assert!(error.starts_with("Failed to parse session"), "{}", error);
+        assert_eq!(loaded.distributions.len(), 2);
+    }
+
+    #[test]
+    fn test_model_spec_import() {
+        let stan = "data {\n  int N;\n}\nmodel {\n  mu ~ normal(0, 10);  // location\n  sigma ~ exponential(2);\n  \
+                    tau ~ gamma(2, 0.5);\n  p ~ beta(2, 2);\n  y ~ lognormal(1 / 2, sqrt(0.25));\n}\n";
+        let declarations = spec::parse(stan).unwrap();
+        let summary: Vec<(&str, FitFamily, [f64; 2], usize)> = declarations.iter()
+            .map(|d| (d.name.as_str(), d.family, d.parameters, d.line))
+            .collect();
+        assert_eq!(summary, vec![
+            ("mu", FitFamily::Normal, [0.0, 10.0], 5),
+            ("sigma", FitFamily::Gamma, [1.0, 0.5], 6),
+            ("tau", FitFamily::Gamma, [2.0, 2.0], 7),
+            ("p", FitFamily::Beta, [2.0, 2.0], 8),
+            ("y", FitFamily::LogNormal, [0.5, 0.5], 9),
+        ]);
+        // YAML-style lists and comments
+        let yaml = "priors:\n  - theta ~ Normal(1.5, 0.5)  # prior\n";
+        assert_eq!(spec::parse(yaml).unwrap()[0].parameters, [1.5, 0.5]);
+
+        let error = |text: &str| spec::parse(text).unwrap_err();
+        assert!(error("a ~ normal(0, 1)\nb ~ cauchy(0, 1)").starts_with("Line 2: Unknown family 'cauchy'"));
+        assert!(error("y ~ normal(mu, 1)").contains("depends on 'mu'"));
+        assert!(error("s ~ normal(0, -1)").contains("must be positive"));
+        assert!(error("s ~ gamma(2)").contains("takes 2 argument(s)"));
+        assert!(error("s ~ normal(0, 1) T[0, ]").contains("Truncation"));
+        assert!(error("real mu;").starts_with("No `name ~ Family(...)` lines"));
+
+        let mut app = PdfViewerApp::new();
+        let ids = app.import_model_spec(stan).unwrap();
+        assert_eq!(ids.len(), 5);
+        let names: Vec<&str> = ids.iter().map(|id| app.distributions[id].name.as_str()).collect();
+        assert_eq!(names, ["mu", "sigma", "tau", "p", "y"]);
+        assert_eq!((app.distributions[&ids[0]].mean, app.distributions[&ids[0]].std_dev), (0.0, 10.0));
+        // Exponential(2) has mean 1/2; Gamma(2, rate 0.5) has mean 4
+        assert_abs_diff_eq!(app.distributions[&ids[1]].mean, 0.5, epsilon = 1e-2);
+        assert_abs_diff_eq!(app.distributions[&ids[2]].mean, 4.0, epsilon = 1e-2);
+        // The log-normal draws from a hidden normal in log units
+        let y = &app.distributions[&ids[4]];
+        assert_eq!(y.operation, Operation::Transform(Transform::Exp));
+        assert!(app.distributions[&y.parent_ids[0]].hidden);
+        assert_abs_diff_eq!(y.mean, (0.5f64 + 0.125).exp(), epsilon = 1e-2);
+
+        // A bad line adds nothing
+        let count = app.distributions.len();
+        assert!(app.import_model_spec("a ~ normal(0, 1)\nb ~ normal(0, 0)").is_err());
+        assert_eq!(app.distributions.len(), count);
+    }
+
+    #[test]
+    fn test_report_export() {
+        assert_eq!(html_escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+        assert!(is_markdown_path(" notes.MD ") && !is_markdown_path("report.html"));
+
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Prior <A>".to_string(), 1.0, 2.0));
+        app.distributions.insert(1, GaussianDistribution::new(1, "B|C".to_string(), 0.0, 1.0));
+        app.next_id = 2;
+        app.png_export.width = 320;
+        app.png_export.height = 200;
+        let html = app.export_report(false, egui::Style::default()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        // The plot is embedded as a PNG
+        assert!(html.contains("<img alt=\"Plot\" src=\"data:image/png;base64,iVBORw0KGgo"));
+        assert!(html.contains("<th>95% interval</th>"));
+        assert!(html.contains("<td>Prior &lt;A&gt;</td><td>N(1.0000, 2.0000²)</td><td>1.0000</td><td>2.0000</td>"));
+        assert!(html.contains(&format!("<td>{:.4}</td>", 0.5 * (2.0 * std::f64::consts::PI * std::f64::consts::E * 4.0).ln())));
+        assert!(html.contains("<td>[-2.9199, 4.9199]</td>"));
+        // The embedded session loads back
+        let start = html.find("<pre id=\"session\">").unwrap() + "<pre id=\"session\">".len();
+        let end = html[start..].find("</pre>").unwrap() + start;
+        let session = html[start..end].replace("&quot;", "\"").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&");
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(session).unwrap();
+        assert_eq!(loaded.distributions[&0].name, "Prior <A>");
+
+        let markdown = app.export_report(true, egui::Style::default()).unwrap();
+        assert!(markdown.starts_with("# PDF Viewer report\n\n![Plot](data:image/png;base64,"));
+        assert!(markdown.contains("| Name | Definition | Mean |"));
+        assert!(markdown.contains("| --- | --- |"));
+        assert!(markdown.contains("| B\\|C | N(0.0000, 1.0000²) |"));
+        assert!(markdown.contains("```json\n{"));
+    }
+
+    #[test]
+    fn test_share_string() {
+        let mut app = PdfViewerApp::new();
+        let samples: Vec<f64> = (0..500).map(|i| f64::from(i) * 0.01).collect();
+        let kde = GaussianDistribution::new_derived(0, "Shared".to_string(), Operation::Kde { samples, bandwidth: None }, Vec::new(), &[]);
+        app.distributions.insert(0, kde);
+        app.next_id = 1;
+        app.update_product_distributions();
+        app.add_workspace().unwrap();
+        let text = app.share_string().unwrap();
+        assert!(text.starts_with(session::SHARE_PREFIX));
+        assert!(!text.contains(char::is_whitespace));
+        // Compressed well below the JSON
+        assert!(text.len() < app.save_session().unwrap().len() / 2);
+
+        // Found inside a chat message
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_share_string(&format!("Here is my prior: {} — thoughts?", text)).unwrap();
+        assert_eq!(loaded.workspaces.tabs.len(), 2);
+        loaded.switch_workspace(0).unwrap();
+        assert_eq!(loaded.distributions[&0].name, "Shared");
+        assert!(matches!(&loaded.distributions[&0].operation, Operation::Kde { samples, .. } if samples.len() == 500));
+
+        // Damaged strings leave the session alone
+        assert!(loaded.load_share_string("no string here").unwrap_err().contains(session::SHARE_PREFIX));
+        let cut = &text[..text.len() / 2];
+        assert!(loaded.load_share_string(cut).unwrap_err().contains("damaged"));
+        assert!(loaded.load_share_string(&format!("{}AAAA", session::SHARE_PREFIX)).is_err());
+        assert_eq!(loaded.distributions[&0].name, "Shared");
+    }
+
+    #[test]
+    fn test_presets_and_templates() {
+        for preset in &PRESETS {
+            let mut app = PdfViewerApp::new();
+            app.load_session(preset.session).unwrap_or_else(|e| panic!("{}: {}", preset.title, e));
+            assert!(!app.distributions.is_empty(), "{}", preset.title);
+            assert!(app.distributions.values().all(|dist| dist.warning.is_none()), "{}", preset.title);
+            assert!(app.plot_bounds.is_some(), "{}", preset.title);
+        }
+        let mut app = PdfViewerApp::new();
+        app.load_session(PRESETS[2].session).unwrap();
+        let posterior = app.distributions.values().find(|dist| dist.name == "Posterior").unwrap();
+        // N(0, 1) prior with N(2.5, 0.75²) likelihood
+        assert_abs_diff_eq!(posterior.mean, 2.5 / (1.0 + 0.5625), epsilon = 1e-3);
+        app.load_session(PRESETS[3].session).unwrap();
+        let mean_of_30 = app.distributions.values().find(|dist| dist.name == "Mean of 30").unwrap();
+        assert_abs_diff_eq!(mean_of_30.std_dev, 1.0 / 30f64.sqrt(), epsilon = 1e-2);
+
+        let directory = std::env::temp_dir().join(format!("pdf_viewer_templates_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let mut app = PdfViewerApp::new();
+        app.enable_templates(directory.clone());
+        assert!(app.templates.names.is_empty());
+        app.distributions.insert(0, GaussianDistribution::new(0, "Mine".to_string(), 3.0, 0.5));
+        app.next_id = 1;
+        app.save_template("My prior").unwrap();
+        app.save_template("another").unwrap();
+        assert_eq!(app.templates.names, ["another", "My prior"]);
+        assert!(app.save_template("../escape").is_err());
+        assert!(app.save_template("  ").is_err());
+
+        // Templates open in the active workspace and survive a restart
+        let mut restarted = PdfViewerApp::new();
+        restarted.enable_templates(directory.clone());
+        assert_eq!(restarted.templates.names.len(), 2);
+        restarted.load_template("My prior").unwrap();
+        assert_eq!(restarted.distributions[&0].name, "Mine");
+        assert_eq!(restarted.distributions[&0].mean, 3.0);
+
+        restarted.delete_template("another").unwrap();
+        assert_eq!(restarted.templates.names, ["My prior"]);
+        assert!(restarted.load_template("another").is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_frozen_grid_survives_save_and_load() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 4.0, 0.4));
+        app.distributions.insert(1, GaussianDistribution::new(1, "Y".to_string(), 2.0, 0.3));
+        app.next_id = 2;
+        let quotient = app.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+        app.distributions.remove(&1);
+        app.update_product_distributions();
+        let frozen = app.distributions[&quotient].clone();
+        assert!(frozen.grid.is_some());
+
+        let mut loaded = PdfViewerApp::new();
+        loaded.load_session(app.save_session().unwrap()).unwrap();
+        let restored = &loaded.distributions[&quotient];
+        assert!(restored.grid.is_some(), "the frozen quotient must not fall back to a Gaussian");
+        for x in [1.0, 1.5, 2.0, 3.0, 4.0] {
+            assert_abs_diff_eq!(restored.evaluate(x), frozen.evaluate(x), epsilon = 1e-12);
+        }
+        assert_abs_diff_eq!(restored.cdf(2.0), frozen.cdf(2.0), epsilon = 1e-12);
+
+        // Only frozen grids are written; live ones are rebuilt from the parents
+        let mut live = PdfViewerApp::new();
+        live.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 4.0, 0.4));
+        live.distributions.insert(1, GaussianDistribution::new(1, "Y".to_string(), 2.0, 0.3));
+        live.next_id = 2;
+        live.add_derived(Operation::Quotient, vec![0, 1]).unwrap();
+        assert!(!live.save_session().unwrap().contains("frozen_grid"));
+        assert!(serde_json::from_str::<DensityGrid>(r#"{"x_min":0,"x_max":1,"densities":[1]}"#).is_err());
+    }
+
+    #[test]
+    fn test_divergences_of_clipped_distributions() {
+        // N(0, 1) clipped to [0, ∞) keeps half its mass; renormalized it is the
+        // half-normal, which is what the metrics must compare
+        let mut half = GaussianDistribution::new(0, "Half".to_string(), 0.0, 1.0);
+        half.domain = Some((0.0, 50.0));
+        let same = half.clone();
+        assert_abs_diff_eq!(divergence::kl_divergence(&half, &same), 0.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(divergence::hellinger_distance(&half, &same), 0.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(divergence::total_variation_distance(&half, &same), 0.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(divergence::bhattacharyya_coefficient(&half, &same), 1.0, epsilon = 1e-6);
+
+        // Heavily clipped: the tail beyond 2.5 holds 0.6% of N(0, 1). Against the
+        // same tail of N(0, 2) the metrics follow the conditional densities
+        let mut tail = GaussianDistribution::new(2, "Tail".to_string(), 0.0, 1.0);
+        tail.domain = Some((2.5, 30.0));
+        let mut wide_tail = GaussianDistribution::new(3, "Wide tail".to_string(), 0.0, 2.0);
+        wide_tail.domain = Some((2.5, 30.0));
+        let conditional = |sigma: f64, x: f64| {
+            let normal = Normal::new(0.0, sigma).unwrap();
+            if x < 2.5 { 0.0 } else { normal.pdf(x) / (1.0 - normal.cdf(2.5)) }
+        };
+        let (step, mut kl, mut tv, mut bc) = (1e-4, 0.0, 0.0, 0.0);
+        for i in 0..275_000 {
+            let x = 2.5 + (i as f64 + 0.5) * step;
+            let (p, q) = (conditional(1.0, x), conditional(2.0, x));
+            if p > 0.0 {
+                kl += p * (p / q).ln() * step;
+            }
+            tv += 0.5 * (p - q).abs() * step;
+            bc += (p * q).sqrt() * step;
+        }
+        assert_abs_diff_eq!(divergence::kl_divergence(&tail, &wide_tail), kl, epsilon = 1e-4);
+        assert_abs_diff_eq!(divergence::total_variation_distance(&tail, &wide_tail), tv, epsilon = 1e-4);
+        assert_abs_diff_eq!(divergence::bhattacharyya_coefficient(&tail, &wide_tail), bc, epsilon = 1e-4);
+        assert_abs_diff_eq!(divergence::hellinger_distance(&tail, &wide_tail), (1.0 - bc).sqrt(), epsilon = 1e-3);
+        // Without renormalizing, TV of two 0.6%-mass curves would sit near 0
+        assert!(divergence::total_variation_distance(&tail, &wide_tail) > 0.2);
+        assert!(divergence::wasserstein_distance(&tail, &wide_tail) > 0.5);
+    }
+
+    #[test]
+    fn test_density_fingerprint_covers_every_grid_sample() {
+        let x = GaussianDistribution::new(0, "X".to_string(), 4.0, 0.4);
+        let y = GaussianDistribution::new(1, "Y".to_string(), 2.0, 0.3);
+        let quotient = GaussianDistribution::new_derived(2, "Q".to_string(), Operation::Quotient, vec![0, 1], &[&x, &y]);
+        let before = quotient.density_fingerprint();
+        for index in [1, 17, 255, 510] {
+            let mut changed = quotient.clone();
+            changed.grid.as_mut().unwrap().densities[index] *= 1.5;
+            assert_ne!(changed.density_fingerprint(), before, "sample {}", index);
+        }
+    }
+
+    #[test]
+    fn test_conditional_without_mass_draws_nothing() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        let conditional = app.add_derived(Operation::Truncate { lower: 50.0, upper: 60.0 }, vec![0]).unwrap();
+        let dist = &app.distributions[&conditional];
+        assert!(dist.warning.as_deref().is_some_and(|warning| warning.contains("no probability mass")));
+        for x in [-1.0, 0.0, 1.0, 50.0, 55.0, 60.0] {
+            assert_eq!(dist.evaluate(x), 0.0);
+        }
+        assert_eq!(dist.peak_density(), 0.0);
+    }
+
+    #[test]
+    fn test_sync_client_joins_empty_host() {
+        fn wait_for(mut condition: impl FnMut() -> bool) {
+            let start = std::time::Instant::now();
+            while !condition() {
+                assert!(start.elapsed() < std::time::Duration::from_secs(5), "sync timed out");
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+
+        let mut host = PdfViewerApp::new();
+        let mut host_sync = sync::SyncSession::host(sync::DEFAULT_INTERFACE, 0).unwrap();
+        assert!(host_sync.address().starts_with("127.0.0.1:"));
+        let port = host_sync.address().rsplit(':').next().unwrap().to_string();
+        let mut client = PdfViewerApp::new();
+        let mut client_sync = sync::SyncSession::connect(&format!("127.0.0.1:{}", port)).unwrap();
+
+        // The host has nothing to send, yet the client's edits still get through
+        client.distributions.insert(0, GaussianDistribution::new(0, "Client".to_string(), 1.0, 2.0));
+        client.next_id = 1;
+        wait_for(|| {
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            client_sync.poll(&mut client.distributions, &mut client.next_id);
+            client_sync.publish(&client.distributions);
+            host.distributions.contains_key(&0)
+        });
+        assert_eq!(host.distributions[&0].mean, 1.0);
+
+        // Definitions too costly to derive are dropped, later edits still arrive
+        client.distributions.insert(1, GaussianDistribution {
+            id: 1,
+            name: "Sum".to_string(),
+            parent_ids: vec![0],
+            operation: Operation::IidSum { copies: u32::MAX, average: false },
+            ..Default::default()
+        });
+        client_sync.publish(&client.distributions);
+        client.distributions.get_mut(&0).unwrap().mean = 3.0;
+        client_sync.publish(&client.distributions);
+        wait_for(|| {
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            host.distributions[&0].mean == 3.0
+        });
+        assert!(!host.distributions.contains_key(&1));
+
+        // Malformed definitions are dropped before they can crash the receiver:
+        // a quotient of one parent, and a frozen node with no usable parameters
+        client.distributions.insert(2, GaussianDistribution {
+            id: 2,
+            name: "Quotient".to_string(),
+            parent_ids: vec![0],
+            operation: Operation::Quotient,
+            ..Default::default()
+        });
+        client.distributions.insert(3, GaussianDistribution {
+            id: 3,
+            name: "Orphan".to_string(),
+            std_dev: 0.0,
+            parent_ids: vec![99],
+            operation: Operation::Temper { beta: 1.0 },
+            ..Default::default()
+        });
+        client_sync.publish(&client.distributions);
+        client.distributions.get_mut(&0).unwrap().mean = 4.0;
+        client_sync.publish(&client.distributions);
+        wait_for(|| {
+            host_sync.poll(&mut host.distributions, &mut host.next_id);
+            host.distributions[&0].mean == 4.0
+        });
+        host.update_product_distributions();
+        for dist in host.distributions.values() {
+            assert!(dist.evaluate(0.0).is_finite() && dist.cdf(0.0).is_finite());
+        }
+        assert!(!host.distributions.contains_key(&2) && !host.distributions.contains_key(&3));
+    }
+
+    #[test]
+    fn test_unchanged_derivations_are_not_recomputed() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "X".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        let clipped = app.add_derived(Operation::Truncate { lower: -1.0, upper: 2.0 }, vec![0]).unwrap();
+        let sum = app.add_derived(Operation::IidSum { copies: 3, average: false }, vec![clipped]).unwrap();
+        app.update_product_distributions();
+
+        // A marker left on the result survives frames in which nothing changed
+        app.distributions.get_mut(&sum).unwrap().warning = Some("cached".to_string());
+        app.update_product_distributions();
+        assert_eq!(app.distributions[&sum].warning.as_deref(), Some("cached"));
+
+        // Changing an ancestor or the operation derives it again
+        app.distributions.get_mut(&0).unwrap().mean = 0.5;
+        app.update_product_distributions();
+        assert!(app.distributions[&sum].warning.is_none());
+        let before = app.distributions[&sum].mean;
+        app.distributions.get_mut(&sum).unwrap().operation = Operation::IidSum { copies: 6, average: false };
+        app.update_product_distributions();
+        assert_abs_diff_eq!(app.distributions[&sum].mean, 2.0 * before, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_history_waits_for_sweeps_and_live_fits() {
+        let mut app = PdfViewerApp::new();
+        app.distributions.insert(0, GaussianDistribution::new(0, "Swept".to_string(), 0.0, 1.0));
+        app.next_id = 1;
+        app.record_history();
+
+        // Every frame of a sweep changes the mean, but none of them is a step
+        app.sweep.target = Some(0);
+        app.sweep.playing = true;
+        for _ in 0..10 {
+            app.advance_sweep(0.1);
+            assert!(!app.record_history());
+        }
+        assert!(app.history.undo.is_empty());
+        assert_ne!(app.distributions[&0].mean, 0.0);
+        // Its end state is one step, and undoing it goes back to before the sweep
+        app.sweep.playing = false;
+        assert!(app.record_history());
+        assert!(app.undo());
+        assert_eq!(app.distributions[&0].mean, 0.0);
+
+        // Values streaming into a running fit are not recorded either
+        let mut app = PdfViewerApp::new();
+        app.record_history();
+        app.live_stream.stream = Some(stream::DataStream::open(stream::StreamSource::Udp, stream::DEFAULT_INTERFACE, 0).unwrap());
+        for batch in 0..10 {
+            app.ingest_stream_values(vec![batch as f64, batch as f64 + 1.0, batch as f64 + 3.0]);
+            assert!(!app.record_history());
+        }
+        app.live_stream.stream = None;
+        assert!(app.record_history());
+        assert_eq!(app.history.undo.len(), 1);
+    }
+}
@@ -0,0 +1,193 @@
+//! Offscreen rendering of egui drawing to PNG.
+//!
+//! The drawing runs in a headless egui context whose screen is the requested
+//! image, so a plot comes out exactly as it is drawn on screen but at any
+//! size. egui tessellates everything into triangle meshes, antialiasing
+//! included, and those are filled here on the CPU and blended in gamma space
+//! into an 8-bit buffer like the glow painter does. Drawing needs the app and
+//! stays on the UI thread; filling is the slow part and can run elsewhere.
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use egui::epaint::{ClippedPrimitive, ImageData, Mesh, Primitive, TextureId, Vertex};
+use egui::Color32;
+
+/// Frames drawn before the image is taken; the first lays out fonts and the
+/// plot, later ones draw with settled bounds
+const PASSES: usize = 3;
+
+/// Largest width or height accepted, in pixels; 64 MiB of canvas at most
+pub const MAX_SIDE: u32 = 4096;
+
+/// A drawing tessellated into triangles, not yet filled in
+pub struct Scene {
+    width: usize,
+    height: usize,
+    pixels_per_point: f32,
+    meshes: Vec<(egui::Rect, Mesh)>,
+    textures: HashMap<TextureId, egui::ColorImage>,
+}
+
+impl Scene {
+    /// Fill the triangles into an image
+    pub fn rasterize(self) -> egui::ColorImage {
+        let mut canvas = Canvas { width: self.width, height: self.height, pixels: vec![Color32::TRANSPARENT; self.width * self.height] };
+        for (clip_rect, mesh) in &self.meshes {
+            canvas.fill_mesh(mesh, *clip_rect * self.pixels_per_point, self.pixels_per_point, self.textures.get(&mesh.texture_id));
+        }
+        egui::ColorImage { size: [self.width, self.height], pixels: canvas.pixels }
+    }
+}
+
+/// Draw with `draw` onto a `width` × `height` pixel scene at `scale` pixels
+/// per point; `Scene::rasterize` then fills it in
+pub fn render(width: u32, height: u32, scale: f32, style: egui::Style, mut draw: impl FnMut(&egui::Context)) -> Result<Scene, String> {
+    if width == 0 || height == 0 || width > MAX_SIDE || height > MAX_SIDE {
+        return Err(format!("The image must be between 1 and {} pixels on each side", MAX_SIDE));
+    }
+    if !(scale > 0.0 && scale.is_finite()) {
+        return Err("The scale must be positive".to_string());
+    }
+    let ctx = egui::Context::default();
+    ctx.set_style(style);
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32) / scale);
+    let mut textures: HashMap<TextureId, egui::ColorImage> = HashMap::new();
+    let mut output = None;
+    for _ in 0..PASSES {
+        let mut input = egui::RawInput { screen_rect: Some(screen), max_texture_side: Some(MAX_SIDE as usize), ..Default::default() };
+        input.viewports.entry(egui::ViewportId::ROOT).or_default().native_pixels_per_point = Some(scale);
+        let frame = ctx.run(input, &mut draw);
+        for (id, delta) in &frame.textures_delta.set {
+            let image = match &delta.image {
+                ImageData::Color(image) => image.as_ref().clone(),
+                ImageData::Font(font) => egui::ColorImage { size: font.size, pixels: font.srgba_pixels(None).collect() },
+            };
+            match delta.pos {
+                Some([x, y]) => {
+                    let Some(target) = textures.get_mut(id) else { continue };
+                    for row in 0..image.size[1] {
+                        let start = (y + row) * target.size[0] + x;
+                        target.pixels[start..start + image.size[0]]
+                            .copy_from_slice(&image.pixels[row * image.size[0]..(row + 1) * image.size[0]]);
+                    }
+                }
+                None => {
+                    textures.insert(*id, image);
+                }
+            }
+        }
+        for id in &frame.textures_delta.free {
+            textures.remove(id);
+        }
+        output = Some(frame);
+    }
+    let output = output.unwrap();
+    let meshes = ctx.tessellate(output.shapes, output.pixels_per_point)
+        .into_iter()
+        // Paint callbacks draw with the GPU and have no offscreen equivalent
+        .filter_map(|ClippedPrimitive { clip_rect, primitive }| match primitive {
+            Primitive::Mesh(mesh) => Some((clip_rect, mesh)),
+            Primitive::Callback(_) => None,
+        })
+        .collect();
+    Ok(Scene { width: width as usize, height: height as usize, pixels_per_point: output.pixels_per_point, meshes, textures })
+}
+
+/// The image as PNG bytes, 8-bit RGBA
+pub fn encode_png(image: &egui::ColorImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image.size[0] as u32, image.size[1] as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.to_srgba_unmultiplied()).collect();
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| format!("Cannot encode the PNG: {}", e))?;
+    Ok(bytes)
+}
+
+/// Premultiplied gamma-space RGBA, 8 bits per channel
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color32>,
+}
+
+impl Canvas {
+    fn fill_mesh(&mut self, mesh: &Mesh, clip: egui::Rect, scale: f32, texture: Option<&egui::ColorImage>) {
+        let clip = clip.intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(self.width as f32, self.height as f32)));
+        if !clip.is_positive() {
+            return;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            self.fill_triangle([a, b, c], scale, clip, texture);
+        }
+    }
+
+    fn fill_triangle(&mut self, vertices: [&Vertex; 3], scale: f32, clip: egui::Rect, texture: Option<&egui::ColorImage>) {
+        let [p0, p1, p2] = vertices.map(|vertex| vertex.pos.to_vec2() * scale);
+        let area = (p1 - p0).x * (p2 - p0).y - (p1 - p0).y * (p2 - p0).x;
+        if area.abs() < 1e-9 {
+            return;
+        }
+        // A pixel centre on an edge shared by two triangles belongs to just one
+        // of them, or it would be blended twice: the edges run opposite ways
+        let owns = |a: egui::Vec2, b: egui::Vec2| {
+            let direction = (b - a) * area.signum();
+            direction.y > 0.0 || (direction.y == 0.0 && direction.x < 0.0)
+        };
+        let edges = [(p1, p2), (p2, p0), (p0, p1)];
+        let min = p0.min(p1).min(p2).max(clip.min.to_vec2());
+        let max = p0.max(p1).max(p2).min(clip.max.to_vec2());
+        // Pixels whose centres fall inside, as on the GPU
+        for y in min.y.floor().max(0.0) as usize..(max.y.ceil() as usize).min(self.height) {
+            for x in min.x.floor().max(0.0) as usize..(max.x.ceil() as usize).min(self.width) {
+                let p = egui::vec2(x as f32 + 0.5, y as f32 + 0.5);
+                if !clip.contains(p.to_pos2()) {
+                    continue;
+                }
+                let edge = |a: egui::Vec2, b: egui::Vec2| ((b - a).x * (p - a).y - (b - a).y * (p - a).x) / area;
+                let weights = edges.map(|(a, b)| edge(a, b));
+                if weights.iter().zip(edges).any(|(&w, (a, b))| w < 0.0 || (w == 0.0 && !owns(a, b))) {
+                    continue;
+                }
+                let mut color = [0.0; 4];
+                let mut uv = egui::Vec2::ZERO;
+                for (vertex, weight) in vertices.iter().zip(weights) {
+                    for (channel, value) in color.iter_mut().zip(vertex.color.to_array()) {
+                        *channel += weight * value as f32 / 255.0;
+                    }
+                    uv += weight * vertex.uv.to_vec2();
+                }
+                if let Some(texture) = texture {
+                    let texel = sample(texture, uv);
+                    for (channel, value) in color.iter_mut().zip(texel) {
+                        *channel *= value;
+                    }
+                }
+                let pixel = &mut self.pixels[y * self.width + x];
+                let remaining = 1.0 - color[3];
+                let [r, g, b, a] = std::array::from_fn(|i| {
+                    let blended = color[i] + pixel.to_array()[i] as f32 / 255.0 * remaining;
+                    (blended.clamp(0.0, 1.0) * 255.0).round() as u8
+                });
+                *pixel = Color32::from_rgba_premultiplied(r, g, b, a);
+            }
+        }
+    }
+}
+
+/// Bilinear sample of a premultiplied texture at `uv` in 0..=1, as 0..=1 channels
+fn sample(texture: &egui::ColorImage, uv: egui::Vec2) -> [f32; 4] {
+    let [width, height] = texture.size;
+    let x = (uv.x * width as f32 - 0.5).clamp(0.0, (width - 1) as f32);
+    let y = (uv.y * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x.fract(), y.fract());
+    let texel = |x: usize, y: usize| texture.pixels[y * width + x].to_array().map(|value| value as f32 / 255.0);
+    let (a, b, c, d) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+    std::array::from_fn(|i| (a[i] * (1.0 - fx) + b[i] * fx) * (1.0 - fy) + (c[i] * (1.0 - fx) + d[i] * fx) * fy)
+}
@@ -0,0 +1,135 @@
+//! Random draws from the viewer's distributions, for Monte Carlo style tools.
+//!
+//! Samples come from inverting each distribution's CDF with a seeded RNG, so
+//! the same seed always reproduces the same draws.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use statrs::distribution::{ContinuousCDF, Normal};
+
+use crate::{Expr, GaussianDistribution, ParameterUncertainty};
+
+pub struct Sampler {
+    rng: StdRng,
+}
+
+impl Sampler {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: StdRng::seed_from_u64(seed) }
+    }
+
+    pub fn sample(&mut self, dist: &GaussianDistribution, count: usize) -> Vec<f64> {
+        // Open interval: the quantiles of 0 and 1 can be infinite
+        (0..count)
+            .map(|_| dist.quantile(self.rng.gen_range(f64::EPSILON..1.0)))
+            .collect()
+    }
+
+    /// One draw from N(0, 1)
+    pub fn standard_normal(&mut self) -> f64 {
+        Normal::new(0.0, 1.0).unwrap().inverse_cdf(self.rng.gen_range(f64::EPSILON..1.0))
+    }
+}
+
+/// Monte Carlo propagation: `count` values of `formula` with its variables
+/// drawn independently from `inputs`, in variable order. Returns the finite
+/// values and how many draws were undefined, e.g. ln of a negative number.
+pub fn propagate(formula: &Expr, inputs: &[&GaussianDistribution], count: usize, seed: u64) -> (Vec<f64>, usize) {
+    let mut sampler = Sampler::new(seed);
+    let columns: Vec<Vec<f64>> = inputs.iter().map(|dist| sampler.sample(dist, count)).collect();
+    let mut row = vec![0.0; inputs.len()];
+    let mut values = Vec::with_capacity(count);
+    for i in 0..count {
+        for (value, column) in row.iter_mut().zip(&columns) {
+            *value = column[i];
+        }
+        let value = formula.eval_at(f64::NAN, &row);
+        if value.is_finite() {
+            values.push(value);
+        }
+    }
+    let undefined = count - values.len();
+    (values, undefined)
+}
+
+/// Pointwise credible band [lower, upper] at each x of the density of a
+/// Gaussian whose mean and std dev are uncertain, from `draws` parameter draws
+pub fn gaussian_density_band(
+    dist: &GaussianDistribution,
+    uncertainty: &ParameterUncertainty,
+    xs: &[f64],
+    draws: usize,
+    seed: u64,
+) -> Vec<[f64; 2]> {
+    let mut sampler = Sampler::new(seed);
+    let parameters: Vec<(f64, f64)> = (0..draws.max(1))
+        .map(|_| {
+            let mu = dist.mean + uncertainty.mean_sd * sampler.standard_normal();
+            let sigma = dist.std_dev * (uncertainty.log_std_dev_sd * sampler.standard_normal()).exp();
+            (mu, sigma)
+        })
+        .collect();
+
+    let tail = (1.0 - uncertainty.level.clamp(0.0, 1.0)) / 2.0;
+    let last = parameters.len() - 1;
+    let (lower_rank, upper_rank) = ((tail * last as f64).round() as usize, ((1.0 - tail) * last as f64).round() as usize);
+    let mut densities = vec![0.0; parameters.len()];
+    xs.iter()
+        .map(|&x| {
+            for (density, &(mu, sigma)) in densities.iter_mut().zip(&parameters) {
+                let z = (x - mu) / sigma;
+                *density = (-0.5 * z * z).exp() / (sigma * (2.0 * std::f64::consts::PI).sqrt());
+            }
+            densities.sort_by(f64::total_cmp);
+            [densities[lower_rank], densities[upper_rank]]
+        })
+        .collect()
+}
+
+/// Self-normalized importance sampling of a target density p through draws
+/// from a proposal density q
+#[derive(Clone, Debug)]
+pub struct ImportanceReport {
+    pub samples: Vec<f64>,
+    /// w̃ᵢ = wᵢ / Σ w with wᵢ = p(xᵢ) / q(xᵢ); all zero when no draw has target mass
+    pub weights: Vec<f64>,
+    /// Kish effective sample size (Σ w)² / Σ w²
+    pub effective_sample_size: f64,
+    /// Σ w̃ᵢ xᵢ, the estimate of the target mean; NaN without usable weights
+    pub mean_estimate: f64,
+}
+
+impl ImportanceReport {
+    pub fn new(proposal: &GaussianDistribution, target: &GaussianDistribution, samples: Vec<f64>) -> Self {
+        let raw: Vec<f64> = samples.iter()
+            .map(|&x| {
+                let q = proposal.evaluate(x);
+                if q > 0.0 { target.evaluate(x) / q } else { 0.0 }
+            })
+            .collect();
+        let total: f64 = raw.iter().sum();
+        let squares: f64 = raw.iter().map(|w| w * w).sum();
+
+        if total <= 0.0 || !total.is_finite() {
+            return Self {
+                weights: vec![0.0; samples.len()],
+                samples,
+                effective_sample_size: 0.0,
+                mean_estimate: f64::NAN,
+            };
+        }
+        let weights: Vec<f64> = raw.iter().map(|w| w / total).collect();
+        let mean_estimate = weights.iter().zip(&samples).map(|(w, x)| w * x).sum();
+        Self {
+            samples,
+            weights,
+            effective_sample_size: total * total / squares,
+            mean_estimate,
+        }
+    }
+
+    /// Largest normalized weight; near 1 means a single draw dominates
+    pub fn max_weight(&self) -> f64 {
+        self.weights.iter().copied().fold(0.0, f64::max)
+    }
+}
@@ -0,0 +1,122 @@
+//! Versioning of the session file format.
+//!
+//! Every session records the format version it was written in. Loading runs
+//! the raw JSON through the migrations from that version up to the current
+//! one before it is parsed, so a field can be renamed, restructured or made
+//! mandatory without breaking older saves. Adding a field with a serde
+//! default needs no migration; anything else bumps `VERSION` and appends a
+//! step to `MIGRATIONS`.
+//!
+//! Sessions are JSON text, or the same tree as MessagePack after a magic
+//! prefix for sessions holding many numbers, e.g. the samples of a KDE. Both
+//! decode to one JSON value, so migrations need not care which was read.
+//!
+//! For sharing without files a session also travels as one line of text: the
+//! binary format, deflated and base64-encoded behind a short marker.
+
+use std::io::{Read, Write};
+
+use base64::Engine;
+use serde_json::{Map, Value};
+
+/// Format written by this build
+pub const VERSION: u32 = 1;
+
+/// First bytes of a binary session; JSON text never starts with them
+pub const BINARY_MAGIC: &[u8] = b"PDFVSESSION\0";
+
+/// File extension under which sessions are saved in the binary format
+pub const BINARY_EXTENSION: &str = "pdfsession";
+
+/// Marks the start of a share string, also inside a longer pasted message
+pub const SHARE_PREFIX: &str = "pdfv:";
+
+/// Largest session a share string may expand to
+const MAX_SHARE_BYTES: u64 = 64 << 20;
+
+/// `MIGRATIONS[n]` turns a version-n session into a version n + 1 one
+const MIGRATIONS: [fn(&mut Map<String, Value>); VERSION as usize] = [explicit_operations];
+
+/// The session tree in the binary format
+pub fn encode_binary(value: &Value) -> Result<Vec<u8>, String> {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    rmp_serde::encode::write_named(&mut bytes, value).map_err(|e| format!("Failed to serialize session: {}", e))?;
+    Ok(bytes)
+}
+
+/// The session tree as a share string, safe in URLs and chat messages
+pub fn encode_share(value: &Value) -> Result<String, String> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    let compressed = encoder.write_all(&encode_binary(value)?)
+        .and_then(|()| encoder.finish())
+        .map_err(|e| format!("Failed to compress session: {}", e))?;
+    Ok(format!("{}{}", SHARE_PREFIX, base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(compressed)))
+}
+
+/// Read the first share string in `text` and bring it up to the current format
+pub fn decode_share(text: &str) -> Result<Value, String> {
+    let start = text.find(SHARE_PREFIX)
+        .ok_or_else(|| format!("No share string found; they start with '{}'", SHARE_PREFIX))?;
+    let encoded: String = text[start + SHARE_PREFIX.len()..].chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    const DAMAGED: &str = "The share string is damaged or cut off";
+    let compressed = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(&encoded).map_err(|_| DAMAGED)?;
+    let mut bytes = Vec::new();
+    flate2::read::DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_SHARE_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|_| DAMAGED)?;
+    if bytes.len() as u64 > MAX_SHARE_BYTES {
+        return Err(format!("The shared session is larger than {} MB", MAX_SHARE_BYTES >> 20));
+    }
+    if !bytes.starts_with(BINARY_MAGIC) {
+        return Err(DAMAGED.to_string());
+    }
+    decode(&bytes)
+}
+
+/// Read a session in either format and bring it up to the current one
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let value = match bytes.strip_prefix(BINARY_MAGIC) {
+        Some(binary) => rmp_serde::from_slice(binary).map_err(|e| format!("Failed to parse session: {}", e))?,
+        None => serde_json::from_slice(bytes).map_err(|e| format!("Failed to parse session: {}", e))?,
+    };
+    migrate(value)
+}
+
+/// Bring a session up to the current format; files without a version
+/// predate versioning and count as version 0
+pub fn migrate(mut value: Value) -> Result<Value, String> {
+    let session = value.as_object_mut().ok_or("Failed to parse session: not a JSON object")?;
+    let version = match session.get("version") {
+        None => 0,
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or("Failed to parse session: the version is not a whole number")?,
+    };
+    if version > VERSION {
+        return Err(format!(
+            "The session was saved by a newer viewer (format {}); this one reads up to format {}",
+            version, VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(session);
+    }
+    session.insert("version".to_string(), VERSION.into());
+    Ok(value)
+}
+
+/// 0 → 1: the first derived distributions were marked only by `is_product`;
+/// spell out their operation so it no longer depends on a serde default
+fn explicit_operations(session: &mut Map<String, Value>) {
+    let Some(Value::Object(distributions)) = session.get_mut("distributions") else {
+        return;
+    };
+    for dist in distributions.values_mut().filter_map(Value::as_object_mut) {
+        if dist.get("is_product") == Some(&Value::Bool(true)) && !dist.contains_key("operation") {
+            dist.insert("operation".to_string(), "Product".into());
+        }
+    }
+}
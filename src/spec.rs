@@ -0,0 +1,129 @@
+//! Distributions declared in a probabilistic-model specification.
+//!
+//! Only the sampling statements are read: one `name ~ Family(a, b)` per line,
+//! as in the model block of a Stan program or a YAML list of priors. Comments
+//! (`#`, `//`), trailing semicolons, list dashes and every line without a `~`
+//! (block headers, declarations, braces) are skipped. Families and their
+//! parameters follow Stan; arguments may be constant formulas such as
+//! `1/sqrt(2)`, but not other model variables.
+
+use crate::{Expr, FitFamily};
+
+/// One sampling statement, in the viewer's parameterization of its family
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    /// Normal and log-normal (μ, σ), Gamma (k, θ), Beta (α, β)
+    pub family: FitFamily,
+    pub parameters: [f64; 2],
+    /// 1-based line in the spec
+    pub line: usize,
+}
+
+/// Every sampling statement of `spec`; the first bad one fails the whole spec
+pub fn parse(spec: &str) -> Result<Vec<Declaration>, String> {
+    let mut declarations = Vec::new();
+    for (index, raw) in spec.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.split("//").next().unwrap_or("").split('#').next().unwrap_or("");
+        let text = text.trim().trim_start_matches("- ").trim_end_matches(';').trim();
+        let Some((name, distribution)) = text.split_once('~') else {
+            continue;
+        };
+        let declaration = parse_statement(name.trim(), distribution.trim())
+            .map_err(|error| format!("Line {}: {}", line, error))?;
+        declarations.push(Declaration { line, ..declaration });
+    }
+    if declarations.is_empty() {
+        return Err("No `name ~ Family(...)` lines found".to_string());
+    }
+    Ok(declarations)
+}
+
+fn parse_statement(name: &str, distribution: &str) -> Result<Declaration, String> {
+    if name.is_empty() {
+        return Err("Missing the name before '~'".to_string());
+    }
+    if distribution.contains("T[") {
+        return Err("Truncation (T[...]) is not supported".to_string());
+    }
+    let (family, arguments) = distribution.split_once('(')
+        .and_then(|(family, rest)| Some((family.trim(), rest.trim_end().strip_suffix(')')?)))
+        .ok_or_else(|| format!("Expected Family(...) after '~', got '{}'", distribution))?;
+    let arguments = split_arguments(arguments)
+        .iter()
+        .map(|argument| constant(argument))
+        .collect::<Result<Vec<f64>, String>>()?;
+    let expect = |count: usize, signature: &str| {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(format!("{} takes {} argument(s): {}", family, count, signature))
+        }
+    };
+    let positive = |value: f64, what: &str| {
+        if value > 0.0 {
+            Ok(value)
+        } else {
+            Err(format!("{} must be positive, got {}", what, value))
+        }
+    };
+    let (family, parameters) = match family.to_ascii_lowercase().as_str() {
+        "normal" | "gaussian" => {
+            expect(2, "mean, standard deviation")?;
+            (FitFamily::Normal, [arguments[0], positive(arguments[1], "The standard deviation")?])
+        }
+        "lognormal" => {
+            expect(2, "log mean, log standard deviation")?;
+            (FitFamily::LogNormal, [arguments[0], positive(arguments[1], "The log standard deviation")?])
+        }
+        // Stan's gamma takes a rate; the viewer keeps the scale
+        "gamma" => {
+            expect(2, "shape, rate")?;
+            (FitFamily::Gamma, [positive(arguments[0], "The shape")?, 1.0 / positive(arguments[1], "The rate")?])
+        }
+        "exponential" => {
+            expect(1, "rate")?;
+            (FitFamily::Gamma, [1.0, 1.0 / positive(arguments[0], "The rate")?])
+        }
+        "beta" => {
+            expect(2, "alpha, beta")?;
+            (FitFamily::Beta, [positive(arguments[0], "Alpha")?, positive(arguments[1], "Beta")?])
+        }
+        _ => return Err(format!("Unknown family '{}' (normal, lognormal, gamma, exponential or beta)", family)),
+    };
+    Ok(Declaration { name: name.to_string(), family, parameters, line: 0 })
+}
+
+/// Split on the commas that are not inside nested parentheses
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&arguments[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&arguments[start..]);
+    parts
+}
+
+fn constant(argument: &str) -> Result<f64, String> {
+    let (expr, variables) = Expr::parse_with_variables(argument.trim())
+        .map_err(|error| format!("Cannot read '{}': {}", argument.trim(), error))?;
+    if let Some(variable) = variables.first() {
+        return Err(format!("'{}' depends on '{}'; only constant parameters can be drawn", argument.trim(), variable));
+    }
+    let value = expr.eval(0.0);
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("'{}' is not a finite number", argument.trim()))
+    }
+}
@@ -0,0 +1,222 @@
+//! Live numeric input for monitoring sensor or simulation output.
+//!
+//! Values arrive as text on stdin, on a TCP port (any number of senders,
+//! one value stream per connection) or as UDP datagrams. Anything that is
+//! not a number is ignored, and values may be separated by whitespace,
+//! commas or semicolons. Reading happens on background threads; the UI
+//! drains what has arrived once per frame.
+//!
+//! Network listeners bind the loopback interface unless another one is
+//! chosen. Stdin cannot be interrupted, so a single reader serves it for the
+//! life of the process and hands its lines to whichever stdin stream is open.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::{TcpListener, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_PORT: u16 = 7879;
+/// Interface the listeners bind unless the user picks another one
+pub const DEFAULT_INTERFACE: &str = "127.0.0.1";
+
+/// Largest UDP datagram read in one go
+const DATAGRAM_SIZE: usize = 65_536;
+// Longest line read from a TCP sender; anything longer closes the connection
+const MAX_LINE_BYTES: u64 = 1 << 20;
+// How often idle readers wake up to notice a stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// The open stdin stream, if any, and whether stdin has reached its end
+static STDIN_SUBSCRIBER: Mutex<Option<Sender<Vec<f64>>>> = Mutex::new(None);
+static STDIN_CLOSED: AtomicBool = AtomicBool::new(false);
+static STDIN_READER: Once = Once::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamSource {
+    Stdin,
+    Tcp,
+    Udp,
+}
+
+impl StreamSource {
+    pub const ALL: [StreamSource; 3] = [StreamSource::Stdin, StreamSource::Tcp, StreamSource::Udp];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StreamSource::Stdin => "stdin",
+            StreamSource::Tcp => "TCP",
+            StreamSource::Udp => "UDP",
+        }
+    }
+}
+
+pub struct DataStream {
+    /// "stdin" or "TCP 127.0.0.1:7879", with the port actually bound
+    description: String,
+    values: Receiver<Vec<f64>>,
+    stop: Arc<AtomicBool>,
+    closed: bool,
+}
+
+impl DataStream {
+    /// Start reading from `source`, listening on `interface` (e.g.
+    /// `DEFAULT_INTERFACE`, or "0.0.0.0" for every interface); both are ignored
+    /// for stdin, and port 0 picks a free one
+    pub fn open(source: StreamSource, interface: &str, port: u16) -> io::Result<Self> {
+        let (sender, values) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let description = match source {
+            StreamSource::Stdin => {
+                subscribe_stdin(sender);
+                "stdin".to_string()
+            }
+            StreamSource::Tcp => {
+                let listener = TcpListener::bind((interface, port))?;
+                let address = listener.local_addr()?;
+                listener.set_nonblocking(true)?;
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                // Blocking, but waking up regularly to notice a stop
+                                if stream.set_nonblocking(false).and_then(|_| stream.set_read_timeout(Some(POLL_INTERVAL))).is_err() {
+                                    continue;
+                                }
+                                let (sender, stop) = (sender.clone(), stop.clone());
+                                thread::spawn(move || forward_lines(BufReader::new(stream), &sender, &stop));
+                            }
+                            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                                thread::sleep(POLL_INTERVAL);
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                });
+                format!("TCP {}", address)
+            }
+            StreamSource::Udp => {
+                let socket = UdpSocket::bind((interface, port))?;
+                let address = socket.local_addr()?;
+                // Wake up regularly to notice a stop
+                socket.set_read_timeout(Some(POLL_INTERVAL))?;
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    let mut buffer = vec![0; DATAGRAM_SIZE];
+                    while !stop.load(Ordering::Relaxed) {
+                        match socket.recv(&mut buffer) {
+                            Ok(length) => {
+                                let values = parse_values(&String::from_utf8_lossy(&buffer[..length]));
+                                if !values.is_empty() && sender.send(values).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(error) if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+                            Err(_) => break,
+                        }
+                    }
+                });
+                format!("UDP {}", address)
+            }
+        };
+        Ok(Self { description, values, stop, closed: false })
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Whether every reader has finished, e.g. stdin reached its end
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Values that arrived since the last call, in arrival order
+    pub fn drain(&mut self) -> Vec<f64> {
+        let mut drained = Vec::new();
+        loop {
+            match self.values.try_recv() {
+                Ok(values) => drained.extend(values),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+        drained
+    }
+}
+
+impl Drop for DataStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Make `sender` the receiver of stdin values, replacing the previous stream's,
+/// and start the one stdin reader on first use
+fn subscribe_stdin(sender: Sender<Vec<f64>>) {
+    STDIN_READER.call_once(|| {
+        thread::spawn(|| {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                let values = parse_values(&line);
+                if values.is_empty() {
+                    continue;
+                }
+                if let Ok(mut subscriber) = STDIN_SUBSCRIBER.lock() {
+                    // A stopped stream has dropped its receiver; its lines go nowhere
+                    if subscriber.as_ref().is_some_and(|sender| sender.send(values).is_err()) {
+                        *subscriber = None;
+                    }
+                }
+            }
+            STDIN_CLOSED.store(true, Ordering::Relaxed);
+            if let Ok(mut subscriber) = STDIN_SUBSCRIBER.lock() {
+                *subscriber = None;
+            }
+        });
+    });
+    // After the end of stdin the sender is dropped, so the stream reads as closed
+    if let Ok(mut subscriber) = STDIN_SUBSCRIBER.lock() {
+        if !STDIN_CLOSED.load(Ordering::Relaxed) {
+            *subscriber = Some(sender);
+        }
+    }
+}
+
+/// Forward the values of each line until the sender disconnects, a line runs
+/// past `MAX_LINE_BYTES` or the stream stops. `reader` should time out so an
+/// idle connection still notices the stop; a line cut by a timeout is resumed.
+fn forward_lines(mut reader: impl BufRead, sender: &Sender<Vec<f64>>, stop: &AtomicBool) {
+    let mut line = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        let limit = MAX_LINE_BYTES - line.len() as u64;
+        let ended = match Read::take(&mut reader, limit).read_until(b'\n', &mut line) {
+            Ok(_) if line.ends_with(b"\n") => false,
+            Ok(_) if line.len() as u64 >= MAX_LINE_BYTES => break,
+            // The end of the stream, after its last (unterminated) line
+            Ok(0) => true,
+            Ok(_) => continue,
+            Err(error) if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+            Err(_) => break,
+        };
+        let values = parse_values(&String::from_utf8_lossy(&line));
+        line.clear();
+        if (!values.is_empty() && sender.send(values).is_err()) || ended {
+            break;
+        }
+    }
+}
+
+/// Finite numbers in `text`, separated by whitespace, commas or semicolons
+pub fn parse_values(text: &str) -> Vec<f64> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter_map(|token| token.parse::<f64>().ok())
+        .filter(|value| value.is_finite())
+        .collect()
+}
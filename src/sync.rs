@@ -0,0 +1,437 @@
+//! Experimental live session sharing over the local network.
+//!
+//! One instance hosts and the others connect to it. Every distribution is a
+//! last-writer-wins register keyed by its id and stamped with a Lamport clock
+//! plus a random replica id, so replicas converge no matter in which order
+//! edits arrive. Messages are newline-delimited JSON over TCP; the host relays
+//! whatever its clients send to everyone else.
+//!
+//! A host can also broadcast only: its clients become read-only viewers that
+//! can still pan and zoom locally, optionally following the host's plot view.
+//!
+//! Hosting is unauthenticated, so a host listens on the loopback interface
+//! unless told otherwise, and definitions received from peers are checked
+//! before they are applied.
+//!
+//! Limitation: two replicas creating a distribution at the same moment can
+//! pick the same id, in which case one of the two creations wins.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GaussianDistribution, Operation, ParameterRanges, Transform};
+
+pub const DEFAULT_PORT: u16 = 7878;
+/// Interface a host listens on unless the user picks another one
+pub const DEFAULT_INTERFACE: &str = "127.0.0.1";
+
+// Longest line read from a peer; anything longer closes the connection
+const MAX_MESSAGE_BYTES: u64 = 16 << 20;
+// A peer that takes longer than this to accept a line is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+// Limits on definitions received from peers, well above what the controls allow
+const MAX_PARENTS: usize = 64;
+const MAX_COPIES: u32 = 1000;
+const MAX_KDE_SAMPLES: usize = 1_000_000;
+const MAX_FORMULA_LENGTH: usize = 256;
+
+/// Version of one distribution: the later clock wins, the replica id breaks ties
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Stamp {
+    clock: u64,
+    replica: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SyncMessage {
+    Upsert { stamp: Stamp, distribution: Box<GaussianDistribution> },
+    Remove { stamp: Stamp, id: u32 },
+    /// Host setting: clients may only view
+    ReadOnly(bool),
+    /// Host plot view as [x_min, x_max, y_min, y_max]
+    View([f64; 4]),
+    /// Host: everything present when the client joined has been sent
+    SnapshotDone,
+}
+
+/// Events from the network threads to the UI thread
+enum Incoming {
+    Joined,
+    Line(String),
+    Left(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Host,
+    Client,
+}
+
+/// A connected peer. Lines go out through a writer thread of its own, so a
+/// slow peer never blocks the UI thread.
+struct Peer {
+    id: usize,
+    stream: TcpStream,
+    outbox: Sender<String>,
+}
+
+type Peers = Arc<Mutex<Vec<Peer>>>;
+
+pub struct SyncSession {
+    role: Role,
+    address: String,
+    replica: u64,
+    clock: u64,
+    peers: Peers,
+    incoming: Receiver<Incoming>,
+    stop: Arc<AtomicBool>,
+    // Newest stamp seen per id, kept after a removal as a tombstone
+    stamps: HashMap<u32, Stamp>,
+    // Shared state of each distribution as last sent or applied, to spot local edits
+    known: HashMap<u32, String>,
+    // A client adopts the host's session, so it publishes nothing before the first snapshot
+    awaiting_snapshot: bool,
+    // Host: clients are viewers only. Client: this host broadcasts only.
+    read_only: bool,
+    // Host: last view sent. Client: presenter's view not yet taken by the UI.
+    view: Option<[f64; 4]>,
+}
+
+impl SyncSession {
+    /// Listen for clients on the given interface, e.g. `DEFAULT_INTERFACE` for
+    /// this computer only or "0.0.0.0" for all of them; port 0 picks a free port
+    pub fn host(interface: &str, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((interface, port))?;
+        let address = listener.local_addr()?.to_string();
+        listener.set_nonblocking(true)?;
+
+        let (sender, incoming) = channel();
+        let session = Self::new(Role::Host, address, incoming);
+        let (peers, stop) = (session.peers.clone(), session.stop.clone());
+        let next_peer = AtomicUsize::new(0);
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let peer = next_peer.fetch_add(1, Ordering::Relaxed);
+                        // Failing peers are simply not added
+                        let _ = add_peer(peer, stream, &peers, &sender);
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(session)
+    }
+
+    /// Connect to a host given as "host:port"
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let socket_address = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address found"))?;
+        let stream = TcpStream::connect_timeout(&socket_address, Duration::from_secs(3))?;
+
+        let (sender, incoming) = channel();
+        let mut session = Self::new(Role::Client, socket_address.to_string(), incoming);
+        session.awaiting_snapshot = true;
+        add_peer(0, stream, &session.peers, &sender)?;
+        Ok(session)
+    }
+
+    fn new(role: Role, address: String, incoming: Receiver<Incoming>) -> Self {
+        Self {
+            role,
+            address,
+            replica: RandomState::new().build_hasher().finish(),
+            clock: 0,
+            peers: Arc::new(Mutex::new(Vec::new())),
+            incoming,
+            stop: Arc::new(AtomicBool::new(false)),
+            stamps: HashMap::new(),
+            known: HashMap::new(),
+            awaiting_snapshot: false,
+            read_only: false,
+            view: None,
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().map_or(0, |peers| peers.len())
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Host only: make every client a read-only viewer, or allow edits again
+    pub fn set_read_only(&mut self, read_only: bool) {
+        if self.role == Role::Host && self.read_only != read_only {
+            self.read_only = read_only;
+            self.send(&SyncMessage::ReadOnly(read_only));
+        }
+    }
+
+    /// Host only: share the plot view when it changed
+    pub fn publish_view(&mut self, view: [f64; 4]) {
+        if self.role == Role::Host && self.view != Some(view) {
+            self.view = Some(view);
+            self.send(&SyncMessage::View(view));
+        }
+    }
+
+    /// Client only: the presenter's view, if it changed since the last call
+    pub fn take_presenter_view(&mut self) -> Option<[f64; 4]> {
+        match self.role {
+            Role::Client => self.view.take(),
+            Role::Host => None,
+        }
+    }
+
+    /// Apply everything received since the last call. Returns true when the
+    /// distributions changed, so derived ones need recomputing.
+    pub fn poll(&mut self, distributions: &mut HashMap<u32, GaussianDistribution>, next_id: &mut u32) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.incoming.try_recv() {
+            match event {
+                // Stale or duplicate entries are ignored, so the whole state can go to everyone
+                Incoming::Joined => {
+                    for id in self.stamps.keys().copied().collect::<Vec<_>>() {
+                        self.send_current(id, distributions);
+                    }
+                    if self.role == Role::Host {
+                        self.send(&SyncMessage::ReadOnly(self.read_only));
+                        if let Some(view) = self.view {
+                            self.send(&SyncMessage::View(view));
+                        }
+                        // Sent even for an empty session, so the client can start publishing
+                        self.send(&SyncMessage::SnapshotDone);
+                    }
+                }
+                Incoming::Line(line) => {
+                    let Ok(message) = serde_json::from_str::<SyncMessage>(&line) else {
+                        continue;
+                    };
+                    let message = match (self.role, message) {
+                        (Role::Client, SyncMessage::ReadOnly(read_only)) => {
+                            self.read_only = read_only;
+                            continue;
+                        }
+                        (Role::Client, SyncMessage::View(view)) => {
+                            self.view = Some(view);
+                            continue;
+                        }
+                        (Role::Client, SyncMessage::SnapshotDone) => {
+                            self.awaiting_snapshot = false;
+                            continue;
+                        }
+                        // Settings and views only flow from the host, and a
+                        // broadcast-only host ignores edits from its viewers
+                        (Role::Host, SyncMessage::ReadOnly(_) | SyncMessage::View(_) | SyncMessage::SnapshotDone) => continue,
+                        (Role::Host, _) if self.read_only => continue,
+                        (_, SyncMessage::Upsert { distribution, .. }) if !acceptable(&distribution) => continue,
+                        (_, message) => message,
+                    };
+                    if self.apply(message, distributions, next_id) {
+                        changed = true;
+                        if self.role == Role::Host {
+                            self.broadcast(&line);
+                        }
+                    }
+                }
+                Incoming::Left(peer) => {
+                    if let Ok(mut peers) = self.peers.lock() {
+                        peers.retain(|known| known.id != peer);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    fn apply(
+        &mut self,
+        message: SyncMessage,
+        distributions: &mut HashMap<u32, GaussianDistribution>,
+        next_id: &mut u32,
+    ) -> bool {
+        let (stamp, id) = match &message {
+            SyncMessage::Upsert { stamp, distribution } => (*stamp, distribution.id),
+            SyncMessage::Remove { stamp, id } => (*stamp, *id),
+            SyncMessage::ReadOnly(_) | SyncMessage::View(_) | SyncMessage::SnapshotDone => return false,
+        };
+        self.clock = self.clock.max(stamp.clock);
+        if self.stamps.get(&id).is_some_and(|current| *current >= stamp) {
+            return false;
+        }
+        self.stamps.insert(id, stamp);
+
+        match message {
+            SyncMessage::Upsert { mut distribution, .. } => {
+                // Slider ranges are local; widen them if a peer went past them
+                distribution.ranges = distributions.get(&id).map_or_else(ParameterRanges::default, |d| d.ranges);
+                distribution.ranges.reconcile(&mut distribution.mean, &mut distribution.std_dev);
+                self.known.insert(id, shared_state(&distribution));
+                *next_id = (*next_id).max(id + 1);
+                distributions.insert(id, *distribution);
+            }
+            SyncMessage::Remove { .. } => {
+                self.known.remove(&id);
+                distributions.remove(&id);
+            }
+            SyncMessage::ReadOnly(_) | SyncMessage::View(_) | SyncMessage::SnapshotDone => {}
+        }
+        true
+    }
+
+    /// Send every local edit made since the last call
+    pub fn publish(&mut self, distributions: &HashMap<u32, GaussianDistribution>) {
+        if self.awaiting_snapshot || (self.role == Role::Client && self.read_only) {
+            return;
+        }
+        let mut edited: Vec<u32> = distributions.iter()
+            .filter(|(id, dist)| self.known.get(id) != Some(&shared_state(dist)))
+            .map(|(id, _)| *id)
+            .collect();
+        edited.extend(self.known.keys().filter(|id| !distributions.contains_key(id)));
+        edited.sort_unstable();
+
+        for id in edited {
+            self.clock += 1;
+            self.stamps.insert(id, Stamp { clock: self.clock, replica: self.replica });
+            match distributions.get(&id) {
+                Some(dist) => self.known.insert(id, shared_state(dist)),
+                None => self.known.remove(&id),
+            };
+            self.send_current(id, distributions);
+        }
+    }
+
+    fn send_current(&self, id: u32, distributions: &HashMap<u32, GaussianDistribution>) {
+        let Some(&stamp) = self.stamps.get(&id) else {
+            return;
+        };
+        let message = match distributions.get(&id) {
+            Some(dist) => SyncMessage::Upsert { stamp, distribution: Box::new(dist.clone()) },
+            None => SyncMessage::Remove { stamp, id },
+        };
+        self.send(&message);
+    }
+
+    fn send(&self, message: &SyncMessage) {
+        if let Ok(line) = serde_json::to_string(message) {
+            self.broadcast(&line);
+        }
+    }
+
+    fn broadcast(&self, line: &str) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.retain(|peer| peer.outbox.send(line.to_string()).is_ok());
+        }
+    }
+}
+
+impl Drop for SyncSession {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Ok(peers) = self.peers.lock() {
+            for peer in peers.iter() {
+                let _ = peer.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// Register a connected stream, forward its lines to the UI thread and write
+/// whatever is broadcast to it
+fn add_peer(peer: usize, stream: TcpStream, peers: &Peers, sender: &Sender<Incoming>) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    let (outbox, outgoing) = channel::<String>();
+    peers.lock()
+        .map_err(|_| io::Error::other("peer list poisoned"))?
+        .push(Peer { id: peer, stream, outbox });
+    let _ = sender.send(Incoming::Joined);
+
+    thread::spawn(move || {
+        for line in outgoing {
+            if writeln!(writer, "{}", line).is_err() {
+                // Ends the reader too, which reports the peer as gone
+                let _ = writer.shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    });
+    let sender = sender.clone();
+    thread::spawn(move || {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match (&mut reader).take(MAX_MESSAGE_BYTES).read_until(b'\n', &mut line) {
+                Ok(read) if read > 0 && line.ends_with(b"\n") => {}
+                // Closed, failed or over-long
+                _ => break,
+            }
+            let text = String::from_utf8_lossy(&line).trim_end().to_string();
+            if sender.send(Incoming::Line(text)).is_err() {
+                break;
+            }
+        }
+        let _ = sender.send(Incoming::Left(peer));
+    });
+    Ok(())
+}
+
+/// Whether a definition from a peer is sound and cheap enough to derive.
+/// A single bad message must not be able to bring down every replica.
+fn acceptable(dist: &GaussianDistribution) -> bool {
+    let operation_ok = match &dist.operation {
+        Operation::IidSum { copies, .. } => *copies <= MAX_COPIES,
+        Operation::Kde { samples, bandwidth } => {
+            samples.len() <= MAX_KDE_SAMPLES && bandwidth.is_none_or(|h| h.is_finite() && h > 0.0)
+        }
+        Operation::Transform(Transform::Expression(formula)) => formula.len() <= MAX_FORMULA_LENGTH,
+        Operation::Mixture { weights, .. } => {
+            weights.len() <= dist.parent_ids.len() && weights.iter().all(|w| w.is_finite() && *w >= 0.0)
+        }
+        Operation::Truncate { lower, upper } => lower.is_finite() && upper.is_finite(),
+        Operation::Temper { beta } => beta.is_finite() && *beta > 0.0,
+        Operation::Family { parameters, .. } => parameters.iter().all(|p| p.is_finite() && *p > 0.0),
+        _ => true,
+    };
+    operation_ok && dist.validate().is_ok() && dist.parent_ids.len() <= MAX_PARENTS
+}
+
+/// The part of a distribution that is synced. Derived values are recomputed by
+/// every replica, so only their definition counts as an edit.
+fn shared_state(dist: &GaussianDistribution) -> String {
+    let mut shared = dist.clone();
+    if shared.is_derived() {
+        shared.mean = 0.0;
+        shared.std_dev = 0.0;
+    }
+    serde_json::to_string(&shared).unwrap_or_default()
+}
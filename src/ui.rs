@@ -0,0 +1,4102 @@
+//! Windows, panels and plots of the viewer. The state they edit, and every
+//! number they show, lives in the crate root; this module only draws it and
+//! turns clicks into calls on it.
+
+use super::*;
+use egui_plot::{Line, Plot, VLine};
+
+/// Open the viewer window and run until it is closed
+pub fn run_gui() -> Result<(), eframe::Error> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([1200.0, 800.0]),
+        ..Default::default()
+    };
+    
+    eframe::run_native(
+        "PDF Viewer",
+        options,
+        Box::new(|_cc| {
+            let mut app = PdfViewerApp::new();
+            if let Some(directory) = eframe::storage_dir("PDF Viewer") {
+                app.enable_autosave(&directory.join("autosave"), std::time::Instant::now());
+                app.enable_templates(directory.join("templates"));
+            }
+            Ok(Box::new(app))
+        }),
+    )
+}
+
+impl eframe::App for PdfViewerApp {
+    /// A clean exit leaves nothing to recover, unless the offer was never answered
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let (Some(path), None) = (&self.autosave.path, &self.autosave.recovery) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.applied_theme != Some(self.theme) {
+            self.theme.apply(ctx);
+            self.applied_theme = Some(self.theme);
+        }
+        if let Some(session) = &mut self.sync {
+            let changed = session.poll(&mut self.distributions, &mut self.next_id);
+            if let Some(view) = session.take_presenter_view() {
+                if self.follow_presenter {
+                    self.plot_bounds = Some(view_bounds(view));
+                    self.requested_bounds = self.plot_bounds;
+                }
+            }
+            if changed {
+                self.update_product_distributions();
+            }
+            // Remote edits arrive without local input, so keep repainting
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        if self.advance_sweep(f64::from(ctx.input(|i| i.stable_dt))) {
+            ctx.request_repaint();
+        }
+        if self.poll_stream() {
+            ctx.request_repaint();
+        }
+        if self.live_stream.stream.is_some() {
+            // Values arrive without any local input
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+        // Text fields keep Ctrl+Z for their own undo. Redo is checked first, as
+        // the undo shortcut also matches with Shift held.
+        if !ctx.wants_keyboard_input() {
+            let (redo, undo) = ctx.input_mut(|i| (
+                i.consume_shortcut(&REDO_SHORTCUT),
+                i.consume_shortcut(&UNDO_SHORTCUT),
+            ));
+            if redo {
+                self.redo();
+            } else if undo {
+                self.undo();
+            }
+        }
+        if self.data.bootstrap.job.poll() {
+            ctx.request_repaint();
+        } else if matches!(self.data.bootstrap.job, bootstrap::BootstrapJob::Running(_)) {
+            // Keep polling until the refits are done
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("PDF Viewer - Probability Density Function Explorer");
+                
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("📝 Algebra Cheat Sheet").clicked() {
+                        self.show_cheat_sheet = true;
+                    }
+                    
+                    if ui.button("⚖ Importance Sampling").clicked() {
+                        self.importance.open = true;
+                    }
+                    if ui.button("🎯 Monte Carlo").clicked() {
+                        self.monte_carlo.open = true;
+                    }
+                    
+                    if ui.button("🗺 Joint").clicked() {
+                        self.joint.open = true;
+                    }
+                    
+                    if ui.button("📐 Compare").clicked() {
+                        self.comparison.open = true;
+                    }
+                    
+                    if ui.button("🖼 Gallery").clicked() {
+                        self.show_welcome = true;
+                    }
+                    
+                    if ui.button("🔄 Updates").clicked() {
+                        self.show_updates = true;
+                    }
+                    
+                    if ui.add_enabled(!self.history.redo.is_empty(), egui::Button::new("↷"))
+                        .on_hover_text(format!("Redo ({})", ctx.format_shortcut(&REDO_SHORTCUT)))
+                        .clicked()
+                    {
+                        self.redo();
+                    }
+                    if ui.add_enabled(!self.history.undo.is_empty(), egui::Button::new("↶"))
+                        .on_hover_text(format!("Undo ({})", ctx.format_shortcut(&UNDO_SHORTCUT)))
+                        .clicked()
+                    {
+                        self.undo();
+                    }
+
+                    if ui.button("💾 Save Session").clicked() {
+                        let path = self.session_dialog().set_file_name("session.json").save_file();
+                        if let Some(path) = path {
+                            self.session_status = Some(self.save_session_to(&path).map(|()| format!("Saved {}", path.display())));
+                            self.session_path = Some(path);
+                        }
+                    }
+                    
+                    if ui.button("🔗 Share").on_hover_text("Copy or paste the session as a line of text").clicked() {
+                        self.share.open = true;
+                    }
+
+                    if ui.button("📁 Load Session").clicked() {
+                        if let Some(path) = self.session_dialog().pick_file() {
+                            self.session_status = Some(self.load_session_from(&path).map(|()| format!("Loaded {}", path.display())));
+                            self.session_path = Some(path);
+                        }
+                    }
+                    match &self.session_status {
+                        Some(Ok(message)) => {
+                            ui.weak(message);
+                        }
+                        Some(Err(error)) => {
+                            ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                        }
+                        None => {}
+                    }
+                });
+            });
+            
+            ui.separator();
+            self.workspace_tabs(ui);
+            ui.separator();
+            
+            // Add initial distribution if none exist (a client gets its distributions from the host)
+            let is_client = self.sync.as_ref().is_some_and(|session| session.role() == sync::Role::Client);
+            if self.distributions.is_empty() && !is_client {
+                let dist = GaussianDistribution::new(
+                    self.next_id,
+                    format!("Gaussian {}", self.next_id + 1),
+                    0.0,
+                    1.0,
+                );
+                self.distributions.insert(self.next_id, dist);
+                self.next_id += 1;
+            }
+            
+            ui.horizontal(|ui| {
+                // Left panel for controls
+                ui.vertical(|ui| {
+                    ui.set_width(300.0);
+                    egui::ScrollArea::vertical().id_salt("controls_scroll").show(ui, |ui| {
+                        self.sync_controls(ui);
+                        // Viewers of a broadcast-only session can look, pan and zoom, but not edit
+                        if self.is_view_only() {
+                            ui.disable();
+                        }
+                    
+                        ui.separator();
+                    
+                        ui.heading("Distribution Controls");
+                    
+                        if ui.button("Add New Gaussian").clicked() {
+                            let dist = GaussianDistribution::new(
+                                self.next_id,
+                                format!("Gaussian {}", self.next_id + 1),
+                                0.0,
+                                1.0,
+                            );
+                            self.distributions.insert(self.next_id, dist);
+                            self.next_id += 1;
+                        }
+                    
+                        ui.separator();
+                    
+                        ui.heading("Data");
+                        self.data_controls(ui);
+                        self.model_spec_controls(ui);
+                    
+                        ui.separator();
+                    
+                        // Visual controls
+                        ui.heading("Visual Options");
+                        ui.checkbox(&mut self.show_shading, "Show shading under curves");
+                        if self.show_shading {
+                            ui.horizontal(|ui| {
+                                ui.label("Opacity:");
+                                ui.add(egui::Slider::new(&mut self.shading_opacity, 0.0..=1.0)
+                                    .fixed_decimals(2));
+                            });
+                        }
+                        ui.checkbox(&mut self.show_std_markers, "Show markers")
+                            .on_hover_text("σ multiples, quantiles or fixed x values, set per distribution under \"Markers\"");
+                        ui.checkbox(&mut self.shade_between, "Shade between two curves")
+                            .on_hover_text("Fills where one curve exceeds the other, in the colour of the upper one");
+                        if self.shade_between {
+                            let choices = self.distribution_choices();
+                            distribution_picker(ui, "First:", "between_first", &mut self.between.0, &choices);
+                            distribution_picker(ui, "Second:", "between_second", &mut self.between.1, &choices);
+                        }
+                        ui.checkbox(&mut self.parameter_handles, "Drag μ and σ on the plot")
+                            .on_hover_text("● moves the mean, ◆ (at μ + σ) sets the standard deviation");
+                        ui.checkbox(&mut self.show_hover_values, "List curve values when hovering the plot");
+                        ui.checkbox(&mut self.crosshair, "Crosshair");
+                        if self.crosshair {
+                            let choices = self.distribution_choices();
+                            distribution_picker(ui, "Active:", "crosshair_target", &mut self.crosshair_target, &choices);
+                        }
+                        self.theme_controls(ui);
+                        self.axis_style_controls(ui);
+                        ui.horizontal(|ui| {
+                            ui.label("Colour by:");
+                            for coloring in [CurveColoring::Palette, CurveColoring::Entropy, CurveColoring::Variance] {
+                                ui.radio_value(&mut self.curve_coloring, coloring, coloring.label());
+                            }
+                        });
+                        if let (_, Some((low, high))) = self.curve_colors() {
+                            colorbar(ui, self.curve_coloring.label(), low, high, Colormap::Viridis);
+                        }
+                        let mut reference = self.z_reference;
+                        distribution_picker(ui, "Standardize x to z of:", "z_reference", &mut reference, &self.distribution_choices());
+                        if reference != self.z_reference {
+                            self.set_z_reference(reference);
+                        }
+                        let mut log_y = self.log_y;
+                        if ui.checkbox(&mut log_y, "Logarithmic y-axis").on_hover_text("Compare tails; the axis shows powers of ten").changed() {
+                            self.set_log_y(log_y);
+                        }
+                        ui.checkbox(&mut self.peak_normalized, "Scale every curve to peak 1")
+                            .on_hover_text("Compare shapes regardless of spread; hovering still shows the true densities");
+                        ui.horizontal(|ui| {
+                            ui.label("Products:");
+                            ui.radio_value(&mut self.show_product_amplitude, false, "Area-normalized")
+                                .on_hover_text("Renormalized to integrate to 1, i.e. a proper density");
+                            ui.radio_value(&mut self.show_product_amplitude, true, "Raw amplitude")
+                                .on_hover_text("The raw product Π fᵢ(x) = S × density; its height is the likelihood scale");
+                        });
+                    
+                        ui.horizontal(|ui| {
+                            ui.label("Resolution:");
+                            let mut per_pixel = self.resolution.interactive == InteractiveResolution::PixelWidth;
+                            ui.radio_value(&mut per_pixel, true, "Per pixel");
+                            ui.radio_value(&mut per_pixel, false, "Fixed");
+                            self.resolution.interactive = match (per_pixel, self.resolution.interactive) {
+                                (true, _) => InteractiveResolution::PixelWidth,
+                                (false, InteractiveResolution::Fixed(points)) => InteractiveResolution::Fixed(points),
+                                (false, InteractiveResolution::PixelWidth) => InteractiveResolution::Fixed(300),
+                            };
+                            if let InteractiveResolution::Fixed(points) = &mut self.resolution.interactive {
+                                ui.add(egui::DragValue::new(points).range(10..=10_000).suffix(" pts"));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Export points:");
+                            ui.add(egui::DragValue::new(&mut self.resolution.export_points)
+                                .range(100..=20_000)
+                                .speed(10));
+                        });
+                        self.tikz_export_controls(ui);
+                        self.python_export_controls(ui);
+                        self.r_export_controls(ui);
+                        self.report_export_controls(ui);
+                        self.auto_export_controls(ui);
+                    
+                        ui.separator();
+                    
+                        // Multiplication controls
+                        ui.heading("Combine PDFs");
+                        ui.horizontal_wrapped(|ui| {
+                            if ui.button("Multiply Selected").clicked()
+                                && self.add_derived(Operation::Product, self.selected_for_multiplication.clone()).is_some()
+                            {
+                                self.selected_for_multiplication.clear();
+                            }
+
+                            if ui.button("Divide Selected (X / Y)").clicked()
+                                && self.add_derived(Operation::Quotient, self.selected_for_multiplication.clone()).is_some()
+                            {
+                                self.selected_for_multiplication.clear();
+                            }
+
+                            if ui.button("Max of Selected").clicked()
+                                && self.add_derived(Operation::Maximum, self.selected_for_multiplication.clone()).is_some()
+                            {
+                                self.selected_for_multiplication.clear();
+                            }
+
+                            if ui.button("Min of Selected").clicked()
+                                && self.add_derived(Operation::Minimum, self.selected_for_multiplication.clone()).is_some()
+                            {
+                                self.selected_for_multiplication.clear();
+                            }
+
+                            // Posteriors and products are averaged by evidence, anything else equally
+                            if ui.button("Mix Selected").on_hover_text("Weighted mixture, e.g. model averaging of posteriors").clicked() {
+                                let selected = self.selected_for_multiplication.clone();
+                                let by_evidence = selected.iter()
+                                    .all(|id| self.distributions.get(id).is_some_and(|d| d.normalizing_constant.is_some()));
+                                let operation = Operation::Mixture { weights: vec![1.0; selected.len()], by_evidence };
+                                if self.add_derived(operation, selected).is_some() {
+                                    self.selected_for_multiplication.clear();
+                                }
+                            }
+                        
+                            if ui.button("Clear Selection").clicked() {
+                                self.selected_for_multiplication.clear();
+                            }
+                        });
+                    
+                        // Division uses selection order: first selected is X, second is Y
+                        if let [x_id, y_id] = self.selected_for_multiplication[..] {
+                            if let (Some(x), Some(y)) = (self.distributions.get(&x_id), self.distributions.get(&y_id)) {
+                                ui.label(format!("Divide: {} / {}", x.name, y.name));
+                            }
+                        }
+                    
+                        if !self.selected_for_multiplication.is_empty() {
+                            ui.label(format!("Selected: {} distributions", self.selected_for_multiplication.len()));
+                        }
+                    
+                        ui.separator();
+                    
+                        // Shared change of units for several inputs, e.g. mm → m
+                        ui.heading("Groups");
+                        self.group_controls(ui);
+                    
+                        ui.separator();
+                    
+                        ui.heading("Reference lines");
+                        self.reference_line_controls(ui);
+                    
+                        ui.separator();
+                    
+                        ui.heading("Annotations");
+                        self.annotation_controls(ui);
+                    
+                        ui.separator();
+                    
+                        ui.heading("Animate parameter");
+                        self.sweep_controls(ui);
+                    
+                        ui.separator();
+                    
+                        ui.heading("Timeline");
+                        self.timeline_controls(ui);
+                    
+                        ui.separator();
+                    
+                        // Bayes update: prior × likelihood, normalized by the evidence
+                        ui.heading("Bayes Update");
+                        let choices = self.distribution_choices();
+                        distribution_picker(ui, "Prior:", "bayes_prior", &mut self.bayes_prior, &choices);
+                        distribution_picker(ui, "Likelihood:", "bayes_likelihood", &mut self.bayes_likelihood, &choices);
+                        if let (Some(prior), Some(likelihood)) = (self.bayes_prior, self.bayes_likelihood) {
+                            if ui.button("Compute Posterior").clicked() {
+                                self.add_derived(Operation::BayesUpdate, vec![prior, likelihood]);
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Conditioning on an interval, selected by dragging on the plot
+                        ui.heading("Condition on Interval");
+                        distribution_picker(ui, "Distribution:", "condition_target", &mut self.condition_target, &choices);
+                        ui.toggle_value(&mut self.selecting_interval, "✏ Drag interval on plot")
+                            .on_hover_text("While active, dragging on the plot selects [a, b] instead of panning");
+                        if let Some((lower, upper)) = &mut self.condition_interval {
+                            ui.horizontal(|ui| {
+                                ui.label("a:");
+                                ui.add(egui::DragValue::new(lower).speed(0.05));
+                                ui.label("b:");
+                                ui.add(egui::DragValue::new(upper).speed(0.05));
+                            });
+                            *upper = upper.max(*lower + 0.01);
+                        } else if let Some(dist) = self.condition_target.and_then(|id| self.distributions.get(&id)) {
+                            self.condition_interval = Some((dist.mean - dist.std_dev, dist.mean + dist.std_dev));
+                        }
+                        if let (Some(target), Some((lower, upper))) = (self.condition_target, self.condition_interval) {
+                            if ui.button("Create Conditional").clicked() {
+                                self.add_derived(Operation::Truncate { lower, upper }, vec![target]);
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Probability mass of the same [a, b] under every curve
+                        ui.heading("Interval Probability");
+                        ui.checkbox(&mut self.show_interval_probability, "Show P(a ≤ X ≤ b) and shade only [a, b]");
+                        if self.show_interval_probability {
+                            if self.condition_interval.is_none() {
+                                let (view_min, view_max) = self.data_range();
+                                let third = (view_max - view_min) / 3.0;
+                                self.condition_interval = Some((view_min + third, view_max - third));
+                            }
+                            if let Some((lower, upper)) = self.condition_interval {
+                                ui.label(format!("[a, b] = [{:.4}, {:.4}]; drag the handles on the plot", lower, upper));
+                            }
+                            egui::Grid::new("interval_probabilities").striped(true).show(ui, |ui| {
+                                for (_, name, probability) in self.interval_probabilities() {
+                                    ui.label(name);
+                                    ui.label(format!("{:.6}", probability));
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                    
+                        ui.separator();
+                    
+                        // Change of variables Y = g(X)
+                        ui.heading("Transform");
+                        distribution_picker(ui, "Distribution:", "transform_target", &mut self.transform_target, &choices);
+                        ui.horizontal_wrapped(|ui| {
+                            ui.selectable_value(&mut self.transform, Transform::Exp, "exp(x)");
+                            ui.selectable_value(&mut self.transform, Transform::Log, "ln(x)");
+                            ui.selectable_value(&mut self.transform, Transform::Square, "x²");
+                            if ui.selectable_label(matches!(self.transform, Transform::Expression(_)), "Custom").clicked() {
+                                self.transform = Transform::Expression(self.custom_transform.clone());
+                            }
+                        });
+                        let mut formula_error = None;
+                        if let Transform::Expression(_) = self.transform {
+                            ui.horizontal(|ui| {
+                                ui.label("g(x) =");
+                                ui.text_edit_singleline(&mut self.custom_transform);
+                            });
+                            self.transform = Transform::Expression(self.custom_transform.clone());
+                            formula_error = self.transform.function().err();
+                        }
+                        if let Some(error) = &formula_error {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                        if let Some(target) = self.transform_target {
+                            if ui.add_enabled(formula_error.is_none(), egui::Button::new("Create Transform")).clicked() {
+                                self.add_derived(Operation::Transform(self.transform.clone()), vec![target]);
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Central limit theorem: sum or mean of n iid copies
+                        ui.heading("Sum of iid Copies (CLT)");
+                        distribution_picker(ui, "Distribution:", "sum_target", &mut self.sum_target, &choices);
+                        ui.add(egui::Slider::new(&mut self.sum_copies, 1..=100).text("n"));
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.sum_average, false, "Sum");
+                            ui.radio_value(&mut self.sum_average, true, "Mean");
+                        });
+                        if let Some(target) = self.sum_target {
+                            if ui.button("Create").clicked() {
+                                let operation = Operation::IidSum { copies: self.sum_copies, average: self.sum_average };
+                                self.add_derived(operation, vec![target]);
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Tempering / annealing: p(x)^β renormalized
+                        ui.heading("Temper");
+                        distribution_picker(ui, "Distribution:", "temper_target", &mut self.temper_target, &choices);
+                        ui.add(egui::Slider::new(&mut self.temper_beta, 0.05..=10.0).logarithmic(true).text("β"));
+                        if let Some(target) = self.temper_target {
+                            if ui.button("Create Tempered").clicked() {
+                                self.add_derived(Operation::Temper { beta: self.temper_beta }, vec![target]);
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Raw value ↔ z-score under one distribution, marked on the plot
+                        ui.heading("Z-score");
+                        distribution_picker(ui, "Distribution:", "zscore_target", &mut self.zscore_target, &choices);
+                        if let Some((mean, std_dev)) = self.zscore_reference() {
+                            ui.horizontal(|ui| {
+                                ui.label("x =");
+                                ui.add(egui::DragValue::new(&mut self.zscore_x).speed(0.01 * std_dev));
+                                ui.label("z =");
+                                let mut z = (self.zscore_x - mean) / std_dev;
+                                if ui.add(egui::DragValue::new(&mut z).speed(0.01).max_decimals(6)).changed() {
+                                    self.zscore_x = mean + z * std_dev;
+                                }
+                            });
+                            ui.weak(format!("z = (x − {:.4}) / {:.4}", mean, std_dev));
+                        }
+                    
+                        ui.separator();
+                    
+                        // Inverse CDF: x with P(X ≤ x) = p
+                        ui.heading("Quantile Lookup");
+                        distribution_picker(ui, "Distribution:", "quantile_target", &mut self.quantile_target, &choices);
+                        ui.horizontal(|ui| {
+                            ui.label("p =");
+                            ui.add(egui::DragValue::new(&mut self.quantile_p).speed(0.001).range(0.0..=1.0).max_decimals(6));
+                            ui.checkbox(&mut self.show_quantile_marker, "Mark on plot");
+                        });
+                        if let Some(dist) = self.quantile_target.and_then(|id| self.distributions.get(&id)) {
+                            ui.label(format!("x = F⁻¹({}) = {:.6}", self.quantile_p, dist.quantile(self.quantile_p)));
+                        }
+                    
+                        ui.separator();
+                    
+                        // Tail probabilities of an observed value, shaded on the plot
+                        ui.heading("p-value");
+                        distribution_picker(ui, "Distribution:", "pvalue_target", &mut self.pvalue_target, &choices);
+                        ui.horizontal(|ui| {
+                            ui.label("Observed x =");
+                            ui.add(egui::DragValue::new(&mut self.pvalue_x).speed(0.01));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Shade:");
+                            ui.radio_value(&mut self.pvalue_tail, Tail::Lower, "Lower");
+                            ui.radio_value(&mut self.pvalue_tail, Tail::Upper, "Upper");
+                            ui.radio_value(&mut self.pvalue_tail, Tail::Two, "Two-tailed");
+                        });
+                        if let Some(dist) = self.pvalue_target.and_then(|id| self.distributions.get(&id)) {
+                            let (lower, upper, two) = tail_probabilities(dist, self.pvalue_x);
+                            ui.label(format!("P(X ≤ x) = {:.6}", lower));
+                            ui.label(format!("P(X ≥ x) = {:.6}", upper));
+                            ui.label(format!("Two-tailed = {:.6}", two))
+                                .on_hover_text("2 · min(P(X ≤ x), P(X ≥ x)): equal-probability tails on both sides");
+                        }
+                    
+                        ui.separator();
+                    
+                        // Central/highest-density interval, shaded with labeled endpoints
+                        ui.heading("Credible interval");
+                        distribution_picker(ui, "Distribution:", "credible_target", &mut self.credible_target, &choices);
+                        ui.horizontal(|ui| {
+                            ui.label("Level:");
+                            for level in [0.5, 0.8, 0.95] {
+                                ui.radio_value(&mut self.credible_level, level, format!("{:.0}%", 100.0 * level));
+                            }
+                        });
+                        if let Some(dist) = self.credible_target.and_then(|id| self.distributions.get(&id)) {
+                            let kind = if dist.has_closed_form() { "Equal-tailed" } else { "HPD" };
+                            for (lower, upper) in dist.credible_regions(self.credible_level) {
+                                ui.label(format!("{}: [{:.4}, {:.4}]", kind, lower, upper));
+                            }
+                        }
+                    
+                        ui.separator();
+                    
+                        // Distribution parameter controls
+                        let evidence = self.chain_evidence();
+                        let mut to_remove = Vec::new();
+                        let mut solo = None;
+                        let mut sample = None;
+                        for (id, dist) in self.distributions.iter_mut() {
+                            ui.group(|ui| {
+                                ui.horizontal(|ui| {
+                                    // Selection checkbox for multiplication
+                                    let mut selected = self.selected_for_multiplication.contains(id);
+                                    if ui.checkbox(&mut selected, "").clicked() {
+                                        if selected {
+                                            if !self.selected_for_multiplication.contains(id) {
+                                                self.selected_for_multiplication.push(*id);
+                                            }
+                                        } else {
+                                            self.selected_for_multiplication.retain(|&x| x != *id);
+                                        }
+                                    }
+                                
+                                    if ui.selectable_label(!dist.hidden, "👁")
+                                        .on_hover_text(if dist.hidden { "Show on the plot" } else { "Hide from the plot" })
+                                        .clicked()
+                                    {
+                                        dist.hidden = !dist.hidden;
+                                    }
+                                    if dist.hidden {
+                                        ui.weak(&dist.name);
+                                    } else {
+                                        ui.label(&dist.name);
+                                    }
+                                    if dist.is_derived() {
+                                        ui.label(format!("({})", dist.operation.label()));
+                                    }
+                                    if ui.small_button("🎲").on_hover_text("Sample N draws").clicked() {
+                                        sample = Some(*id);
+                                    }
+                                    if ui.small_button("Solo")
+                                        .on_hover_text("Hide every other curve; again to show them all")
+                                        .clicked()
+                                    {
+                                        solo = Some(*id);
+                                    }
+                                    if ui.small_button("✖").clicked() {
+                                        to_remove.push(*id);
+                                    }
+                                });
+                            
+                                // Only show parameter controls for input distributions
+                                if !dist.is_derived() {
+                                    let (mean_min, mean_max) = dist.ranges.mean;
+                                    let (std_dev_min, std_dev_max) = dist.ranges.std_dev;
+                                    ui.horizontal(|ui| {
+                                        ui.label("Mean:");
+                                        ui.add(egui::DragValue::new(&mut dist.mean)
+                                            .speed(0.1)
+                                            .range(mean_min..=mean_max));
+                                    });
+                                
+                                    ui.horizontal(|ui| {
+                                        ui.label("Std Dev:");
+                                        ui.add(egui::DragValue::new(&mut dist.std_dev)
+                                            .speed(0.01)
+                                            .range(std_dev_min..=std_dev_max));
+                                    });
+                                
+                                    // Slider versions
+                                    ui.add(egui::Slider::new(&mut dist.mean, mean_min..=mean_max)
+                                        .text("Mean"));
+                                    ui.add(egui::Slider::new(&mut dist.std_dev, std_dev_min..=std_dev_max)
+                                        .text("Std Dev"));
+                                    
+                                    if let Some(fit) = &dist.fit {
+                                        for line in fit.lines() {
+                                            ui.weak(line);
+                                        }
+                                    }
+                                    if let Some(notice) = &dist.load_notice {
+                                        let mut dismissed = false;
+                                        ui.horizontal_wrapped(|ui| {
+                                            ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", notice));
+                                            dismissed = ui.small_button("OK").clicked();
+                                        });
+                                        if dismissed {
+                                            dist.load_notice = None;
+                                        }
+                                    }
+
+                                    let mut uncertain = dist.uncertainty.is_some();
+                                    if ui.checkbox(&mut uncertain, "Parameter uncertainty band").changed() {
+                                        dist.uncertainty = uncertain.then(ParameterUncertainty::default);
+                                    }
+                                    if let Some(uncertainty) = &mut dist.uncertainty {
+                                        ui.horizontal(|ui| {
+                                            ui.label("sd(μ):");
+                                            ui.add(egui::DragValue::new(&mut uncertainty.mean_sd).speed(0.01).range(0.0..=10.0));
+                                            ui.label("sd(ln σ):");
+                                            ui.add(egui::DragValue::new(&mut uncertainty.log_std_dev_sd).speed(0.01).range(0.0..=2.0));
+                                        });
+                                        ui.add(egui::Slider::new(&mut uncertainty.level, 0.5..=0.99).text("band level"));
+                                    }
+                                } else {
+                                    // Show read-only info for product distributions
+                                    ui.label(format!("Mean: {:.3}", dist.mean));
+                                    ui.label(format!("Std Dev: {:.3}", dist.std_dev));
+                                    ui.label(format!("Parents: {:?}", dist.parent_ids));
+                                    match &mut dist.operation {
+                                        Operation::Temper { beta } => {
+                                            ui.add(egui::Slider::new(beta, 0.05..=10.0).logarithmic(true).text("β"));
+                                        }
+                                        Operation::IidSum { copies, .. } => {
+                                            ui.add(egui::Slider::new(copies, 1..=100).text("n"));
+                                        }
+                                        Operation::Kde { samples, bandwidth } => {
+                                            ui.label(format!("{} samples", samples.len()));
+                                            let before = *bandwidth;
+                                            let mut silverman = bandwidth.is_none();
+                                            ui.horizontal(|ui| {
+                                                ui.checkbox(&mut silverman, "Silverman's rule");
+                                                if let Some(h) = bandwidth.as_mut() {
+                                                    let speed = *h * 0.01;
+                                                    ui.add(egui::DragValue::new(h).speed(speed).range(1e-9..=f64::INFINITY).prefix("h = "));
+                                                }
+                                            });
+                                            *bandwidth = match (silverman, *bandwidth) {
+                                                (true, _) => None,
+                                                // A manual bandwidth starts from the rule's value
+                                                (false, None) => {
+                                                    let mut sorted = samples.clone();
+                                                    sorted.sort_by(f64::total_cmp);
+                                                    Some(data::silverman_bandwidth(&sorted))
+                                                }
+                                                (false, chosen) => chosen,
+                                            };
+                                            if *bandwidth != before {
+                                                // Rebuilt by the update below
+                                                dist.grid = None;
+                                            }
+                                        }
+                                        Operation::Family { family, parameters } => {
+                                            let before = *parameters;
+                                            ui.horizontal(|ui| {
+                                                for (name, value) in family.parameter_names().into_iter().zip(parameters.iter_mut()) {
+                                                    let speed = *value * 0.01;
+                                                    ui.add(egui::DragValue::new(value).speed(speed).range(1e-6..=f64::INFINITY).prefix(format!("{} = ", name)));
+                                                }
+                                            });
+                                            if *parameters != before {
+                                                dist.grid = None;
+                                            }
+                                        }
+                                        Operation::Mixture { weights, by_evidence } => {
+                                            ui.checkbox(by_evidence, "Evidence-proportional weights");
+                                            ui.horizontal_wrapped(|ui| {
+                                                for (i, weight) in weights.iter_mut().enumerate() {
+                                                    ui.label(format!("w{}", i + 1));
+                                                    ui.add_enabled(
+                                                        !*by_evidence,
+                                                        egui::DragValue::new(weight).speed(0.01).range(0.0..=f64::INFINITY),
+                                                    );
+                                                }
+                                            });
+                                        }
+                                        _ => {}
+                                    }
+                                    match (&dist.operation, dist.normalizing_constant) {
+                                        (Operation::BayesUpdate, Some(evidence)) => {
+                                            ui.label(format!("Evidence p(D): {:.6} (log {:.4})", evidence, evidence.ln()));
+                                        }
+                                        (Operation::Truncate { lower, upper }, Some(probability)) => {
+                                            ui.label(format!("P({:.3} ≤ X ≤ {:.3}) = {:.4}", lower, upper, probability));
+                                        }
+                                        (Operation::Product, Some(scale)) => {
+                                            ui.label(format!("Scale S: {:.6} (raw product = S × shown density)", scale))
+                                                .on_hover_text("The product of PDFs is only proportional to a PDF; \
+                                                                S = ∫ Π fᵢ(x) dx is the factor removed by renormalizing");
+                                            ui.label(format!("log S: {:.4}", scale.ln()));
+                                            ui.checkbox(&mut dist.show_unnormalized, "Plot unnormalized product");
+                                        }
+                                        _ => {}
+                                    }
+                                    if let Some(fit) = &dist.fit {
+                                        for line in fit.lines() {
+                                            ui.weak(line);
+                                        }
+                                    }
+                                    if let (Some(total), Some(own)) = (evidence.get(id), dist.normalizing_constant) {
+                                        if (total - own).abs() > 1e-12 * own.abs() {
+                                            ui.label(format!("Total evidence incl. upstream products: {:.6} (log {:.4})", total, total.ln()))
+                                                .on_hover_text("Marginal likelihood of the whole chain: ∫ of the raw product of all input densities");
+                                        }
+                                    }
+                                    if let Some(warning) = &dist.warning {
+                                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", warning));
+                                    }
+                                }
+                            
+                                // Optional evaluation domain, independent of the plot view
+                                let mut restricted = dist.domain.is_some();
+                                if ui.checkbox(&mut restricted, "Restrict domain").changed() {
+                                    dist.domain = restricted.then_some(
+                                        (dist.mean - 3.0 * dist.std_dev, dist.mean + 3.0 * dist.std_dev),
+                                    );
+                                }
+                                if let Some((lower, upper)) = &mut dist.domain {
+                                    ui.horizontal(|ui| {
+                                        ui.label("x ∈ [");
+                                        ui.add(egui::DragValue::new(lower).speed(0.05));
+                                        ui.label(",");
+                                        ui.add(egui::DragValue::new(upper).speed(0.05));
+                                        ui.label("]");
+                                    });
+                                    *upper = upper.max(*lower + 0.01);
+                                }
+
+                                egui::CollapsingHeader::new("Line style")
+                                    .id_salt(("line_style", *id))
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            for style in StrokeStyle::ALL {
+                                                ui.radio_value(&mut dist.line_style, style, style.label());
+                                            }
+                                        });
+                                        ui.add(egui::Slider::new(&mut dist.line_width, 0.5..=6.0).text("width (px)"));
+                                        ui.checkbox(&mut dist.secondary_axis, "Secondary y-axis (right)")
+                                            .on_hover_text("For curves much taller or flatter than the rest: scaled to fit, read off the right-hand axis");
+                                    });
+
+                                egui::CollapsingHeader::new("Markers")
+                                    .id_salt(("markers", *id))
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            for kind in [MarkerKind::Sigmas, MarkerKind::Quantiles, MarkerKind::Values] {
+                                                if ui.radio(dist.markers.kind == kind, kind.label()).clicked() && dist.markers.kind != kind {
+                                                    dist.markers = MarkerScheme { kind, values: kind.default_values(dist) };
+                                                    self.marker_text.remove(id);
+                                                }
+                                            }
+                                        });
+                                        let text = self.marker_text.entry(*id).or_insert_with(|| format_marker_values(&dist.markers.values));
+                                        let valid = parse_marker_values(text);
+                                        let edit = egui::TextEdit::singleline(text).hint_text("comma-separated, e.g. 2.5, 50, 97.5");
+                                        if ui.add(edit).changed() {
+                                            if let Some(values) = parse_marker_values(text) {
+                                                dist.markers.values = values;
+                                            }
+                                        }
+                                        if valid.is_none() {
+                                            ui.colored_label(egui::Color32::from_rgb(230, 140, 0), "⚠ Not a list of numbers");
+                                        }
+                                    });
+
+                                // Only computed while expanded: numeric shapes need a quadrature pass
+                                egui::CollapsingHeader::new("Moments")
+                                    .id_salt(("moments", *id))
+                                    .show(ui, |ui| {
+                                        let summary = dist.moment_summary();
+                                        egui::Grid::new(("moments_grid", *id)).show(ui, |ui| {
+                                            for (label, value) in [
+                                                ("Mean", summary.mean),
+                                                ("Std dev", summary.std_dev),
+                                                ("Skewness", summary.skewness),
+                                                ("Excess kurtosis", summary.excess_kurtosis),
+                                                ("Mode", summary.mode),
+                                                ("Median", summary.median),
+                                            ] {
+                                                ui.label(label);
+                                                ui.label(format!("{:.4}", value));
+                                                ui.end_row();
+                                            }
+                                        });
+                                        if !dist.has_closed_form() {
+                                            ui.weak("Numerical, over the plotted support");
+                                        }
+                                    });
+                            });
+                        }
+                        if let Some(id) = solo {
+                            self.isolate_curve(id);
+                        }
+                        if let Some(id) = sample {
+                            self.sampler.target = Some(id);
+                            self.sampler.open = true;
+                        }
+                    
+                        // Remove marked distributions
+                        for id in to_remove {
+                            for group in &mut self.groups {
+                                group.members.retain(|&member| member != id);
+                            }
+                            if self.z_reference == Some(id) {
+                                self.set_z_reference(None);
+                            }
+                            self.distributions.remove(&id);
+                            // Also remove from selection
+                            self.selected_for_multiplication.retain(|&x| x != id);
+                        }
+                    
+                        // Update product distributions when their parents change
+                        self.update_product_distributions();
+                    });
+                });
+                
+                ui.separator();
+                
+                // Right panel for plot
+                ui.vertical(|ui| {
+                    ui.heading("Probability Density Functions");
+                    ui.horizontal(|ui| {
+                        ui.label("Show:");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::Density, "Density");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::Cdf, "CDF F(x)");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::Survival, "Survival S(x)");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::Hazard, "Hazard h(x)");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::LogDensity, "ln f(x)");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::Mgf, "MGF M(t)");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::CharacteristicFunction, "|φ(t)|");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::QuantileQuantile, "Q-Q")
+                            .on_hover_text("Data or distribution quantiles against a theoretical distribution");
+                        ui.radio_value(&mut self.plot_mode, PlotMode::ProbabilityProbability, "P-P")
+                            .on_hover_text("Data or distribution cumulative probabilities against a theoretical distribution");
+                        ui.separator();
+                        ui.label("Layout:");
+                        ui.radio_value(&mut self.plot_layout, PlotLayout::Single, "Single");
+                        ui.radio_value(&mut self.plot_layout, PlotLayout::PdfOverCdf, "PDF + CDF")
+                            .on_hover_text("CDF below the main plot, with the x-axis and cursor linked");
+                        ui.radio_value(&mut self.plot_layout, PlotLayout::PdfOverRatio, "PDF + ratio")
+                            .on_hover_text("Likelihood ratio f(x)/g(x) of two curves below the main plot");
+                        ui.radio_value(&mut self.plot_layout, PlotLayout::SmallMultiples, "Small multiples")
+                            .on_hover_text("Each distribution, or each group, in its own plot over the same x-range");
+                        ui.radio_value(&mut self.plot_layout, PlotLayout::Violin, "Violin")
+                            .on_hover_text("Each density mirrored about its own baseline, stacked vertically");
+                    });
+                    if self.plot_layout == PlotLayout::PdfOverRatio {
+                        let choices = self.distribution_choices();
+                        ui.horizontal(|ui| {
+                            distribution_picker(ui, "Ratio f:", "ratio_numerator", &mut self.ratio_pair.0, &choices);
+                            distribution_picker(ui, "over g:", "ratio_denominator", &mut self.ratio_pair.1, &choices);
+                        });
+                    }
+                    if matches!(self.plot_mode, PlotMode::Mgf | PlotMode::CharacteristicFunction) {
+                        self.transform_plot(ui);
+                        return;
+                    }
+                    if matches!(self.plot_mode, PlotMode::QuantileQuantile | PlotMode::ProbabilityProbability) {
+                        self.probability_plot(ui);
+                        return;
+                    }
+                    if self.plot_layout == PlotLayout::SmallMultiples {
+                        self.small_multiples(ui);
+                        return;
+                    }
+                    if self.plot_layout == PlotLayout::Violin {
+                        self.violin_plot(ui);
+                        return;
+                    }
+                    
+                    // Plot controls
+                    let locked = self.view_lock.is_some();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!locked, egui::Button::new("Reset View")).clicked() {
+                            self.plot_bounds = None;
+                            self.requested_bounds = None;
+                            self.reset_plot = true;
+                        }
+                        if ui.add_enabled(!locked, egui::Button::new("Auto-fit")).clicked() {
+                            self.auto_fit_view();
+                        }
+                        if ui.button("📋 Copy Curve Data (CSV)").clicked() {
+                            let csv = self.export_curve_csv();
+                            ui.output_mut(|o| o.copied_text = csv);
+                        }
+                        if ui.button("🖼 Export PNG").clicked() {
+                            self.png_export.open = true;
+                        }
+                        ui.radio_value(&mut self.export_full_curves, false, "Current view");
+                        ui.radio_value(&mut self.export_full_curves, true, "Full curves");
+                        if !locked {
+                            ui.label("| Mouse: drag to pan, scroll to zoom");
+                        }
+                    });
+                    
+                    // Exact axis bounds, so figures from different sessions share one framing
+                    ui.horizontal(|ui| {
+                        let [x_min, x_max, y_min, y_max] = &mut self.view_input;
+                        ui.label("x ∈ [");
+                        ui.add(egui::DragValue::new(x_min).speed(0.1));
+                        ui.label(",");
+                        ui.add(egui::DragValue::new(x_max).speed(0.1));
+                        ui.label("]  y ∈ [");
+                        ui.add(egui::DragValue::new(y_min).speed(0.01));
+                        ui.label(",");
+                        ui.add(egui::DragValue::new(y_max).speed(0.01));
+                        ui.label("]");
+                        *x_max = x_max.max(*x_min + 1e-6);
+                        *y_max = y_max.max(*y_min + 1e-6);
+                        
+                        if ui.add_enabled(!locked, egui::Button::new("Apply")).clicked() {
+                            self.plot_bounds = Some(view_bounds(self.view_input));
+                            self.requested_bounds = self.plot_bounds;
+                        }
+                        if ui.add_enabled(!locked, egui::Button::new("From view")).clicked() {
+                            if let Some(bounds) = &self.plot_bounds {
+                                self.view_input = bounds_array(bounds);
+                            }
+                        }
+                        let mut lock = locked;
+                        ui.checkbox(&mut lock, "🔒 Lock view")
+                            .on_hover_text("Fix the view to these bounds: pan and zoom are disabled, and the lock is saved with the session");
+                        self.view_lock = lock.then_some(self.view_input);
+                    });
+                    
+                    let heights = self.plot_layout.heights(ui.available_height());
+                    self.density_plot(ui, None);
+                    
+                    match self.plot_layout {
+                        PlotLayout::Single | PlotLayout::SmallMultiples | PlotLayout::Violin => {}
+                        PlotLayout::PdfOverCdf => self.cdf_subplot(ui, heights[1], locked),
+                        PlotLayout::PdfOverRatio => self.ratio_subplot(ui, heights[1], locked),
+                    }
+                });
+            });
+        });
+        
+        if self.show_cheat_sheet {
+            let markdown = self.algebra_cheat_sheet();
+            let export = &mut self.cheat_sheet_export;
+            egui::Window::new("Distribution Algebra Cheat Sheet")
+                .open(&mut self.show_cheat_sheet)
+                .default_width(520.0)
+                .show(ctx, |ui| {
+                    // Saved or copied as Markdown
+                    export.controls(ui, || Ok(markdown.clone()));
+                    ui.separator();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.monospace(&markdown);
+                    });
+                });
+        }
+        
+        if self.importance.open {
+            self.importance_window(ctx);
+        }
+
+        if self.sampler.open {
+            self.sample_window(ctx);
+        }
+
+        if self.monte_carlo.open {
+            self.monte_carlo_window(ctx);
+        }
+
+        if self.png_export.open {
+            self.png_export_window(ctx);
+        }
+        
+        if self.comparison.open {
+            self.comparison_window(ctx);
+        }
+        
+        if self.joint.open {
+            self.joint_window(ctx);
+        }
+        
+        if self.share.open {
+            self.share_window(ctx);
+        }
+        
+        if self.show_updates {
+            self.updates_window(ctx);
+        }
+        
+        if self.show_welcome {
+            self.welcome_window(ctx);
+        }
+        
+        if self.timeline.recording {
+            let time = self.timeline.started.map_or(0.0, |started| started.elapsed().as_secs_f64());
+            self.record_keyframe(time);
+        }
+        
+        // A drag becomes one step when it is released, not one per frame
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.record_history();
+        }
+
+        if self.run_auto_export(std::time::Instant::now()) {
+            ctx.request_repaint();
+        }
+        self.recovery_window(ctx);
+        self.run_autosave(std::time::Instant::now());
+        if self.autosave.path.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(AUTOSAVE_SECONDS));
+        }
+        if let ExportTrigger::Every { seconds } = self.auto_export.trigger {
+            if self.auto_export.enabled {
+                ctx.request_repaint_after(std::time::Duration::from_secs(u64::from(seconds.max(1))));
+            }
+        }
+        
+        if let Some(session) = &mut self.sync {
+            session.publish(&self.distributions);
+            if let Some(bounds) = &self.plot_bounds {
+                session.publish_view([bounds.min()[0], bounds.max()[0], bounds.min()[1], bounds.max()[1]]);
+            }
+        }
+    }
+}
+
+impl PdfViewerApp {
+    /// Open/save dialog for session files, starting where the last one was
+    fn session_dialog(&self) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("Session", &["json"])
+            .add_filter("Compact session", &[session::BINARY_EXTENSION]);
+        match self.session_path.as_deref().and_then(std::path::Path::parent) {
+            Some(directory) => dialog.set_directory(directory),
+            None => dialog,
+        }
+    }
+
+    fn workspace_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut switch = None;
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            let closable = self.workspaces.tabs.len() > 1;
+            for (index, tab) in self.workspaces.tabs.iter_mut().enumerate() {
+                let response = ui.selectable_label(index == self.workspaces.active, &tab.name);
+                if response.clicked() {
+                    switch = Some(index);
+                }
+                response.context_menu(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut tab.name);
+                    });
+                    if ui.add_enabled(closable, egui::Button::new("Close tab")).clicked() {
+                        close = Some(index);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if ui.button("➕").on_hover_text("New workspace; right-click a tab to rename or close it").clicked() {
+                self.session_status = self.add_workspace().err().map(Err);
+            }
+        });
+        let result = match (switch, close) {
+            (_, Some(index)) => self.close_workspace(index),
+            (Some(index), None) => self.switch_workspace(index),
+            (None, None) => Ok(()),
+        };
+        if let Err(error) = result {
+            self.session_status = Some(Err(error));
+        }
+    }
+
+    /// Host / join controls for live session sharing over the LAN
+    fn sync_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Live Sync (experimental)").show(ui, |ui| {
+            if let Some(session) = &self.sync {
+                match session.role() {
+                    sync::Role::Host => ui.label(format!(
+                        "Hosting on {} · {} connected", session.address(), session.peer_count()
+                    )),
+                    sync::Role::Client if session.peer_count() == 0 => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), "⚠ Host disconnected")
+                    }
+                    sync::Role::Client => ui.label(format!("Following {}", session.address())),
+                };
+                let mut stop = false;
+                if let Some(session) = &mut self.sync {
+                    match session.role() {
+                        sync::Role::Host => {
+                            let mut read_only = session.read_only();
+                            ui.checkbox(&mut read_only, "Broadcast only (viewers can't edit)");
+                            session.set_read_only(read_only);
+                        }
+                        sync::Role::Client => {
+                            if session.read_only() {
+                                ui.label("👁 View only: the host broadcasts this session");
+                            }
+                            ui.checkbox(&mut self.follow_presenter, "Follow presenter's view");
+                        }
+                    }
+                    stop = ui.button("Stop Sync").clicked();
+                }
+                if stop {
+                    self.sync = None;
+                }
+                return;
+            }
+        
+            ui.horizontal(|ui| {
+                ui.label("Interface:");
+                ui.add(egui::TextEdit::singleline(&mut self.sync_interface).desired_width(110.0))
+                    .on_hover_text("127.0.0.1 accepts connections from this computer only. \
+                        0.0.0.0 lets anyone on the network join, without a password.");
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut self.sync_port).range(1024..=65535));
+                if ui.button("Host Session").clicked() {
+                    let result = sync::SyncSession::host(self.sync_interface.trim(), self.sync_port);
+                    self.start_sync(result);
+                }
+            });
+            let mut join = false;
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.sync_address)
+                    .on_hover_text("Address of the hosting instance, as host:port");
+                if ui.button("Join").clicked() {
+                    join = self.distributions.is_empty();
+                    self.sync_join_pending = !join;
+                }
+            });
+            if self.sync_join_pending {
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 140, 0),
+                    format!("⚠ Joining replaces your {} distributions with the host's session.", self.distributions.len()),
+                );
+                ui.horizontal(|ui| {
+                    join = ui.button("Join Anyway").clicked();
+                    if join || ui.button("Cancel").clicked() {
+                        self.sync_join_pending = false;
+                    }
+                });
+            }
+            if join {
+                let result = sync::SyncSession::connect(&self.sync_address);
+                // A joining instance adopts the host's session
+                if result.is_ok() {
+                    self.distributions.clear();
+                    self.selected_for_multiplication.clear();
+                    self.next_id = 0;
+                }
+                self.start_sync(result);
+            }
+            if let Some(error) = &self.sync_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+    }
+
+    /// Mark curves whose peak is above the visible y-range with an arrow and the
+    /// peak value at the top edge; clicking a marker rescales y to show that peak
+    fn show_off_scale_peaks(&mut self, plot_ui: &mut egui_plot::PlotUi, peaks: &[([f64; 2], egui::Color32)]) {
+        let bounds = plot_ui.plot_bounds();
+        let (top, height, width) = (bounds.max()[1], bounds.height(), bounds.width());
+        let clipped: Vec<&([f64; 2], egui::Color32)> = peaks.iter().filter(|([_, y], _)| *y > top).collect();
+
+        for &&([x, y], color) in &clipped {
+            plot_ui.arrows(egui_plot::Arrows::new(
+                vec![[x, top - 0.12 * height]],
+                vec![[x, top - 0.02 * height]],
+            ).color(color));
+            plot_ui.text(egui_plot::Text::new(
+                egui_plot::PlotPoint::new(x, top - 0.13 * height),
+                format!("peak {:.3}", y),
+            ).color(color).anchor(egui::Align2::CENTER_TOP));
+        }
+
+        let clicked = plot_ui.response().clicked().then(|| plot_ui.pointer_coordinate()).flatten();
+        let Some(pointer) = clicked else {
+            return;
+        };
+        let target = clipped.iter()
+            .filter(|([x, _], _)| (x - pointer.x).abs() < 0.03 * width && pointer.y > top - 0.2 * height)
+            .min_by(|a, b| (a.0[0] - pointer.x).abs().total_cmp(&(b.0[0] - pointer.x).abs()));
+        if let Some(([_, peak], _)) = target {
+            let rescaled = rescale_to_peak(bounds, *peak);
+            if let Some(view) = &mut self.view_lock {
+                view[3] = rescaled.max()[1];
+                self.view_input = *view;
+            }
+            self.plot_bounds = Some(rescaled);
+            self.requested_bounds = Some(rescaled);
+        }
+    }
+
+    /// Heatmap of the joint density of two distributions under a Gaussian
+    /// copula, rebuilt only when an input changes
+    fn joint_window(&mut self, ctx: &egui::Context) {
+        let choices = self.distribution_choices();
+        let mut open = self.joint.open;
+        egui::Window::new("Joint Density")
+            .open(&mut open)
+            .default_width(440.0)
+            .show(ctx, |ui| {
+                let tool = &mut self.joint;
+                distribution_picker(ui, "X:", "joint_x", &mut tool.x, &choices);
+                distribution_picker(ui, "Y:", "joint_y", &mut tool.y, &choices);
+                ui.add(egui::Slider::new(&mut tool.rho, -bivariate::MAX_CORRELATION..=bivariate::MAX_CORRELATION).text("ρ"))
+                    .on_hover_text("Gaussian copula correlation; for two plain Gaussians this is the bivariate normal");
+                ui.horizontal(|ui| {
+                    for colormap in Colormap::ALL {
+                        ui.radio_value(&mut tool.colormap, colormap, colormap.label());
+                    }
+                });
+                ui.add(egui::Slider::new(&mut tool.resolution, 40..=300).text("cells per axis"));
+
+                let (Some(x_dist), Some(y_dist)) = (
+                    tool.x.and_then(|id| self.distributions.get(&id)),
+                    tool.y.and_then(|id| self.distributions.get(&id)),
+                ) else {
+                    ui.label("Pick two distributions.");
+                    return;
+                };
+                let (x_range, y_range) = (Self::joint_range(x_dist), Self::joint_range(y_dist));
+                let key = (x_dist.density_fingerprint(), y_dist.density_fingerprint(), tool.rho.to_bits(), tool.colormap as usize, tool.resolution);
+                if tool.computed_for != Some(key) || tool.texture.is_none() {
+                    let raster = bivariate::density_raster(x_dist, y_dist, tool.rho, x_range, y_range, tool.resolution, tool.resolution);
+                    let pixels: Vec<egui::Color32> = raster.values.iter()
+                        .map(|value| tool.colormap.color(if raster.max > 0.0 { value / raster.max } else { 0.0 }))
+                        .collect();
+                    let image = egui::ColorImage { size: [raster.width, raster.height], pixels };
+                    tool.texture = Some(ui.ctx().load_texture("joint_density", image, egui::TextureOptions::LINEAR));
+                    tool.computed_for = Some(key);
+                }
+                let texture = tool.texture.as_ref().unwrap();
+                let center = egui_plot::PlotPoint::new(0.5 * (x_range.0 + x_range.1), 0.5 * (y_range.0 + y_range.1));
+                let size = egui::vec2((x_range.1 - x_range.0) as f32, (y_range.1 - y_range.0) as f32);
+                let (x_dist, y_dist, rho) = (x_dist.clone(), y_dist.clone(), tool.rho);
+                Plot::new("joint_density_plot")
+                    .view_aspect(1.0)
+                    .data_aspect(size.x / size.y)
+                    .x_axis_label(&x_dist.name)
+                    .y_axis_label(&y_dist.name)
+                    .label_formatter(move |_, value| {
+                        let density = bivariate::joint_density(&x_dist, &y_dist, rho, value.x, value.y);
+                        format!("x = {:.4}\ny = {:.4}\nf(x, y) = {:.6}", value.x, value.y, density)
+                    })
+                    .show(ui, |plot_ui| {
+                        plot_ui.image(egui_plot::PlotImage::new(texture, center, size));
+                    });
+                colorbar(ui, "f(x, y) relative to its maximum", 0.0, 1.0, tool.colormap);
+            });
+        self.joint.open = open;
+    }
+
+    /// Reweight draws from a proposal q by p/q to approximate a target p, showing
+    /// the weights and the effective sample size (and how badly it can collapse)
+    fn importance_window(&mut self, ctx: &egui::Context) {
+        let choices = self.distribution_choices();
+        let mut open = self.importance.open;
+        egui::Window::new("Importance Sampling")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                let tool = &mut self.importance;
+                distribution_picker(ui, "Proposal q:", "importance_proposal", &mut tool.proposal, &choices);
+                distribution_picker(ui, "Target p:", "importance_target", &mut tool.target, &choices);
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut tool.samples, 10..=10_000).logarithmic(true).text("draws"));
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut tool.seed));
+                    if ui.button("🎲").on_hover_text("Draw with a new seed").clicked() {
+                        tool.seed = tool.seed.wrapping_add(1);
+                    }
+                });
+                
+                self.refresh_importance();
+                let (Some(report), Some(proposal), Some(target)) = (
+                    &self.importance.report,
+                    self.importance.proposal.and_then(|id| self.distributions.get(&id)),
+                    self.importance.target.and_then(|id| self.distributions.get(&id)),
+                ) else {
+                    ui.label("Pick a proposal and a target distribution.");
+                    return;
+                };
+                
+                let n = report.samples.len() as f64;
+                ui.separator();
+                ui.label(format!(
+                    "Effective sample size: {:.1} of {} ({:.1}%)",
+                    report.effective_sample_size, report.samples.len(), 100.0 * report.effective_sample_size / n
+                ));
+                ui.label(format!("Largest weight: {:.4} (uniform would be {:.4})", report.max_weight(), 1.0 / n));
+                if report.mean_estimate.is_nan() {
+                    ui.colored_label(egui::Color32::from_rgb(230, 140, 0), "⚠ No draw lands where the target has mass");
+                } else {
+                    ui.label(format!("Estimated target mean: {:.4} (target mean {:.4})", report.mean_estimate, target.mean));
+                }
+                if report.effective_sample_size < 0.1 * n {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(230, 140, 0),
+                        "⚠ Weights are degenerate: a few draws carry most of the mass. Try a wider proposal.",
+                    );
+                }
+                
+                // Weighted histogram of the draws against both densities
+                let (x_min, x_max) = report.samples.iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+                let bins = 40;
+                let width = ((x_max - x_min) / bins as f64).max(1e-9);
+                let mut heights = vec![0.0; bins];
+                for (x, w) in report.samples.iter().zip(&report.weights) {
+                    let bin = (((x - x_min) / width) as usize).min(bins - 1);
+                    heights[bin] += w / width;
+                }
+                let bars: Vec<egui_plot::Bar> = heights.iter().enumerate()
+                    .map(|(i, h)| egui_plot::Bar::new(x_min + width * (i as f64 + 0.5), *h).width(width))
+                    .collect();
+                let density_line = |dist: &GaussianDistribution, color| {
+                    let points: Vec<[f64; 2]> = (0..=200)
+                        .map(|i| x_min + (x_max - x_min) * i as f64 / 200.0)
+                        .map(|x| [x, dist.evaluate(x)])
+                        .collect();
+                    Line::new(points).name(&dist.name).color(color)
+                };
+                Plot::new("importance_histogram")
+                    .height(180.0)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |plot_ui| {
+                        plot_ui.bar_chart(egui_plot::BarChart::new(bars)
+                            .name("Weighted draws")
+                            .color(egui::Color32::from_rgba_unmultiplied(120, 120, 120, 120)));
+                        plot_ui.line(density_line(proposal, egui::Color32::BLUE));
+                        plot_ui.line(density_line(target, egui::Color32::RED));
+                    });
+                
+                ui.label("Normalized weights w̃ᵢ at each draw xᵢ:");
+                let weights: Vec<[f64; 2]> = report.samples.iter().zip(&report.weights).map(|(&x, &w)| [x, w]).collect();
+                Plot::new("importance_weights")
+                    .height(140.0)
+                    .show(ui, |plot_ui| {
+                        plot_ui.points(egui_plot::Points::new(weights).stems(0.0).radius(1.5));
+                    });
+            });
+        self.importance.open = open;
+    }
+
+    /// Seeded draws from any distribution, derived ones included, shown as a
+    /// histogram and rug against its density and exportable as CSV
+    fn sample_window(&mut self, ctx: &egui::Context) {
+        let choices = self.distribution_choices();
+        let mut open = self.sampler.open;
+        egui::Window::new("Random Samples")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let tool = &mut self.sampler;
+                distribution_picker(ui, "Distribution:", "sample_target", &mut tool.target, &choices);
+                let mut draw = false;
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut tool.count).range(1..=MAX_SAMPLE_DRAWS).prefix("N = "));
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut tool.seed));
+                    draw = ui.add_enabled(tool.target.is_some(), egui::Button::new("Sample N draws"))
+                        .on_hover_text("The same seed always gives the same draws")
+                        .clicked();
+                });
+                if draw {
+                    if let Err(error) = self.draw_samples() {
+                        self.sampler.status = Some(Err(error));
+                    }
+                }
+
+                let tool = &mut self.sampler;
+                if let Some(draws) = &tool.draws {
+                    let (mean, variance) = data::moments(&draws.values);
+                    ui.label(format!("{} draws from {} with seed {}", draws.values.len(), draws.name, draws.seed));
+                    ui.label(format!("Sample mean {:.4}, sd {:.4}", mean, variance.sqrt()));
+
+                    let bars: Vec<egui_plot::Bar> = draws.histogram.densities()
+                        .into_iter()
+                        .map(|(left, right, density)| egui_plot::Bar::new(0.5 * (left + right), density).width(right - left))
+                        .collect();
+                    let (x_min, x_max) = draws.values.iter()
+                        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+                    let density = tool.target.and_then(|id| self.distributions.get(&id)).map(|dist| {
+                        let points: Vec<[f64; 2]> = (0..=200)
+                            .map(|i| x_min + (x_max - x_min) * i as f64 / 200.0)
+                            .map(|x| [x, dist.evaluate(x)])
+                            .collect();
+                        Line::new(points).name(&dist.name).color(egui::Color32::BLUE)
+                    });
+                    let rug: Vec<[f64; 2]> = draws.values.iter().take(MAX_RUG_DRAWS).map(|&x| [x, 0.0]).collect();
+                    Plot::new("sample_histogram")
+                        .height(200.0)
+                        .legend(egui_plot::Legend::default())
+                        .show(ui, |plot_ui| {
+                            plot_ui.bar_chart(egui_plot::BarChart::new(bars)
+                                .name("Draws")
+                                .color(egui::Color32::from_rgba_unmultiplied(120, 120, 120, 120)));
+                            if let Some(density) = density {
+                                plot_ui.line(density);
+                            }
+                            plot_ui.points(egui_plot::Points::new(rug)
+                                .shape(egui_plot::MarkerShape::Up)
+                                .radius(3.0)
+                                .color(egui::Color32::from_gray(90)));
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("CSV file:");
+                        ui.text_edit_singleline(&mut tool.path);
+                        if ui.button("Export").clicked() {
+                            let path = std::path::Path::new(tool.path.trim());
+                            tool.status = Some(match write_replacing(path, draws.csv()) {
+                                Ok(()) => Ok(format!("Wrote {} draws to {}", draws.values.len(), path.display())),
+                                Err(error) => Err(format!("Cannot write {}: {}", path.display(), error)),
+                            });
+                        }
+                    });
+                }
+                match &tool.status {
+                    Some(Ok(message)) => {
+                        ui.weak(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                    }
+                    None => {}
+                }
+            });
+        self.sampler.open = open;
+    }
+
+    /// The main plot in its current view, drawn offscreen at `width` × `height`
+    /// pixels and encoded as PNG
+    pub fn export_png(&mut self, width: u32, height: u32, scale: f32, style: egui::Style) -> Result<Vec<u8>, String> {
+        raster::encode_png(&self.plot_scene(width, height, scale, style)?.rasterize())
+    }
+
+    /// The main plot in its current view, drawn offscreen but not yet filled in
+    fn plot_scene(&mut self, width: u32, height: u32, scale: f32, style: egui::Style) -> Result<raster::Scene, String> {
+        // The offscreen plot must not take what the on-screen one picks up next
+        // frame, nor leave its own bounds behind
+        let (requested, reset, bounds) = (self.requested_bounds, std::mem::take(&mut self.reset_plot), self.plot_bounds);
+        let scene = raster::render(width, height, scale, style, |ctx| {
+            self.requested_bounds = bounds;
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let size = ui.available_size();
+                self.density_plot(ui, Some(size));
+            });
+        });
+        self.requested_bounds = requested;
+        self.reset_plot = reset;
+        self.plot_bounds = bounds;
+        scene
+    }
+
+    fn png_export_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.png_export.open;
+        let mut export = false;
+        egui::Window::new("Export PNG")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let tool = &mut self.png_export;
+                egui::Grid::new("png_export").num_columns(2).show(ui, |ui| {
+                    ui.label("Size:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut tool.width).range(16..=raster::MAX_SIDE).suffix(" px"));
+                        ui.label("×");
+                        ui.add(egui::DragValue::new(&mut tool.height).range(16..=raster::MAX_SIDE).suffix(" px"));
+                    });
+                    ui.end_row();
+                    ui.label("Scale:");
+                    ui.add(egui::DragValue::new(&mut tool.scale).range(0.5..=8.0).speed(0.05).suffix("×"))
+                        .on_hover_text("Pixels per point: larger makes text and lines bigger relative to the image");
+                    ui.end_row();
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut tool.path);
+                    ui.end_row();
+                });
+                ui.weak("The plot as shown, in its current view, with shading, markers and annotations");
+                if let Some(pending) = &tool.pending {
+                    match pending.try_recv() {
+                        Ok(status) => tool.status = Some(status),
+                        Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => tool.status = Some(Err("The export stopped unexpectedly".to_string())),
+                    }
+                    if tool.status.is_some() {
+                        tool.pending = None;
+                    }
+                }
+                let ready = tool.pending.is_none() && !tool.path.trim().is_empty();
+                export = ui.add_enabled(ready, egui::Button::new("Export")).clicked();
+                if tool.pending.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.weak("Rendering…");
+                    });
+                    ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+                }
+                match &tool.status {
+                    Some(Ok(message)) => {
+                        ui.weak(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                    }
+                    None => {}
+                }
+            });
+        if export {
+            let (width, height, scale) = (self.png_export.width, self.png_export.height, self.png_export.scale);
+            let path = std::path::PathBuf::from(self.png_export.path.trim());
+            match self.plot_scene(width, height, scale, (*ctx.style()).clone()) {
+                Ok(scene) => {
+                    // Filling and encoding take a while at large sizes, so the UI goes on meanwhile
+                    let (sender, receiver) = std::sync::mpsc::channel();
+                    std::thread::spawn(move || {
+                        let status = raster::encode_png(&scene.rasterize()).and_then(|bytes| {
+                            write_replacing(&path, bytes).map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+                        });
+                        let _ = sender.send(status.map(|()| format!("Wrote {} × {} plot to {}", width, height, path.display())));
+                    });
+                    self.png_export.status = None;
+                    self.png_export.pending = Some(receiver);
+                }
+                Err(error) => self.png_export.status = Some(Err(error)),
+            }
+        }
+        self.png_export.open = open;
+    }
+
+    /// Estimate the distribution of a formula over named distributions by
+    /// sampling them, with the exact normal alongside when there is one
+    fn monte_carlo_window(&mut self, ctx: &egui::Context) {
+        let choices = self.distribution_choices();
+        let mut open = self.monte_carlo.open;
+        egui::Window::new("Monte Carlo")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Formula:");
+                    ui.text_edit_singleline(&mut self.monte_carlo.formula)
+                        .on_hover_text("Names are variables, e.g. Z = X*Y + 2 or W = exp(A) / (1 + B^2); the inputs are drawn independently");
+                });
+                let variables = match Self::monte_carlo_formula(&self.monte_carlo.formula) {
+                    Ok((_, _, variables)) => variables,
+                    Err(error) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                        Vec::new()
+                    }
+                };
+                for variable in &variables {
+                    let mut selected = self.monte_carlo_binding(variable);
+                    distribution_picker(ui, &format!("{} ~", variable), &format!("monte_carlo_{}", variable), &mut selected, &choices);
+                    match selected {
+                        Some(id) => self.monte_carlo.bindings.insert(variable.clone(), id),
+                        None => self.monte_carlo.bindings.remove(variable),
+                    };
+                }
+                let tool = &mut self.monte_carlo;
+                let mut run = false;
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut tool.count, 100..=MAX_MONTE_CARLO_DRAWS).logarithmic(true).text("draws"));
+                    ui.label("Seed:");
+                    ui.add(egui::DragValue::new(&mut tool.seed));
+                    run = ui.add_enabled(!variables.is_empty(), egui::Button::new("Run")).clicked();
+                });
+                if run {
+                    self.monte_carlo.error = self.run_monte_carlo().err();
+                }
+                if let Some(error) = &self.monte_carlo.error {
+                    ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                }
+
+                let Some(result) = &self.monte_carlo.result else {
+                    return;
+                };
+                ui.separator();
+                let (mean, variance) = data::moments(&result.samples);
+                ui.label(format!(
+                    "{}: mean {:.4}, sd {:.4}, 95% of draws in [{:.4}, {:.4}]",
+                    result.name,
+                    mean,
+                    variance.sqrt(),
+                    data::sample_quantile(&result.samples, 0.025),
+                    data::sample_quantile(&result.samples, 0.975)
+                ));
+                if let Some((exact_mean, exact_std_dev)) = result.exact {
+                    ui.label(format!("Exact: normal with mean {:.4}, sd {:.4} (affine in Gaussian inputs)", exact_mean, exact_std_dev));
+                }
+                if result.undefined > 0 {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(230, 140, 0),
+                        format!("⚠ {} draws were undefined and left out", result.undefined),
+                    );
+                }
+
+                let bars: Vec<egui_plot::Bar> = result.histogram.densities()
+                    .into_iter()
+                    .map(|(left, right, density)| egui_plot::Bar::new(0.5 * (left + right), density).width(right - left))
+                    .collect();
+                let (x_min, x_max) = (result.samples[0], result.samples[result.samples.len() - 1]);
+                let curve = |f: &dyn Fn(f64) -> f64| -> Vec<[f64; 2]> {
+                    (0..=200).map(|i| x_min + (x_max - x_min) * i as f64 / 200.0).map(|x| [x, f(x)]).collect()
+                };
+                let kde = curve(&|x| data::kernel_density(&result.samples, result.bandwidth, x));
+                let exact = result.exact.map(|(mean, std_dev)| {
+                    let normal = Normal::new(mean, std_dev).unwrap();
+                    curve(&|x| normal.pdf(x))
+                });
+                Plot::new("monte_carlo_histogram")
+                    .height(200.0)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |plot_ui| {
+                        plot_ui.bar_chart(egui_plot::BarChart::new(bars)
+                            .name("Draws")
+                            .color(egui::Color32::from_rgba_unmultiplied(120, 120, 120, 120)));
+                        plot_ui.line(Line::new(kde).name("KDE").color(egui::Color32::BLUE));
+                        if let Some(exact) = exact {
+                            plot_ui.line(Line::new(exact)
+                                .name("Exact")
+                                .color(egui::Color32::RED)
+                                .style(egui_plot::LineStyle::dashed_loose()));
+                        }
+                    });
+                if ui.button("➕ Add to plot").on_hover_text("Kernel density estimate of the draws").clicked() {
+                    self.add_monte_carlo_curve();
+                }
+            });
+        self.monte_carlo.open = open;
+    }
+
+    /// The main plot of the density, CDF and related views; `size` fixes its
+    /// size instead of filling the panel, as when it is exported
+    fn density_plot(&mut self, ui: &mut egui::Ui, size: Option<egui::Vec2>) {
+        let locked = self.view_lock.is_some();
+        let num_points = self.resolution.interactive_points(ui.available_width());
+
+        // Curves are sampled over the visible x-range, so x must not auto-fit to
+        // the data (that would widen the view every frame); y still follows the data
+        let mut plot = Plot::new("pdf_plot")
+            .view_aspect(2.0)
+            .allow_zoom(!locked)
+            .allow_drag(!self.selecting_interval && !self.handle_hovered && !locked)
+            .allow_scroll(!locked)
+            .allow_boxed_zoom(!locked)
+            .allow_double_click_reset(!locked)
+            .auto_bounds([false, !locked].into())
+            .include_x(-6.0)
+            .include_x(6.0)
+            .show_axes([true, true]);
+        self.secondary_scale = self.secondary_axis_scale();
+        if let Some(factor) = self.secondary_scale {
+            // The right axis reads plotted heights back in secondary units
+            let factor = factor * self.axis_map().unit;
+            let mut names: Vec<&str> = self.distributions.values()
+                .filter(|dist| dist.secondary_axis && !dist.hidden)
+                .map(|dist| dist.name.as_str())
+                .collect();
+            names.sort_unstable();
+            let secondary = egui_plot::AxisHints::new_y()
+                .placement(egui_plot::HPlacement::Right)
+                .label(names.join(", "))
+                .formatter(move |mark, _| {
+                    let decimals = (-(mark.step_size / factor).log10().floor()).max(0.0) as usize;
+                    format!("{:.*}", decimals, mark.value / factor)
+                });
+            plot = plot.custom_y_axes(vec![egui_plot::AxisHints::new_y(), secondary]);
+        }
+        let heights = self.plot_layout.heights(ui.available_height());
+        if let Some(size) = size {
+            plot = plot.width(size.x).height(size.y);
+        } else if self.plot_layout != PlotLayout::Single {
+            plot = plot.width(ui.available_width())
+                .height(heights[0])
+                .link_axis(PlotLayout::LINK, true, false)
+                .link_cursor(PlotLayout::LINK, true, false);
+        }
+        // Hovering a derived curve shows how it was built
+        let recipes: HashMap<String, String> = self.distributions.values()
+            .filter_map(|dist| Some((dist.name.clone(), self.recipe(dist.id)?)))
+            .collect();
+        // ln f(x) is already logarithmic and mostly negative
+        let log_y = self.log_y && self.plot_mode != PlotMode::LogDensity;
+        // Peak-normalized heights are divided back to densities for the hover label
+        let peak_scales: HashMap<String, f64> = if self.peak_normalized && self.plot_mode == PlotMode::Density {
+            self.distributions.values()
+                .map(|dist| (self.curve_label(dist, true), self.curve_scale(dist) * self.axis_map().unit))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        plot = plot.label_formatter(move |name, value| {
+            let height = if log_y { 10f64.powf(value.y) } else { value.y };
+            let position = match peak_scales.get(name) {
+                Some(scale) => format!("x = {:.4}\nf(x) = {:.4}  ({:.1}% of peak)", value.x, height / scale, 100.0 * height),
+                None if log_y => format!("x = {:.4}\ny = {:.4e}", value.x, height),
+                None => format!("x = {:.4}\ny = {:.4}", value.x, value.y),
+            };
+            if name.is_empty() {
+                return position;
+            }
+            match curve_recipe(&recipes, name) {
+                Some(recipe) => format!("{}\n{}\n{}", name, recipe, position),
+                None => format!("{}\n{}", name, position),
+            }
+        });
+        let axis = self.axis_map().with_log_y(log_y);
+        // Overlays that shade areas under densities are left out of the other views
+        let density_view = self.plot_mode == PlotMode::Density;
+        if log_y {
+            plot = plot.y_axis_formatter(|mark, _| format!("1e{}", mark.value));
+        }
+        match self.plot_mode {
+            PlotMode::Density if self.peak_normalized => plot = plot.y_axis_label("f(x) / max f"),
+            PlotMode::Cdf => plot = plot.y_axis_label("F(x)"),
+            PlotMode::Survival => plot = plot.y_axis_label("S(x)"),
+            PlotMode::Hazard => plot = plot.y_axis_label("h(x)"),
+            PlotMode::LogDensity => plot = plot.y_axis_label("ln f(x)"),
+            _ => {}
+        }
+        if let Some(reference) = self.z_reference.and_then(|id| self.distributions.get(&id)) {
+            plot = plot.x_axis_label(format!("z = (x − {:.3}) / {:.3}  [{}]", axis.origin, axis.unit, reference.name));
+        }
+        let style = self.axis_style.clone();
+        plot = plot.show_grid(style.show_grid);
+        if !style.default_spacing() {
+            let (x_step, y_step, minor) = (style.x_step, style.y_step, style.minor_grid);
+            plot = plot.x_grid_spacer(move |input| grid_marks(input, x_step, minor))
+                .y_grid_spacer(move |input| grid_marks(input, y_step, minor));
+        }
+        if style.number_format != NumberFormat::Auto {
+            let (format, decimals) = (style.number_format, style.decimals);
+            plot = plot.x_axis_formatter(move |mark, _| format.format(mark.value, decimals))
+                .y_axis_formatter(move |mark, _| {
+                    format.format(if log_y { 10f64.powf(mark.value) } else { mark.value }, decimals)
+                });
+        }
+        if !style.x_label.trim().is_empty() {
+            plot = plot.x_axis_label(style.x_label.trim());
+        }
+        if !style.y_label.trim().is_empty() {
+            plot = plot.y_axis_label(style.y_label.trim());
+        }
+        if std::mem::take(&mut self.reset_plot) {
+            plot = plot.reset();
+        }
+        self.point_cache.retain_ids(&self.distributions);
+    
+        // The legend hides what is in the plot memory; keep the curves
+        // there in step with their `hidden` flags, leaving other entries alone
+        let plot_id = ui.make_persistent_id("pdf_plot");
+        if let Some(mut memory) = egui_plot::PlotMemory::load(ui.ctx(), plot_id) {
+            for dist in self.distributions.values() {
+                let label = self.curve_label(dist, density_view);
+                if dist.hidden {
+                    memory.hidden_items.insert(label);
+                } else {
+                    memory.hidden_items.remove(&label);
+                }
+            }
+            memory.store(ui.ctx(), plot_id);
+        }
+        let plot = plot.legend(egui_plot::Legend::default());
+        let plot_response = plot.show(ui, |plot_ui| {
+            // Fills reach down to y = 0, which on a log axis is the bottom of the view
+            let fill_base = if log_y { plot_ui.plot_bounds().min()[1] } else { 0.0 };
+            if let Some(view) = self.view_lock {
+                self.requested_bounds = None;
+                self.plot_bounds = Some(view_bounds(view));
+                plot_ui.set_plot_bounds(view_bounds(view));
+            } else if let Some(bounds) = self.requested_bounds.take() {
+                plot_ui.set_plot_bounds(bounds);
+            } else if plot_ui.plot_bounds().is_valid_x() {
+                self.plot_bounds = Some(plot_ui.plot_bounds());
+            }
+    
+            if self.selecting_interval {
+                self.handle_interval_drag(plot_ui);
+            } else {
+                self.drag_interval_handles(plot_ui);
+                self.drag_parameter_handles(plot_ui);
+            }
+            // Vertical lines only: anything spanning the y-range would
+            // feed back into the y auto-bounds and grow them every frame
+            if let Some((lower, upper)) = self.condition_interval {
+                let width = if self.handle_hovered { 3.0 } else { 1.5 };
+                for x in [lower, upper] {
+                    plot_ui.vline(VLine::new(axis.to_view(x))
+                        .stroke(egui::Stroke::new(width, egui::Color32::GRAY))
+                        .name("Interval"));
+                }
+            }
+    
+            // Imported data behind the curves, as a density so it compares with them
+            if let Some(histogram) = self.data.histogram.as_ref().filter(|_| self.data.show_histogram && density_view) {
+                let bins = histogram.densities();
+                let tallest = bins.iter().map(|&(_, _, density)| density).fold(0.0, f64::max);
+                let scale = if self.peak_normalized && tallest > 0.0 { 1.0 / tallest } else { 1.0 };
+                let name = self.data.dataset.as_ref().map_or("Data", |dataset| dataset.name.as_str());
+                let fill = egui::Color32::from_gray(128).gamma_multiply(0.35);
+                for (left, right, density) in bins.into_iter().filter(|&(_, _, density)| density > 0.0) {
+                    let [x0, top] = axis.view_point([left, scale * density]);
+                    let [x1, _] = axis.view_point([right, scale * density]);
+                    let bottom = if log_y { fill_base } else { 0.0 };
+                    plot_ui.polygon(egui_plot::Polygon::new(PlotPoints::new(vec![[x0, bottom], [x1, bottom], [x1, top], [x0, top]]))
+                        .name(name)
+                        .fill_color(fill)
+                        .stroke(egui::Stroke::new(0.5, egui::Color32::from_gray(110))));
+                }
+            }
+    
+            // Where the data's ECDF is furthest from the tested CDF
+            if let (PlotMode::Cdf, Some((ks, _))) = (self.plot_mode, &self.data.goodness_of_fit) {
+                let x = axis.to_view(ks.location);
+                let gap = vec![[x, axis.y_to_view(ks.theoretical)], [x, axis.y_to_view(ks.empirical)]];
+                let color = egui::Color32::from_rgb(220, 50, 50);
+                plot_ui.line(Line::new(PlotPoints::new(gap.clone())).color(color).width(2.5).name(format!("KS D = {:.4}", ks.statistic)));
+                plot_ui.points(egui_plot::Points::new(PlotPoints::new(gap)).color(color).radius(3.0));
+            }
+    
+            // Data's ECDF on top of the theoretical CDFs
+            if let Some(dataset) = self.data.dataset.as_ref().filter(|_| self.data.show_ecdf && self.plot_mode == PlotMode::Cdf) {
+                let (view_min, view_max) = self.data_range();
+                let steps: Vec<[f64; 2]> = data::ecdf_steps(&dataset.sorted(), view_min, view_max)
+                    .into_iter()
+                    .map(|[x, y]| [axis.to_view(x), axis.y_to_view(y)])
+                    .filter(|[_, y]| y.is_finite())
+                    .collect();
+                plot_ui.line(Line::new(PlotPoints::new(steps))
+                    .color(egui::Color32::from_gray(60))
+                    .width(1.5)
+                    .name(format!("ECDF of {}", dataset.name)));
+            }
+
+            let mut peaks = Vec::new();
+            let (colors, _) = self.curve_colors();
+            for dist in self.distributions.values() {
+                let (view_min, view_max) = self.data_range();
+                let (x_min, x_max) = dist.clip_to_domain(view_min, view_max);
+                if x_min >= x_max {
+                    continue;
+                }
+                let color = colors[&dist.id];
+        
+                let display_scale = if density_view { self.curve_scale(dist) } else { 1.0 };
+                let mut curve = if density_view {
+                    self.point_cache.points(dist, x_min, x_max, num_points)
+                } else {
+                    let mode = self.plot_mode;
+                    sample_curve(x_min, x_max, num_points, |x| mode.value(dist, x))
+                };
+                if display_scale != 1.0 {
+                    curve.iter_mut().for_each(|point| point[1] *= display_scale);
+                }
+                if axis != AxisMap::IDENTITY {
+                    // Probabilities are not densities: only x is standardized
+                    curve.iter_mut().for_each(|point| {
+                        *point = if density_view { axis.view_point(*point) } else { [axis.to_view(point[0]), axis.y_to_view(point[1])] };
+                    });
+                }
+                curve.retain(|[_, y]| y.is_finite());
+                if dist.hidden {
+                    // Only its legend entry, where a click brings it back
+                    plot_ui.line(Line::new(PlotPoints::new(curve)).name(self.curve_label(dist, density_view)).color(color));
+                    continue;
+                }
+                if let Some(peak) = curve_peak(&curve) {
+                    peaks.push((peak, color));
+                }
+        
+                // Draw shading if enabled  
+                if self.show_shading && density_view {
+                    // Use Line's native fill() method instead of manual polygon
+                    let points = match self.condition_interval.filter(|_| self.show_interval_probability) {
+                        Some((lower, upper)) => {
+                            // Only [a, b], closed off by the exact curve height at both ends
+                            let (lower, upper) = (axis.to_view(lower), axis.to_view(upper));
+                            let edge = |x: f64| {
+                                let data_x = axis.to_data(x);
+                                axis.view_point([data_x, display_scale * dist.evaluate(data_x)])
+                            };
+                            let mut region = vec![edge(lower)];
+                            region.extend(curve.iter().filter(|[x, _]| *x > lower && *x < upper));
+                            region.push(edge(upper));
+                            region.retain(|[_, y]| y.is_finite());
+                            PlotPoints::new(region)
+                        }
+                        None => PlotPoints::new(curve.clone()),
+                    };
+            
+                    // Create color with user-controlled opacity for the fill
+                    // Ensure minimum alpha of 1 to prevent auto-color assignment
+                    let alpha = ((255.0 * self.shading_opacity) as u8).max(1);
+                    let fill_color = egui::Color32::from_rgba_unmultiplied(
+                        color.r(),
+                        color.g(), 
+                        color.b(),
+                        alpha
+                    );
+            
+                    // Unnamed, so the legend lists the curve only once
+                    let line_with_fill = Line::new(points)
+                        .color(fill_color)
+                        .stroke(egui::Stroke::new(0.0, egui::Color32::TRANSPARENT))  // Make stroke invisible
+                        .fill(fill_base as f32);  // Fill area between line and y=0
+                    plot_ui.line(line_with_fill);
+                }
+        
+                // Pointwise credible band from the parameter uncertainty, behind the curve
+                if let Some(uncertainty) = dist.uncertainty.filter(|_| !dist.is_derived() && density_view) {
+                    let xs: Vec<f64> = curve.iter().map(|&[x, _]| axis.to_data(x)).collect();
+                    let band = sampling::gaussian_density_band(dist, &uncertainty, &xs, BAND_DRAWS, u64::from(dist.id));
+                    let edge = |pick: usize| -> Vec<[f64; 2]> {
+                        xs.iter().zip(&band).map(|(&x, bounds)| axis.view_point([x, display_scale * bounds[pick]])).collect()
+                    };
+                    let name = format!("{} ({:.0}% band)", dist.name, 100.0 * uncertainty.level);
+                    shaded_band(plot_ui, edge(0), edge(1), color, &name);
+                }
+
+                // Bootstrap band of a fitted curve, in the views it holds values for
+                if let Some(band) = self.data.bootstrap.band_for(dist).filter(|_| matches!(self.plot_mode, PlotMode::Density | PlotMode::Cdf)) {
+                    let edge = |pick: usize| -> Vec<[f64; 2]> {
+                        if density_view {
+                            band.xs.iter().zip(&band.density).map(|(&x, bounds)| axis.view_point([x, display_scale * bounds[pick]])).collect()
+                        } else {
+                            band.xs.iter().zip(&band.cdf).map(|(&x, bounds)| [axis.to_view(x), axis.y_to_view(bounds[pick])]).collect()
+                        }
+                    };
+                    let name = format!("{} ({:.0}% bootstrap band)", dist.name, 100.0 * band.level);
+                    shaded_band(plot_ui, edge(0), edge(1), color, &name);
+                }
+        
+                // Draw the curve line
+                let points = PlotPoints::new(curve.clone());
+                let line = Line::new(points)
+                    .name(self.curve_label(dist, density_view))
+                    .color(color)
+                    .width(dist.line_width)
+                    .style(dist.line_style.line_style());
+                plot_ui.line(line);
+        
+                // The overlay is what amplitude view already draws
+                let overlay = dist.normalizing_constant.filter(|_| dist.show_unnormalized && self.display_scale(dist) == 1.0 && density_view && !self.peak_normalized);
+                if let Some(scale) = overlay {
+                    let raw: Vec<[f64; 2]> = curve.iter()
+                        .map(|&[x, y]| [x, if log_y { y + scale.log10() } else { scale * y }])
+                        .collect();
+                    plot_ui.line(Line::new(raw)
+                        .name(format!("{} (unnormalized)", dist.name))
+                        .color(color)
+                        .style(egui_plot::LineStyle::Dotted { spacing: 6.0 }));
+                }
+        
+                // Draw standard deviation markers if enabled
+                if self.show_std_markers {
+                    for (marker_x, center) in dist.marker_positions() {
+                        if marker_x >= x_min && marker_x <= x_max {
+                            let marker_style = if center {
+                                egui::Stroke::new(2.0, color)
+                            } else {
+                                egui::Stroke::new(1.0, color.gamma_multiply(0.7))
+                            };
+                    
+                            let vline = VLine::new(axis.to_view(marker_x))
+                                .style(egui_plot::LineStyle::Dashed { length: 5.0 })
+                                .stroke(marker_style);
+                            plot_ui.vline(vline);
+                        }
+                    }
+                }
+            }
+    
+            if let Some(dist) = self.pvalue_target.and_then(|id| self.distributions.get(&id)) {
+                let (view_min, view_max) = self.data_range();
+                let scale = self.curve_scale(dist);
+                let regions = if density_view { self.pvalue_regions() } else { Vec::new() };
+                for (lower, upper) in regions {
+                    let (lower, upper) = (lower.max(view_min), upper.min(view_max));
+                    if lower >= upper {
+                        continue;
+                    }
+                    let region: Vec<[f64; 2]> = (0..=200)
+                        .map(|i| {
+                            let x = lower + (upper - lower) * i as f64 / 200.0;
+                            axis.view_point([x, scale * dist.evaluate(x)])
+                        })
+                        .filter(|[_, y]| y.is_finite())
+                        .collect();
+                    plot_ui.line(Line::new(PlotPoints::new(region))
+                        .name("p-value region")
+                        .color(egui::Color32::from_rgba_unmultiplied(200, 30, 30, 110))
+                        .stroke(egui::Stroke::new(0.0, egui::Color32::TRANSPARENT))
+                        .fill(fill_base as f32));
+                }
+                plot_ui.vline(VLine::new(axis.to_view(self.pvalue_x))
+                    .stroke(egui::Stroke::new(1.5, egui::Color32::from_rgb(200, 30, 30)))
+                    .name("Observed x"));
+            }
+    
+            if let Some(dist) = self.credible_target.and_then(|id| self.distributions.get(&id)) {
+                let (view_min, view_max) = self.data_range();
+                let scale = self.curve_scale(dist);
+                let color = colors[&dist.id];
+                let name = format!("{:.0}% credible interval", 100.0 * self.credible_level);
+                for (lower, upper) in dist.credible_regions(self.credible_level) {
+                    let (shade_min, shade_max) = (lower.max(view_min), upper.min(view_max));
+                    if shade_min < shade_max && density_view {
+                        let region: Vec<[f64; 2]> = (0..=200)
+                            .map(|i| {
+                                let x = shade_min + (shade_max - shade_min) * i as f64 / 200.0;
+                                axis.view_point([x, scale * dist.evaluate(x)])
+                            })
+                            .filter(|[_, y]| y.is_finite())
+                            .collect();
+                        plot_ui.line(Line::new(PlotPoints::new(region))
+                            .name(&name)
+                            .color(color.gamma_multiply(0.35))
+                            .stroke(egui::Stroke::new(0.0, egui::Color32::TRANSPARENT))
+                            .fill(fill_base as f32));
+                    }
+                    for (x, anchor) in [(lower, egui::Align2::RIGHT_BOTTOM), (upper, egui::Align2::LEFT_BOTTOM)] {
+                        let [view_x, view_y] = if density_view {
+                            axis.view_point([x, scale * dist.evaluate(x)])
+                        } else {
+                            [axis.to_view(x), axis.y_to_view(self.plot_mode.value(dist, x))]
+                        };
+                        let view_y = if view_y.is_finite() { view_y } else { fill_base };
+                        plot_ui.vline(VLine::new(view_x)
+                            .stroke(egui::Stroke::new(1.0, color))
+                            .name(&name));
+                        plot_ui.text(egui_plot::Text::new(
+                            egui_plot::PlotPoint::new(view_x, view_y),
+                            format!(" {:.3} ", x),
+                        ).color(color).anchor(anchor));
+                    }
+                }
+            }
+    
+            if let Some((mean, std_dev)) = self.zscore_reference() {
+                plot_ui.vline(VLine::new(axis.to_view(self.zscore_x))
+                    .stroke(egui::Stroke::new(1.5, egui::Color32::from_rgb(120, 60, 170)))
+                    .style(egui_plot::LineStyle::Dashed { length: 4.0 })
+                    .name(format!("x = {:.3} (z = {:.3})", self.zscore_x, (self.zscore_x - mean) / std_dev)));
+            }
+    
+            if let Some(x) = self.quantile_marker() {
+                plot_ui.vline(VLine::new(axis.to_view(x))
+                    .stroke(egui::Stroke::new(1.5, egui::Color32::DARK_GREEN))
+                    .style(egui_plot::LineStyle::Dashed { length: 6.0 })
+                    .name(format!("Quantile p = {}", self.quantile_p)));
+            }
+    
+            if self.comparison.shade_overlap && density_view {
+                if let Some(overlap) = self.comparison_curve(num_points, f64::min) {
+                    plot_ui.line(Line::new(PlotPoints::new(overlap))
+                        .name("Overlap min(p, q)")
+                        .color(egui::Color32::from_rgba_unmultiplied(90, 90, 90, 110))
+                        .stroke(egui::Stroke::new(0.0, egui::Color32::TRANSPARENT))
+                        .fill(fill_base as f32));
+                }
+            }
+    
+            if self.comparison.show_bhattacharyya_curve && density_view {
+                if let Some(curve) = self.comparison_curve(num_points, |p, q| (p * q).sqrt()) {
+                    plot_ui.line(Line::new(PlotPoints::new(curve))
+                        .name("√(p q)")
+                        .color(egui::Color32::DARK_GRAY)
+                        .style(egui_plot::LineStyle::Dashed { length: 8.0 }));
+                }
+            }
+    
+            if let (true, true, Some(first), Some(second)) = (
+                self.shade_between,
+                density_view,
+                self.between.0.and_then(|id| self.distributions.get(&id)),
+                self.between.1.and_then(|id| self.distributions.get(&id)),
+            ) {
+                let (x_min, x_max) = self.data_range();
+                let sample = |dist: &GaussianDistribution| -> Vec<[f64; 2]> {
+                    let scale = self.curve_scale(dist);
+                    sample_curve(x_min, x_max, num_points, |x| scale * dist.evaluate(x))
+                        .into_iter()
+                        .map(|point| axis.view_point(point))
+                        .collect()
+                };
+                let opacity = ((255.0 * self.shading_opacity) as u8).max(1);
+                let tint = |id: u32| {
+                    let color = colors[&id];
+                    egui::Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), opacity)
+                };
+                for (piece, first_on_top) in between_regions(&sample(first), &sample(second)) {
+                    if piece.iter().any(|[_, y]| !y.is_finite()) {
+                        continue;
+                    }
+                    let color = if first_on_top { tint(first.id) } else { tint(second.id) };
+                    plot_ui.polygon(egui_plot::Polygon::new(PlotPoints::new(piece))
+                        .fill_color(color)
+                        .stroke(egui::Stroke::NONE));
+                }
+            }
+    
+            for line in &self.reference_lines {
+                let [r, g, b] = line.color;
+                let stroke = egui::Stroke::new(line.width, egui::Color32::from_rgb(r, g, b));
+                if line.horizontal {
+                    let y = axis.y_to_view(line.position);
+                    if y.is_finite() {
+                        plot_ui.hline(egui_plot::HLine::new(y).stroke(stroke).style(line.style.line_style()));
+                    }
+                } else {
+                    plot_ui.vline(VLine::new(axis.to_view(line.position)).stroke(stroke).style(line.style.line_style()));
+                }
+            }
+    
+            if self.parameter_handles && density_view {
+                for (id, handle, point) in self.parameter_handle_points() {
+                    let active = self.dragged_parameter == Some((id, handle));
+                    let shape = match handle {
+                        ParameterHandle::Mean => egui_plot::MarkerShape::Circle,
+                        ParameterHandle::StdDev => egui_plot::MarkerShape::Diamond,
+                    };
+                    plot_ui.points(egui_plot::Points::new(vec![point])
+                        .shape(shape)
+                        .filled(true)
+                        .radius(if active { 6.5 } else { 5.0 })
+                        .color(colors[&id]));
+                }
+            }
+    
+            let pointer = plot_ui.pointer_coordinate().filter(|_| plot_ui.response().hovered());
+            if let Some(readout) = pointer.and_then(|pointer| self.crosshair_readout(axis.to_data(pointer.x))) {
+                let dist = &self.distributions[&self.crosshair_target.unwrap()];
+                let height = match self.plot_mode {
+                    PlotMode::Density => axis.y_to_view(axis.unit * self.curve_scale(dist) * readout.density),
+                    mode => axis.y_to_view(mode.value(dist, readout.x)),
+                };
+                let view_x = axis.to_view(readout.x);
+                let stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(110));
+                plot_ui.vline(VLine::new(view_x).stroke(stroke));
+                if height.is_finite() {
+                    plot_ui.hline(egui_plot::HLine::new(height).stroke(stroke));
+                    plot_ui.points(egui_plot::Points::new(vec![[view_x, height]])
+                        .radius(4.0)
+                        .color(colors[&dist.id]));
+                }
+            }
+    
+            // y auto-fits the whole range on a log axis, and the rescale is linear
+            if !log_y {
+                self.show_off_scale_peaks(plot_ui, &peaks);
+            }
+        });
+
+        // Legend clicks toggle curves; a double click shows only that curve
+        if let Some(memory) = egui_plot::PlotMemory::load(ui.ctx(), plot_id) {
+            let labels: Vec<(u32, String)> = self.distributions.values()
+                .map(|dist| (dist.id, self.curve_label(dist, density_view)))
+                .collect();
+            let double_clicked = ui.input(|input| input.pointer.button_double_clicked(egui::PointerButton::Primary));
+            let isolate = memory.hovered_legend_item.as_ref()
+                .filter(|_| double_clicked)
+                .and_then(|name| labels.iter().find(|(_, label)| label == name))
+                .map(|(id, _)| *id);
+            for (id, label) in &labels {
+                self.distributions.get_mut(id).unwrap().hidden = memory.hidden_items.contains(label);
+            }
+            if let Some(id) = isolate {
+                self.isolate_curve(id);
+            }
+        }
+
+        // Reference line labels, painted like the crosshair readout below
+        let painter = ui.painter_at(plot_response.response.rect);
+        let frame = plot_response.response.rect;
+        for line in &self.reference_lines {
+            let [r, g, b] = line.color;
+            let color = egui::Color32::from_rgb(r, g, b);
+            let galley = painter.layout_no_wrap(line.caption(), egui::FontId::proportional(12.0), color);
+            let anchor = if line.horizontal {
+                let y = axis.y_to_view(line.position);
+                let screen = plot_response.transform.position_from_point(&egui_plot::PlotPoint::new(0.0, y));
+                egui::pos2(frame.right() - galley.size().x - 6.0, screen.y - galley.size().y - 2.0)
+            } else {
+                let screen = plot_response.transform.position_from_point(&egui_plot::PlotPoint::new(axis.to_view(line.position), 0.0));
+                egui::pos2(screen.x + 4.0, frame.top() + 4.0)
+            };
+            if anchor.x.is_finite() && anchor.y.is_finite() && frame.contains(anchor) {
+                painter.galley(anchor, galley, egui::Color32::PLACEHOLDER);
+            }
+        }
+
+        // Text annotations and their arrows, on top of the reference line labels
+        let screen_of = |[x, y]: [f64; 2]| {
+            plot_response.transform.position_from_point(&egui_plot::PlotPoint::new(axis.to_view(x), axis.y_to_view(y)))
+        };
+        for annotation in &self.annotations {
+            let [r, g, b] = annotation.color;
+            let color = egui::Color32::from_rgb(r, g, b);
+            let anchor = screen_of(annotation.position);
+            if !anchor.x.is_finite() || !anchor.y.is_finite() {
+                continue;
+            }
+            let galley = painter.layout_no_wrap(annotation.text.clone(), egui::FontId::proportional(annotation.size), color);
+            let text_rect = egui::Align2::CENTER_CENTER.anchor_size(anchor, galley.size());
+            if let Some(tip) = annotation.arrow_to.map(screen_of).filter(|tip| tip.x.is_finite() && tip.y.is_finite()) {
+                // Start at the edge of the text box facing the tip
+                let start = text_rect.expand(3.0).clamp(tip);
+                painter.arrow(start, tip - start, egui::Stroke::new(1.5, color));
+            }
+            painter.galley(text_rect.min, galley, egui::Color32::PLACEHOLDER);
+        }
+        if let (Some((index, arrow)), true) = (self.placing_annotation, plot_response.response.clicked()) {
+            let position = plot_response.response.interact_pointer_pos()
+                .map(|pos| plot_response.transform.value_from_position(pos))
+                .map(|point| [axis.to_data(point.x), axis.y_to_data(point.y)]);
+            if let (Some(position), Some(annotation)) = (position, self.annotations.get_mut(index)) {
+                if arrow {
+                    annotation.arrow_to = Some(position);
+                } else {
+                    annotation.position = position;
+                }
+            }
+            self.placing_annotation = None;
+        }
+
+        // Every curve's height at the pointer's x, highest first
+        let hovered_x = plot_response.response.hover_pos()
+            .filter(|_| self.show_hover_values)
+            .map(|pos| axis.to_data(plot_response.transform.value_from_position(pos).x));
+        // Painted over the plot rather than placed in it, so it cannot
+        // feed into the y auto-bounds
+        let readout = plot_response.response.hover_pos()
+            .and_then(|pos| self.crosshair_readout(axis.to_data(plot_response.transform.value_from_position(pos).x)));
+        if let Some(readout) = readout {
+            let name = &self.distributions[&self.crosshair_target.unwrap()].name;
+            let text = format!(
+                "{}\nx = {:.4}\nf(x) = {:.6}\nF(x) = {:.6}\nz = {:.4}",
+                name, readout.x, readout.density, readout.cdf, readout.z
+            );
+            let painter = ui.painter_at(plot_response.response.rect);
+            let corner = plot_response.response.rect.left_top() + egui::vec2(8.0, 8.0);
+            let galley = painter.layout_no_wrap(text, egui::FontId::monospace(12.0), ui.visuals().text_color());
+            let frame = egui::Rect::from_min_size(corner, galley.size()).expand(4.0);
+            painter.rect_filled(frame, 3.0, ui.visuals().extreme_bg_color.gamma_multiply(0.9));
+            painter.galley(corner, galley, egui::Color32::PLACEHOLDER);
+        }
+        if let Some(x) = hovered_x {
+            let values = self.hover_values(x);
+            let (colors, _) = self.curve_colors();
+            plot_response.response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!("x = {:.4}", x));
+                egui::Grid::new("hover_values").num_columns(2).show(ui, |ui| {
+                    for (id, value) in values {
+                        ui.colored_label(colors[&id], &self.distributions[&id].name);
+                        ui.monospace(format!("{:.6}", value));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+    }
+
+    /// CDFs under the main plot; the x-range comes from the main plot through
+    /// the axis link, so the curves are sampled over the same data range
+    fn cdf_subplot(&self, ui: &mut egui::Ui, height: f32, locked: bool) {
+        let axis = self.axis_map().with_log_y(false);
+        let (x_min, x_max) = self.data_range();
+        let num_points = self.resolution.interactive_points(ui.available_width());
+        let (colors, _) = self.curve_colors();
+        let mut ids: Vec<u32> = self.distributions.keys().copied().collect();
+        ids.sort_unstable();
+        Plot::new("cdf_subplot")
+            .width(ui.available_width())
+            .height(height)
+            .link_axis(PlotLayout::LINK, true, false)
+            .link_cursor(PlotLayout::LINK, true, false)
+            .allow_zoom(!locked)
+            .allow_drag(!locked)
+            .allow_scroll(!locked)
+            .allow_boxed_zoom(false)
+            .auto_bounds([false, true].into())
+            .include_y(0.0)
+            .include_y(1.0)
+            .y_axis_label("F(x)")
+            .label_formatter(|name, value| {
+                let position = format!("x = {:.4}\nF = {:.4}", value.x, value.y);
+                if name.is_empty() { position } else { format!("{}\n{}", name, position) }
+            })
+            .show(ui, |plot_ui| {
+                for id in ids {
+                    let dist = &self.distributions[&id];
+                    let (lower, upper) = dist.clip_to_domain(x_min, x_max);
+                    if lower >= upper {
+                        continue;
+                    }
+                    let curve: Vec<[f64; 2]> = sample_curve(lower, upper, num_points, |x| dist.quantile_level(x))
+                        .into_iter()
+                        .map(|[x, y]| [axis.to_view(x), y])
+                        .collect();
+                    plot_ui.line(Line::new(PlotPoints::new(curve))
+                        .name(&dist.name)
+                        .color(colors[&id])
+                        .width(dist.line_width)
+                        .style(dist.line_style.line_style()));
+                }
+                if let Some(x) = self.quantile_marker() {
+                    plot_ui.vline(VLine::new(axis.to_view(x))
+                        .stroke(egui::Stroke::new(1.5, egui::Color32::DARK_GREEN))
+                        .style(egui_plot::LineStyle::Dashed { length: 6.0 }));
+                    plot_ui.hline(egui_plot::HLine::new(self.quantile_p)
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::DARK_GREEN))
+                        .style(egui_plot::LineStyle::Dashed { length: 6.0 }));
+                }
+            });
+    }
+
+    /// log₁₀ f(x)/g(x) under the main plot, on the same linked x-axis. The
+    /// ratio is left out where g vanishes, so tails show as gaps, not spikes.
+    fn ratio_subplot(&self, ui: &mut egui::Ui, height: f32, locked: bool) {
+        let pair = (
+            self.ratio_pair.0.and_then(|id| self.distributions.get(&id)),
+            self.ratio_pair.1.and_then(|id| self.distributions.get(&id)),
+        );
+        let axis = self.axis_map().with_log_y(false);
+        let (x_min, x_max) = self.data_range();
+        let num_points = self.resolution.interactive_points(ui.available_width());
+        let (colors, _) = self.curve_colors();
+        let y_label = match pair {
+            (Some(f), Some(g)) => format!("{} / {}", f.name, g.name),
+            _ => "Pick f and g above".to_string(),
+        };
+        Plot::new("ratio_subplot")
+            .width(ui.available_width())
+            .height(height)
+            .link_axis(PlotLayout::LINK, true, false)
+            .link_cursor(PlotLayout::LINK, true, false)
+            .allow_zoom(!locked)
+            .allow_drag(!locked)
+            .allow_scroll(!locked)
+            .allow_boxed_zoom(false)
+            .auto_bounds([false, true].into())
+            .include_y(-1.0)
+            .include_y(1.0)
+            .y_axis_label(y_label)
+            .y_axis_formatter(|mark, _| format!("1e{}", mark.value))
+            .label_formatter(|_, value| format!("x = {:.4}\nf/g = {:.4e}", value.x, 10f64.powf(value.y)))
+            .show(ui, |plot_ui| {
+                // Λ = 1: both curves equally likely
+                plot_ui.hline(egui_plot::HLine::new(0.0)
+                    .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
+                    .style(egui_plot::LineStyle::Dashed { length: 6.0 }));
+                let (Some(f), Some(g)) = pair else {
+                    return;
+                };
+                for segment in likelihood_ratio_segments(f, g, x_min, x_max, num_points) {
+                    let segment: Vec<[f64; 2]> = segment.into_iter().map(|[x, y]| [axis.to_view(x), y]).collect();
+                    plot_ui.line(Line::new(PlotPoints::new(segment)).color(colors[&f.id]).width(2.0));
+                }
+            });
+    }
+
+    /// Grid of small plots sharing the main plot's x-range, panned and zoomed together
+    fn small_multiples(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.small_multiples_by_group, false, "Per distribution");
+            ui.radio_value(&mut self.small_multiples_by_group, true, "Per group");
+            ui.separator();
+            ui.label("Columns:");
+            ui.add(egui::DragValue::new(&mut self.small_multiples_columns).range(0..=8).custom_formatter(|n, _| {
+                if n == 0.0 { "auto".to_string() } else { format!("{}", n) }
+            }));
+            ui.checkbox(&mut self.small_multiples_shared_y, "Shared y-axis");
+        });
+        let panels = self.small_multiple_panels();
+        if panels.is_empty() {
+            ui.weak("No distributions to show");
+            return;
+        }
+        let columns = match self.small_multiples_columns {
+            0 => (panels.len() as f64).sqrt().ceil() as usize,
+            columns => columns,
+        };
+        let rows = panels.len().div_ceil(columns);
+        let spacing = ui.spacing().item_spacing;
+        let width = ((ui.available_width() - spacing.x * (columns - 1) as f32) / columns as f32).max(80.0);
+        // Room for each panel's title above its plot
+        let title = ui.text_style_height(&egui::TextStyle::Body) + spacing.y;
+        let height = ((ui.available_height() - (title + spacing.y) * rows as f32) / rows as f32).max(60.0);
+
+        let (x_min, x_max) = self.data_range();
+        let num_points = self.resolution.interactive_points(width);
+        let (colors, _) = self.curve_colors();
+        let mode = self.plot_mode;
+        let shared_y = self.small_multiples_shared_y;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("small_multiples").spacing(spacing).show(ui, |ui| {
+                for (index, (name, members)) in panels.iter().enumerate() {
+                    ui.vertical(|ui| {
+                        ui.strong(name);
+                        Plot::new(("small_multiple", index))
+                            .width(width)
+                            .height(height)
+                            .link_axis("small_multiples", true, shared_y)
+                            .link_cursor("small_multiples", true, false)
+                            .include_x(x_min)
+                            .include_x(x_max)
+                            .include_y(0.0)
+                            .allow_boxed_zoom(false)
+                            .show(ui, |plot_ui| {
+                                for id in members {
+                                    let dist = &self.distributions[id];
+                                    let (lower, upper) = dist.clip_to_domain(x_min, x_max);
+                                    if lower >= upper {
+                                        continue;
+                                    }
+                                    let curve = match mode {
+                                        PlotMode::Density => {
+                                            let scale = self.display_scale(dist);
+                                            sample_curve(lower, upper, num_points, |x| scale * dist.evaluate(x))
+                                        }
+                                        mode => sample_curve(lower, upper, num_points, |x| mode.value(dist, x)),
+                                    };
+                                    let curve: Vec<[f64; 2]> = curve.into_iter().filter(|[_, y]| y.is_finite()).collect();
+                                    plot_ui.line(Line::new(PlotPoints::new(curve))
+                                        .name(&dist.name)
+                                        .color(colors[id])
+                                        .width(dist.line_width)
+                                        .style(dist.line_style.line_style()));
+                                }
+                            });
+                    });
+                    if (index + 1) % columns == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+    }
+
+    /// Violin layout: every density mirrored about its own baseline, one row
+    /// per distribution, with the median marked
+    fn violin_plot(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.violin_common_scale, "Common scale")
+                .on_hover_text("Scale every violin by the tallest density, so widths compare across rows");
+        });
+        let (x_min, x_max) = self.data_range();
+        let shapes = self.violin_shapes(x_min, x_max, self.resolution.interactive_points(ui.available_width()));
+        if shapes.is_empty() {
+            ui.weak("No distributions to show");
+            return;
+        }
+        let (colors, _) = self.curve_colors();
+        let rows: Vec<(f64, String)> = shapes.iter()
+            .map(|(id, baseline, _)| (*baseline, self.distributions[id].name.clone()))
+            .collect();
+        let row_names = rows.clone();
+        Plot::new("violin_plot")
+            .include_x(x_min)
+            .include_x(x_max)
+            .include_y(-0.5)
+            .include_y(rows.len() as f64 - 0.5)
+            .allow_boxed_zoom(false)
+            .x_axis_label(&self.axis_style.x_label)
+            .y_grid_spacer(move |_| rows.iter().map(|(baseline, _)| egui_plot::GridMark { value: *baseline, step_size: 1.0 }).collect())
+            .y_axis_formatter(move |mark, _| {
+                row_names.iter()
+                    .find(|(baseline, _)| (baseline - mark.value).abs() < 1e-9)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_default()
+            })
+            .label_formatter(|name, value| {
+                if name.is_empty() { format!("x = {:.4}", value.x) } else { format!("{}\nx = {:.4}", name, value.x) }
+            })
+            .show(ui, |plot_ui| {
+                for (id, baseline, outline) in shapes {
+                    let dist = &self.distributions[&id];
+                    let color = colors[&id];
+                    plot_ui.polygon(egui_plot::Polygon::new(PlotPoints::new(outline))
+                        .name(&dist.name)
+                        .fill_color(color.gamma_multiply(self.shading_opacity))
+                        .stroke(egui::Stroke::new(dist.line_width, color)));
+                    let median = dist.quantile(0.5);
+                    if median.is_finite() {
+                        // Median tick, a little shorter than the tallest violin
+                        let tick = 0.3;
+                        plot_ui.line(Line::new(PlotPoints::new(vec![[median, baseline - tick], [median, baseline + tick]]))
+                            .color(color)
+                            .width(dist.line_width + 0.5));
+                    }
+                }
+            });
+    }
+
+    /// MGF or characteristic function modulus of the selected distributions
+    /// (all of them when none is selected) over t ∈ [−t_max, t_max]
+    fn transform_plot(&mut self, ui: &mut egui::Ui) {
+        const POINTS: usize = 400;
+        ui.horizontal(|ui| {
+            ui.label("t ∈ ± ");
+            ui.add(egui::DragValue::new(&mut self.transform_t_max).speed(0.05).range(0.01..=1e3));
+            ui.weak("The transform of a sum of independent variables is the product of their transforms");
+        });
+        let mut ids: Vec<u32> = if self.selected_for_multiplication.is_empty() {
+            self.distributions.keys().copied().collect()
+        } else {
+            self.selected_for_multiplication.clone()
+        };
+        ids.sort_unstable();
+        let (colors, _) = self.curve_colors();
+        let t_max = self.transform_t_max;
+        let (mode, label) = match self.plot_mode {
+            PlotMode::Mgf => (PlotMode::Mgf, "M(t)"),
+            _ => (PlotMode::CharacteristicFunction, "|φ(t)|"),
+        };
+        Plot::new("transform_plot")
+            .view_aspect(2.0)
+            .legend(egui_plot::Legend::default())
+            .x_axis_label("t")
+            .y_axis_label(label)
+            .show(ui, |plot_ui| {
+                for id in ids {
+                    let Some(dist) = self.distributions.get(&id) else {
+                        continue;
+                    };
+                    let points: Vec<[f64; 2]> = (0..=POINTS)
+                        .map(|i| {
+                            let t = -t_max + 2.0 * t_max * i as f64 / POINTS as f64;
+                            let y = match mode {
+                                PlotMode::Mgf => dist.mgf(t),
+                                _ => dist.characteristic_modulus(t),
+                            };
+                            [t, y]
+                        })
+                        .filter(|[_, y]| y.is_finite())
+                        .collect();
+                    plot_ui.line(Line::new(PlotPoints::new(points))
+                        .name(format!("{} {}", dist.name, label))
+                        .color(colors[&id]));
+                }
+            });
+    }
+
+    /// Sample and theoretical pickers shared by the Q-Q and P-P views
+    fn probability_plot_inputs(&mut self, ui: &mut egui::Ui) {
+        let choices = self.distribution_choices();
+        let inputs = &mut self.probability_plot;
+        ui.horizontal(|ui| {
+            ui.label("Sample:");
+            ui.add_enabled_ui(self.data.dataset.is_some(), |ui| {
+                ui.radio_value(&mut inputs.sample_from_data, true, "Imported data");
+            });
+            ui.radio_value(&mut inputs.sample_from_data, false, "Distribution");
+            if !inputs.sample_from_data {
+                distribution_picker(ui, "", "probability_plot_sample", &mut inputs.sample, &choices);
+            }
+            ui.separator();
+            distribution_picker(ui, "Theoretical:", "probability_plot_reference", &mut inputs.reference, &choices);
+        });
+    }
+
+    /// Q-Q or P-P view: points on the dashed identity line mean the sample
+    /// follows the theoretical distribution. Q-Q bends show skew and tails,
+    /// P-P departures show misfit in the body of the distribution.
+    fn probability_plot(&mut self, ui: &mut egui::Ui) {
+        self.probability_plot_inputs(ui);
+        let quantiles = self.plot_mode == PlotMode::QuantileQuantile;
+        let points = if quantiles { self.quantile_points() } else { self.probability_points() };
+        let points = match points {
+            Ok(points) if !points.is_empty() => points,
+            Ok(_) => {
+                ui.weak("No finite values to compare");
+                return;
+            }
+            Err(message) => {
+                ui.weak(message);
+                return;
+            }
+        };
+        let (low, high) = if quantiles {
+            points.iter()
+                .flat_map(|point| point.iter().copied())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| (low.min(v), high.max(v)))
+        } else {
+            (0.0, 1.0)
+        };
+        let (id, x_label, y_label) = if quantiles {
+            ("quantile_plot", "Theoretical quantiles", "Sample quantiles")
+        } else {
+            ("probability_plot", "Theoretical cumulative probability", "Sample cumulative probability")
+        };
+        let from_data = self.probability_plot.sample_from_data;
+        Plot::new(id)
+            .view_aspect(1.5)
+            .legend(egui_plot::Legend::default())
+            .x_axis_label(x_label)
+            .y_axis_label(y_label)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::new(vec![[low, low], [high, high]]))
+                    .color(egui::Color32::GRAY)
+                    .style(egui_plot::LineStyle::dashed_loose())
+                    .name("y = x"));
+                let color = egui::Color32::from_rgb(31, 119, 180);
+                if from_data {
+                    plot_ui.points(egui_plot::Points::new(PlotPoints::new(points)).radius(2.5).color(color).name("Data"));
+                } else {
+                    plot_ui.line(Line::new(PlotPoints::new(points)).width(2.0).color(color).name(if quantiles { "Quantiles" } else { "Probabilities" }));
+                }
+            });
+    }
+
+    /// Presets and the user's templates, each a button that opens it
+    fn template_library(&mut self, ui: &mut egui::Ui) -> Option<Result<(), String>> {
+        let mut opened = None;
+        ui.strong("Presets");
+        ui.horizontal_wrapped(|ui| {
+            for preset in &PRESETS {
+                if ui.button(preset.title).on_hover_text(preset.description).clicked() {
+                    opened = Some(self.load_workspace(preset.session).map_err(|error| format!("{}: {}", preset.title, error)));
+                }
+            }
+        });
+        if self.templates.directory.is_none() {
+            return opened;
+        }
+        ui.add_space(8.0);
+        ui.strong("My templates");
+        if self.templates.names.is_empty() {
+            ui.weak("None yet; save the current workspace below to reuse it.");
+        }
+        let mut deleted = None;
+        ui.horizontal_wrapped(|ui| {
+            for name in self.templates.names.clone() {
+                if ui.button(&name).clicked() {
+                    opened = Some(self.load_template(&name));
+                }
+                if ui.small_button("🗑").on_hover_text(format!("Delete '{}'", name)).clicked() {
+                    deleted = Some(name);
+                }
+            }
+        });
+        if let Some(name) = deleted {
+            self.templates.status = Some(self.delete_template(&name).map(|()| format!("Deleted '{}'", name)));
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.templates.new_name).hint_text("Template name").desired_width(160.0));
+            let name = self.templates.new_name.trim().to_string();
+            let replaces = self.templates.names.contains(&name);
+            if ui.add_enabled(!name.is_empty(), egui::Button::new(if replaces { "💾 Replace" } else { "💾 Save current" }))
+                .on_hover_text("Saves the active workspace as a template")
+                .clicked()
+            {
+                self.templates.status = Some(self.save_template(&name).map(|()| format!("Saved '{}'", name)));
+                if matches!(self.templates.status, Some(Ok(_))) {
+                    self.templates.new_name.clear();
+                }
+            }
+        });
+        opened
+    }
+
+    /// Welcome screen: the example sessions, presets and the user's templates,
+    /// each loadable with one click
+    fn welcome_window(&mut self, ctx: &egui::Context) {
+        let thumbnails = self.gallery_thumbnails.get_or_insert_with(|| {
+            GALLERY.iter().map(|example| gallery_thumbnail(example.session).unwrap_or_default()).collect()
+        }).clone();
+        let palette = self.palette();
+        let mut open = self.show_welcome;
+        let mut chosen = None;
+        let mut opened = None;
+        egui::Window::new("Welcome")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(440.0)
+            .show(ctx, |ui| {
+                ui.label("Start from an example, or close this window to begin with a blank session.");
+                ui.separator();
+                egui::Grid::new("gallery").num_columns(2).spacing([16.0, 16.0]).show(ui, |ui| {
+                    for (index, (example, curves)) in GALLERY.iter().zip(&thumbnails).enumerate() {
+                        ui.vertical(|ui| {
+                            ui.strong(example.title);
+                            let thumbnail = Plot::new(("gallery_thumbnail", index))
+                                .width(200.0)
+                                .height(90.0)
+                                .show_axes(false)
+                                .show_grid(false)
+                                .show_x(false)
+                                .show_y(false)
+                                .allow_drag(false)
+                                .allow_zoom(false)
+                                .allow_scroll(false)
+                                .allow_boxed_zoom(false)
+                                .allow_double_click_reset(false)
+                                .show(ui, |plot_ui| {
+                                    for (slot, curve) in curves.iter().enumerate() {
+                                        plot_ui.line(Line::new(PlotPoints::new(curve.clone())).color(palette[slot % palette.len()]));
+                                    }
+                                });
+                            ui.add(egui::Label::new(example.description).wrap());
+                            if thumbnail.response.clicked() || ui.button("Open").clicked() {
+                                chosen = Some(example);
+                            }
+                        });
+                        if index % 2 == 1 {
+                            ui.end_row();
+                        }
+                    }
+                });
+                ui.separator();
+                opened = self.template_library(ui);
+                // Outcome of the last open, save or delete in this window
+                match &self.templates.status {
+                    Some(Ok(message)) => {
+                        ui.weak(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                    }
+                    None => {}
+                }
+            });
+        if let Some(example) = chosen {
+            opened = Some(self.load_workspace(example.session).map_err(|error| format!("{}: {}", example.title, error)));
+        }
+        match opened {
+            Some(Ok(())) => {
+                open = false;
+                self.templates.status = None;
+            }
+            Some(Err(error)) => self.templates.status = Some(Err(error)),
+            None => {}
+        }
+        self.show_welcome = open;
+    }
+
+    /// Session as text for chat: copy it out, or paste one in to load it
+    fn share_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.share.open;
+        egui::Window::new("Share Session")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.label("The whole session, every workspace included, as one line to paste into a chat.");
+                if ui.button("📋 Copy share string").clicked() {
+                    self.share.status = Some(self.share_string().map(|text| {
+                        let message = format!("Copied {} characters to the clipboard", text.len());
+                        ui.output_mut(|o| o.copied_text = text);
+                        message
+                    }));
+                }
+                ui.separator();
+                ui.label("Paste a share string (surrounding text is ignored):");
+                ui.add(egui::TextEdit::multiline(&mut self.share.pasted)
+                    .hint_text(format!("{}…", session::SHARE_PREFIX))
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY));
+                if ui.add_enabled(!self.share.pasted.trim().is_empty(), egui::Button::new("📁 Load"))
+                    .on_hover_text("Replaces the current session")
+                    .clicked()
+                {
+                    let text = std::mem::take(&mut self.share.pasted);
+                    self.share.status = Some(self.load_share_string(&text).map(|()| "Loaded the shared session".to_string()));
+                    if matches!(self.share.status, Some(Err(_))) {
+                        self.share.pasted = text;
+                    }
+                }
+                match &self.share.status {
+                    Some(Ok(message)) => {
+                        ui.weak(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                    }
+                    None => {}
+                }
+            });
+        self.share.open = open;
+    }
+
+    /// Manual release check; the network is only used after "Check for updates"
+    fn updates_window(&mut self, ctx: &egui::Context) {
+        if self.updates.poll() {
+            ctx.request_repaint();
+        }
+        let mut open = self.show_updates;
+        egui::Window::new("Updates")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(format!("Installed version: {}", updates::CURRENT_VERSION));
+                let checking = matches!(self.updates, updates::UpdateStatus::Checking(_));
+                if ui.add_enabled(!checking, egui::Button::new("Check for updates"))
+                    .on_hover_text("Asks the GitHub releases API for the latest release")
+                    .clicked()
+                {
+                    self.updates.check();
+                }
+                match &self.updates {
+                    updates::UpdateStatus::Idle => {}
+                    updates::UpdateStatus::Checking(_) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Checking…");
+                        });
+                        // Keep polling until the answer arrives
+                        ctx.request_repaint_after(std::time::Duration::from_millis(200));
+                    }
+                    updates::UpdateStatus::UpToDate(release) => {
+                        ui.label(format!("You are up to date (latest release: {}).", release.tag_name));
+                    }
+                    updates::UpdateStatus::Available(release) => {
+                        ui.strong(format!("New release: {}", release.name.as_deref().unwrap_or(&release.tag_name)));
+                        ui.hyperlink_to("Open the release page", &release.html_url);
+                        ui.separator();
+                        ui.label("Changelog:");
+                        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            ui.monospace(release.body.as_deref().unwrap_or("(no release notes)"));
+                        });
+                    }
+                    updates::UpdateStatus::Failed(error) => {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                    }
+                }
+            });
+        self.show_updates = open;
+    }
+
+    /// Divergences between two picked distributions, recomputed every frame so
+    /// they follow parameter edits live
+    fn comparison_window(&mut self, ctx: &egui::Context) {
+        let choices = self.distribution_choices();
+        let mut open = self.comparison.open;
+        egui::Window::new("Compare Distributions")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let tool = &mut self.comparison;
+                distribution_picker(ui, "P:", "compare_first", &mut tool.first, &choices);
+                distribution_picker(ui, "Q:", "compare_second", &mut tool.second, &choices);
+                let pair = (
+                    tool.first.and_then(|id| self.distributions.get(&id)),
+                    tool.second.and_then(|id| self.distributions.get(&id)),
+                );
+                match pair {
+                    (Some(p), Some(q)) => {
+                        Self::comparison_metrics(ui, p, q);
+                        ui.checkbox(&mut tool.shade_overlap, "Shade overlap min(p, q) on the plot");
+                        ui.checkbox(&mut tool.show_bhattacharyya_curve, "Draw √(p q) curve (area = BC)");
+                    }
+                    _ => {
+                        ui.label("Pick two distributions.");
+                    }
+                }
+
+                ui.separator();
+                egui::CollapsingHeader::new("Model evidence")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let evidence = self.chain_evidence();
+                        if evidence.is_empty() {
+                            ui.label("No products or posteriors yet.");
+                            return;
+                        }
+                        egui::Grid::new("model_evidence").striped(true).show(ui, |ui| {
+                            ui.strong("Product / posterior");
+                            ui.strong("Z");
+                            ui.strong("log Z");
+                            ui.end_row();
+                            for (id, name) in &choices {
+                                if let Some(z) = evidence.get(id) {
+                                    ui.label(name);
+                                    ui.label(format!("{:.6e}", z));
+                                    ui.label(format!("{:.4}", z.ln()));
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                        ui.label("Z is the marginal likelihood ∫ Π fᵢ(x) dx of the raw product, \
+                                  including the scales of any upstream products.");
+                    });
+
+                ui.separator();
+                egui::CollapsingHeader::new("Bayes factor")
+                    .default_open(true)
+                    .show(ui, |ui| self.bayes_factor_section(ui, &choices));
+            });
+        self.comparison.open = open;
+    }
+
+    /// Evidence ratio of two products/posteriors, typically the same
+    /// likelihood combined with alternative priors
+    fn bayes_factor_section(&mut self, ui: &mut egui::Ui, choices: &[(u32, String)]) {
+        let evidence = self.chain_evidence();
+        let models: Vec<(u32, String)> = choices.iter()
+            .filter(|(id, _)| evidence.contains_key(id))
+            .cloned()
+            .collect();
+        let tool = &mut self.comparison;
+        distribution_picker(ui, "M₁:", "bayes_factor_one", &mut tool.model_one, &models);
+        distribution_picker(ui, "M₂:", "bayes_factor_two", &mut tool.model_two, &models);
+        let (Some(one), Some(two)) = (tool.model_one, tool.model_two) else {
+            ui.label("Pick two products or posteriors.");
+            return;
+        };
+
+        let log_factor = evidence[&one].ln() - evidence[&two].ln();
+        let favored = if log_factor >= 0.0 { "M₁" } else { "M₂" };
+        ui.label(format!("BF₁₂ = Z₁ / Z₂ = {:.6e}", log_factor.exp()));
+        ui.label(format!("log BF₁₂ = {:.4}, log₁₀ BF₁₂ = {:.4}", log_factor, log_factor / std::f64::consts::LN_10));
+        ui.strong(format!("{} evidence for {}", jeffreys_strength(log_factor.exp()), favored))
+            .on_hover_text("Jeffreys scale on the factor favoring the better model: \
+                            < 3.2 barely worth mentioning, < 10 substantial, < 31.6 strong, < 100 very strong, else decisive");
+
+        let (parents_one, parents_two) = (&self.distributions[&one].parent_ids, &self.distributions[&two].parent_ids);
+        if !parents_one.iter().any(|parent| parents_two.contains(parent)) {
+            ui.colored_label(
+                egui::Color32::from_rgb(230, 140, 0),
+                "⚠ The two models share no factor; a Bayes factor compares alternative priors for the same likelihood",
+            );
+        }
+    }
+
+    /// Document color names per curve color and the label font for TikZ export
+    fn tikz_export_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("TikZ/pgfplots export").show(ui, |ui| {
+            let mut export = std::mem::take(&mut self.tikz_export);
+            export.controls(ui, || self.export_tikz());
+            self.tikz_export = export;
+            ui.weak("A tikzpicture of the current view for \\input into a document that loads pgfplots");
+            ui.separator();
+            ui.label("Theme:");
+            let theme = &mut self.latex_theme;
+            theme.color_names.resize(PALETTE.len(), String::new());
+            egui::Grid::new("latex_colors").show(ui, |ui| {
+                for (slot, color) in PALETTE.iter().enumerate() {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, *color);
+                    let name = &mut theme.color_names[slot];
+                    ui.add(egui::TextEdit::singleline(name).hint_text("RGB").desired_width(120.0));
+                    let trimmed = name.trim();
+                    if !trimmed.is_empty() && !LatexTheme::is_valid_color_name(trimmed) {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), "⚠ not a color name");
+                    } else {
+                        ui.weak(format!("color={}", theme.tikz_color(slot)));
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Font:");
+                ui.add(egui::TextEdit::singleline(&mut theme.font).hint_text("document default").desired_width(140.0));
+                if let Some(option) = theme.font_option() {
+                    ui.weak(option);
+                }
+            });
+            ui.label("Names refer to colors defined in your document, e.g. \\definecolor{MyBlue}{HTML}{1F77B4}.");
+        });
+    }
+
+    /// Divergence table for the chosen pair P, Q
+    fn comparison_metrics(ui: &mut egui::Ui, p: &GaussianDistribution, q: &GaussianDistribution) {
+        ui.separator();
+        let method = if p.has_closed_form() && q.has_closed_form() { "closed form" } else { "numerical" };
+        egui::Grid::new("comparison_metrics").striped(true).show(ui, |ui| {
+            ui.label("D_KL(P‖Q)");
+            ui.label(format_metric(divergence::kl_divergence(p, q), "nats"));
+            ui.end_row();
+            ui.label("D_KL(Q‖P)");
+            ui.label(format_metric(divergence::kl_divergence(q, p), "nats"));
+            ui.end_row();
+            ui.label("Hellinger H(P, Q)")
+                .on_hover_text("√(1 − ∫ √(p q) dx): symmetric, 0 for identical and 1 for disjoint distributions");
+            ui.label(format!("{:.6}", divergence::hellinger_distance(p, q)));
+            ui.end_row();
+            ui.label("Wasserstein W₁(P, Q)")
+                .on_hover_text("∫₀¹ |F_P⁻¹(u) − F_Q⁻¹(u)| du: the earth-mover distance, finite even for disjoint supports");
+            ui.label(format!("{:.6} (x units)", divergence::wasserstein_distance(p, q)));
+            ui.end_row();
+            let total_variation = divergence::total_variation_distance(p, q);
+            ui.label("Total variation TV(P, Q)")
+                .on_hover_text("½ ∫ |p − q| dx: the largest difference in probability P and Q assign to any event");
+            ui.label(format!("{:.6} (overlap ∫ min(p, q) dx = {:.6})", total_variation, 1.0 - total_variation));
+            ui.end_row();
+            let coefficient = divergence::bhattacharyya_coefficient(p, q);
+            ui.label("Bhattacharyya BC(P, Q)")
+                .on_hover_text("∫ √(p q) dx: 1 for identical, 0 for disjoint distributions");
+            ui.label(format!("{:.6}", coefficient));
+            ui.end_row();
+            ui.label("Bhattacharyya D_B(P, Q)")
+                .on_hover_text("−ln BC. Classifying between P and Q with equal priors errs with probability at most ½ e^(−D_B)");
+            ui.label(format!("{} (Bayes error ≤ {:.6})", format_metric(divergence::bhattacharyya_distance(p, q), "nats"), 0.5 * coefficient));
+            ui.end_row();
+        });
+        ui.label(format!(
+            "Computed in {}. KL is asymmetric and unbounded; Hellinger and TV are symmetric and within [0, 1]; \
+             W₁ is symmetric and measured in units of x.",
+            method,
+        ));
+        if p.domain.is_some() || q.domain.is_some() {
+            ui.weak("A distribution clipped to a domain is compared renormalized to it, i.e. as X given X in the domain.");
+        }
+    }
+
+    /// Turn a primary-button drag on the plot into the conditioning interval
+    fn handle_interval_drag(&mut self, plot_ui: &egui_plot::PlotUi) {
+        let response = plot_ui.response();
+        let axis = self.axis_map();
+        let pointer_x = plot_ui.pointer_coordinate().map(|p| axis.to_data(p.x));
+        if response.drag_started() {
+            self.interval_drag_start = pointer_x;
+        }
+        if let (true, Some(start), Some(x)) = (response.dragged(), self.interval_drag_start, pointer_x) {
+            if start != x {
+                self.condition_interval = Some((start.min(x), start.max(x)));
+            }
+        }
+        if response.drag_stopped() {
+            self.interval_drag_start = None;
+        }
+    }
+
+    /// Drag the endpoints of the interval on the plot. Hovering a handle is
+    /// remembered so the next frame can turn plot panning off.
+    fn drag_interval_handles(&mut self, plot_ui: &egui_plot::PlotUi) {
+        const GRAB_DISTANCE: f32 = 6.0;
+        let Some((lower, upper)) = self.condition_interval else {
+            self.handle_hovered = false;
+            self.dragged_handle = None;
+            return;
+        };
+        let axis = self.axis_map();
+        let response = plot_ui.response();
+        let pointer = plot_ui.pointer_coordinate();
+        let hovered = pointer.and_then(|pointer| {
+            let pointer_x = plot_ui.screen_from_plot(pointer).x;
+            [lower, upper].iter().position(|&x| {
+                let handle_x = plot_ui.screen_from_plot(egui_plot::PlotPoint::new(axis.to_view(x), pointer.y)).x;
+                (handle_x - pointer_x).abs() <= GRAB_DISTANCE
+            })
+        });
+
+        if response.drag_started() {
+            self.dragged_handle = hovered;
+        }
+        if let (Some(handle), Some(pointer), true) = (self.dragged_handle, pointer, response.dragged()) {
+            let (interval, handle) = move_interval_endpoint((lower, upper), handle, axis.to_data(pointer.x));
+            self.condition_interval = Some(interval);
+            self.dragged_handle = Some(handle);
+        }
+        if response.drag_stopped() {
+            self.dragged_handle = None;
+        }
+        self.handle_hovered = hovered.is_some() || self.dragged_handle.is_some();
+        if self.handle_hovered {
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        }
+    }
+
+    /// Drag the μ / σ handles of input distributions; derived distributions
+    /// follow within the same frame
+    fn drag_parameter_handles(&mut self, plot_ui: &egui_plot::PlotUi) {
+        const GRAB_DISTANCE: f32 = 8.0;
+        if !self.parameter_handles || self.plot_mode != PlotMode::Density || self.is_view_only() {
+            self.dragged_parameter = None;
+            return;
+        }
+        let response = plot_ui.response();
+        let pointer = plot_ui.pointer_coordinate();
+        let hovered = pointer.and_then(|pointer| {
+            let pointer = plot_ui.screen_from_plot(pointer);
+            self.parameter_handle_points().into_iter()
+                .map(|(id, handle, point)| {
+                    let screen = plot_ui.screen_from_plot(egui_plot::PlotPoint::new(point[0], point[1]));
+                    (id, handle, screen.distance(pointer))
+                })
+                .filter(|(_, _, distance)| *distance <= GRAB_DISTANCE)
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(id, handle, _)| (id, handle))
+        });
+
+        if response.drag_started() {
+            self.dragged_parameter = hovered;
+        }
+        if let (Some((id, handle)), Some(pointer), true) = (self.dragged_parameter, pointer, response.dragged()) {
+            let x = self.axis_map().to_data(pointer.x);
+            if let Some(dist) = self.distributions.get_mut(&id) {
+                dist.drag_handle(handle, x);
+                self.update_product_distributions();
+            }
+        }
+        if response.drag_stopped() {
+            self.dragged_parameter = None;
+        }
+        if hovered.is_some() || self.dragged_parameter.is_some() {
+            self.handle_hovered = true;
+            plot_ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        }
+    }
+
+    fn python_export_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Python export").show(ui, |ui| {
+            let mut export = std::mem::take(&mut self.python_export);
+            export.controls(ui, || self.export_python());
+            self.python_export = export;
+            ui.weak("A NumPy/SciPy/Matplotlib script that rebuilds the distributions and draws the current view");
+        });
+    }
+
+    fn r_export_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("R export").show(ui, |ui| {
+            let mut export = std::mem::take(&mut self.r_export);
+            export.controls(ui, || self.export_r());
+            self.r_export = export;
+            ui.weak("An R script that rebuilds the distributions and draws the current view with ggplot2");
+        });
+    }
+
+    fn report_export_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Report").show(ui, |ui| {
+            let markdown = is_markdown_path(&self.report_export.path);
+            let style = (*ui.ctx().style()).clone();
+            let mut export = std::mem::take(&mut self.report_export);
+            export.controls(ui, || self.export_report(markdown, style));
+            self.report_export = export;
+            ui.weak("A self-contained HTML page (Markdown for .md) with the plot at the PNG export size, \
+                     a table of every distribution and the session embedded");
+        });
+    }
+
+    /// Report for sharing: the plot as an embedded PNG, the parameters and
+    /// summary statistics of every distribution, and the session it came from
+    pub fn export_report(&mut self, markdown: bool, style: egui::Style) -> Result<String, String> {
+        use base64::Engine;
+        let (width, height, scale) = (self.png_export.width, self.png_export.height, self.png_export.scale);
+        let png = self.export_png(width, height, scale, style)?;
+        let image = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png));
+        let session = self.save_session()?;
+
+        let level = format!("{:.0}% interval", 100.0 * self.credible_level);
+        let header = ["Name", "Definition", "Mean", "SD", "Median", "Mode", "Entropy (nats)", level.as_str()];
+        let mut dists: Vec<&GaussianDistribution> = self.distributions.values().collect();
+        dists.sort_by_key(|dist| dist.id);
+        let rows: Vec<Vec<String>> = dists.iter()
+            .map(|dist| {
+                let summary = dist.moment_summary();
+                let definition = if dist.is_derived() {
+                    let parent_names: Vec<String> = dist.parent_ids.iter()
+                        .map(|id| self.distributions.get(id).map_or_else(|| format!("#{}", id), |parent| parent.name.clone()))
+                        .collect();
+                    dist.operation.expression(&parent_names)
+                } else {
+                    format!("N({:.4}, {:.4}²)", dist.mean, dist.std_dev)
+                };
+                let intervals: Vec<String> = dist.credible_regions(self.credible_level).iter()
+                    .map(|(lower, upper)| format!("[{:.4}, {:.4}]", lower, upper))
+                    .collect();
+                let name = if dist.hidden { format!("{} (hidden)", dist.name) } else { dist.name.clone() };
+                vec![
+                    name,
+                    definition.replace('\n', " "),
+                    format!("{:.4}", summary.mean),
+                    format!("{:.4}", summary.std_dev),
+                    format!("{:.4}", summary.median),
+                    format!("{:.4}", summary.mode),
+                    format!("{:.4}", dist.entropy()),
+                    intervals.join(" ∪ "),
+                ]
+            })
+            .collect();
+
+        let title = "PDF Viewer report";
+        if markdown {
+            let cell = |text: &str| text.replace('|', "\\|");
+            let line = |cells: &[String]| format!("| {} |\n", cells.iter().map(|text| cell(text)).collect::<Vec<_>>().join(" | "));
+            let mut report = format!("# {}\n\n![Plot]({})\n\n## Distributions\n\n", title, image);
+            report.push_str(&line(&header.map(str::to_string)));
+            report.push_str(&line(&header.map(|_| "---".to_string())));
+            for row in &rows {
+                report.push_str(&line(row));
+            }
+            // A fence longer than any backtick run in the session
+            let fence = "`".repeat(session.split(|c| c != '`').map(str::len).max().unwrap_or(0).max(2) + 1);
+            report.push_str(&format!("\n## Session\n\nLoad this JSON with \"Load Session\" to continue the analysis.\n\n{}json\n{}\n{}\n", fence, session, fence));
+            return Ok(report);
+        }
+        let cells = |tag: &str, cells: &[String]| -> String {
+            cells.iter().map(|text| format!("<{}>{}</{}>", tag, html_escape(text), tag)).collect()
+        };
+        let mut table = format!("<tr>{}</tr>\n", cells("th", &header.map(str::to_string)));
+        for row in &rows {
+            table.push_str(&format!("<tr>{}</tr>\n", cells("td", row)));
+        }
+        Ok(format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{style}\n</style>\n</head>\n<body>\n\
+             <h1>{title}</h1>\n<img alt=\"Plot\" src=\"{image}\">\n<h2>Distributions</h2>\n<table>\n{table}</table>\n\
+             <h2>Session</h2>\n<p>Save this JSON to a file and open it with \"Load Session\" to continue the analysis.</p>\n\
+             <details><summary>Session JSON</summary>\n<pre id=\"session\">{session}</pre>\n</details>\n</body>\n</html>\n",
+            title = title,
+            style = REPORT_STYLE,
+            image = image,
+            table = table,
+            session = html_escape(&session),
+        ))
+    }
+
+    fn recovery_window(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = &self.autosave.recovery else {
+            return;
+        };
+        let age = recovery.saved
+            .and_then(|saved| saved.elapsed().ok())
+            .map_or("at an unknown time".to_string(), |age| match age.as_secs() {
+                0..=59 => "less than a minute ago".to_string(),
+                60..=3599 => format!("{} min ago", age.as_secs() / 60),
+                seconds => format!("{:.1} h ago", seconds as f64 / 3600.0),
+            });
+        let mut answer = None;
+        egui::Window::new("Recover session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The viewer did not close cleanly last time.");
+                ui.label(format!("An autosaved session from {} is available.", age));
+                if let Some(error) = &recovery.error {
+                    ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        answer = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        answer = Some(false);
+                    }
+                });
+            });
+        if let Some(restore) = answer {
+            if let (Err(error), Some(recovery)) = (self.resolve_recovery(restore), &mut self.autosave.recovery) {
+                recovery.error = Some(format!("Cannot restore it: {}", error));
+            }
+        }
+    }
+
+    fn stream_controls(&mut self, ui: &mut egui::Ui) {
+        let mut stop = false;
+        egui::CollapsingHeader::new("Live stream").show(ui, |ui| {
+            let live = &mut self.live_stream;
+            match &live.stream {
+                Some(stream) => {
+                    ui.label(format!("Reading {} · {} values received", stream.description(), live.received));
+                    if stream.closed() {
+                        ui.weak("The stream has ended");
+                    }
+                    stop = ui.button("Stop").clicked();
+                }
+                None => {
+                    ui.horizontal(|ui| {
+                        for source in stream::StreamSource::ALL {
+                            ui.radio_value(&mut live.source, source, source.label());
+                        }
+                        if live.source != stream::StreamSource::Stdin {
+                            ui.add(egui::TextEdit::singleline(&mut live.interface).desired_width(90.0))
+                                .on_hover_text("127.0.0.1 accepts values from this computer only. \
+                                    0.0.0.0 accepts them from anyone on the network.");
+                            ui.add(egui::DragValue::new(&mut live.port).range(1..=65535).prefix("port "));
+                        }
+                    });
+                    if ui.button("Start")
+                        .on_hover_text("Numbers separated by spaces, commas, semicolons or newlines; anything else is ignored")
+                        .clicked()
+                    {
+                        match stream::DataStream::open(live.source, live.interface.trim(), live.port) {
+                            Ok(stream) => {
+                                live.stream = Some(stream);
+                                live.buffer.clear();
+                                live.received = 0;
+                                live.error = None;
+                            }
+                            Err(error) => live.error = Some(format!("Cannot open the {} stream: {}", live.source.label(), error)),
+                        }
+                    }
+                }
+            }
+            ui.add(egui::DragValue::new(&mut live.window)
+                .range(10..=1_000_000)
+                .prefix("keep last ")
+                .suffix(" values"));
+            ui.checkbox(&mut live.running_fit, "Running normal fit")
+                .on_hover_text("Maximum-likelihood normal over the kept values, refitted as they arrive");
+            if let Some(error) = &live.error {
+                ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+            }
+        });
+        if stop {
+            self.live_stream.stream = None;
+        }
+    }
+
+    fn data_controls(&mut self, ui: &mut egui::Ui) {
+        self.stream_controls(ui);
+        let mut load = false;
+        ui.horizontal(|ui| {
+            ui.label("Data file:");
+            let response = ui.add(egui::TextEdit::singleline(&mut self.data.path).desired_width(150.0))
+                .on_hover_text("CSV or other delimited text, Parquet, or Arrow IPC (Feather v2)");
+            load = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            load |= ui.add_enabled(!self.data.path.trim().is_empty(), egui::Button::new("Load")).clicked();
+        });
+        if load {
+            let path = std::path::PathBuf::from(self.data.path.trim());
+            let source = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+            match columnar::Format::detect(&path) {
+                Some(format) => self.import_columnar(source, path, format),
+                None => match std::fs::read_to_string(&path) {
+                    Ok(text) => self.import_data(source, text),
+                    Err(error) => self.data.error = Some(format!("Cannot read {}: {}", path.display(), error)),
+                },
+            }
+        }
+
+        if self.data.columns.len() > 1 {
+            let mut column = self.data.column;
+            egui::ComboBox::from_label("Column")
+                .selected_text(self.data.columns.get(column).map(String::as_str).unwrap_or(""))
+                .show_ui(ui, |ui| {
+                    for (index, name) in self.data.columns.iter().enumerate() {
+                        ui.selectable_value(&mut column, index, name);
+                    }
+                });
+            if column != self.data.column {
+                self.select_data_column(column);
+            }
+        }
+        let import = &mut self.data;
+        if let Some(dataset) = &import.dataset {
+            let (lower, upper) = dataset.range();
+            ui.label(format!(
+                "n = {}, mean {:.4}, sd {:.4}, range [{:.4}, {:.4}]",
+                dataset.values.len(),
+                dataset.mean(),
+                dataset.std_dev(),
+                lower,
+                upper
+            ));
+            if dataset.skipped > 0 {
+                ui.weak(format!("{} rows without a number were skipped", dataset.skipped));
+            }
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut import.show_histogram, "Histogram");
+                let speed = import.bin_width * 0.01;
+                let response = ui.add(egui::DragValue::new(&mut import.bin_width)
+                    .speed(speed)
+                    .range(1e-9..=f64::INFINITY)
+                    .prefix("bin width "));
+                if ui.small_button("Auto").on_hover_text("Freedman–Diaconis rule").clicked() {
+                    import.bin_width = data::default_bin_width(dataset);
+                }
+                if response.changed() || import.histogram.as_ref().is_some_and(|histogram| histogram.bin_width != import.bin_width) {
+                    import.histogram = Some(data::Histogram::new(&dataset.values, import.bin_width));
+                }
+            });
+            ui.checkbox(&mut import.show_ecdf, "Empirical CDF")
+                .on_hover_text("Step function of the data, drawn in the CDF view");
+            if ui.button("➕ Kernel density estimate")
+                .on_hover_text("Gaussian kernel with Silverman's bandwidth; the bandwidth can be changed on the new curve")
+                .clicked()
+            {
+                self.add_kde(None);
+            }
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("fit_family")
+                    .selected_text(self.data.fit_family.label())
+                    .show_ui(ui, |ui| {
+                        for family in FitFamily::ALL {
+                            ui.selectable_value(&mut self.data.fit_family, family, family.label());
+                        }
+                    });
+                if !self.data.fit_family.has_closed_form_mle() {
+                    self.data.fit_method = FitMethod::Moments;
+                }
+                ui.add_enabled_ui(self.data.fit_family.has_closed_form_mle(), |ui| {
+                    ui.radio_value(&mut self.data.fit_method, FitMethod::MaximumLikelihood, FitMethod::MaximumLikelihood.label());
+                });
+                ui.radio_value(&mut self.data.fit_method, FitMethod::Moments, "Moments")
+                    .on_hover_text("Match the sample mean and variance");
+            });
+            if ui.button("Fit distribution to data").clicked() {
+                match self.fit_to_data(self.data.fit_family, self.data.fit_method) {
+                    Ok(id) => {
+                        self.data.gof_target = Some(id);
+                        self.data.bootstrap.target = Some(id);
+                        self.data.error = None;
+                    }
+                    Err(error) => self.data.error = Some(error),
+                }
+            }
+            self.mixture_controls(ui);
+            self.bootstrap_controls(ui);
+            self.goodness_of_fit_controls(ui);
+            self.residual_controls(ui);
+        }
+        let import = &mut self.data;
+        if let Some(error) = &import.error {
+            ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+        }
+    }
+
+    fn model_spec_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Import from model spec").show(ui, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut self.model_spec.text)
+                .code_editor()
+                .desired_rows(4)
+                .desired_width(f32::INFINITY)
+                .hint_text("mu ~ normal(0, 10);\nsigma ~ exponential(1);\np ~ beta(2, 2);"));
+            if ui.add_enabled(!self.model_spec.text.trim().is_empty(), egui::Button::new("Import")).clicked() {
+                let text = self.model_spec.text.clone();
+                self.model_spec.status = Some(self.import_model_spec(&text).map(|ids| format!("Added {} distribution(s)", ids.len())));
+            }
+            match &self.model_spec.status {
+                Some(Ok(message)) => {
+                    ui.weak(message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+                }
+                None => {}
+            }
+            ui.weak("One `name ~ family(...)` per line, with Stan's normal, lognormal, gamma (shape, rate), exponential and beta; other lines are skipped");
+        });
+    }
+
+    fn mixture_controls(&mut self, ui: &mut egui::Ui) {
+        let (mut fit, mut compare) = (false, false);
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.data.mixture.components)
+                .range(2..=MAX_MIXTURE_COMPONENTS)
+                .prefix("k = "));
+            fit = ui.button("Fit Gaussian mixture")
+                .on_hover_text("Expectation–maximization; the components are listed as hidden inputs of the mixture")
+                .clicked();
+            compare = ui.button("Compare k")
+                .on_hover_text(format!("Fit k = 1 to {} and compare them by BIC", MAX_MIXTURE_COMPONENTS))
+                .clicked();
+        });
+        let result = if fit {
+            self.fit_mixture_to_data(self.data.mixture.components).map(|id| {
+                self.data.gof_target = Some(id);
+            })
+        } else if compare {
+            self.compare_mixtures()
+        } else {
+            Ok(())
+        };
+        match result {
+            Ok(()) if fit || compare => self.data.error = None,
+            Ok(()) => {}
+            Err(error) => self.data.error = Some(error),
+        }
+
+        let comparison = &self.data.mixture.comparison;
+        let Some(best) = comparison.iter().map(|fit| fit.bic).min_by(f64::total_cmp) else {
+            return;
+        };
+        egui::Grid::new("mixture_comparison").num_columns(3).show(ui, |ui| {
+            ui.strong("k");
+            ui.strong("log-likelihood");
+            ui.strong("BIC");
+            ui.end_row();
+            for fit in comparison {
+                ui.label(fit.weights.len().to_string());
+                ui.monospace(format!("{:.4}", fit.log_likelihood));
+                let bic = ui.monospace(format!("{:.4}{}", fit.bic, if fit.bic == best { " ★" } else { "" }));
+                if !fit.converged {
+                    bic.on_hover_text(format!("EM stopped after {} iterations without converging", fit.iterations));
+                }
+                ui.end_row();
+            }
+        });
+        ui.weak("★ lowest BIC, the preferred number of components");
+    }
+
+    fn bootstrap_controls(&mut self, ui: &mut egui::Ui) {
+        let choices: Vec<(u32, String)> = self.distribution_choices()
+            .into_iter()
+            .filter(|(id, _)| self.distributions[id].fit.is_some() && !self.distributions[id].hidden)
+            .collect();
+        distribution_picker(ui, "Bootstrap band for:", "bootstrap_target", &mut self.data.bootstrap.target, &choices);
+        let tool = &mut self.data.bootstrap;
+        let running = matches!(tool.job, bootstrap::BootstrapJob::Running(_));
+        let mut start = None;
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut tool.replicates).range(20..=5000).prefix("B = "))
+                .on_hover_text("Resamples of the data to refit");
+            let mut percent = 100.0 * tool.level;
+            if ui.add(egui::DragValue::new(&mut percent).range(50.0..=99.9).speed(0.5).suffix("%")).changed() {
+                tool.level = percent / 100.0;
+            }
+            if ui.add_enabled(tool.target.is_some() && !running, egui::Button::new("Compute band"))
+                .on_hover_text("Resample the data with replacement, refit each resample and shade the spread of the refitted curves")
+                .clicked()
+            {
+                start = tool.target;
+            }
+        });
+        if let Some(id) = start {
+            if let Err(error) = self.start_bootstrap(id) {
+                self.data.bootstrap.job = bootstrap::BootstrapJob::Failed(error);
+            }
+        }
+
+        let tool = &self.data.bootstrap;
+        match &tool.job {
+            bootstrap::BootstrapJob::Idle => {}
+            bootstrap::BootstrapJob::Running(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!("Refitting {} resamples…", tool.replicates));
+                });
+            }
+            bootstrap::BootstrapJob::Done(band) => {
+                let target = tool.computed_for.and_then(|(id, _)| self.distributions.get(&id));
+                match target {
+                    Some(dist) if tool.band_for(dist).is_some() => {
+                        ui.weak(format!(
+                            "{:.0}% band of {} from {} refits, shaded in the density and CDF views",
+                            100.0 * band.level,
+                            dist.name,
+                            band.replicates
+                        ));
+                    }
+                    Some(_) => {
+                        ui.weak("The curve changed since the band was computed");
+                    }
+                    None => {}
+                }
+            }
+            bootstrap::BootstrapJob::Failed(error) => {
+                ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+            }
+        }
+    }
+
+    fn goodness_of_fit_controls(&mut self, ui: &mut egui::Ui) {
+        let choices = self.distribution_choices();
+        distribution_picker(ui, "Test against:", "gof_target", &mut self.data.gof_target, &choices);
+        self.refresh_goodness_of_fit();
+        let Some((ks, anderson_darling)) = self.data.goodness_of_fit else {
+            return;
+        };
+        egui::Grid::new("goodness_of_fit").num_columns(3).show(ui, |ui| {
+            ui.label("");
+            ui.strong("statistic");
+            ui.strong("p-value");
+            ui.end_row();
+            ui.label("Kolmogorov–Smirnov D");
+            ui.monospace(format!("{:.4}", ks.statistic));
+            ui.monospace(format!("{:.4}", ks.p_value));
+            ui.end_row();
+            ui.label("Anderson–Darling A²");
+            ui.monospace(format!("{:.4}", anderson_darling.statistic));
+            ui.monospace(format!("{:.4}", anderson_darling.p_value));
+            ui.end_row();
+        });
+        ui.weak(format!("Largest CDF gap at x = {:.4}, marked in the CDF view", ks.location));
+        if self.data.gof_target.and_then(|id| self.distributions.get(&id)).is_some_and(|dist| dist.fit.is_some()) {
+            ui.weak("p-values assume the parameters were not fitted to this data, so they are optimistic");
+        }
+    }
+
+    /// Histogram counts against those the tested distribution expects, with
+    /// Pearson residuals and a chi-square test
+    fn residual_controls(&mut self, ui: &mut egui::Ui) {
+        let Some((bins, chi_square)) = &self.data.residuals else {
+            return;
+        };
+        egui::CollapsingHeader::new("Residual diagnostics").id_salt("residual_diagnostics").show(ui, |ui| {
+            match chi_square {
+                Some(test) => {
+                    ui.label(format!(
+                        "Pearson χ² = {:.3} on {} df, p = {:.4}",
+                        test.statistic, test.degrees_of_freedom, test.p_value
+                    ));
+                    ui.weak(format!(
+                        "{} histogram bins pooled into {} expecting at least {} each",
+                        bins.len(),
+                        test.pooled_bins,
+                        data::MIN_EXPECTED_COUNT
+                    ));
+                }
+                None => {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(230, 140, 0),
+                        "⚠ Too few data per bin for a chi-square test; try wider bins",
+                    );
+                }
+            }
+
+            let width = |bin: &data::ResidualBin| bin.right - bin.left;
+            let centre = |bin: &data::ResidualBin| 0.5 * (bin.left + bin.right);
+            let observed: Vec<egui_plot::Bar> = bins.iter()
+                .map(|bin| egui_plot::Bar::new(centre(bin), bin.observed as f64).width(width(bin)))
+                .collect();
+            let expected: Vec<[f64; 2]> = bins.iter().map(|bin| [centre(bin), bin.expected]).collect();
+            Plot::new("residual_counts")
+                .height(140.0)
+                .legend(egui_plot::Legend::default())
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(egui_plot::BarChart::new(observed)
+                        .name("Observed")
+                        .color(egui::Color32::from_rgba_unmultiplied(120, 120, 120, 120)));
+                    plot_ui.line(Line::new(expected.clone()).name("Expected").color(egui::Color32::RED));
+                    plot_ui.points(egui_plot::Points::new(expected).radius(2.5).color(egui::Color32::RED));
+                });
+
+            // Bins expecting nothing have no finite residual
+            let residuals: Vec<egui_plot::Bar> = bins.iter()
+                .filter(|bin| bin.residual.is_finite())
+                .map(|bin| {
+                    let color = if bin.residual.abs() > 2.0 { egui::Color32::from_rgb(230, 140, 0) } else { egui::Color32::GRAY };
+                    egui_plot::Bar::new(centre(bin), bin.residual).width(width(bin)).fill(color)
+                })
+                .collect();
+            Plot::new("pearson_residuals")
+                .height(120.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(egui_plot::BarChart::new(residuals).name("Pearson residual"));
+                    for level in [-2.0, 2.0] {
+                        plot_ui.hline(egui_plot::HLine::new(level).color(egui::Color32::DARK_GRAY).style(egui_plot::LineStyle::dashed_loose()));
+                    }
+                });
+            ui.weak("Bars are (O − E)/√E; beyond ±2 they are highlighted");
+        });
+    }
+
+    fn auto_export_controls(&mut self, ui: &mut egui::Ui) {
+        let export = &mut self.auto_export;
+        egui::CollapsingHeader::new("Auto-export (dashboards)").show(ui, |ui| {
+            ui.checkbox(&mut export.enabled, "Keep curves.csv and stats.csv up to date");
+            ui.horizontal(|ui| {
+                ui.label("Folder:");
+                ui.text_edit_singleline(&mut export.directory);
+            });
+            ui.horizontal(|ui| {
+                let mut timed = matches!(export.trigger, ExportTrigger::Every { .. });
+                ui.radio_value(&mut timed, false, "On change");
+                ui.radio_value(&mut timed, true, "Every");
+                export.trigger = match (timed, export.trigger) {
+                    (false, _) => ExportTrigger::OnChange,
+                    (true, ExportTrigger::Every { seconds }) => ExportTrigger::Every { seconds },
+                    (true, ExportTrigger::OnChange) => ExportTrigger::Every { seconds: 60 },
+                };
+                if let ExportTrigger::Every { seconds } = &mut export.trigger {
+                    ui.add(egui::DragValue::new(seconds).range(1..=86_400).suffix(" s"));
+                }
+            });
+            if let Some(error) = &export.error {
+                ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+            } else if export.exports > 0 {
+                ui.weak(format!("Written {} times", export.exports));
+            }
+        });
+    }
+
+    fn sweep_controls(&mut self, ui: &mut egui::Ui) {
+        let inputs: Vec<(u32, String)> = self.distribution_choices().into_iter()
+            .filter(|(id, _)| self.distributions.get(id).is_some_and(|dist| !dist.is_derived()))
+            .collect();
+        let sweep = &mut self.sweep;
+        distribution_picker(ui, "Distribution:", "sweep_target", &mut sweep.target, &inputs);
+        ui.horizontal(|ui| {
+            for parameter in [SweepParameter::Mean, SweepParameter::StdDev] {
+                if ui.radio_value(&mut sweep.parameter, parameter, parameter.label()).changed() {
+                    (sweep.from, sweep.to) = parameter.default_range();
+                }
+            }
+        });
+        let minimum = match sweep.parameter {
+            SweepParameter::Mean => f64::NEG_INFINITY,
+            SweepParameter::StdDev => 1e-3,
+        };
+        ui.horizontal(|ui| {
+            ui.label("From");
+            ui.add(egui::DragValue::new(&mut sweep.from).speed(0.05).range(minimum..=f64::INFINITY));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut sweep.to).speed(0.05).range(minimum..=f64::INFINITY));
+            ui.label("in");
+            ui.add(egui::DragValue::new(&mut sweep.period).speed(0.1).range(0.2..=120.0).suffix(" s"));
+        });
+        ui.checkbox(&mut sweep.bounce, "Back and forth");
+        ui.horizontal(|ui| {
+            let label = if sweep.playing { "⏸ Pause" } else { "▶ Play" };
+            if ui.add_enabled(sweep.target.is_some(), egui::Button::new(label)).clicked() {
+                sweep.playing = !sweep.playing;
+            }
+            if ui.button("⏹ Rewind").clicked() {
+                sweep.elapsed = 0.0;
+            }
+            ui.label(format!("{} = {:.3}", sweep.parameter.label(), sweep.value()));
+        });
+    }
+
+    fn timeline_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = if self.timeline.recording { "⏹ Stop recording" } else { "⏺ Record" };
+            if ui.button(label).clicked() {
+                let timeline = &mut self.timeline;
+                timeline.recording = !timeline.recording;
+                if timeline.recording && timeline.keyframes.is_empty() {
+                    timeline.started = Some(std::time::Instant::now());
+                    timeline.last_fingerprint = None;
+                }
+            }
+            if ui.add_enabled(!self.timeline.keyframes.is_empty(), egui::Button::new("Clear")).clicked() {
+                self.timeline = Timeline { recording: self.timeline.recording, ..Default::default() };
+                if self.timeline.recording {
+                    self.timeline.started = Some(std::time::Instant::now());
+                }
+            }
+        });
+        let count = self.timeline.keyframes.len();
+        if count == 0 {
+            ui.label("Record, then change parameters: every change becomes a keyframe.");
+            return;
+        }
+        let mut position = self.timeline.position.min(count - 1);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(position > 0, egui::Button::new("◀")).clicked() {
+                position -= 1;
+            }
+            ui.add(egui::Slider::new(&mut position, 0..=count - 1).show_value(false));
+            if ui.add_enabled(position + 1 < count, egui::Button::new("▶")).clicked() {
+                position += 1;
+            }
+        });
+        ui.label(format!(
+            "Keyframe {} of {} at t = {:.1} s",
+            position + 1,
+            count,
+            self.timeline.keyframes[position].time
+        ));
+        if position != self.timeline.position {
+            self.restore_keyframe(position);
+        }
+        if let Some(error) = &self.timeline.error {
+            ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", error));
+        }
+    }
+
+    fn group_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.group_name).hint_text("Group name").desired_width(120.0));
+            let inputs: Vec<u32> = self.selected_for_multiplication.iter().copied()
+                .filter(|id| self.distributions.get(id).is_some_and(|dist| !dist.is_derived()))
+                .collect();
+            if ui.add_enabled(!inputs.is_empty(), egui::Button::new("Group Selected")).clicked() {
+                let name = match self.group_name.trim() {
+                    "" => format!("Group {}", self.groups.len() + 1),
+                    name => name.to_string(),
+                };
+                self.groups.push(DistributionGroup { name, members: inputs, shift: 0.0, scale: 1.0 });
+                self.group_name.clear();
+                self.selected_for_multiplication.clear();
+            }
+        });
+
+        let mut ungroup = None;
+        for index in 0..self.groups.len() {
+            let group = &self.groups[index];
+            let members: Vec<&str> = group.members.iter()
+                .filter_map(|id| self.distributions.get(id).map(|dist| dist.name.as_str()))
+                .collect();
+            let members = members.join(", ");
+            let (mut shift, mut scale) = (group.shift, group.scale);
+            egui::CollapsingHeader::new(&group.name).id_salt(("group", index)).show(ui, |ui| {
+                ui.label(format!("Members: {}", members));
+                ui.horizontal(|ui| {
+                    ui.label("x ↦");
+                    ui.add(egui::DragValue::new(&mut scale).speed(0.01).range(1e-9..=1e9).max_decimals(9));
+                    ui.label("· x +");
+                    ui.add(egui::DragValue::new(&mut shift).speed(0.05));
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Reset transform").clicked() {
+                        (shift, scale) = (0.0, 1.0);
+                    }
+                    if ui.button("Ungroup").on_hover_text("Keeps the members' current parameters").clicked() {
+                        ungroup = Some(index);
+                    }
+                });
+            });
+            if (shift, scale) != (group.shift, group.scale) {
+                self.set_group_transform(index, shift, scale);
+            }
+        }
+        if let Some(index) = ungroup {
+            self.groups.remove(index);
+        }
+    }
+
+    fn reference_line_controls(&mut self, ui: &mut egui::Ui) {
+        if ui.button("➕ Add line").clicked() {
+            let (x_min, x_max) = self.data_range();
+            self.reference_lines.push(ReferenceLine::new((0.5 * (x_min + x_max) * 100.0).round() / 100.0));
+        }
+        let mut remove = None;
+        for (index, line) in self.reference_lines.iter_mut().enumerate() {
+            ui.push_id(("reference_line", index), |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut line.label).hint_text("Label").desired_width(100.0));
+                    ui.radio_value(&mut line.horizontal, false, "x =");
+                    ui.radio_value(&mut line.horizontal, true, "y =");
+                    ui.add(egui::DragValue::new(&mut line.position).speed(0.01));
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut line.color);
+                    egui::ComboBox::from_id_salt("style")
+                        .selected_text(line.style.label())
+                        .width(70.0)
+                        .show_ui(ui, |ui| {
+                            for style in StrokeStyle::ALL {
+                                ui.selectable_value(&mut line.style, style, style.label());
+                            }
+                        });
+                    ui.add(egui::DragValue::new(&mut line.width).speed(0.1).range(0.5..=6.0).suffix(" px"));
+                });
+            });
+        }
+        if let Some(index) = remove {
+            self.reference_lines.remove(index);
+        }
+    }
+
+    fn annotation_controls(&mut self, ui: &mut egui::Ui) {
+        if ui.button("➕ Add text").clicked() {
+            let bounds = self.plot_bounds.as_ref().map(bounds_array).unwrap_or([-6.0, 6.0, 0.0, 0.5]);
+            let [z_min, z_max, y_min, y_max] = bounds;
+            let axis = self.axis_map();
+            self.annotations.push(TextAnnotation::new([axis.to_data(0.5 * (z_min + z_max)), axis.y_to_data(0.5 * (y_min + y_max))]));
+        }
+        if self.placing_annotation.is_some() {
+            ui.weak("Click the plot to place it");
+        }
+        let mut remove = None;
+        for (index, annotation) in self.annotations.iter_mut().enumerate() {
+            ui.push_id(("annotation", index), |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut annotation.text).desired_width(140.0));
+                    ui.color_edit_button_srgb(&mut annotation.color);
+                    ui.add(egui::DragValue::new(&mut annotation.size).speed(0.2).range(8.0..=48.0).suffix(" pt"));
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let [x, y] = &mut annotation.position;
+                    ui.label("at");
+                    ui.add(egui::DragValue::new(x).speed(0.01));
+                    ui.add(egui::DragValue::new(y).speed(0.001));
+                    let placing = self.placing_annotation == Some((index, false));
+                    if ui.selectable_label(placing, "📍").on_hover_text("Place with a click on the plot").clicked() {
+                        self.placing_annotation = (!placing).then_some((index, false));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut arrow = annotation.arrow_to.is_some();
+                    if ui.checkbox(&mut arrow, "Arrow to").changed() {
+                        let [x, y] = annotation.position;
+                        annotation.arrow_to = arrow.then_some([x, 0.5 * y]);
+                    }
+                    if let Some([x, y]) = &mut annotation.arrow_to {
+                        ui.add(egui::DragValue::new(x).speed(0.01));
+                        ui.add(egui::DragValue::new(y).speed(0.001));
+                        let placing = self.placing_annotation == Some((index, true));
+                        if ui.selectable_label(placing, "📍").on_hover_text("Place the arrow tip with a click on the plot").clicked() {
+                            self.placing_annotation = (!placing).then_some((index, true));
+                        }
+                    }
+                });
+            });
+        }
+        if let Some(index) = remove {
+            self.annotations.remove(index);
+            self.placing_annotation = None;
+        }
+    }
+
+    fn axis_style_controls(&mut self, ui: &mut egui::Ui) {
+        let style = &mut self.axis_style;
+        egui::CollapsingHeader::new("Axes and grid").show(ui, |ui| {
+            egui::Grid::new("axis_labels").num_columns(2).show(ui, |ui| {
+                ui.label("x label:");
+                ui.add(egui::TextEdit::singleline(&mut style.x_label).hint_text("automatic"));
+                ui.end_row();
+                ui.label("y label:");
+                ui.add(egui::TextEdit::singleline(&mut style.y_label).hint_text("automatic"));
+                ui.end_row();
+                for (label, step) in [("x ticks every:", &mut style.x_step), ("y ticks every:", &mut style.y_step)] {
+                    ui.label(label);
+                    ui.horizontal(|ui| {
+                        let mut fixed = step.is_some();
+                        ui.checkbox(&mut fixed, "");
+                        *step = match (fixed, *step) {
+                            (true, Some(value)) => Some(value),
+                            (true, None) => Some(1.0),
+                            (false, _) => None,
+                        };
+                        if let Some(value) = step {
+                            ui.add(egui::DragValue::new(value).speed(0.01).range(1e-9..=1e9));
+                        } else {
+                            ui.weak("auto");
+                        }
+                    });
+                    ui.end_row();
+                }
+                ui.label("Numbers:");
+                ui.horizontal(|ui| {
+                    for format in [NumberFormat::Auto, NumberFormat::Fixed, NumberFormat::Scientific] {
+                        ui.radio_value(&mut style.number_format, format, format.label());
+                    }
+                    if style.number_format != NumberFormat::Auto {
+                        ui.add(egui::DragValue::new(&mut style.decimals).range(0..=10).suffix(" dp"));
+                    }
+                });
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut style.show_grid, "Grid");
+                ui.add_enabled(style.show_grid, egui::Checkbox::new(&mut style.minor_grid, "Minor grid lines"));
+            });
+        });
+    }
+
+    fn theme_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Theme:");
+            egui::ComboBox::from_id_salt("plot_theme")
+                .selected_text(self.theme.label())
+                .show_ui(ui, |ui| {
+                    for theme in PlotTheme::ALL {
+                        ui.selectable_value(&mut self.theme, theme, theme.label());
+                    }
+                });
+        });
+        egui::CollapsingHeader::new("Custom palette").show(ui, |ui| {
+            if self.custom_palette.is_empty() {
+                ui.weak("Using the theme's colours");
+                if ui.button("Edit a copy of the theme's colours").clicked() {
+                    self.custom_palette = self.theme.palette().iter().map(|color| [color.r(), color.g(), color.b()]).collect();
+                }
+                return;
+            }
+            let mut remove = None;
+            ui.horizontal_wrapped(|ui| {
+                for (slot, color) in self.custom_palette.iter_mut().enumerate() {
+                    let response = ui.color_edit_button_srgb(color);
+                    if response.secondary_clicked() {
+                        remove = Some(slot);
+                    }
+                    response.on_hover_text("Right-click to remove");
+                }
+                if ui.small_button("➕").clicked() {
+                    self.custom_palette.push([128, 128, 128]);
+                }
+            });
+            if let Some(slot) = remove {
+                self.custom_palette.remove(slot);
+            }
+            if ui.button("Back to the theme's colours").clicked() {
+                self.custom_palette.clear();
+            }
+        });
+    }
+}
+
+/// Shaded band between the `lower` and `upper` edges (view coordinates over
+/// the same xs), with dotted edges listed in the legend as `name`
+fn shaded_band(plot_ui: &mut egui_plot::PlotUi, lower: Vec<[f64; 2]>, upper: Vec<[f64; 2]>, color: egui::Color32, name: &str) {
+    // Polygons only fill correctly when convex, so fill slice by slice
+    let fill = color.gamma_multiply(0.25);
+    for i in 1..lower.len() {
+        let slice = vec![lower[i - 1], lower[i], upper[i], upper[i - 1]];
+        if slice.iter().any(|[_, y]| !y.is_finite()) {
+            continue;
+        }
+        plot_ui.polygon(egui_plot::Polygon::new(PlotPoints::new(slice))
+            .fill_color(fill)
+            .stroke(egui::Stroke::NONE));
+    }
+    for mut edge in [lower, upper] {
+        edge.retain(|[_, y]| y.is_finite());
+        plot_ui.line(Line::new(PlotPoints::new(edge))
+            .name(name)
+            .color(color.gamma_multiply(0.6))
+            .style(egui_plot::LineStyle::Dotted { spacing: 4.0 }));
+    }
+}
+
+/// Horizontal viridis gradient from `low` to `high`, labeled at both ends
+fn colorbar(ui: &mut egui::Ui, label: &str, low: f64, high: f64, colormap: Colormap) {
+    ui.horizontal(|ui| {
+        ui.label(format!("{:.3}", low));
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 12.0), egui::Sense::hover());
+        const STEPS: usize = 48;
+        let width = rect.width() / STEPS as f32;
+        for step in 0..STEPS {
+            let left = rect.left() + width * step as f32;
+            let cell = egui::Rect::from_min_max(egui::pos2(left, rect.top()), egui::pos2(left + width + 0.5, rect.bottom()));
+            ui.painter().rect_filled(cell, 0.0, colormap.color(step as f64 / (STEPS - 1) as f64));
+        }
+        ui.label(format!("{:.3}", high));
+    }).response.on_hover_text(format!("{}: low → high", label));
+}
+
+/// Labeled combo box choosing one distribution by id
+fn distribution_picker(
+    ui: &mut egui::Ui,
+    label: &str,
+    id_salt: &str,
+    selected: &mut Option<u32>,
+    choices: &[(u32, String)],
+) {
+    if selected.is_some_and(|id| !choices.iter().any(|(choice, _)| *choice == id)) {
+        *selected = None;
+    }
+    let current = choices.iter()
+        .find(|(id, _)| Some(*id) == *selected)
+        .map(|(_, name)| name.as_str())
+        .unwrap_or("(none)");
+
+    ui.horizontal(|ui| {
+        ui.label(label);
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                for (id, name) in choices {
+                    ui.selectable_value(selected, Some(*id), name);
+                }
+            });
+    });
+}
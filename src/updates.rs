@@ -0,0 +1,103 @@
+//! Opt-in check for newer releases on GitHub.
+//!
+//! Nothing here runs on its own: a check starts only when the user asks for
+//! one, and the request runs on a background thread so the UI stays live.
+
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+pub const RELEASES_URL: &str = "https://api.github.com/repos/ShabbirHasan1/pdf_viewer/releases/latest";
+
+/// Version of this build, compared against the release tags
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes, in Markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+}
+
+#[derive(Default)]
+pub enum UpdateStatus {
+    #[default]
+    Idle,
+    Checking(Receiver<Result<Release, String>>),
+    UpToDate(Release),
+    Available(Release),
+    Failed(String),
+}
+
+impl UpdateStatus {
+    /// Start a check in the background; a check already running is kept
+    pub fn check(&mut self) {
+        if matches!(self, UpdateStatus::Checking(_)) {
+            return;
+        }
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(fetch_latest_release());
+        });
+        *self = UpdateStatus::Checking(receiver);
+    }
+
+    /// Pick up the result of a running check; true when it just finished
+    pub fn poll(&mut self) -> bool {
+        let UpdateStatus::Checking(receiver) = self else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err("The update check stopped unexpectedly".to_string()),
+        };
+        *self = match result {
+            Ok(release) if is_newer(&release.tag_name, CURRENT_VERSION) => UpdateStatus::Available(release),
+            Ok(release) => UpdateStatus::UpToDate(release),
+            Err(error) => UpdateStatus::Failed(error),
+        };
+        true
+    }
+}
+
+fn fetch_latest_release() -> Result<Release, String> {
+    let response = ureq::get(RELEASES_URL)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", concat!("pdf_viewer/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(15))
+        .call()
+        .map_err(|e| format!("Could not reach GitHub: {}", e))?;
+    let json = response.into_string().map_err(|e| format!("Could not read the response: {}", e))?;
+    parse_release(&json)
+}
+
+pub fn parse_release(json: &str) -> Result<Release, String> {
+    serde_json::from_str(json).map_err(|e| format!("Unexpected response from GitHub: {}", e))
+}
+
+/// Whether release tag `tag` (e.g. "v1.2.0") is a later version than `current`.
+/// Tags that are not dotted numbers never count as newer.
+pub fn is_newer(tag: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Option<Vec<u64>> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        // Pre-release and build suffixes ("-rc1", "+abc") are ignored
+        let core = version.split(['-', '+']).next()?;
+        core.split('.').map(|part| part.parse().ok()).collect()
+    };
+    match (parse(tag), parse(current)) {
+        (Some(mut tag), Some(mut current)) => {
+            let length = tag.len().max(current.len());
+            tag.resize(length, 0);
+            current.resize(length, 0);
+            tag > current
+        }
+        _ => false,
+    }
+}